@@ -0,0 +1,81 @@
+use std::{
+    io::{self, BufWriter, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+use json_pretty_printer::{
+    parser::parse,
+    printer::{json_to_string_with_options, write_json, PrintOptions},
+};
+
+use crate::common::measure;
+
+mod common;
+
+fn large_fixture() -> String {
+    let records: Vec<String> = (0..500_000)
+        .map(|id| {
+            format!(
+                r#"{{"id": {id}, "name": "record é {id}", "tags": ["a", "b"], "ratio": {id}.25e-2, "active": true, "parent": null, "child": {{"id": {id}, "note": "line\none"}}}}"#
+            )
+        })
+        .collect();
+
+    format!("[{}]", records.join(",\n"))
+}
+
+/// Runs the binary on the input through pipes, as a shell pipeline would,
+/// returning the number of bytes it wrote.
+fn run_binary(input: &str) -> usize {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_json_pretty_printer"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("the binary runs");
+    let mut stdout = child.stdout.take().unwrap();
+    let reader = thread::spawn(move || io::copy(&mut stdout, &mut io::sink()).unwrap());
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+    reader.join().unwrap() as usize
+}
+
+fn main() {
+    let fixture = large_fixture();
+    let json = parse(&fixture).unwrap();
+    let options = PrintOptions::default();
+    println!("fixture size: {} bytes", fixture.len());
+
+    let string = measure(3, || {
+        let output = json_to_string_with_options(&json, &options).unwrap();
+        writeln!(io::sink(), "{output}").unwrap();
+    });
+    println!(
+        "string then write: {:?}, {} bytes allocated",
+        string.elapsed, string.allocated_bytes
+    );
+
+    let stream = measure(3, || {
+        let mut output = BufWriter::new(io::sink());
+        write_json(&json, &mut output, &options).unwrap();
+        output.flush().unwrap();
+    });
+    println!(
+        "buffered stream:   {:?}, {} bytes allocated ({:.1}x faster)",
+        stream.elapsed,
+        stream.allocated_bytes,
+        string.elapsed.as_secs_f64() / stream.elapsed.as_secs_f64()
+    );
+
+    let mut output_size = 0;
+    let binary_time = measure(3, || output_size = run_binary(&fixture)).elapsed;
+    println!(
+        "end to end:        {binary_time:?} for {output_size} bytes ({:.0} MB/s)",
+        output_size as f64 / binary_time.as_secs_f64() / 1e6
+    );
+}
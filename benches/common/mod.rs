@@ -0,0 +1,57 @@
+//! The allocation counting shared by the benches, each of which includes it
+//! with `mod common;`.
+
+// Each bench reports only some of the counts.
+#![allow(dead_code)]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// The average cost of one iteration of a bench.
+pub struct Measurement {
+    pub elapsed: Duration,
+    pub allocations: usize,
+    pub allocated_bytes: usize,
+}
+
+pub fn measure<T>(iterations: u32, mut f: impl FnMut() -> T) -> Measurement {
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_before = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..iterations {
+        std::hint::black_box(f());
+    }
+    let elapsed = start.elapsed() / iterations;
+    let allocations =
+        (ALLOCATIONS.load(Ordering::Relaxed) - allocations_before) / iterations as usize;
+    let allocated_bytes =
+        (ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_before) / iterations as usize;
+    Measurement {
+        elapsed,
+        allocations,
+        allocated_bytes,
+    }
+}
@@ -0,0 +1,42 @@
+use std::time::{Duration, Instant};
+
+use json_pretty_printer::parser::{parse, parse_pointer};
+
+fn large_fixture() -> String {
+    let results: Vec<String> = (0..100_000)
+        .map(|id| {
+            format!(
+                r#"{{"id": {id}, "name": "item {id}", "tags": ["a", "b", "c"], "score": {id}.5}}"#
+            )
+        })
+        .collect();
+
+    format!(
+        r#"{{"results": [{}], "count": 100000}}"#,
+        results.join(", ")
+    )
+}
+
+fn time<T>(iterations: u32, mut f: impl FnMut() -> T) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        std::hint::black_box(f());
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    let fixture = large_fixture();
+    println!("fixture size: {} bytes", fixture.len());
+
+    let full = time(5, || parse(&fixture).unwrap());
+    println!("parse:                        {full:?}");
+
+    for pointer in ["/results/0/id", "/results/50000/id", "/count"] {
+        let lazy = time(5, || parse_pointer(&fixture, pointer).unwrap());
+        println!(
+            "parse_pointer({pointer:<18}): {lazy:?} ({:.1}x faster)",
+            full.as_secs_f64() / lazy.as_secs_f64()
+        );
+    }
+}
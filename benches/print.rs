@@ -0,0 +1,77 @@
+use json_pretty_printer::{
+    ast::Json,
+    dsl::ToJson,
+    printer::{json_to_string, json_to_string_with_options, PrintOptions},
+};
+
+use crate::common::{measure, Measurement};
+
+mod common;
+
+fn deep_and_wide_fixture() -> Json {
+    let mut value = (0..20).map(|id| id.to_json()).collect::<Vec<_>>().to_json();
+    for depth in 0..40 {
+        let siblings: Vec<Json> = (0..20)
+            .map(|id| Json::object().set("id", id).set("depth", depth))
+            .collect();
+        value = Json::object().set("siblings", siblings).set("child", value);
+    }
+    (0..50).map(|_| value.clone()).collect::<Vec<_>>().to_json()
+}
+
+fn string_heavy_fixture() -> Json {
+    (0..100_000)
+        .map(|id| {
+            Json::object()
+                .set("name", format!("record {id} with a reasonably long name"))
+                .set("path", format!("C:\\data\\records\\{id}.json"))
+                .set(
+                    "note",
+                    format!("line one\nline two\t\"quoted\" \u{1} é {id}"),
+                )
+        })
+        .collect::<Vec<_>>()
+        .to_json()
+}
+
+fn report(name: &str, output_size: usize, measurement: Measurement) {
+    let Measurement {
+        elapsed,
+        allocations,
+        ..
+    } = measurement;
+    println!(
+        "{name:<8} {elapsed:?} for {output_size} bytes ({:.0} MB/s), {allocations} allocations",
+        output_size as f64 / elapsed.as_secs_f64() / 1e6
+    );
+}
+
+fn main() {
+    let fixture = deep_and_wide_fixture();
+
+    for indent in [2, 8] {
+        let output_size = json_to_string(&fixture, indent).unwrap().len();
+        let name = format!("indent {indent}:");
+        report(
+            &name,
+            output_size,
+            measure(5, || json_to_string(&fixture, indent).unwrap()),
+        );
+    }
+
+    let tabs = PrintOptions::new().indent("\t");
+    let output_size = json_to_string_with_options(&fixture, &tabs).unwrap().len();
+    report(
+        "tabs:",
+        output_size,
+        measure(5, || json_to_string_with_options(&fixture, &tabs).unwrap()),
+    );
+
+    let strings = string_heavy_fixture();
+    let output_size = json_to_string(&strings, 2).unwrap().len();
+    report(
+        "strings:",
+        output_size,
+        measure(5, || json_to_string(&strings, 2).unwrap()),
+    );
+}
@@ -0,0 +1,36 @@
+use json_pretty_printer::parser::{parse, validate};
+
+use crate::common::measure;
+
+mod common;
+
+fn large_fixture() -> String {
+    let records: Vec<String> = (0..100_000)
+        .map(|id| {
+            format!(
+                r#"{{"id": {id}, "name": "record é {id}", "tags": ["a", "b"], "ratio": {id}.25e-2, "active": true, "parent": null}}"#
+            )
+        })
+        .collect();
+
+    format!("[{}]", records.join(",\n"))
+}
+
+fn main() {
+    let fixture = large_fixture();
+    println!("fixture size: {} bytes", fixture.len());
+
+    let parse = measure(5, || parse(&fixture).unwrap());
+    println!(
+        "parse:    {:?}, {} allocations",
+        parse.elapsed, parse.allocations
+    );
+
+    let validate = measure(5, || validate(&fixture).unwrap());
+    println!(
+        "validate: {:?}, {} allocations ({:.1}x faster)",
+        validate.elapsed,
+        validate.allocations,
+        parse.elapsed.as_secs_f64() / validate.elapsed.as_secs_f64()
+    );
+}
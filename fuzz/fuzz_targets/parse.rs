@@ -0,0 +1,57 @@
+//! Feeds arbitrary text to each parser, which must fail rather than panic,
+//! and must agree with `parse` on which documents are valid.
+
+#![no_main]
+
+use json_pretty_printer::{
+    incremental::IncrementalParser,
+    parser::{
+        parse, parse_pointer, parse_with_comments, parse_with_options, validate, ParseOptions,
+    },
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let parsed = parse(text);
+    assert_eq!(validate(text).is_ok(), parsed.is_ok());
+
+    let mut incremental = IncrementalParser::new();
+    let middle = (0..=text.len() / 2)
+        .rev()
+        .find(|&index| text.is_char_boundary(index))
+        .unwrap_or(0);
+    let incremental = incremental
+        .feed(&text[..middle])
+        .and_then(|_| incremental.feed(&text[middle..]))
+        .and_then(|_| incremental.finish());
+    assert_eq!(incremental.ok().as_ref(), parsed.as_ref().ok());
+
+    let _ = parse_with_comments(text);
+    let _ = parse_pointer(text, "/0/a");
+    let _ = parse_with_options(
+        text,
+        &ParseOptions {
+            allow_numeric_underscores: true,
+            ..ParseOptions::default()
+        },
+    );
+
+    // The JSON5 syntax only adds to the grammar, so every document `parse`
+    // accepts must parse the same way with it allowed.
+    let json5 = parse_with_options(
+        text,
+        &ParseOptions {
+            allow_unquoted_keys: true,
+            allow_single_quotes: true,
+            allow_trailing_commas: true,
+            ..ParseOptions::default()
+        },
+    );
+    if let Ok(parsed) = &parsed {
+        assert_eq!(json5.as_ref().ok(), Some(parsed));
+    }
+});
@@ -0,0 +1,61 @@
+//! Prints arbitrary values in each style and parses them back, which must
+//! give the same value.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use json_pretty_printer::{
+    ast::Json,
+    parser::parse,
+    printer::{json_to_string_with_options, PrintOptions, PrintStyle, ScalarArrayLayout},
+};
+use libfuzzer_sys::fuzz_target;
+
+/// A value to print. Numbers are finite, since the default options can't
+/// print any others.
+#[derive(Arbitrary, Debug)]
+enum Value {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn to_json(&self) -> Json {
+        match self {
+            Value::Null => Json::Null,
+            Value::Boolean(value) => Json::Boolean(*value),
+            Value::Number(number) if number.is_finite() => Json::Number(*number),
+            Value::Number(_) => Json::Number(0.0),
+            Value::String(text) => Json::String(text.clone()),
+            Value::Array(items) => Json::Array(items.iter().map(Value::to_json).collect()),
+            Value::Object(properties) => Json::Object(
+                properties
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_json()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fuzz_target!(|value: Value| {
+    let json = value.to_json();
+    let styles = [
+        PrintOptions::new(),
+        PrintOptions::new().style(PrintStyle::OneLine),
+        PrintOptions::new().style(PrintStyle::Compact),
+        PrintOptions::new().style(PrintStyle::Fit).width(20),
+        PrintOptions::new()
+            .html_safe(true)
+            .scalar_arrays(ScalarArrayLayout::Wrap(10)),
+    ];
+
+    for options in styles {
+        let printed = json_to_string_with_options(&json, &options).unwrap();
+        assert_eq!(parse(&printed).unwrap(), json, "{printed}");
+    }
+});
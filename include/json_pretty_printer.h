@@ -0,0 +1,28 @@
+/* C declarations for the functions of json_pretty_printer's `ffi` feature. */
+
+#ifndef JSON_PRETTY_PRINTER_H
+#define JSON_PRETTY_PRINTER_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Formats a NUL-terminated JSON document, indenting by `indent` spaces, and
+ * returns the output as a new NUL-terminated string. On failure, returns
+ * NULL and, if `out_err` isn't NULL, points it at a new string describing the
+ * error. It is pointed at NULL on success. Strings returned either way must
+ * be freed with json_pretty_free.
+ */
+char *json_pretty_format(const char *input, uint32_t indent, char **out_err);
+
+/* Frees a string returned by json_pretty_format. Does nothing with NULL. */
+void json_pretty_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,176 @@
+//! Typed lookups which explain what they found when it isn't what was
+//! expected, for reporting problems in a document such as a config file.
+
+use std::{error, fmt};
+
+use crate::{
+    ast::{escape_pointer_token, Json},
+    map::Map,
+};
+
+/// Why a typed getter couldn't give a value, displayed as a message such as
+/// `expected string at /server/host, found number`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonAccessError {
+    /// There is no value at this JSON Pointer.
+    Missing(String),
+    /// The value at the JSON Pointer has the wrong type.
+    WrongType {
+        pointer: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+impl fmt::Display for JsonAccessError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = |pointer: &str| match pointer {
+            "" => "the root".to_owned(),
+            _ => pointer.to_owned(),
+        };
+        match self {
+            JsonAccessError::Missing(pointer) => {
+                formatter.write_fmt(format_args!("missing value at {}", location(pointer)))
+            }
+            JsonAccessError::WrongType {
+                pointer,
+                expected,
+                found,
+            } => formatter.write_fmt(format_args!(
+                "expected {expected} at {}, found {found}",
+                location(pointer)
+            )),
+        }
+    }
+}
+
+impl error::Error for JsonAccessError {}
+
+impl Json {
+    /// Gets a property of an object as a string, failing with the property's
+    /// JSON Pointer if it is missing or isn't a string. The other typed
+    /// getters work the same way.
+    pub fn get_str(&self, property: &str) -> Result<&str, JsonAccessError> {
+        self.typed_property(property, "string", Json::as_str)
+    }
+    pub fn get_f64(&self, property: &str) -> Result<f64, JsonAccessError> {
+        self.typed_property(property, "number", Json::as_f64)
+    }
+    pub fn get_bool(&self, property: &str) -> Result<bool, JsonAccessError> {
+        self.typed_property(property, "boolean", Json::as_bool)
+    }
+    pub fn get_array(&self, property: &str) -> Result<&Vec<Json>, JsonAccessError> {
+        self.typed_property(property, "array", Json::as_array)
+    }
+    pub fn get_object(&self, property: &str) -> Result<&Map, JsonAccessError> {
+        self.typed_property(property, "object", Json::as_object)
+    }
+
+    /// Gets the value at a JSON Pointer as a string, failing with the pointer
+    /// if it is missing or isn't a string. The other `_at` getters work the
+    /// same way.
+    pub fn get_str_at(&self, pointer: &str) -> Result<&str, JsonAccessError> {
+        typed(self.pointer(pointer), pointer, "string", Json::as_str)
+    }
+    pub fn get_f64_at(&self, pointer: &str) -> Result<f64, JsonAccessError> {
+        typed(self.pointer(pointer), pointer, "number", Json::as_f64)
+    }
+    pub fn get_bool_at(&self, pointer: &str) -> Result<bool, JsonAccessError> {
+        typed(self.pointer(pointer), pointer, "boolean", Json::as_bool)
+    }
+    pub fn get_array_at(&self, pointer: &str) -> Result<&Vec<Json>, JsonAccessError> {
+        typed(self.pointer(pointer), pointer, "array", Json::as_array)
+    }
+    pub fn get_object_at(&self, pointer: &str) -> Result<&Map, JsonAccessError> {
+        typed(self.pointer(pointer), pointer, "object", Json::as_object)
+    }
+
+    fn typed_property<'a, T>(
+        &'a self,
+        property: &str,
+        expected: &'static str,
+        accessor: fn(&'a Json) -> Option<T>,
+    ) -> Result<T, JsonAccessError> {
+        let pointer = format!("/{}", escape_pointer_token(property));
+        typed(self.get(property), &pointer, expected, accessor)
+    }
+}
+
+fn typed<'a, T>(
+    value: Option<&'a Json>,
+    pointer: &str,
+    expected: &'static str,
+    accessor: fn(&'a Json) -> Option<T>,
+) -> Result<T, JsonAccessError> {
+    let value = value.ok_or_else(|| JsonAccessError::Missing(pointer.to_owned()))?;
+    accessor(value).ok_or_else(|| JsonAccessError::WrongType {
+        pointer: pointer.to_owned(),
+        expected,
+        found: value.type_name(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Json, dsl::ToJson, parser::parse};
+
+    fn config() -> Json {
+        parse(
+            r#"{
+                "name": "api",
+                "debug": false,
+                "server": {"host": 8080, "ports": [80, 443], "tls/ssl": {"enabled": true}}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_gets_values_of_the_expected_type() {
+        let config = config();
+
+        assert_eq!(config.get_str("name"), Ok("api"));
+        assert_eq!(config.get_bool("debug"), Ok(false));
+        assert_eq!(
+            config.get_object("server").map(|server| server.len()),
+            Ok(3)
+        );
+        assert_eq!(config.get_f64_at("/server/host"), Ok(8080.0));
+        assert_eq!(
+            config.get_array_at("/server/ports"),
+            Ok(&vec![80.to_json(), 443.to_json()])
+        );
+        assert_eq!(config.get_bool_at("/server/tls~1ssl/enabled"), Ok(true));
+        assert_eq!(config.get_object_at("").map(|root| root.len()), Ok(3));
+    }
+
+    #[test]
+    fn its_errors_say_where_the_problem_is_and_what_was_found() {
+        let config = config();
+        let messages = [
+            config.get_str_at("/server/host").unwrap_err(),
+            config.get_str_at("/server/user").unwrap_err(),
+            config.get_str_at("/server/ports/2").unwrap_err(),
+            config.get_f64_at("/server/tls~1ssl/enabled").unwrap_err(),
+            config.get_bool("name").unwrap_err(),
+            config.get_str("a/b").unwrap_err(),
+            config.get_array_at("").unwrap_err(),
+            config.get_object_at("/server/ports").unwrap_err(),
+        ]
+        .map(|error| error.to_string());
+
+        assert_eq!(
+            messages,
+            [
+                "expected string at /server/host, found number",
+                "missing value at /server/user",
+                "missing value at /server/ports/2",
+                "expected number at /server/tls~1ssl/enabled, found boolean",
+                "expected boolean at /name, found string",
+                "missing value at /a~1b",
+                "expected array at the root, found object",
+                "expected object at /server/ports, found array",
+            ]
+        );
+    }
+}
@@ -1,15 +1,189 @@
-use std::collections::BTreeMap;
+use alloc::{
+    borrow::ToOwned,
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    mem,
+    ops::{Index, IndexMut},
+};
+#[cfg(feature = "std")]
+use std::error;
 
-use crate::dsl::ToJson;
+use crate::{
+    dsl::{ToJson, MAX_SAFE_INTEGER},
+    map::Map,
+};
 
-#[derive(Clone, Debug, PartialEq)]
+/// A JSON value. Rather than matching on it, reach into a value with `get`,
+/// `get_index` or `pointer` and the `as_*` accessors, as in
+/// `json.get("name").and_then(Json::as_str)`.
+#[derive(Clone, Debug)]
 pub enum Json {
     Null,
     Boolean(bool),
     String(String),
     Number(f64),
+    /// An integer beyond `MAX_SAFE_INTEGER` either way, as the parser reads
+    /// one, held exactly rather than rounded to an `f64` so that it prints as
+    /// it was written. It is a number like any other, and equal to a `Number`
+    /// holding the same integer.
+    BigInteger(i128),
     Array(Vec<Json>),
-    Object(BTreeMap<String, Json>),
+    Object(Map),
+}
+
+/// A comment from a JSONC document.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Comment {
+    /// A `//` comment, holding the text after the slashes up to the end of the line.
+    Line(String),
+    /// A `/* */` comment, holding the text between the delimiters.
+    Block(String),
+}
+
+/// The comments from a JSONC document, attached to the values they describe
+/// by their JSON Pointer.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comments {
+    /// Comments which come before a value, or before the key of an object entry.
+    pub leading: BTreeMap<String, Vec<Comment>>,
+    /// Comments which come after the last item of an array or object, keyed by
+    /// the pointer of the array or object.
+    pub dangling: BTreeMap<String, Vec<Comment>>,
+    /// Comments which come after the top-level value.
+    pub trailing: Vec<Comment>,
+}
+
+/// An error from `Json::set_path`, `entry_path` or `remove_path`, giving the JSON
+/// Pointer of the value which couldn't be changed or descended into.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonPathError {
+    /// The pointer is neither empty nor starts with `/`, or is empty where
+    /// the whole value can't be addressed.
+    InvalidPointer(String),
+    /// There is no value at this pointer.
+    NotFound(String),
+    /// The value at this pointer is a scalar, so has no properties or items.
+    NotAContainer(String),
+    /// The token after this pointer, which is an array, isn't an index.
+    InvalidIndex(String),
+    /// The index after this pointer is more than the length of its array.
+    IndexOutOfBounds(String),
+}
+
+impl JsonPathError {
+    /// What went wrong, and the pointer it went wrong at.
+    pub(crate) fn describe(&self) -> (&'static str, &str) {
+        match self {
+            JsonPathError::InvalidPointer(pointer) => ("JSON pointer must start with '/'", pointer),
+            JsonPathError::NotFound(pointer) => ("no value at path", pointer),
+            JsonPathError::NotAContainer(pointer) => ("not an array or object", pointer),
+            JsonPathError::InvalidIndex(pointer) => ("invalid index into array", pointer),
+            JsonPathError::IndexOutOfBounds(pointer) => ("index out of bounds of array", pointer),
+        }
+    }
+}
+
+impl fmt::Display for JsonPathError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (message, pointer) = self.describe();
+        formatter.write_fmt(format_args!(
+            "ERROR: Invalid JSON path - {message}: {pointer:?}"
+        ))
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for JsonPathError {}
+
+/// An error from combining two values which aren't both of the type the
+/// combination needs, such as `Json::concat` on an array and an object.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CombineError {
+    /// The type both values should have had.
+    pub expected: &'static str,
+    /// The types the values had.
+    pub found: (&'static str, &'static str),
+}
+
+impl fmt::Display for CombineError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (left, right) = self.found;
+        formatter.write_fmt(format_args!(
+            "ERROR: Cannot combine JSON - expected two {}s, found {left} and {right}",
+            self.expected
+        ))
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for CombineError {}
+
+/// An error from changing a value as if it had a type it doesn't, such as
+/// `Json::try_set` on `null`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonTypeError {
+    /// The type the value should have had.
+    pub expected: &'static str,
+    /// The type the value had.
+    pub found: &'static str,
+}
+
+impl fmt::Display for JsonTypeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_fmt(format_args!(
+            "ERROR: Invalid JSON type - expected {}, found {}",
+            self.expected, self.found
+        ))
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for JsonTypeError {}
+
+/// Which values `Json::compact_with` removes besides `null` properties and
+/// empty arrays and objects.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CompactOptions {
+    /// Remove `null` items from arrays, moving the later items down.
+    pub drop_nulls_in_arrays: bool,
+    /// Remove empty strings like empty arrays and objects.
+    pub drop_empty_strings: bool,
+}
+
+pub(crate) fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Appends a token to a JSON Pointer, giving the length to truncate it back
+/// to.
+pub(crate) fn push_pointer_token(pointer: &mut String, token: &str) -> usize {
+    let length = pointer.len();
+    pointer.push('/');
+    pointer.push_str(&escape_pointer_token(token));
+    length
+}
+
+pub(crate) fn unescape_pointer_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Reads a JSON Pointer token as an array index, which must be `0` or a
+/// number without leading zeros.
+pub(crate) fn parse_array_index(token: &str) -> Option<usize> {
+    let is_array_index = token == "0"
+        || (!token.is_empty()
+            && !token.starts_with('0')
+            && token.chars().all(|c| c.is_ascii_digit()));
+
+    token.parse().ok().filter(|_| is_array_index)
 }
 
 impl Json {
@@ -17,7 +191,7 @@ impl Json {
         Json::Array(Vec::new())
     }
     pub fn object() -> Json {
-        Json::Object(BTreeMap::new())
+        Json::Object(Map::new())
     }
     pub fn get(&self, property: &str) -> Option<&Json> {
         match self {
@@ -25,6 +199,338 @@ impl Json {
             _ => None,
         }
     }
+    /// Returns the value of a property of an object, first adding it with the
+    /// default value if it is missing.
+    ///
+    /// Panics if the value isn't an object.
+    pub fn get_or_insert_with(
+        &mut self,
+        property: &str,
+        default: impl FnOnce() -> Json,
+    ) -> &mut Json {
+        let Json::Object(properties) = self else {
+            panic!("cannot insert {property:?} into {}", self.type_name());
+        };
+        if !properties.contains_key(property) {
+            properties.insert(property.to_owned(), default());
+        }
+        properties
+            .get_mut(property)
+            .expect("The property was inserted")
+    }
+    pub fn get_index(&self, index: usize) -> Option<&Json> {
+        match self {
+            Json::Array(items) => items.get(index),
+            _ => None,
+        }
+    }
+    /// The number of items in an array or properties in an object.
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            Json::Array(items) => Some(items.len()),
+            Json::Object(properties) => Some(properties.len()),
+            _ => None,
+        }
+    }
+    pub fn is_empty(&self) -> Option<bool> {
+        self.len().map(|len| len == 0)
+    }
+    /// The greatest number of arrays and objects nested inside each other,
+    /// which is 0 for a scalar and 1 for an array of scalars.
+    pub fn depth(&self) -> usize {
+        let mut max_depth = 0;
+        let mut pending = vec![(self, 0)];
+        while let Some((value, depth)) = pending.pop() {
+            if let Some(children) = value.children() {
+                max_depth = max_depth.max(depth + 1);
+                pending.extend(children.map(|child| (child, depth + 1)));
+            }
+        }
+        max_depth
+    }
+
+    /// The number of values in the value, counting itself and every array
+    /// and object as well as the scalars.
+    pub fn node_count(&self) -> usize {
+        let mut count = 0;
+        let mut pending = vec![self];
+        while let Some(value) = pending.pop() {
+            count += 1;
+            pending.extend(value.children().into_iter().flatten());
+        }
+        count
+    }
+
+    /// A rough estimate of the memory the value takes up, counting the size
+    /// of each value, the text of its strings and keys, and the space each
+    /// array item or object entry takes in its container. Spare capacity and
+    /// allocator overhead aren't counted.
+    pub fn estimated_size_bytes(&self) -> usize {
+        // An object keeps each key twice: with its value, and in its index.
+        const ENTRY_SIZE: usize =
+            mem::size_of::<(String, Json)>() + mem::size_of::<(String, usize)>();
+
+        let mut size = mem::size_of::<Json>();
+        let mut pending = vec![self];
+        while let Some(value) = pending.pop() {
+            size += match value {
+                Json::String(string) => string.len(),
+                Json::Array(items) => items.len() * mem::size_of::<Json>(),
+                Json::Object(properties) => properties
+                    .keys()
+                    .map(|key| ENTRY_SIZE + 2 * key.len())
+                    .sum(),
+                _ => 0,
+            };
+            pending.extend(value.children().into_iter().flatten());
+        }
+        size
+    }
+
+    fn children(&self) -> Option<Box<dyn Iterator<Item = &Json> + '_>> {
+        match self {
+            Json::Array(items) => Some(Box::new(items.iter())),
+            Json::Object(properties) => Some(Box::new(properties.values())),
+            _ => None,
+        }
+    }
+
+    /// The name of the value's type, for use in messages about a value not
+    /// having the expected type.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Boolean(_) => "boolean",
+            Json::String(_) => "string",
+            Json::Number(_) | Json::BigInteger(_) => "number",
+            Json::Array(_) => "array",
+            Json::Object(_) => "object",
+        }
+    }
+    pub fn is_null(&self) -> bool {
+        matches!(self, Json::Null)
+    }
+    pub fn is_bool(&self) -> bool {
+        matches!(self, Json::Boolean(_))
+    }
+    pub fn is_string(&self) -> bool {
+        matches!(self, Json::String(_))
+    }
+    pub fn is_number(&self) -> bool {
+        matches!(self, Json::Number(_) | Json::BigInteger(_))
+    }
+    pub fn is_array(&self) -> bool {
+        matches!(self, Json::Array(_))
+    }
+    pub fn is_object(&self) -> bool {
+        matches!(self, Json::Object(_))
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(string) => Some(string),
+            _ => None,
+        }
+    }
+    /// Gets a number as an `f64`, rounding a `BigInteger` to the nearest.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(number) => Some(*number),
+            Json::BigInteger(integer) => Some(*integer as f64),
+            _ => None,
+        }
+    }
+    /// Gets a number as an integer, if it is a whole number in the range
+    /// where every integer has an exact `f64`: -(2^53 - 1) to 2^53 - 1, or a
+    /// `BigInteger` which fits. Larger `Number`s give `None`, because the
+    /// number parsed may not be the one stored, as 2^53 + 1 would be stored
+    /// as 2^53. Negative zero gives `0`.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Number(number)
+                if is_whole_number(*number) && number.abs() <= MAX_SAFE_INTEGER as f64 =>
+            {
+                Some(*number as i64)
+            }
+            Json::BigInteger(integer) => i64::try_from(*integer).ok(),
+            _ => None,
+        }
+    }
+    /// Like `as_i64`, but also `None` for negative numbers.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::BigInteger(integer) => u64::try_from(*integer).ok(),
+            _ => self.as_i64().and_then(|number| u64::try_from(number).ok()),
+        }
+    }
+    /// Like `as_u64`, but also `None` for numbers too big for a `usize`.
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_u64()
+            .and_then(|number| usize::try_from(number).ok())
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Boolean(boolean) => Some(*boolean),
+            _ => None,
+        }
+    }
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Json>> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+    pub fn as_object(&self) -> Option<&Map> {
+        match self {
+            Json::Object(properties) => Some(properties),
+            _ => None,
+        }
+    }
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
+        match self {
+            Json::Object(properties) => Some(properties),
+            _ => None,
+        }
+    }
+    /// Moves the value out, leaving `Json::Null` in its place.
+    pub fn take(&mut self) -> Json {
+        mem::replace(self, Json::Null)
+    }
+    /// Turns the value into its string, or gives it back if it isn't one.
+    /// The other `into_*` conversions work the same way.
+    pub fn into_string(self) -> Result<String, Json> {
+        match self {
+            Json::String(string) => Ok(string),
+            other => Err(other),
+        }
+    }
+    pub fn into_f64(self) -> Result<f64, Json> {
+        match self {
+            Json::Number(number) => Ok(number),
+            Json::BigInteger(integer) => Ok(integer as f64),
+            other => Err(other),
+        }
+    }
+    pub fn into_bool(self) -> Result<bool, Json> {
+        match self {
+            Json::Boolean(boolean) => Ok(boolean),
+            other => Err(other),
+        }
+    }
+    pub fn into_array(self) -> Result<Vec<Json>, Json> {
+        match self {
+            Json::Array(items) => Ok(items),
+            other => Err(other),
+        }
+    }
+    pub fn into_object(self) -> Result<Map, Json> {
+        match self {
+            Json::Object(properties) => Ok(properties),
+            other => Err(other),
+        }
+    }
+    /// Looks up the value addressed by a JSON Pointer (RFC 6901), such as
+    /// `/a/b/0`. The empty pointer addresses the whole value.
+    pub fn pointer(&self, pointer: &str) -> Option<&Json> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+
+        let mut value = self;
+        for token in pointer.strip_prefix('/')?.split('/') {
+            value = match value {
+                Json::Object(properties) => properties.get(&unescape_pointer_token(token))?,
+                Json::Array(items) => items.get(parse_array_index(token)?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// Like `pointer`, but returns a mutable reference.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Json> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+
+        let mut value = self;
+        for token in pointer.strip_prefix('/')?.split('/') {
+            value = match value {
+                Json::Object(properties) => properties.get_mut(&unescape_pointer_token(token))?,
+                Json::Array(items) => items.get_mut(parse_array_index(token)?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// Sets the value at a JSON Pointer, adding any missing objects on the way
+    /// to it. An array index equal to the array's length, or `-`, appends to
+    /// the array. The empty pointer replaces the whole value.
+    pub fn set_path(&mut self, pointer: &str, value: impl ToJson) -> Result<(), JsonPathError> {
+        *self.entry_path(pointer)? = value.to_json();
+        Ok(())
+    }
+
+    /// Returns the value at a JSON Pointer, adding any missing objects on the
+    /// way to it and `null` at the end of it if there is no value there yet.
+    /// An array index equal to the array's length, or `-`, appends to the array.
+    pub fn entry_path(&mut self, pointer: &str) -> Result<&mut Json, JsonPathError> {
+        if pointer.is_empty() {
+            return Ok(self);
+        }
+        let Some(tokens) = pointer.strip_prefix('/') else {
+            return Err(JsonPathError::InvalidPointer(pointer.to_owned()));
+        };
+
+        let mut target = self;
+        let mut parent_length = 0;
+        let mut tokens = tokens.split('/').peekable();
+
+        while let Some(token) = tokens.next() {
+            let parent = &pointer[..parent_length];
+            parent_length += 1 + token.len();
+            let missing = if tokens.peek().is_none() {
+                Json::Null
+            } else {
+                Json::object()
+            };
+
+            target = match target {
+                Json::Object(properties) => {
+                    let key = unescape_pointer_token(token);
+                    if !properties.contains_key(&key) {
+                        properties.insert(key.clone(), missing);
+                    }
+                    properties.get_mut(&key).expect("The key was inserted")
+                }
+                Json::Array(items) => {
+                    let index = match token {
+                        "-" => items.len(),
+                        _ => parse_array_index(token)
+                            .ok_or_else(|| JsonPathError::InvalidIndex(parent.to_owned()))?,
+                    };
+                    match index.cmp(&items.len()) {
+                        Ordering::Less => {}
+                        Ordering::Equal => items.push(missing),
+                        Ordering::Greater => {
+                            return Err(JsonPathError::IndexOutOfBounds(parent.to_owned()))
+                        }
+                    }
+                    &mut items[index]
+                }
+                _ => return Err(JsonPathError::NotAContainer(parent.to_owned())),
+            };
+        }
+        Ok(target)
+    }
+
+    /// Like `try_set`, but leaves a value which isn't an object unchanged.
     pub fn set(self, property: &str, value: impl ToJson) -> Json {
         if let Json::Object(mut properties) = self {
             properties.insert(property.to_owned(), value.to_json());
@@ -33,46 +539,1902 @@ impl Json {
             self
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::{ast::Json, dsl::ToJson};
+    /// Like `set`, but leaves the property out, rather than setting it to
+    /// `null`, if the value is `None`.
+    pub fn set_opt(self, property: &str, value: Option<impl ToJson>) -> Json {
+        match value {
+            Some(value) => self.set(property, value),
+            None => self,
+        }
+    }
 
-    #[test]
-    fn get_returns_the_value_of_a_property_if_called_on_an_object() {
-        assert_eq!(
-            [("foo", "bar")].to_json().get("foo"),
-            Some(&"bar".to_json())
-        );
+    /// Sets a property of an object, replacing any value it had, or fails
+    /// with the type of a value which isn't an object.
+    pub fn try_set(mut self, property: &str, value: impl ToJson) -> Result<Json, JsonTypeError> {
+        self.try_insert(property, value)?;
+        Ok(self)
     }
-    #[test]
-    fn get_returns_none_if_the_property_is_not_in_the_object() {
-        assert_eq!([("foo", "bar")].to_json().get("baz"), None);
+
+    /// Like `try_set`, but changes the object in place, giving back the value
+    /// the property had before.
+    pub fn try_insert(
+        &mut self,
+        property: &str,
+        value: impl ToJson,
+    ) -> Result<Option<Json>, JsonTypeError> {
+        match self {
+            Json::Object(properties) => Ok(properties.insert(property.to_owned(), value.to_json())),
+            other => Err(other.type_error("object")),
+        }
     }
-    #[test]
-    fn get_returns_none_if_called_on_a_non_object() {
-        assert_eq!(Json::Null.get("foo"), None);
-        assert_eq!("a string".to_json().get("foo"), None);
-        assert_eq!(123.to_json().get("foo"), None);
-        assert_eq!(true.to_json().get("foo"), None);
-        assert_eq!([1, 2, 3].to_json().get("foo"), None);
+
+    /// Sets an item of an array, or appends it if the index is the array's
+    /// length. Fails with the type of a value which isn't an array.
+    ///
+    /// Panics if the index is greater than the array's length.
+    pub fn set_index(mut self, index: usize, value: impl ToJson) -> Result<Json, JsonTypeError> {
+        match &mut self {
+            Json::Array(items) if index == items.len() => items.push(value.to_json()),
+            Json::Array(items) => {
+                let length = items.len();
+                match items.get_mut(index) {
+                    Some(item) => *item = value.to_json(),
+                    None => panic!("index {index} out of bounds of array of length {length}"),
+                }
+            }
+            other => return Err(other.type_error("array")),
+        }
+        Ok(self)
     }
 
-    #[test]
-    fn set_can_be_chained_to_construct_an_object() {
-        assert_eq!(
-            Json::object().set("foo", "bar").set("baz", 123),
-            [("foo", "bar".to_json()), ("baz", 123.to_json())].to_json()
-        );
+    fn type_error(&self, expected: &'static str) -> JsonTypeError {
+        JsonTypeError {
+            expected,
+            found: self.type_name(),
+        }
     }
 
-    #[test]
-    fn set_has_no_effect_on_a_non_object() {
-        assert_eq!(Json::Null.set("foo", "bar"), Json::Null);
-        assert_eq!("hello".to_json().set("foo", "bar"), "hello".to_json());
-        assert_eq!(123.to_json().set("foo", "bar"), 123.to_json());
-        assert_eq!(true.to_json().set("foo", "bar"), true.to_json());
-        assert_eq!([1, 2, 3].to_json().set("foo", "bar"), [1, 2, 3].to_json());
+    pub fn push(self, value: impl ToJson) -> Json {
+        self.try_push(value).unwrap_or_else(|unchanged| unchanged)
+    }
+
+    /// Like `push`, but gives back the value unchanged if it isn't an array.
+    pub fn try_push(self, value: impl ToJson) -> Result<Json, Json> {
+        if let Json::Array(mut items) = self {
+            items.push(value.to_json());
+            Ok(Json::Array(items))
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Inserts an item into an array, shifting the items after it up.
+    ///
+    /// Panics if the index is greater than the array's length.
+    pub fn insert_at(self, index: usize, value: impl ToJson) -> Json {
+        if let Json::Array(mut items) = self {
+            items.insert(index, value.to_json());
+            Json::Array(items)
+        } else {
+            self
+        }
+    }
+
+    /// Compares two values in a fixed order across types: null, then
+    /// booleans, numbers, strings, arrays and objects. Values of the same type
+    /// compare by value, with `false` before `true`, NaN after every other
+    /// number, strings by Unicode scalar value, arrays compared item by item
+    /// and objects compared entry by entry in key order. This is the order of
+    /// `Ord`, and two values compare equal exactly when they are `==`.
+    pub fn total_cmp(&self, other: &Json) -> Ordering {
+        fn rank(value: &Json) -> u8 {
+            match value {
+                Json::Null => 0,
+                Json::Boolean(_) => 1,
+                Json::Number(_) | Json::BigInteger(_) => 2,
+                Json::String(_) => 3,
+                Json::Array(_) => 4,
+                Json::Object(_) => 5,
+            }
+        }
+
+        match (self, other) {
+            (Json::Boolean(a), Json::Boolean(b)) => a.cmp(b),
+            (Json::Number(a), Json::Number(b)) => {
+                normalize_number(*a).total_cmp(&normalize_number(*b))
+            }
+            (Json::BigInteger(a), Json::BigInteger(b)) => a.cmp(b),
+            (Json::BigInteger(a), Json::Number(b)) => compare_big_integer(*a, *b),
+            (Json::Number(a), Json::BigInteger(b)) => compare_big_integer(*b, *a).reverse(),
+            (Json::String(a), Json::String(b)) => a.cmp(b),
+            (Json::Array(a), Json::Array(b)) => a
+                .iter()
+                .zip(b)
+                .map(|(a, b)| a.total_cmp(b))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            (Json::Object(a), Json::Object(b)) => a
+                .iter_sorted()
+                .zip(b.iter_sorted())
+                .map(|((a_key, a), (b_key, b))| a_key.cmp(b_key).then_with(|| a.total_cmp(b)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+
+    /// Stably sorts every array in the value with `total_cmp`.
+    pub fn sort_arrays(&mut self) {
+        self.sort_arrays_by(Json::total_cmp);
+    }
+
+    /// Stably sorts every array in the value with the given comparison. The
+    /// values inside an array are sorted before the array itself, so nested
+    /// arrays are compared in their sorted order.
+    pub fn sort_arrays_by(&mut self, compare: impl Fn(&Json, &Json) -> Ordering) {
+        self.sort_arrays_with(&compare);
+    }
+
+    fn sort_arrays_with(&mut self, compare: &impl Fn(&Json, &Json) -> Ordering) {
+        match self {
+            Json::Array(items) => {
+                for item in items.iter_mut() {
+                    item.sort_arrays_with(compare);
+                }
+                items.sort_by(compare);
+            }
+            Json::Object(properties) => {
+                for value in properties.values_mut() {
+                    value.sort_arrays_with(compare);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Rebuilds the value with `transform` applied to every value which isn't
+    /// an array or object, keeping the arrays and objects as they are.
+    pub fn map_values(self, transform: impl Fn(Json) -> Json) -> Json {
+        self.map_values_with(&transform)
+    }
+
+    fn map_values_with(self, transform: &impl Fn(Json) -> Json) -> Json {
+        match self {
+            Json::Array(items) => Json::Array(
+                items
+                    .into_iter()
+                    .map(|item| item.map_values_with(transform))
+                    .collect(),
+            ),
+            Json::Object(properties) => Json::Object(
+                properties
+                    .into_iter()
+                    .map(|(key, value)| (key, value.map_values_with(transform)))
+                    .collect(),
+            ),
+            leaf => transform(leaf),
+        }
+    }
+
+    /// Like `map_values`, but only transforms strings.
+    pub fn map_strings(self, transform: impl Fn(String) -> Json) -> Json {
+        self.map_values(|value| match value {
+            Json::String(string) => transform(string),
+            other => other,
+        })
+    }
+
+    /// Like `map_values`, but only transforms numbers.
+    pub fn map_numbers(self, transform: impl Fn(f64) -> Json) -> Json {
+        self.map_values(|value| match value {
+            Json::Number(number) => transform(number),
+            Json::BigInteger(integer) => transform(integer as f64),
+            other => other,
+        })
+    }
+
+    /// Removes `null` properties, and the arrays and objects left empty by
+    /// removing them, using the default `CompactOptions`.
+    pub fn compact(self) -> Json {
+        self.compact_with(CompactOptions::default())
+    }
+
+    /// Removes `null` properties, empty arrays and objects, and whichever
+    /// other values the options ask for, from every level of the value.
+    /// Arrays and objects are compacted before their parents, so one left
+    /// empty is removed as well. The value itself is never removed: an array
+    /// or object with nothing left in it is returned empty.
+    pub fn compact_with(self, options: CompactOptions) -> Json {
+        let is_removed = |value: &Json, in_array: bool| match value {
+            Json::Null => !in_array || options.drop_nulls_in_arrays,
+            Json::String(string) => options.drop_empty_strings && string.is_empty(),
+            Json::Array(items) => items.is_empty(),
+            Json::Object(properties) => properties.is_empty(),
+            _ => false,
+        };
+
+        match self {
+            Json::Array(items) => Json::Array(
+                items
+                    .into_iter()
+                    .map(|item| item.compact_with(options))
+                    .filter(|item| !is_removed(item, true))
+                    .collect(),
+            ),
+            Json::Object(properties) => Json::Object(
+                properties
+                    .into_iter()
+                    .map(|(key, value)| (key, value.compact_with(options)))
+                    .filter(|(_, value)| !is_removed(value, false))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Appends the items of another array to this one.
+    pub fn concat(self, other: Json) -> Result<Json, CombineError> {
+        match (self, other) {
+            (Json::Array(mut items), Json::Array(other_items)) => {
+                items.extend(other_items);
+                Ok(Json::Array(items))
+            }
+            (left, right) => Err(CombineError {
+                expected: "array",
+                found: (left.type_name(), right.type_name()),
+            }),
+        }
+    }
+
+    /// Adds the properties of another object to this one, replacing any this
+    /// one already has. Only the top-level properties are combined: use
+    /// `merge_patch` to merge nested objects too.
+    pub fn union(self, other: Json) -> Result<Json, CombineError> {
+        self.union_with(other, |_, right| right)
+    }
+
+    /// Like `union`, but calls `resolve` with the values of a property both
+    /// objects have, to find the value to keep.
+    pub fn union_with(
+        self,
+        other: Json,
+        mut resolve: impl FnMut(Json, Json) -> Json,
+    ) -> Result<Json, CombineError> {
+        match (self, other) {
+            (Json::Object(mut properties), Json::Object(other_properties)) => {
+                for (key, right) in other_properties {
+                    match properties.get_mut(&key) {
+                        Some(slot) => {
+                            let left = mem::replace(slot, Json::Null);
+                            *slot = resolve(left, right);
+                        }
+                        None => {
+                            properties.insert(key, right);
+                        }
+                    }
+                }
+                Ok(Json::Object(properties))
+            }
+            (left, right) => Err(CombineError {
+                expected: "object",
+                found: (left.type_name(), right.type_name()),
+            }),
+        }
+    }
+
+    /// Applies a JSON Merge Patch (RFC 7386). An object patch is merged into
+    /// the value property by property, with `null` removing a property, while
+    /// any other patch replaces the value.
+    pub fn merge_patch(&mut self, patch: &Json) {
+        let Json::Object(patch_properties) = patch else {
+            *self = patch.clone();
+            return;
+        };
+        if !self.is_object() {
+            *self = Json::object();
+        }
+        for (key, value) in patch_properties {
+            if value.is_null() {
+                self.remove(key);
+            } else {
+                self[key.as_str()].merge_patch(value);
+            }
+        }
+    }
+
+    /// Removes the items of an array which are equal to an earlier item,
+    /// keeping the order of the rest. Returns the number of items removed, or
+    /// `None` if the value isn't an array.
+    pub fn dedup(&mut self) -> Option<usize> {
+        self.dedup_by(|item| Some(item))
+    }
+
+    /// Like `dedup`, but treats two items as the same if they are objects
+    /// with equal values of the property. Items without the property are
+    /// always kept.
+    pub fn dedup_by_key(&mut self, property: &str) -> Option<usize> {
+        self.dedup_by(|item| item.get(property))
+    }
+
+    fn dedup_by(&mut self, key: impl Fn(&Json) -> Option<&Json>) -> Option<usize> {
+        let items = self.as_array_mut()?;
+        let mut seen = BTreeSet::new();
+        let is_first: Vec<bool> = items
+            .iter()
+            .map(|item| key(item).is_none_or(|key| seen.insert(key)))
+            .collect();
+
+        let length = items.len();
+        let mut is_first = is_first.into_iter();
+        items.retain(|_| is_first.next().unwrap_or(true));
+        Some(length - items.len())
+    }
+
+    /// Removes a property from an object, returning its value.
+    pub fn remove(&mut self, property: &str) -> Option<Json> {
+        match self {
+            Json::Object(properties) => properties.remove(property),
+            _ => None,
+        }
+    }
+
+    /// Removes an item from an array, shifting the items after it down.
+    pub fn remove_index(&mut self, index: usize) -> Option<Json> {
+        match self {
+            Json::Array(items) if index < items.len() => Some(items.remove(index)),
+            _ => None,
+        }
+    }
+
+    /// Removes the value at a JSON Pointer from its parent, returning it.
+    pub fn remove_path(&mut self, pointer: &str) -> Result<Json, JsonPathError> {
+        let (parent_pointer, token) = match pointer.rsplit_once('/') {
+            Some(split) if pointer.starts_with('/') => split,
+            _ => return Err(JsonPathError::InvalidPointer(pointer.to_owned())),
+        };
+
+        let parent = self
+            .pointer_mut(parent_pointer)
+            .ok_or_else(|| JsonPathError::NotFound(parent_pointer.to_owned()))?;
+        let removed = match parent {
+            Json::Object(_) => parent.remove(&unescape_pointer_token(token)),
+            Json::Array(_) => {
+                let index = parse_array_index(token)
+                    .ok_or_else(|| JsonPathError::InvalidIndex(parent_pointer.to_owned()))?;
+                parent.remove_index(index)
+            }
+            _ => return Err(JsonPathError::NotAContainer(parent_pointer.to_owned())),
+        };
+        removed.ok_or_else(|| JsonPathError::NotFound(pointer.to_owned()))
+    }
+}
+
+/// Whether a number has no fractional part, like `number.fract() == 0.0`,
+/// which needs `std`. Every `f64` from 2^52 up is whole, and every smaller
+/// one fits in an `i64`.
+pub(crate) fn is_whole_number(number: f64) -> bool {
+    (number.is_finite() && number.abs() >= 4503599627370496.0) || number == number as i64 as f64
+}
+
+/// An integer as a `Number` if it is within `MAX_SAFE_INTEGER` either way,
+/// or else as a `BigInteger`, which holds it exactly.
+pub(crate) fn integer_to_json(integer: i128) -> Json {
+    if integer.unsigned_abs() <= MAX_SAFE_INTEGER as u128 {
+        Json::Number(integer as f64)
+    } else {
+        Json::BigInteger(integer)
+    }
+}
+
+/// Maps every zero to 0 and every NaN to the same NaN, so that numbers with
+/// the same bits are the numbers `Json` treats as equal.
+fn normalize_number(number: f64) -> f64 {
+    if number == 0.0 {
+        0.0
+    } else if number.is_nan() {
+        f64::NAN
+    } else {
+        number
+    }
+}
+
+/// Compares an integer with a number exactly, as comparing the integer
+/// rounded to an `f64` wouldn't be.
+fn compare_big_integer(integer: i128, number: f64) -> Ordering {
+    match (integer as f64).total_cmp(&normalize_number(number)) {
+        // The number is whole, but `as` would saturate it to `i128::MAX`.
+        Ordering::Equal if number >= i128::MAX as f64 => Ordering::Less,
+        Ordering::Equal => integer.cmp(&(number as i128)),
+        ordering => ordering,
+    }
+}
+
+/// The `f64` holding exactly the integer, if there is one.
+fn exact_f64(integer: i128) -> Option<f64> {
+    let number = integer as f64;
+    (number < i128::MAX as f64 && number as i128 == integer).then_some(number)
+}
+
+/// Values are equal if they have the same type and contents, with objects
+/// equal if they have the same properties in any order. Numbers are equal if
+/// they are the same number, except that every NaN is equal to every other,
+/// so that every value is equal to itself.
+impl PartialEq for Json {
+    fn eq(&self, other: &Json) -> bool {
+        match (self, other) {
+            (Json::Null, Json::Null) => true,
+            (Json::Boolean(a), Json::Boolean(b)) => a == b,
+            (Json::String(a), Json::String(b)) => a == b,
+            (Json::Number(a), Json::Number(b)) => {
+                normalize_number(*a).to_bits() == normalize_number(*b).to_bits()
+            }
+            (Json::BigInteger(a), Json::BigInteger(b)) => a == b,
+            (Json::BigInteger(integer), Json::Number(number))
+            | (Json::Number(number), Json::BigInteger(integer)) => {
+                exact_f64(*integer) == Some(*number)
+            }
+            (Json::Array(a), Json::Array(b)) => a == b,
+            (Json::Object(a), Json::Object(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Json {}
+
+/// Orders values with `Json::total_cmp`.
+impl PartialOrd for Json {
+    fn partial_cmp(&self, other: &Json) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Json {
+    fn cmp(&self, other: &Json) -> Ordering {
+        self.total_cmp(other)
+    }
+}
+
+/// Hashes a value consistently with `==`: objects hash the same whatever the
+/// order of their properties, and 0 and -0, or any two NaNs, hash the same.
+impl Hash for Json {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        if let Json::BigInteger(integer) = self {
+            if let Some(number) = exact_f64(*integer) {
+                return Json::Number(number).hash(state);
+            }
+        }
+        mem::discriminant(self).hash(state);
+        match self {
+            Json::Null => {}
+            Json::Boolean(boolean) => boolean.hash(state),
+            Json::String(string) => string.hash(state),
+            Json::Number(number) => normalize_number(*number).to_bits().hash(state),
+            Json::BigInteger(integer) => integer.hash(state),
+            Json::Array(items) => items.hash(state),
+            Json::Object(properties) => properties.hash(state),
+        }
+    }
+}
+
+/// Looks up a property of an object, panicking if the value isn't an object
+/// or doesn't have the property.
+impl Index<&str> for Json {
+    type Output = Json;
+
+    fn index(&self, property: &str) -> &Json {
+        match self {
+            Json::Object(properties) => properties
+                .get(property)
+                .unwrap_or_else(|| panic!("no property {property:?} in object")),
+            _ => panic!("cannot index into {} with {property:?}", self.type_name()),
+        }
+    }
+}
+
+/// Looks up an item of an array, panicking if the value isn't an array or
+/// the index is out of bounds.
+impl Index<usize> for Json {
+    type Output = Json;
+
+    fn index(&self, index: usize) -> &Json {
+        match self {
+            Json::Array(items) => items.get(index).unwrap_or_else(|| {
+                panic!(
+                    "index {index} out of bounds of array of length {}",
+                    items.len()
+                )
+            }),
+            _ => panic!("cannot index into {} with {index}", self.type_name()),
+        }
+    }
+}
+
+/// Looks up a property of an object, adding it as `null` if it is missing.
+/// A `null` value is first replaced with an empty object, so that nested
+/// objects can be built up with `json["a"]["b"] = value`.
+impl IndexMut<&str> for Json {
+    fn index_mut(&mut self, property: &str) -> &mut Json {
+        if self.is_null() {
+            *self = Json::object();
+        }
+        match self {
+            Json::Object(properties) => {
+                if !properties.contains_key(property) {
+                    properties.insert(property.to_owned(), Json::Null);
+                }
+                properties
+                    .get_mut(property)
+                    .expect("The property was inserted")
+            }
+            _ => panic!("cannot index into {} with {property:?}", self.type_name()),
+        }
+    }
+}
+
+/// Looks up an item of an array, adding a `null` item if the index is the
+/// array's length. A `null` value is first replaced with an empty array.
+impl IndexMut<usize> for Json {
+    fn index_mut(&mut self, index: usize) -> &mut Json {
+        if self.is_null() {
+            *self = Json::array();
+        }
+        match self {
+            Json::Array(items) => {
+                if index == items.len() {
+                    items.push(Json::Null);
+                }
+                let length = items.len();
+                items.get_mut(index).unwrap_or_else(|| {
+                    panic!("index {index} out of bounds of array of length {length}")
+                })
+            }
+            _ => panic!("cannot index into {} with {index}", self.type_name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Ordering,
+        collections::{BTreeMap, HashSet},
+        hash::{DefaultHasher, Hash, Hasher},
+        mem,
+    };
+
+    use crate::{
+        ast::{
+            exact_f64, is_whole_number, CombineError, CompactOptions, Json, JsonPathError,
+            JsonTypeError,
+        },
+        dsl::ToJson,
+        parser::parse,
+    };
+
+    #[test]
+    fn get_returns_the_value_of_a_property_if_called_on_an_object() {
+        assert_eq!(
+            [("foo", "bar")].to_json().get("foo"),
+            Some(&"bar".to_json())
+        );
+    }
+    #[test]
+    fn get_returns_none_if_the_property_is_not_in_the_object() {
+        assert_eq!([("foo", "bar")].to_json().get("baz"), None);
+    }
+    #[test]
+    fn get_returns_none_if_called_on_a_non_object() {
+        assert_eq!(Json::Null.get("foo"), None);
+        assert_eq!("a string".to_json().get("foo"), None);
+        assert_eq!(123.to_json().get("foo"), None);
+        assert_eq!(true.to_json().get("foo"), None);
+        assert_eq!([1, 2, 3].to_json().get("foo"), None);
+    }
+
+    #[test]
+    fn pointer_looks_up_the_values_in_the_rfc_examples() {
+        let document = parse(
+            r#"{
+                "foo": ["bar", "baz"],
+                "": 0,
+                "a/b": 1,
+                "c%d": 2,
+                "e^f": 3,
+                "g|h": 4,
+                "i\\j": 5,
+                "k\"l": 6,
+                " ": 7,
+                "m~n": 8
+            }"#,
+        )
+        .unwrap();
+
+        for (pointer, expected) in [
+            ("", document.clone()),
+            ("/foo", ["bar", "baz"].to_json()),
+            ("/foo/0", "bar".to_json()),
+            ("/", 0.to_json()),
+            ("/a~1b", 1.to_json()),
+            ("/c%d", 2.to_json()),
+            ("/e^f", 3.to_json()),
+            ("/g|h", 4.to_json()),
+            ("/i\\j", 5.to_json()),
+            ("/k\"l", 6.to_json()),
+            ("/ ", 7.to_json()),
+            ("/m~0n", 8.to_json()),
+        ] {
+            assert_eq!(document.pointer(pointer), Some(&expected), "{pointer}");
+        }
+    }
+
+    #[test]
+    fn pointer_returns_none_for_values_which_are_not_there() {
+        let document = parse(r#"{"a": [10, {"1": true}], "b": "text", "~1": null}"#).unwrap();
+
+        assert_eq!(document.pointer("/a/1/1"), Some(&true.to_json()));
+        assert_eq!(document.pointer("/~01"), Some(&Json::Null));
+        for pointer in [
+            "a", "/c", "/a/2", "/a/01", "/a/-", "/a/+1", "/a/", "/b/0", "/b/x", "/a/0/0",
+        ] {
+            assert_eq!(document.pointer(pointer), None, "{pointer}");
+        }
+    }
+
+    #[test]
+    fn pointer_mut_changes_the_value_in_place() {
+        let mut document = parse(r#"{"a": [1, {"b": 2}], "": {"": 3}}"#).unwrap();
+
+        *document.pointer_mut("/a/1/b").unwrap() = "two".to_json();
+        *document.pointer_mut("//").unwrap() = Json::Null;
+
+        assert_eq!(
+            document,
+            parse(r#"{"a": [1, {"b": "two"}], "": {"": null}}"#).unwrap()
+        );
+        assert_eq!(document.pointer_mut("/a/2"), None);
+        assert_eq!(document.pointer_mut("a"), None);
+        *document.pointer_mut("").unwrap() = Json::Null;
+        assert_eq!(document, Json::Null);
+    }
+
+    #[test]
+    fn set_path_adds_missing_objects_and_appends_to_arrays() {
+        let mut document = parse(r#"{"a": [1]}"#).unwrap();
+
+        document.set_path("/x/y/z", 1).unwrap();
+        document.set_path("/a/0", "one").unwrap();
+        document.set_path("/a/1", 2).unwrap();
+        document.set_path("/a/-", 3).unwrap();
+        document.set_path("/a/3/b", 4).unwrap();
+        document.set_path("//", "empty keys").unwrap();
+        document.set_path("/m~0n~1o", true).unwrap();
+
+        assert_eq!(
+            document,
+            parse(
+                r#"{"a": ["one", 2, 3, {"b": 4}], "x": {"y": {"z": 1}}, "": {"": "empty keys"}, "m~n/o": true}"#
+            )
+            .unwrap()
+        );
+
+        document.set_path("", vec![1, 2]).unwrap();
+        assert_eq!(document, vec![1, 2].to_json());
+    }
+
+    #[test]
+    fn get_or_insert_with_groups_values_without_cloning_the_groups() {
+        let events = parse(
+            r#"[
+                {"type": "click", "id": 1},
+                {"type": "view", "id": 2},
+                {"type": "click", "id": 3}
+            ]"#,
+        )
+        .unwrap();
+        let mut groups = Json::object();
+
+        for event in events.as_array().unwrap() {
+            let group = groups.get_or_insert_with(event["type"].as_str().unwrap(), Json::array);
+            group.as_array_mut().unwrap().push(event["id"].clone());
+        }
+
+        assert_eq!(groups, parse(r#"{"click": [1, 3], "view": [2]}"#).unwrap());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"cannot insert "a" into array"#)]
+    fn get_or_insert_with_panics_on_a_non_object() {
+        Json::array().get_or_insert_with("a", Json::object);
+    }
+
+    #[test]
+    fn entry_path_adds_the_missing_levels_and_slot() {
+        let mut document = Json::object().set("metrics", Json::object().set("weekly", 7));
+
+        for day in [1, 2] {
+            let daily = document.entry_path("/metrics/daily").unwrap();
+            if daily.is_null() {
+                *daily = Json::array();
+            }
+            daily.as_array_mut().unwrap().push(day.to_json());
+        }
+        *document.entry_path("/metrics/daily/-").unwrap() = 3.to_json();
+        document.entry_path("/other/new").unwrap();
+
+        assert_eq!(
+            document,
+            parse(r#"{"metrics": {"weekly": 7, "daily": [1, 2, 3]}, "other": {"new": null}}"#)
+                .unwrap()
+        );
+        assert_eq!(document.entry_path("/metrics/weekly"), Ok(&mut 7.to_json()));
+        assert_eq!(
+            document.entry_path("/metrics/weekly/x"),
+            Err(JsonPathError::NotAContainer("/metrics/weekly".to_owned()))
+        );
+    }
+
+    #[test]
+    fn set_path_fails_rather_than_replacing_values_on_the_way() {
+        let mut document = parse(r#"{"a": [1], "s": "text"}"#).unwrap();
+        let original = document.clone();
+
+        for (pointer, error) in [
+            ("a", JsonPathError::InvalidPointer("a".to_owned())),
+            ("/s/x", JsonPathError::NotAContainer("/s".to_owned())),
+            ("/a/0/x", JsonPathError::NotAContainer("/a/0".to_owned())),
+            ("/a/2", JsonPathError::IndexOutOfBounds("/a".to_owned())),
+            ("/a/01", JsonPathError::InvalidIndex("/a".to_owned())),
+            ("/a/x", JsonPathError::InvalidIndex("/a".to_owned())),
+        ] {
+            assert_eq!(document.set_path(pointer, 0), Err(error), "{pointer}");
+        }
+        assert_eq!(document, original);
+    }
+
+    #[test]
+    fn each_value_has_one_type() {
+        let values = [
+            Json::Null,
+            false.to_json(),
+            "".to_json(),
+            0.to_json(),
+            Json::array(),
+            Json::object(),
+        ];
+        let predicates = [
+            Json::is_null,
+            Json::is_bool,
+            Json::is_string,
+            Json::is_number,
+            Json::is_array,
+            Json::is_object,
+        ];
+
+        for (value_index, value) in values.iter().enumerate() {
+            for (predicate_index, predicate) in predicates.iter().enumerate() {
+                assert_eq!(
+                    predicate(value),
+                    value_index == predicate_index,
+                    "{value:?} {predicate_index}"
+                );
+            }
+        }
+        assert_eq!(
+            values.iter().map(Json::type_name).collect::<Vec<_>>(),
+            ["null", "boolean", "string", "number", "array", "object"]
+        );
+    }
+
+    #[test]
+    fn it_can_be_indexed_by_property_and_item() {
+        let document = parse(r#"{"users": [{"name": "Ada"}, {"name": "Grace"}]}"#).unwrap();
+
+        assert_eq!(document["users"][1]["name"], "Grace".to_json());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"no property "missing" in object"#)]
+    fn indexing_panics_on_a_missing_property() {
+        let _ = &Json::object()["missing"];
+    }
+
+    #[test]
+    #[should_panic(expected = r#"cannot index into array with "name""#)]
+    fn indexing_panics_on_a_property_of_a_non_object() {
+        let _ = &Json::array()["name"];
+    }
+
+    #[test]
+    #[should_panic(expected = "index 2 out of bounds of array of length 2")]
+    fn indexing_panics_on_an_index_out_of_bounds() {
+        let _ = &vec![1, 2].to_json()[2];
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index into null with 0")]
+    fn indexing_panics_on_an_item_of_a_non_array() {
+        let _ = &Json::Null[0];
+    }
+
+    #[test]
+    fn mutable_indexing_builds_up_nested_values_from_null() {
+        let mut document = Json::Null;
+
+        document["a"]["b"] = 5.to_json();
+        document["a"]["list"][0] = "first".to_json();
+        document["a"]["list"][1]["c"] = true.to_json();
+        document["a"]["list"][0] = "replaced".to_json();
+
+        assert_eq!(
+            document,
+            parse(r#"{"a": {"b": 5, "list": ["replaced", {"c": true}]}}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn mutable_indexing_adds_a_missing_property_as_null() {
+        let mut object = Json::object().set("a", 1);
+
+        let added = &mut object["b"];
+        assert_eq!(added, &Json::Null);
+        assert_eq!(object, Json::object().set("a", 1).set("b", Json::Null));
+    }
+
+    #[test]
+    #[should_panic(expected = "index 2 out of bounds of array of length 1")]
+    fn mutable_indexing_only_appends_at_the_end_of_an_array() {
+        let mut array = Json::array();
+        array[0] = 1.to_json();
+        array[2] = 2.to_json();
+    }
+
+    #[test]
+    #[should_panic(expected = r#"cannot index into number with "a""#)]
+    fn mutable_indexing_panics_on_a_property_of_a_scalar() {
+        let mut number = 1.to_json();
+        number["a"] = Json::Null;
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index into object with 0")]
+    fn mutable_indexing_panics_on_an_item_of_an_object() {
+        let mut object = Json::object();
+        object[0] = Json::Null;
+    }
+
+    fn deeply_nested_array(depth: usize) -> Json {
+        let mut value = Json::array();
+        for _ in 1..depth {
+            value = Json::Array(vec![value, Json::Null]);
+        }
+        value
+    }
+
+    fn drop_deeply_nested_array(mut value: Json) {
+        while let Json::Array(mut items) = value {
+            value = if items.is_empty() {
+                Json::Null
+            } else {
+                items.swap_remove(0)
+            };
+        }
+    }
+
+    #[test]
+    fn depth_counts_the_containers_around_the_deepest_value() {
+        for (json, depth) in [
+            ("1", 0),
+            ("[]", 1),
+            ("[1, 2]", 1),
+            (r#"{"a": [1, {"b": []}], "c": 2}"#, 4),
+            (r#"[[[]], {}]"#, 3),
+        ] {
+            assert_eq!(parse(json).unwrap().depth(), depth, "{json}");
+        }
+    }
+
+    #[test]
+    fn node_count_counts_containers_and_scalars() {
+        for (json, count) in [
+            ("1", 1),
+            ("[]", 1),
+            ("[1, 2]", 3),
+            (r#"{"a": [1, {"b": []}], "c": 2}"#, 6),
+        ] {
+            assert_eq!(parse(json).unwrap().node_count(), count, "{json}");
+        }
+    }
+
+    #[test]
+    fn structural_metrics_work_on_deeply_nested_values() {
+        let value = deeply_nested_array(200_000);
+
+        assert_eq!(value.depth(), 200_000);
+        assert_eq!(value.node_count(), 2 * 200_000 - 1);
+        assert_eq!(
+            value.estimated_size_bytes(),
+            value.node_count() * mem::size_of::<Json>()
+        );
+
+        drop_deeply_nested_array(value);
+    }
+
+    #[test]
+    fn estimated_size_bytes_is_close_to_the_data_held() {
+        let strings = Json::Array(vec!["ten bytes!".to_json(); 1000]);
+        let held = 1000 * (10 + mem::size_of::<Json>());
+        let estimate = strings.estimated_size_bytes();
+        assert!(held <= estimate && estimate < 2 * held, "{estimate} {held}");
+
+        let object = Json::Object(
+            (0..1000)
+                .map(|i| (format!("key{i:05}"), i.to_json()))
+                .collect(),
+        );
+        let held = 1000 * (8 + mem::size_of::<Json>());
+        let estimate = object.estimated_size_bytes();
+        assert!(held <= estimate && estimate < 4 * held, "{estimate} {held}");
+
+        assert_eq!(Json::Null.estimated_size_bytes(), mem::size_of::<Json>());
+    }
+
+    fn hash_of(value: &Json) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Makes a value equal to the given one but built differently: with the
+    /// properties of its objects reversed, its zeros negated, its NaNs
+    /// given different bits and its big integers held as an `f64` if one
+    /// holds them exactly.
+    fn equal_but_different(value: &Json) -> Json {
+        match value {
+            Json::BigInteger(integer) => exact_f64(*integer).map_or(value.clone(), Json::Number),
+            Json::Number(number) if *number == 0.0 => Json::Number(-number),
+            Json::Number(number) if number.is_nan() => {
+                Json::Number(f64::from_bits(number.to_bits() ^ 0x8000_0000_0000_0001))
+            }
+            Json::Array(items) => Json::Array(items.iter().map(equal_but_different).collect()),
+            Json::Object(properties) => Json::Object(
+                properties
+                    .iter()
+                    .rev()
+                    .map(|(key, value)| (key.clone(), equal_but_different(value)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// Generates a pseudo-random value from a seed, favouring numbers which
+    /// are tricky to compare.
+    fn generated_value(seed: &mut u64, depth: u32) -> Json {
+        let mut next = || {
+            *seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            *seed >> 33
+        };
+        let numbers = [
+            0.0,
+            -0.0,
+            f64::NAN,
+            -f64::NAN,
+            f64::INFINITY,
+            1.0,
+            -1.5,
+            1e300,
+            f64::MIN_POSITIVE,
+        ];
+        let integers = [1 << 53, (1 << 53) + 1, -(1 << 63), i128::MAX, i128::MIN];
+
+        match next() % if depth == 0 { 5 } else { 7 } {
+            0 => Json::Null,
+            1 => (next() % 2 == 0).to_json(),
+            2 => ["", "a", "b"][next() as usize % 3].to_json(),
+            3 => numbers[next() as usize % numbers.len()].to_json(),
+            4 => match integers[next() as usize % integers.len()] {
+                integer if next() % 2 == 0 => Json::BigInteger(integer),
+                integer => Json::Number(integer as f64),
+            },
+            5 => Json::Array(
+                (0..next() % 4)
+                    .map(|_| generated_value(seed, depth - 1))
+                    .collect(),
+            ),
+            _ => Json::Object(
+                (0..next() % 4)
+                    .map(|i| (format!("k{i}"), generated_value(seed, depth - 1)))
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn every_value_is_equal_to_itself() {
+        for number in [f64::NAN, -f64::NAN, 0.0, f64::NEG_INFINITY] {
+            assert_eq!(number.to_json(), number.to_json());
+        }
+        assert_eq!(0.0.to_json(), (-0.0).to_json());
+        assert_ne!(1.0.to_json(), f64::NAN.to_json());
+        assert_ne!(Json::Null, 0.to_json());
+        assert_ne!(vec![1, 2].to_json(), vec![2, 1].to_json());
+    }
+
+    #[test]
+    fn equal_values_have_equal_hashes() {
+        let mut seed = 1;
+        for _ in 0..2000 {
+            let value = generated_value(&mut seed, 4);
+            let equal = equal_but_different(&value);
+
+            assert_eq!(value, equal);
+            assert_eq!(hash_of(&value), hash_of(&equal), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn values_can_be_deduplicated_in_a_hash_set() {
+        let values =
+            parse(r#"[{"a": 1, "b": [0]}, {"b": [-0], "a": 1.0}, {"a": 2}, null, null]"#).unwrap();
+
+        let unique: HashSet<_> = values.as_array().unwrap().iter().collect();
+
+        assert_eq!(unique.len(), 3);
+        assert!(unique.contains(&parse(r#"{"a": 2}"#).unwrap()));
+    }
+
+    #[test]
+    fn integer_accessors_return_only_exact_whole_numbers() {
+        let integers = |number: f64| {
+            let number = number.to_json();
+            (number.as_i64(), number.as_u64(), number.as_usize())
+        };
+
+        assert_eq!(integers(42.0), (Some(42), Some(42), Some(42)));
+        assert_eq!(integers(-1.0), (Some(-1), None, None));
+        assert_eq!(integers(1.5), (None, None, None));
+        assert_eq!(integers(f64::NAN), (None, None, None));
+        assert_eq!(integers(f64::INFINITY), (None, None, None));
+        assert_eq!(integers(f64::NEG_INFINITY), (None, None, None));
+        assert_eq!("1".to_json().as_i64(), None);
+        assert_eq!(Json::Null.as_u64(), None);
+    }
+
+    #[test]
+    fn integer_accessors_at_the_boundaries() {
+        let max_safe = 2f64.powi(53) - 1.0;
+
+        assert_eq!((-0.0).to_json().as_i64(), Some(0));
+        assert_eq!((-0.0).to_json().as_u64(), Some(0));
+        assert_eq!(max_safe.to_json().as_i64(), Some(9_007_199_254_740_991));
+        assert_eq!((-max_safe).to_json().as_i64(), Some(-9_007_199_254_740_991));
+        assert_eq!(max_safe.to_json().as_u64(), Some(9_007_199_254_740_991));
+        // 2^53 might have been rounded from 2^53 + 1, so isn't trusted.
+        assert_eq!(2f64.powi(53).to_json().as_i64(), None);
+        // The parser keeps larger integers exactly, so they are.
+        assert_eq!(
+            parse("9007199254740993").unwrap().as_i64(),
+            Some(9_007_199_254_740_993)
+        );
+        assert_eq!(
+            parse("-9223372036854775808").unwrap().as_i64(),
+            Some(i64::MIN)
+        );
+        assert_eq!(parse("9223372036854775808").unwrap().as_i64(), None);
+        assert_eq!(
+            parse("18446744073709551615").unwrap().as_u64(),
+            Some(u64::MAX)
+        );
+        assert_eq!(parse("-9007199254740993").unwrap().as_u64(), None);
+        assert_eq!((i64::MAX as f64).to_json().as_i64(), None);
+        assert_eq!((i64::MIN as f64).to_json().as_i64(), None);
+        assert_eq!((u64::MAX as f64).to_json().as_u64(), None);
+        assert_eq!(1e300.to_json().as_usize(), None);
+    }
+
+    #[test]
+    fn is_whole_number_agrees_with_fract() {
+        let numbers = [
+            0.0,
+            -0.0,
+            0.5,
+            -2.5,
+            1e-300,
+            4503599627370495.5,
+            4503599627370496.0,
+            9007199254740993.0,
+            -1e300,
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            f64::NAN,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ];
+
+        for number in numbers {
+            assert_eq!(is_whole_number(number), number.fract() == 0.0, "{number}");
+        }
+    }
+
+    #[test]
+    fn scalar_accessors_return_the_value_only_for_their_variant() {
+        let values = [
+            Json::Null,
+            true.to_json(),
+            "text".to_json(),
+            1.5.to_json(),
+            Json::array(),
+            Json::object(),
+        ];
+
+        let strings: Vec<_> = values.iter().map(Json::as_str).collect();
+        let numbers: Vec<_> = values.iter().map(Json::as_f64).collect();
+        let booleans: Vec<_> = values.iter().map(Json::as_bool).collect();
+
+        assert_eq!(strings, [None, None, Some("text"), None, None, None]);
+        assert_eq!(numbers, [None, None, None, Some(1.5), None, None]);
+        assert_eq!(booleans, [None, Some(true), None, None, None, None]);
+    }
+
+    #[test]
+    fn container_accessors_return_the_contents_only_for_their_variant() {
+        let mut array = vec![1, 2].to_json();
+        let mut object = Json::object().set("a", 1);
+
+        assert_eq!(array.as_array(), Some(&vec![1.to_json(), 2.to_json()]));
+        assert_eq!(array.as_object(), None);
+        assert_eq!(
+            object.as_object().and_then(|o| o.get("a")),
+            Some(&1.to_json())
+        );
+        assert_eq!(object.as_array(), None);
+        assert_eq!(Json::Null.as_array(), None);
+        assert_eq!("a".to_json().as_object(), None);
+
+        array.as_array_mut().unwrap().push(3.to_json());
+        object
+            .as_object_mut()
+            .unwrap()
+            .insert("b".to_owned(), 2.to_json());
+        assert_eq!(array, vec![1, 2, 3].to_json());
+        assert_eq!(object, Json::object().set("a", 1).set("b", 2));
+        assert_eq!(array.as_object_mut(), None);
+        assert_eq!(object.as_array_mut(), None);
+    }
+
+    #[test]
+    fn take_moves_a_subtree_out_leaving_null() {
+        let mut document = parse(r#"{"items": [1, 2], "name": "x"}"#).unwrap();
+
+        let items = document["items"].take();
+
+        assert_eq!(items, vec![1, 2].to_json());
+        assert_eq!(document, parse(r#"{"items": null, "name": "x"}"#).unwrap());
+        assert_eq!(document["items"].take(), Json::Null);
+    }
+
+    #[test]
+    fn into_conversions_move_the_contents_out_of_their_variant() {
+        let long = "x".repeat(10_000);
+        let mut document = Json::object()
+            .set("text", long.as_str())
+            .set("list", vec![1, 2])
+            .set("nested", Json::object().set("a", true));
+
+        assert_eq!(document["text"].take().into_string(), Ok(long));
+        assert_eq!(
+            document["list"].take().into_array(),
+            Ok(vec![1.to_json(), 2.to_json()])
+        );
+        let nested = document["nested"].take().into_object().unwrap();
+        assert_eq!(
+            nested.get("a").cloned().map(Json::into_bool),
+            Some(Ok(true))
+        );
+        assert_eq!(2.5.to_json().into_f64(), Ok(2.5));
+        assert_eq!(
+            document,
+            parse(r#"{"text": null, "list": null, "nested": null}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn into_conversions_give_back_values_of_other_types() {
+        let object = Json::object().set("a", 1);
+
+        assert_eq!(object.clone().into_array(), Err(object.clone()));
+        assert_eq!(
+            object.clone().into_string().unwrap_err().type_name(),
+            "object"
+        );
+        assert_eq!("1".to_json().into_f64(), Err("1".to_json()));
+        assert_eq!(Json::Null.into_bool(), Err(Json::Null));
+        assert_eq!(
+            vec![1].to_json().into_object().map(|map| map.len()),
+            Err(vec![1].to_json())
+        );
+    }
+
+    #[test]
+    fn accessors_combine_with_get_and_pointer() {
+        let document = parse(r#"{"name": "Ada", "langs": [{"year": 1843}]}"#).unwrap();
+
+        assert_eq!(document.get("name").and_then(Json::as_str), Some("Ada"));
+        assert_eq!(
+            document.pointer("/langs/0/year").and_then(Json::as_f64),
+            Some(1843.0)
+        );
+        assert_eq!(document.get("langs").and_then(Json::as_str), None);
+    }
+
+    #[test]
+    fn array_builders_can_be_chained() {
+        let document = Json::object()
+            .set("tags", Json::array().push("b").push("c").insert_at(0, "a"))
+            .set("matrix", Json::array().push(vec![1, 2]).push(Json::array()));
+
+        assert_eq!(
+            document,
+            parse(r#"{"tags": ["a", "b", "c"], "matrix": [[1, 2], []]}"#).unwrap()
+        );
+        assert_eq!(
+            document.get("tags").and_then(|tags| tags.get_index(2)),
+            Some(&"c".to_json())
+        );
+        assert_eq!(
+            document.get("matrix").and_then(|matrix| matrix.len()),
+            Some(2)
+        );
+        assert_eq!(document.len(), Some(2));
+    }
+
+    #[test]
+    fn array_helpers_leave_other_values_alone() {
+        assert_eq!(Json::object().try_push(1), Err(Json::object()));
+        assert_eq!(Json::array().try_push(1), Ok(vec![1].to_json()));
+        assert_eq!("a".to_json().push(1), "a".to_json());
+        assert_eq!(Json::Null.insert_at(0, 1), Json::Null);
+        assert_eq!(Json::object().get_index(0), None);
+        assert_eq!(vec![1].to_json().get_index(1), None);
+        assert_eq!(true.to_json().len(), None);
+        assert_eq!(true.to_json().is_empty(), None);
+        assert_eq!(Json::object().is_empty(), Some(true));
+    }
+
+    #[test]
+    fn try_set_sets_properties_of_objects_only() {
+        let type_error = |found| JsonTypeError {
+            expected: "object",
+            found,
+        };
+
+        assert_eq!(
+            Json::object()
+                .try_set("a", 1)
+                .and_then(|o| o.try_set("a", 2)),
+            Ok(Json::object().set("a", 2))
+        );
+        assert_eq!(Json::Null.try_set("a", 1), Err(type_error("null")));
+        assert_eq!(true.to_json().try_set("a", 1), Err(type_error("boolean")));
+        assert_eq!(1.to_json().try_set("a", 1), Err(type_error("number")));
+        assert_eq!("a".to_json().try_set("a", 1), Err(type_error("string")));
+        assert_eq!(Json::array().try_set("a", 1), Err(type_error("array")));
+        assert_eq!(
+            Json::Null.try_set("a", 1).unwrap_err().to_string(),
+            "ERROR: Invalid JSON type - expected object, found null"
+        );
+    }
+
+    #[test]
+    fn try_insert_changes_an_object_in_place() {
+        let mut object = Json::object().set("a", 1);
+        let mut array = Json::array();
+
+        assert_eq!(object.try_insert("a", 2), Ok(Some(1.to_json())));
+        assert_eq!(object.try_insert("b", 3), Ok(None));
+        assert_eq!(object, Json::object().set("a", 2).set("b", 3));
+        assert_eq!(
+            array.try_insert("a", 1),
+            Err(JsonTypeError {
+                expected: "object",
+                found: "array"
+            })
+        );
+        assert_eq!(array, Json::array());
+    }
+
+    #[test]
+    fn set_index_replaces_or_appends_items_of_arrays_only() {
+        let type_error = |found| JsonTypeError {
+            expected: "array",
+            found,
+        };
+
+        assert_eq!(
+            vec![1, 2].to_json().set_index(0, "a"),
+            Ok(vec!["a".to_json(), 2.to_json()].to_json())
+        );
+        assert_eq!(
+            Json::array()
+                .set_index(0, 1)
+                .and_then(|a| a.set_index(1, 2)),
+            Ok(vec![1, 2].to_json())
+        );
+        assert_eq!(Json::Null.set_index(0, 1), Err(type_error("null")));
+        assert_eq!(false.to_json().set_index(0, 1), Err(type_error("boolean")));
+        assert_eq!(0.to_json().set_index(0, 1), Err(type_error("number")));
+        assert_eq!("".to_json().set_index(0, 1), Err(type_error("string")));
+        assert_eq!(Json::object().set_index(0, 1), Err(type_error("object")));
+    }
+
+    #[test]
+    #[should_panic(expected = "index 2 out of bounds of array of length 1")]
+    fn set_index_panics_if_the_index_is_past_the_end() {
+        let _ = vec![1].to_json().set_index(2, true);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_at_panics_if_the_index_is_past_the_end() {
+        Json::array().insert_at(1, true);
+    }
+
+    #[test]
+    fn total_cmp_orders_values_by_type_then_value() {
+        let ordered = [
+            "null",
+            "false",
+            "true",
+            "-1",
+            "2.5",
+            r#""""#,
+            r#""a""#,
+            "[]",
+            "[1]",
+            "[1, 2]",
+            "[2]",
+            "{}",
+            r#"{"a": 1}"#,
+            r#"{"a": 2}"#,
+            r#"{"a": 2, "b": 1}"#,
+            r#"{"b": 0}"#,
+        ]
+        .map(|value| parse(value).unwrap());
+
+        for (i, a) in ordered.iter().enumerate() {
+            for (j, b) in ordered.iter().enumerate() {
+                assert_eq!(a.total_cmp(b), i.cmp(&j), "{a:?} {b:?}");
+            }
+        }
+        assert_eq!(
+            parse(r#"{"b": 1, "a": 2}"#)
+                .unwrap()
+                .total_cmp(&parse(r#"{"a": 2, "b": 1}"#).unwrap()),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn big_integers_compare_exactly_with_numbers() {
+        let big = |text: &str| parse(text).unwrap();
+        let two_to_the = |power: i32| 2f64.powi(power).to_json();
+
+        assert_eq!(big("9007199254740992"), two_to_the(53));
+        assert_eq!(hash_of(&big("9007199254740992")), hash_of(&two_to_the(53)));
+        assert_ne!(big("9007199254740993"), two_to_the(53));
+        assert!(big("9007199254740993") > two_to_the(53));
+        assert!(big("9007199254740993") < (2f64.powi(53) + 2.0).to_json());
+        assert!(big("-9007199254740993") < (-2f64.powi(53)).to_json());
+        assert!(big("170141183460469231731687303715884105727") < two_to_the(127));
+        assert!(big("-170141183460469231731687303715884105728") == (-2f64.powi(127)).to_json());
+        assert!(big("9007199254740993") < f64::NAN.to_json());
+        assert!(big("9007199254740993") < "".to_json());
+        assert_eq!(big("9007199254740993").type_name(), "number");
+    }
+
+    #[test]
+    fn ord_sorts_a_mixed_array_in_a_fixed_order() {
+        let mut values = parse(
+            r#"[{"b": 1}, "b", [1], 2, null, true, "", -1e300, {}, [], false, "a", [0, 5], 0]"#,
+        )
+        .unwrap()
+        .into_array()
+        .unwrap();
+        values.extend([f64::NAN, f64::NEG_INFINITY, -0.0].map(|number| number.to_json()));
+
+        values.sort();
+
+        assert_eq!(
+            values,
+            [
+                Json::Null,
+                false.to_json(),
+                true.to_json(),
+                f64::NEG_INFINITY.to_json(),
+                (-1e300).to_json(),
+                0.to_json(),
+                (-0.0).to_json(),
+                2.to_json(),
+                f64::NAN.to_json(),
+                "".to_json(),
+                "a".to_json(),
+                "b".to_json(),
+                Json::array(),
+                vec![0, 5].to_json(),
+                vec![1].to_json(),
+                Json::object(),
+                Json::object().set("b", 1),
+            ]
+        );
+        assert!("é".to_json() > "z".to_json());
+        assert!(Json::Null < false.to_json());
+    }
+
+    #[test]
+    fn ord_is_consistent_with_eq() {
+        let mut seed = 7;
+        let values: Vec<_> = (0..300).map(|_| generated_value(&mut seed, 3)).collect();
+
+        for a in &values {
+            assert_eq!(a.cmp(&equal_but_different(a)), Ordering::Equal, "{a:?}");
+            for b in &values[..30] {
+                assert_eq!(a.cmp(b) == Ordering::Equal, a == b, "{a:?} {b:?}");
+                assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{a:?} {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn values_can_be_keys_of_a_btree_map() {
+        let mut counts = BTreeMap::new();
+        for value in parse(r#"["x", 1, {"a": 1}, 1.0, "x", {"a": 1}, -0, 0]"#)
+            .unwrap()
+            .members()
+        {
+            *counts.entry(value.clone()).or_insert(0) += 1;
+        }
+
+        assert_eq!(
+            counts.into_iter().collect::<Vec<_>>(),
+            [
+                (0.to_json(), 2),
+                (1.to_json(), 2),
+                ("x".to_json(), 2),
+                (Json::object().set("a", 1), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_arrays_sorts_scalars_everywhere_in_the_value() {
+        let mut document =
+            parse(r#"{"tags": ["b", "c", "a"], "mixed": [{}, "x", 1, null, [], true]}"#).unwrap();
+
+        document.sort_arrays();
+
+        assert_eq!(
+            document,
+            parse(r#"{"tags": ["a", "b", "c"], "mixed": [null, true, 1, "x", [], {}]}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn sort_arrays_by_sorts_objects_by_a_field_stably() {
+        let mut document = parse(
+            r#"[
+                {"id": 2, "name": "b", "tags": ["y", "x"]},
+                {"id": 1, "name": "a"},
+                {"id": 2, "name": "c"}
+            ]"#,
+        )
+        .unwrap();
+
+        document.sort_arrays_by(|a, b| match (a.get("id"), b.get("id")) {
+            (Some(a), Some(b)) => a.total_cmp(b),
+            _ => a.total_cmp(b),
+        });
+
+        assert_eq!(
+            document,
+            parse(
+                r#"[
+                    {"id": 1, "name": "a"},
+                    {"id": 2, "name": "b", "tags": ["x", "y"]},
+                    {"id": 2, "name": "c"}
+                ]"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn sort_arrays_sorts_nested_arrays_before_their_parents() {
+        let mut document = parse("[[3, 1], [2, 4], [[2, 0], 1]]").unwrap();
+
+        document.sort_arrays();
+
+        assert_eq!(document, parse("[[1, 3], [1, [0, 2]], [2, 4]]").unwrap());
+    }
+
+    #[test]
+    fn map_values_transforms_every_leaf_and_keeps_the_structure() {
+        let document =
+            parse(r#"{"z": [" a ", 1.234, [], {}], "y": {"n": null, "b": true}}"#).unwrap();
+
+        let mapped = document.map_values(|value| match value {
+            Json::Null => "none".to_json(),
+            Json::Boolean(boolean) => (!boolean).to_json(),
+            other => other,
+        });
+
+        assert_eq!(
+            mapped,
+            parse(r#"{"z": [" a ", 1.234, [], {}], "y": {"n": "none", "b": false}}"#).unwrap()
+        );
+        assert_eq!(
+            mapped.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["z", "y"]
+        );
+    }
+
+    #[test]
+    fn map_values_with_the_identity_leaves_the_value_unchanged() {
+        let document = parse(r#"{"b": [1, "x", [[]], {"c": null}], "a": {}}"#).unwrap();
+
+        assert_eq!(document.clone().map_values(|value| value), document);
+        assert_eq!(Json::array().map_values(|_| Json::Null), Json::array());
+    }
+
+    #[test]
+    fn map_strings_and_map_numbers_only_touch_their_own_values() {
+        let document =
+            parse(r#"{"name": " Ada ", "year": "1843", "score": 9.876, "ok": true}"#).unwrap();
+
+        let mapped = document
+            .map_strings(|string| match string.parse::<f64>() {
+                Ok(number) => number.to_json(),
+                Err(_) => string.trim().to_json(),
+            })
+            .map_numbers(|number| ((number * 100.0).round() / 100.0).to_json());
+
+        assert_eq!(
+            mapped,
+            parse(r#"{"name": "Ada", "year": 1843, "score": 9.88, "ok": true}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn compact_removes_nulls_and_the_containers_they_leave_empty() {
+        let document = parse(
+            r#"{
+                "keep": {"a": 1, "b": null},
+                "cascade": {"child": {"grandchild": {"leaf": null}}, "list": [{"x": null}, []]},
+                "list": [null, {}, "", 0, false, [null]],
+                "empty": ""
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            document.compact(),
+            parse(r#"{"keep": {"a": 1}, "list": [null, "", 0, false, [null]], "empty": ""}"#)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn compact_with_can_remove_nulls_in_arrays_and_empty_strings() {
+        let document =
+            parse(r#"{"list": [null, "", 0, [null, ""]], "empty": "", "nested": {"s": ""}}"#)
+                .unwrap();
+        let options = |drop_nulls_in_arrays, drop_empty_strings| CompactOptions {
+            drop_nulls_in_arrays,
+            drop_empty_strings,
+        };
+
+        assert_eq!(
+            document.clone().compact_with(options(true, false)),
+            parse(r#"{"list": ["", 0, [""]], "empty": "", "nested": {"s": ""}}"#).unwrap()
+        );
+        assert_eq!(
+            document.clone().compact_with(options(false, true)),
+            parse(r#"{"list": [null, 0, [null]]}"#).unwrap()
+        );
+        assert_eq!(
+            document.compact_with(options(true, true)),
+            parse(r#"{"list": [0]}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn compact_keeps_the_value_itself_even_if_it_is_left_empty() {
+        assert_eq!(
+            parse(r#"{"a": {"b": null}}"#).unwrap().compact(),
+            Json::object()
+        );
+        assert_eq!(parse("[[], {}]").unwrap().compact(), Json::array());
+        assert_eq!(Json::Null.compact(), Json::Null);
+        assert_eq!("".to_json().compact(), "".to_json());
+    }
+
+    #[test]
+    fn concat_appends_one_array_to_another() {
+        assert_eq!(
+            vec![1, 2]
+                .to_json()
+                .concat(parse(r#"[[3], {"a": 4}]"#).unwrap()),
+            Ok(parse(r#"[1, 2, [3], {"a": 4}]"#).unwrap())
+        );
+        assert_eq!(
+            Json::array().concat(Json::object()),
+            Err(CombineError {
+                expected: "array",
+                found: ("array", "object")
+            })
+        );
+        assert_eq!(
+            "a".to_json().concat(Json::Null).unwrap_err().to_string(),
+            "ERROR: Cannot combine JSON - expected two arrays, found string and null"
+        );
+    }
+
+    #[test]
+    fn union_replaces_top_level_properties_rather_than_merging_them() {
+        let left = parse(r#"{"a": 1, "nested": {"x": 1, "y": 2}, "b": 2}"#).unwrap();
+        let right = parse(r#"{"nested": {"x": 10}, "c": 3, "a": null}"#).unwrap();
+
+        let union = left.clone().union(right.clone()).unwrap();
+        assert_eq!(
+            union,
+            parse(r#"{"a": null, "nested": {"x": 10}, "b": 2, "c": 3}"#).unwrap()
+        );
+        assert_eq!(
+            union.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["a", "nested", "b", "c"]
+        );
+
+        let mut merged = left;
+        merged.merge_patch(&right);
+        assert_eq!(
+            merged,
+            parse(r#"{"nested": {"x": 10, "y": 2}, "b": 2, "c": 3}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn union_with_resolves_conflicts_with_the_given_function() {
+        let left = parse(r#"{"tags": ["a"], "count": 1, "only_left": true}"#).unwrap();
+        let right = parse(r#"{"tags": ["b", "c"], "count": 2}"#).unwrap();
+
+        let union = left.union_with(right, |left, right| {
+            match left.clone().concat(right.clone()) {
+                Ok(concatenated) => concatenated,
+                Err(_) => left,
+            }
+        });
+
+        assert_eq!(
+            union,
+            Ok(parse(r#"{"tags": ["a", "b", "c"], "count": 1, "only_left": true}"#).unwrap())
+        );
+        assert_eq!(
+            Json::object().union(vec![1].to_json()),
+            Err(CombineError {
+                expected: "object",
+                found: ("object", "array")
+            })
+        );
+    }
+
+    #[test]
+    fn merge_patch_passes_the_rfc_7386_examples() {
+        let examples = [
+            (r#"{"a":"b"}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"b":"c"}"#, r#"{"a":"b","b":"c"}"#),
+            (r#"{"a":"b"}"#, r#"{"a":null}"#, r#"{}"#),
+            (r#"{"a":"b","b":"c"}"#, r#"{"a":null}"#, r#"{"b":"c"}"#),
+            (r#"{"a":["b"]}"#, r#"{"a":"c"}"#, r#"{"a":"c"}"#),
+            (r#"{"a":"c"}"#, r#"{"a":["b"]}"#, r#"{"a":["b"]}"#),
+            (
+                r#"{"a":{"b":"c"}}"#,
+                r#"{"a":{"b":"d","c":null}}"#,
+                r#"{"a":{"b":"d"}}"#,
+            ),
+            (r#"{"a":[{"b":"c"}]}"#, r#"{"a":[1]}"#, r#"{"a":[1]}"#),
+            (r#"["a","b"]"#, r#"["c","d"]"#, r#"["c","d"]"#),
+            (r#"{"a":"b"}"#, r#"["c"]"#, r#"["c"]"#),
+            (r#"{"a":"foo"}"#, r#"null"#, r#"null"#),
+            (r#"{"a":"foo"}"#, r#""bar""#, r#""bar""#),
+            (r#"{"e":null}"#, r#"{"a":1}"#, r#"{"e":null,"a":1}"#),
+            (r#"[1,2]"#, r#"{"a":"b","c":null}"#, r#"{"a":"b"}"#),
+            (
+                r#"{}"#,
+                r#"{"a":{"bb":{"ccc":null}}}"#,
+                r#"{"a":{"bb":{}}}"#,
+            ),
+        ];
+
+        for (target, patch, result) in examples {
+            let mut patched = parse(target).unwrap();
+            patched.merge_patch(&parse(patch).unwrap());
+            assert_eq!(patched, parse(result).unwrap(), "{target} {patch}");
+        }
+    }
+
+    #[test]
+    fn merge_patch_updates_the_rfc_7386_document() {
+        let mut document = parse(
+            r#"{
+                "title": "Goodbye!",
+                "author": {"givenName": "John", "familyName": "Doe"},
+                "tags": ["example", "sample"],
+                "content": "This will be unchanged"
+            }"#,
+        )
+        .unwrap();
+        let patch = parse(
+            r#"{
+                "title": "Hello!",
+                "phoneNumber": "+01-123-456-7890",
+                "author": {"familyName": null},
+                "tags": ["example"]
+            }"#,
+        )
+        .unwrap();
+
+        document.merge_patch(&patch);
+
+        assert_eq!(
+            document,
+            parse(
+                r#"{
+                    "title": "Hello!",
+                    "author": {"givenName": "John"},
+                    "tags": ["example"],
+                    "content": "This will be unchanged",
+                    "phoneNumber": "+01-123-456-7890"
+                }"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn remove_takes_a_property_out_of_an_object() {
+        let mut object = parse(r#"{"a": 1, "b": 2, "c": 3}"#).unwrap();
+
+        assert_eq!(object.remove("b"), Some(2.to_json()));
+        assert_eq!(object.remove("b"), None);
+        assert_eq!(object, parse(r#"{"a": 1, "c": 3}"#).unwrap());
+        assert_eq!("b".to_json().remove("b"), None);
+    }
+
+    #[test]
+    fn dedup_keeps_the_first_of_each_equal_value() {
+        let mut array = parse(
+            r#"[
+                null, 1, "a", true, [1, {"x": 1}], {"a": {"b": [1, 2]}},
+                null, 1.0, "a", false, true, [1, {"x": 1}], {"a": {"b": [1, 3]}},
+                {"a": {"b": [1, 2]}}, -0, 0, "A"
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(array.dedup(), Some(7));
+        assert_eq!(
+            array,
+            parse(
+                r#"[
+                    null, 1, "a", true, [1, {"x": 1}], {"a": {"b": [1, 2]}},
+                    false, {"a": {"b": [1, 3]}}, -0, "A"
+                ]"#
+            )
+            .unwrap()
+        );
+        assert_eq!(array.dedup(), Some(0));
+        assert_eq!(Json::object().dedup(), None);
+    }
+
+    #[test]
+    fn dedup_by_key_compares_one_property_of_each_object() {
+        let mut array = parse(
+            r#"[
+                {"id": 1, "v": "first"}, {"id": 2}, {"id": 1, "v": "second"},
+                {"v": "no id"}, {"v": "no id"}, "text", "text", {"id": {"n": 1}}, {"id": {"n": 1}}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(array.dedup_by_key("id"), Some(2));
+        assert_eq!(
+            array,
+            parse(
+                r#"[
+                    {"id": 1, "v": "first"}, {"id": 2},
+                    {"v": "no id"}, {"v": "no id"}, "text", "text", {"id": {"n": 1}}
+                ]"#
+            )
+            .unwrap()
+        );
+        assert_eq!("text".to_json().dedup_by_key("id"), None);
+    }
+
+    #[test]
+    fn remove_index_shifts_the_later_items_down() {
+        let mut array = vec![1, 2, 3].to_json();
+
+        assert_eq!(array.remove_index(0), Some(1.to_json()));
+        assert_eq!(array.remove_index(2), None);
+        assert_eq!(array, vec![2, 3].to_json());
+        assert_eq!(Json::object().remove_index(0), None);
+    }
+
+    #[test]
+    fn remove_path_removes_the_last_token_from_its_parent() {
+        let mut document = parse(r#"{"a": [1, {"b": 2, "c": 3}], "": {"": 4}}"#).unwrap();
+
+        assert_eq!(document.remove_path("/a/1/b"), Ok(2.to_json()));
+        assert_eq!(document.remove_path("/a/0"), Ok(1.to_json()));
+        assert_eq!(document.remove_path("//"), Ok(4.to_json()));
+        assert_eq!(document, parse(r#"{"a": [{"c": 3}], "": {}}"#).unwrap());
+    }
+
+    #[test]
+    fn remove_path_fails_if_there_is_nothing_to_remove() {
+        let mut document = parse(r#"{"a": [1], "s": "text"}"#).unwrap();
+        let original = document.clone();
+
+        for (pointer, error) in [
+            ("", JsonPathError::InvalidPointer("".to_owned())),
+            ("a", JsonPathError::InvalidPointer("a".to_owned())),
+            ("/x", JsonPathError::NotFound("/x".to_owned())),
+            ("/x/y", JsonPathError::NotFound("/x".to_owned())),
+            ("/a/1", JsonPathError::NotFound("/a/1".to_owned())),
+            ("/a/-", JsonPathError::InvalidIndex("/a".to_owned())),
+            ("/s/x", JsonPathError::NotAContainer("/s".to_owned())),
+        ] {
+            assert_eq!(document.remove_path(pointer), Err(error), "{pointer}");
+        }
+        assert_eq!(document, original);
+    }
+
+    #[test]
+    fn set_can_be_chained_to_construct_an_object() {
+        assert_eq!(
+            Json::object().set("foo", "bar").set("baz", 123),
+            [("foo", "bar".to_json()), ("baz", 123.to_json())].to_json()
+        );
+    }
+
+    #[test]
+    fn set_has_no_effect_on_a_non_object() {
+        assert_eq!(Json::Null.set("foo", "bar"), Json::Null);
+        assert_eq!("hello".to_json().set("foo", "bar"), "hello".to_json());
+        assert_eq!(123.to_json().set("foo", "bar"), 123.to_json());
+        assert_eq!(true.to_json().set("foo", "bar"), true.to_json());
+        assert_eq!([1, 2, 3].to_json().set("foo", "bar"), [1, 2, 3].to_json());
+    }
+
+    #[test]
+    fn set_opt_leaves_out_properties_which_are_none() {
+        let nickname: Option<&str> = None;
+
+        let object = Json::object()
+            .set_opt("name", Some("ada"))
+            .set_opt("nickname", nickname)
+            .set("email", None::<&str>);
+
+        assert_eq!(object.get("name"), Some(&"ada".to_json()));
+        assert_eq!(object.get("nickname"), None);
+        assert_eq!(object.keys().collect::<Vec<_>>(), ["name", "email"]);
+        assert_eq!(object.get("email"), Some(&Json::Null));
+        assert_eq!(
+            Json::object().set("a", 1).set_opt("a", None::<i32>),
+            Json::object().set("a", 1)
+        );
     }
 }
@@ -0,0 +1,305 @@
+//! Encoding binary data as base64 text, as RFC 4648 defines, for carrying it
+//! in JSON strings.
+
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::{
+    ast::Json,
+    dsl::{FromJson, FromJsonError, ToJson},
+};
+
+/// The characters used for the values 62 and 63, and whether the text is
+/// padded with `=` to a multiple of four characters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Alphabet {
+    /// `+` and `/`, with padding.
+    #[default]
+    Standard,
+    /// `-` and `_`, without padding, so the text can go in URLs and file
+    /// names unescaped.
+    UrlSafe,
+}
+
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes bytes as base64 text in the alphabet.
+pub fn encode(bytes: &[u8], alphabet: Alphabet) -> String {
+    let table = match alphabet {
+        Alphabet::Standard => STANDARD,
+        Alphabet::UrlSafe => URL_SAFE,
+    };
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0, |group, (index, &byte)| {
+            group | u32::from(byte) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = group >> (18 - 6 * index) & 0x3f;
+                text.push(char::from(table[sextet as usize]));
+            } else if alphabet == Alphabet::Standard {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// Decodes text in either alphabet, with or without padding.
+pub fn decode(text: &str) -> Result<Vec<u8>, Base64Error> {
+    let unpadded = text
+        .strip_suffix("==")
+        .or_else(|| text.strip_suffix('='))
+        .unwrap_or(text);
+    let mut bytes = Vec::with_capacity(unpadded.len() / 4 * 3 + 2);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for (index, byte) in unpadded.bytes().enumerate() {
+        let sextet = sextet(byte).ok_or_else(|| Base64Error::InvalidCharacter {
+            index,
+            character: text[index..].chars().next().unwrap_or_default(),
+        })?;
+        buffer = (buffer << 6 | u32::from(sextet)) & 0xfff;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+
+    let is_padded = unpadded.len() < text.len();
+    if (is_padded && !text.len().is_multiple_of(4)) || unpadded.len() % 4 == 1 {
+        return Err(Base64Error::InvalidLength(text.len()));
+    }
+    Ok(bytes)
+}
+
+fn sextet(byte: u8) -> Option<u8> {
+    match byte {
+        b'A'..=b'Z' => Some(byte - b'A'),
+        b'a'..=b'z' => Some(byte - b'a' + 26),
+        b'0'..=b'9' => Some(byte - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    }
+}
+
+/// An error from decoding text which isn't base64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Base64Error {
+    /// A character which isn't in either alphabet, or is padding before the
+    /// end, at this byte index of the text.
+    InvalidCharacter { index: usize, character: char },
+    /// The text has this length, which no bytes encode to.
+    InvalidLength(usize),
+}
+
+impl Base64Error {
+    fn reason(&self) -> String {
+        match self {
+            Base64Error::InvalidCharacter { index, character } => {
+                format!("invalid character {character:?} at index {index}")
+            }
+            Base64Error::InvalidLength(length) => format!("invalid length {length}"),
+        }
+    }
+}
+
+impl fmt::Display for Base64Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_fmt(format_args!("ERROR: Invalid base64 - {}", self.reason()))
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for Base64Error {}
+
+/// Bytes to convert to a base64 string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bytes<'a> {
+    pub bytes: &'a [u8],
+    pub alphabet: Alphabet,
+}
+
+impl<'a> Bytes<'a> {
+    pub fn new(bytes: &'a [u8]) -> Bytes<'a> {
+        Bytes {
+            bytes,
+            alphabet: Alphabet::Standard,
+        }
+    }
+
+    pub fn url_safe(bytes: &'a [u8]) -> Bytes<'a> {
+        Bytes {
+            bytes,
+            alphabet: Alphabet::UrlSafe,
+        }
+    }
+}
+
+impl ToJson for Bytes<'_> {
+    fn to_json(&self) -> Json {
+        Json::String(encode(self.bytes, self.alphabet))
+    }
+}
+
+/// Like `Bytes`, but owning the bytes, so it can also be converted from a
+/// base64 string. That accepts either alphabet, and gives the standard one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BytesBuf {
+    pub bytes: Vec<u8>,
+    pub alphabet: Alphabet,
+}
+
+impl BytesBuf {
+    pub fn new(bytes: Vec<u8>) -> BytesBuf {
+        BytesBuf {
+            bytes,
+            alphabet: Alphabet::Standard,
+        }
+    }
+
+    pub fn url_safe(bytes: Vec<u8>) -> BytesBuf {
+        BytesBuf {
+            bytes,
+            alphabet: Alphabet::UrlSafe,
+        }
+    }
+}
+
+impl ToJson for BytesBuf {
+    fn to_json(&self) -> Json {
+        Json::String(encode(&self.bytes, self.alphabet))
+    }
+}
+
+impl FromJson for BytesBuf {
+    fn from_json(json: &Json) -> Result<BytesBuf, FromJsonError> {
+        let text = json
+            .as_str()
+            .ok_or_else(|| FromJsonError::new(json, "base64 string"))?;
+        decode(text)
+            .map(BytesBuf::new)
+            .map_err(|error| FromJsonError {
+                pointer: String::new(),
+                expected: "base64 string".to_owned(),
+                found: format!("{text:?} ({})", error.reason()),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        base64::{decode, encode, Alphabet, Base64Error, Bytes, BytesBuf},
+        dsl::{FromJson, ToJson},
+        from_json_str,
+    };
+
+    /// Bytes from a xorshift generator, which are the same on every run.
+    fn random_bytes(length: usize) -> Vec<u8> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_encodes_the_rfc_4648_test_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+
+        for (bytes, text) in vectors {
+            assert_eq!(encode(bytes.as_bytes(), Alphabet::Standard), text);
+            assert_eq!(decode(text).unwrap(), bytes.as_bytes());
+            assert_eq!(
+                encode(bytes.as_bytes(), Alphabet::UrlSafe),
+                text.trim_end_matches('=')
+            );
+        }
+    }
+
+    #[test]
+    fn the_alphabets_differ_in_the_last_two_characters() {
+        let bytes = [0xfb, 0xff, 0xbf];
+
+        assert_eq!(Bytes::new(&bytes).to_json(), "+/+/".to_json());
+        assert_eq!(Bytes::url_safe(&bytes).to_json(), "-_-_".to_json());
+        assert_eq!(
+            decode("+/+/-_-_").unwrap(),
+            [0xfb, 0xff, 0xbf, 0xfb, 0xff, 0xbf]
+        );
+    }
+
+    #[test]
+    fn bytes_round_trip_through_json() {
+        for length in [0, 1, 2, 3, 4, 5, 6, 4096, 4097, 4098] {
+            let bytes = random_bytes(length);
+            for buffer in [
+                BytesBuf::new(bytes.clone()),
+                BytesBuf::url_safe(bytes.clone()),
+            ] {
+                let json = buffer.to_json();
+
+                assert_eq!(
+                    json,
+                    Bytes {
+                        bytes: &bytes,
+                        alphabet: buffer.alphabet
+                    }
+                    .to_json()
+                );
+                assert_eq!(BytesBuf::from_json(&json).unwrap().bytes, bytes, "{length}");
+            }
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_characters_and_lengths() {
+        let invalid_character =
+            |index, character| Base64Error::InvalidCharacter { index, character };
+
+        assert_eq!(decode("Zm9v!mFy"), Err(invalid_character(4, '!')));
+        assert_eq!(decode("Zm9v YmFy"), Err(invalid_character(4, ' ')));
+        assert_eq!(decode("Zg=a"), Err(invalid_character(2, '=')));
+        assert_eq!(decode("Z==="), Err(invalid_character(1, '=')));
+        assert_eq!(decode("Zm9vé"), Err(invalid_character(4, 'é')));
+        assert_eq!(decode("Zm9vY"), Err(Base64Error::InvalidLength(5)));
+        assert_eq!(decode("Zg="), Err(Base64Error::InvalidLength(3)));
+        assert_eq!(
+            decode("Zm9v!").unwrap_err().to_string(),
+            "ERROR: Invalid base64 - invalid character '!' at index 4"
+        );
+    }
+
+    #[test]
+    fn from_json_errors_point_at_the_invalid_string() {
+        let error = from_json_str::<Vec<BytesBuf>>(r#"["Zm8=", "Zm.v"]"#).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            r#"ERROR: Cannot convert JSON - expected base64 string at /1, found "Zm.v" (invalid character '.' at index 2)"#
+        );
+        assert_eq!(
+            BytesBuf::from_json(&Json::Null).unwrap_err().to_string(),
+            "ERROR: Cannot convert JSON - expected base64 string at the root, found null"
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! Canonical JSON output, following the JSON Canonicalization Scheme (JCS) of
+//! RFC 8785, for producing byte-stable output to sign or hash.
+
+use alloc::{format, string::String};
+use core::fmt::{self, Write};
+
+use crate::{
+    ast::Json,
+    printer::{json_to_string_with_options, KeyOrder, PrintError, PrintOptions, PrintStyle},
+    sha256::sha256,
+};
+
+/// Formats a value in the canonical form defined by RFC 8785: no whitespace,
+/// object keys sorted by their UTF-16 code units, only the escapes JSON
+/// requires, and numbers formatted like ECMAScript's `Number.prototype.toString`.
+/// Fails on NaN and infinities, which have no canonical form.
+pub fn json_to_canonical_string(value: &Json) -> Result<String, PrintError> {
+    let mut options = PrintOptions::new()
+        .style(PrintStyle::Compact)
+        .key_order(KeyOrder::custom(|a, b| {
+            a.encode_utf16().cmp(b.encode_utf16())
+        }));
+    options.canonical = true;
+    json_to_string_with_options(value, &options)
+}
+
+impl Json {
+    /// The SHA-256 digest of the value's canonical form, which is the same for
+    /// every value equal to it however it was formatted or its keys ordered.
+    /// Fails on NaN and infinities, which have no canonical form.
+    pub fn canonical_hash(&self) -> Result<[u8; 32], PrintError> {
+        json_to_canonical_string(self).map(|canonical| sha256(canonical.as_bytes()))
+    }
+
+    /// The `canonical_hash` as 64 lowercase hex digits.
+    pub fn canonical_hash_hex(&self) -> Result<String, PrintError> {
+        let mut hex = String::with_capacity(64);
+        for byte in self.canonical_hash()? {
+            hex.write_fmt(format_args!("{byte:02x}"))
+                .map_err(|_| PrintError::Write)?;
+        }
+        Ok(hex)
+    }
+}
+
+/// Writes a finite number in the shortest form which parses back to it, using
+/// the layout ECMAScript uses: plain digits for magnitudes from 1e-6 up to
+/// 1e21, and an exponent like `1e+21` or `1.5e-7` outside that range.
+pub(crate) fn display_number<W: Write>(number: f64, output: &mut W) -> Result<(), fmt::Error> {
+    if number == 0.0 {
+        return output.write_char('0');
+    }
+    if number < 0.0 {
+        output.write_char('-')?;
+    }
+
+    // Rust's exponent form gives the shortest digits which round-trip, as
+    // `d.ddde-7`, but where two are equally short it may not pick the closest
+    // to the exact value, rounding ties to even, as ECMAScript requires. Rounding
+    // the exact value to that many digits does, if the result still round-trips.
+    let shortest = format!("{:e}", number.abs());
+    let significant_digits = shortest.find('e').map_or(1, |end| end.max(2) - 1);
+    let precision = significant_digits - 1;
+    let closest = format!("{:.precision$e}", number.abs());
+    let scientific = if closest.parse() == Ok(number.abs()) {
+        closest
+    } else {
+        shortest
+    };
+
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .expect("Rust formats floats with an exponent");
+    let digits = mantissa.replace('.', "");
+    let exponent: i32 = exponent.parse().expect("The exponent is an integer");
+
+    // The value is 0.digits × 10^point.
+    let point = exponent + 1;
+    let digit_count = digits.len() as i32;
+
+    if digit_count <= point && point <= 21 {
+        output.write_str(&digits)?;
+        (digit_count..point).try_for_each(|_| output.write_char('0'))
+    } else if 0 < point && point <= 21 {
+        let (whole, fraction) = digits.split_at(point as usize);
+        output.write_fmt(format_args!("{whole}.{fraction}"))
+    } else if -6 < point && point <= 0 {
+        output.write_str("0.")?;
+        (point..0).try_for_each(|_| output.write_char('0'))?;
+        output.write_str(&digits)
+    } else {
+        let (first, rest) = digits.split_at(1);
+        let sign = if exponent < 0 { '-' } else { '+' };
+        output.write_str(first)?;
+        if !rest.is_empty() {
+            output.write_fmt(format_args!(".{rest}"))?;
+        }
+        output.write_fmt(format_args!("e{sign}{}", exponent.abs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        canonical::{display_number, json_to_canonical_string},
+        dsl::ToJson,
+        parser::parse,
+        printer::PrintError,
+    };
+
+    fn canonical_number(bits: u64) -> String {
+        let mut output = String::new();
+        display_number(f64::from_bits(bits), &mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn it_formats_the_numbers_from_the_rfc_appendix() {
+        for (bits, expected) in [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ] {
+            assert_eq!(canonical_number(bits), expected, "{bits:#018x}");
+        }
+    }
+
+    #[test]
+    fn it_rejects_the_non_finite_numbers_from_the_rfc_appendix() {
+        for bits in [0x7fffffffffffffff, 0x7ff0000000000000] {
+            assert!(matches!(
+                json_to_canonical_string(&[f64::from_bits(bits)].to_json()),
+                Err(PrintError::NonFiniteNumber(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn it_canonicalizes_the_rfc_example() {
+        let value = parse(
+            r#"{
+                "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+                "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+                "literals": [null, true, false]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            json_to_canonical_string(&value).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+
+    #[test]
+    fn it_sorts_keys_by_their_utf16_code_units() {
+        let value = parse(
+            r#"{
+                "€": "Euro Sign",
+                "\r": "Carriage Return",
+                "דּ": "Hebrew Letter Dalet With Dagesh",
+                "1": "One",
+                "😀": "Emoji: Grinning Face",
+                "\u0080": "Control",
+                "ö": "Latin Small Letter O With Diaeresis"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            json_to_canonical_string(&value).unwrap(),
+            concat!(
+                r#"{"\r":"Carriage Return","1":"One","#,
+                "\"\u{80}\":\"Control\",",
+                "\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",",
+                "\"\u{20ac}\":\"Euro Sign\",",
+                "\"\u{1f600}\":\"Emoji: Grinning Face\",",
+                "\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}",
+            )
+        );
+    }
+
+    #[test]
+    fn equal_values_have_the_same_hash_however_they_are_formatted() {
+        let compact = parse(r#"{"b":[1,{"y":null,"x":"é"}],"a":1.50}"#).unwrap();
+        let spaced = parse(
+            r#"{
+                "a" : 1.5,
+                "b" : [ 1e0, { "x" : "é", "y" : null } ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(compact.canonical_hash(), spaced.canonical_hash());
+        assert_ne!(
+            compact.canonical_hash(),
+            parse(r#"{"a":1.5,"b":[{"y":null,"x":"é"},1]}"#)
+                .unwrap()
+                .canonical_hash()
+        );
+    }
+
+    #[test]
+    fn it_hashes_the_canonical_form_with_sha256() {
+        assert_eq!(
+            parse(r#"{"b": [true, null, "x"], "a": 1}"#)
+                .unwrap()
+                .canonical_hash_hex(),
+            Ok("eca8cfb31ab74533e1eb2f4c74d2d55dfe3c79ac704787e54be8647ea7777eb1".to_owned())
+        );
+        assert_eq!(
+            Json::Null.canonical_hash_hex(),
+            Ok("74234e98afe7498fb5daf1f36ac2d78acc339464f950703b8c019892f982b90b".to_owned())
+        );
+        assert_eq!(
+            parse(r#"{"é": 0.00000015, "": "\u0000"}"#)
+                .unwrap()
+                .canonical_hash_hex(),
+            Ok("2c86d8d6fe7914b17c72cb7d06dbc539a0231097528f1fccdc57a0f16d04788d".to_owned())
+        );
+        assert_eq!(
+            Json::Null.canonical_hash().map(|hash| hash[..4].to_vec()),
+            Ok(vec![0x74, 0x23, 0x4e, 0x98])
+        );
+        assert!(matches!(
+            [f64::NAN].to_json().canonical_hash(),
+            Err(PrintError::NonFiniteNumber(_))
+        ));
+    }
+}
@@ -0,0 +1,709 @@
+//! Encoding values as CBOR, the binary format of RFC 8949, and decoding the
+//! part of CBOR which has an equivalent in JSON.
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt, str};
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::{
+    ast::{integer_to_json, is_whole_number, Json},
+    base64::{self, Alphabet},
+    map::Map,
+};
+
+/// An error from decoding CBOR which isn't well-formed, or has no equivalent
+/// in JSON. The offsets are of the first byte of the item in the input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CborError {
+    /// The input ended in the middle of an item.
+    UnexpectedEnd,
+    /// A complete item was followed by more bytes, from this offset.
+    TrailingBytes(usize),
+    /// An item has the tag, which JSON has no equivalent for.
+    Tag { offset: usize, tag: u64 },
+    /// A byte string, which is only decoded with `byte_strings_as_base64`.
+    ByteString(usize),
+    /// A map key which isn't a text string.
+    NonStringKey(usize),
+    /// A simple value other than `false`, `true` and `null`, such as
+    /// `undefined`.
+    SimpleValue { offset: usize, value: u8 },
+    /// A text string which isn't valid UTF-8.
+    InvalidUtf8(usize),
+    /// An item which isn't well-formed, such as a `break` outside an item
+    /// of indefinite length.
+    Malformed(usize),
+    /// An array or map nested inside more than `MAX_DEPTH` others.
+    TooDeep(usize),
+}
+
+impl fmt::Display for CborError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("ERROR: Invalid CBOR - ")?;
+        match self {
+            CborError::UnexpectedEnd => formatter.write_str("the input ended inside an item"),
+            CborError::TrailingBytes(offset) => {
+                formatter.write_fmt(format_args!("unexpected bytes after the item at {offset}"))
+            }
+            CborError::Tag { offset, tag } => {
+                formatter.write_fmt(format_args!("unsupported tag {tag} at {offset}"))
+            }
+            CborError::ByteString(offset) => {
+                formatter.write_fmt(format_args!("unsupported byte string at {offset}"))
+            }
+            CborError::NonStringKey(offset) => {
+                formatter.write_fmt(format_args!("map key which isn't a string at {offset}"))
+            }
+            CborError::SimpleValue { offset, value } => {
+                formatter.write_fmt(format_args!("unsupported simple value {value} at {offset}"))
+            }
+            CborError::InvalidUtf8(offset) => {
+                formatter.write_fmt(format_args!("text string which isn't UTF-8 at {offset}"))
+            }
+            CborError::Malformed(offset) => {
+                formatter.write_fmt(format_args!("malformed item at {offset}"))
+            }
+            CborError::TooDeep(offset) => formatter.write_fmt(format_args!(
+                "item nested more than {MAX_DEPTH} deep at {offset}"
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for CborError {}
+
+/// How many arrays and maps `cbor_to_json` reads inside each other before
+/// failing, rather than running out of stack.
+pub const MAX_DEPTH: usize = 256;
+
+/// Ways of decoding CBOR which has no equivalent in JSON, rather than failing.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CborOptions {
+    /// Decode byte strings as base64 strings in the alphabet.
+    pub byte_strings_as_base64: Option<Alphabet>,
+}
+
+/// Encodes a value as CBOR, with definite lengths and properties in the map's
+/// order. Whole numbers from -2^64 to 2^64 - 1 are integers in the shortest
+/// form, and other numbers, including `-0`, are 64-bit floats.
+pub fn json_to_cbor(value: &Json) -> Vec<u8> {
+    let mut output = Vec::new();
+    encode(value, &mut output);
+    output
+}
+
+fn encode(value: &Json, output: &mut Vec<u8>) {
+    match value {
+        Json::Null => output.push(0xf6),
+        Json::Boolean(false) => output.push(0xf4),
+        Json::Boolean(true) => output.push(0xf5),
+        Json::Number(number) => encode_number(*number, output),
+        Json::BigInteger(integer) => {
+            if !encode_integer(*integer, output) {
+                encode_number(*integer as f64, output);
+            }
+        }
+        Json::String(string) => encode_string(string, output),
+        Json::Array(items) => {
+            encode_head(4, items.len() as u64, output);
+            for item in items {
+                encode(item, output);
+            }
+        }
+        Json::Object(properties) => {
+            encode_head(5, properties.len() as u64, output);
+            for (key, value) in properties {
+                encode_string(key, output);
+                encode(value, output);
+            }
+        }
+    }
+}
+
+fn encode_number(number: f64, output: &mut Vec<u8>) {
+    // Every whole `f64` this far from zero is exactly an `i128`.
+    let is_integer = is_whole_number(number)
+        && number.abs() <= 18446744073709551616.0
+        && !(number == 0.0 && number.is_sign_negative());
+    if !(is_integer && encode_integer(number as i128, output)) {
+        output.push(0xfb);
+        output.extend(number.to_be_bytes());
+    }
+}
+
+/// Writes an integer from -2^64 to 2^64 - 1, returning whether it was in
+/// that range.
+fn encode_integer(integer: i128, output: &mut Vec<u8>) -> bool {
+    match (u64::try_from(integer), u64::try_from(-1 - integer)) {
+        (Ok(argument), _) => encode_head(0, argument, output),
+        (_, Ok(argument)) => encode_head(1, argument, output),
+        _ => return false,
+    }
+    true
+}
+
+fn encode_string(string: &str, output: &mut Vec<u8>) {
+    encode_head(3, string.len() as u64, output);
+    output.extend_from_slice(string.as_bytes());
+}
+
+/// Writes the major type and its argument in the fewest bytes.
+fn encode_head(major: u8, argument: u64, output: &mut Vec<u8>) {
+    let major = major << 5;
+    match argument {
+        0..=23 => output.push(major | argument as u8),
+        24..=0xff => output.extend([major | 24, argument as u8]),
+        0x100..=0xffff => {
+            output.push(major | 25);
+            output.extend((argument as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            output.push(major | 26);
+            output.extend((argument as u32).to_be_bytes());
+        }
+        _ => {
+            output.push(major | 27);
+            output.extend(argument.to_be_bytes());
+        }
+    }
+}
+
+/// Decodes one CBOR item, failing on anything which has no equivalent in
+/// JSON. Integers beyond `MAX_SAFE_INTEGER` either way are kept exactly as
+/// a `Json::BigInteger`. A map with a repeated key keeps the last value in
+/// the first position, as `Map::insert` does.
+pub fn cbor_to_json(bytes: &[u8]) -> Result<Json, CborError> {
+    cbor_to_json_with_options(bytes, &CborOptions::default())
+}
+
+pub fn cbor_to_json_with_options(bytes: &[u8], options: &CborOptions) -> Result<Json, CborError> {
+    let mut decoder = Decoder {
+        bytes,
+        offset: 0,
+        depth: 0,
+        options,
+    };
+    let value = decoder.value()?;
+    match decoder.offset {
+        offset if offset < bytes.len() => Err(CborError::TrailingBytes(offset)),
+        _ => Ok(value),
+    }
+}
+
+/// The initial byte of an item, with the offset it was read from.
+#[derive(Clone, Copy)]
+struct Head {
+    offset: usize,
+    major: u8,
+    info: u8,
+}
+
+/// The additional information of a head which marks an indefinite length,
+/// or by itself, the `break` which ends one.
+const INDEFINITE: u8 = 31;
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    /// The number of arrays and maps the decoder is inside.
+    depth: usize,
+    options: &'a CborOptions,
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, length: u64) -> Result<&[u8], CborError> {
+        let rest = &self.bytes[self.offset..];
+        let length = usize::try_from(length)
+            .ok()
+            .filter(|&length| length <= rest.len())
+            .ok_or(CborError::UnexpectedEnd)?;
+        self.offset += length;
+        Ok(&rest[..length])
+    }
+
+    fn head(&mut self) -> Result<Head, CborError> {
+        let offset = self.offset;
+        let byte = self.take(1)?[0];
+        Ok(Head {
+            offset,
+            major: byte >> 5,
+            info: byte & 0x1f,
+        })
+    }
+
+    /// Whether the next byte is the `break` ending an item of indefinite
+    /// length, which it consumes if so.
+    fn at_break(&mut self) -> Result<bool, CborError> {
+        match self.bytes.get(self.offset) {
+            Some(0xff) => {
+                self.offset += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Err(CborError::UnexpectedEnd),
+        }
+    }
+
+    /// Reads the argument following a head of a definite length or value.
+    fn argument(&mut self, head: Head) -> Result<u64, CborError> {
+        let size = match head.info {
+            0..=23 => return Ok(u64::from(head.info)),
+            24..=27 => 1 << (head.info - 24),
+            _ => return Err(CborError::Malformed(head.offset)),
+        };
+        let bytes = self.take(size)?;
+        Ok(bytes
+            .iter()
+            .fold(0, |argument, &byte| argument << 8 | u64::from(byte)))
+    }
+
+    fn value(&mut self) -> Result<Json, CborError> {
+        let head = self.head()?;
+        match head.major {
+            0 => Ok(integer_to_json(self.argument(head)?.into())),
+            1 => Ok(integer_to_json(-1 - i128::from(self.argument(head)?))),
+            2 => match self.options.byte_strings_as_base64 {
+                Some(alphabet) => Ok(Json::String(base64::encode(&self.string(head)?, alphabet))),
+                None => Err(CborError::ByteString(head.offset)),
+            },
+            3 => self.text(head).map(Json::String),
+            4 => {
+                let mut items = Vec::new();
+                self.items(head, |decoder| {
+                    items.push(decoder.value()?);
+                    Ok(())
+                })?;
+                Ok(Json::Array(items))
+            }
+            5 => {
+                let mut properties = Map::new();
+                self.items(head, |decoder| {
+                    let key = decoder.head()?;
+                    if key.major != 3 {
+                        return Err(CborError::NonStringKey(key.offset));
+                    }
+                    let key = decoder.text(key)?;
+                    properties.insert(key, decoder.value()?);
+                    Ok(())
+                })?;
+                Ok(Json::Object(properties))
+            }
+            6 => Err(CborError::Tag {
+                offset: head.offset,
+                tag: self.argument(head)?,
+            }),
+            _ => self.simple(head),
+        }
+    }
+
+    /// Reads the items of an array or the properties of a map, calling
+    /// `item` for each.
+    fn items(
+        &mut self,
+        head: Head,
+        mut item: impl FnMut(&mut Self) -> Result<(), CborError>,
+    ) -> Result<(), CborError> {
+        if self.depth == MAX_DEPTH {
+            return Err(CborError::TooDeep(head.offset));
+        }
+        self.depth += 1;
+        if head.info == INDEFINITE {
+            while !self.at_break()? {
+                item(self)?;
+            }
+        } else {
+            for _ in 0..self.argument(head)? {
+                item(self)?;
+            }
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    /// Reads the bytes of a byte or text string, joining the chunks of one
+    /// of indefinite length, which must be strings of the same type.
+    fn string(&mut self, head: Head) -> Result<Vec<u8>, CborError> {
+        if head.info != INDEFINITE {
+            let length = self.argument(head)?;
+            return self.take(length).map(<[u8]>::to_vec);
+        }
+        let mut bytes = Vec::new();
+        while !self.at_break()? {
+            let chunk = self.head()?;
+            if chunk.major != head.major || chunk.info == INDEFINITE {
+                return Err(CborError::Malformed(chunk.offset));
+            }
+            let start = bytes.len();
+            bytes.extend(self.string(chunk)?);
+            if head.major == 3 && str::from_utf8(&bytes[start..]).is_err() {
+                return Err(CborError::InvalidUtf8(chunk.offset));
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn text(&mut self, head: Head) -> Result<String, CborError> {
+        String::from_utf8(self.string(head)?).map_err(|_| CborError::InvalidUtf8(head.offset))
+    }
+
+    fn simple(&mut self, head: Head) -> Result<Json, CborError> {
+        match head.info {
+            20 => Ok(Json::Boolean(false)),
+            21 => Ok(Json::Boolean(true)),
+            22 => Ok(Json::Null),
+            0..=19 | 23 => Err(CborError::SimpleValue {
+                offset: head.offset,
+                value: head.info,
+            }),
+            24 => Err(CborError::SimpleValue {
+                offset: head.offset,
+                value: self.take(1)?[0],
+            }),
+            25 => Ok(Json::Number(half_to_f64(self.argument(head)? as u16))),
+            26 => Ok(Json::Number(f64::from(f32::from_bits(
+                self.argument(head)? as u32,
+            )))),
+            27 => Ok(Json::Number(f64::from_bits(self.argument(head)?))),
+            _ => Err(CborError::Malformed(head.offset)),
+        }
+    }
+}
+
+/// Converts the bits of an IEEE 754 half-precision float.
+fn half_to_f64(bits: u16) -> f64 {
+    let exponent = u64::from(bits >> 10 & 0x1f);
+    let fraction = u64::from(bits & 0x3ff);
+    let magnitude = match exponent {
+        0 => fraction as f64 / 16777216.0,
+        31 if fraction == 0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => f64::from_bits((exponent + 1008) << 52 | fraction << 42),
+    };
+    if bits & 0x8000 == 0 {
+        magnitude
+    } else {
+        -magnitude
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        base64::Alphabet,
+        cbor::{
+            cbor_to_json, cbor_to_json_with_options, json_to_cbor, CborError, CborOptions,
+            MAX_DEPTH,
+        },
+        dsl::ToJson,
+        parser::parse,
+    };
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn decoded(hex: &str) -> Result<Json, CborError> {
+        cbor_to_json(&bytes(hex))
+    }
+
+    /// Examples from Appendix A of RFC 8949 which JSON can hold, as the JSON
+    /// they decode to and their encoding in hex. The examples of numbers
+    /// which `json_to_cbor` encodes differently are in
+    /// `it_decodes_the_rfc_8949_floats`.
+    const EXAMPLES: [(&str, &str); 29] = [
+        ("0", "00"),
+        ("1", "01"),
+        ("10", "0a"),
+        ("23", "17"),
+        ("24", "1818"),
+        ("25", "1819"),
+        ("100", "1864"),
+        ("1000", "1903e8"),
+        ("1000000", "1a000f4240"),
+        ("1000000000000", "1b000000e8d4a51000"),
+        ("-18446744073709551616", "3bffffffffffffffff"),
+        ("-1", "20"),
+        ("-10", "29"),
+        ("-100", "3863"),
+        ("-1000", "3903e7"),
+        ("1.1", "fb3ff199999999999a"),
+        ("-4.1", "fbc010666666666666"),
+        ("1e300", "fb7e37e43c8800759c"),
+        ("false", "f4"),
+        ("true", "f5"),
+        ("null", "f6"),
+        (r#""""#, "60"),
+        (r#""a""#, "6161"),
+        (r#""IETF""#, "6449455446"),
+        (r#""\"\\""#, "62225c"),
+        (r#""ü""#, "62c3bc"),
+        (r#""水""#, "63e6b0b4"),
+        ("[1, [2, 3], [4, 5]]", "8301820203820405"),
+        (r#"{"a": 1, "b": [2, 3]}"#, "a26161016162820203"),
+    ];
+
+    #[test]
+    fn it_encodes_and_decodes_the_rfc_8949_examples() {
+        for (json, hex) in EXAMPLES {
+            let value = parse(json).unwrap();
+
+            assert_eq!(json_to_cbor(&value), bytes(hex), "{json}");
+            assert_eq!(decoded(hex), Ok(value), "{hex}");
+        }
+    }
+
+    #[test]
+    fn it_encodes_containers_with_the_shortest_lengths() {
+        let items: Vec<_> = (1..=25).collect();
+        let mut expected = bytes("9819");
+        expected.extend(bytes("0102030405060708090a0b0c0d0e0f1011121314151617"));
+        expected.extend(bytes("18181819"));
+
+        assert_eq!(json_to_cbor(&items.to_json()), expected);
+        assert_eq!(json_to_cbor(&Json::array()), bytes("80"));
+        assert_eq!(json_to_cbor(&Json::object()), bytes("a0"));
+        assert_eq!(
+            json_to_cbor(&parse(r#"["a", {"b": "c"}]"#).unwrap()),
+            bytes("826161a161626163")
+        );
+        assert_eq!(
+            json_to_cbor(&"x".repeat(256).to_json())[..3],
+            bytes("790100")
+        );
+    }
+
+    #[test]
+    fn numbers_which_are_not_integers_are_64_bit_floats() {
+        assert_eq!(
+            json_to_cbor(&Json::Number(-0.0)),
+            bytes("fb8000000000000000")
+        );
+        assert_eq!(
+            json_to_cbor(&Json::Number(1.5)),
+            bytes("fb3ff8000000000000")
+        );
+        assert_eq!(
+            json_to_cbor(&Json::Number(18446744073709551616.0)),
+            bytes("fb43f0000000000000")
+        );
+        assert_eq!(
+            json_to_cbor(&f64::INFINITY.to_json()),
+            bytes("fb7ff0000000000000")
+        );
+        assert_eq!(
+            json_to_cbor(&Json::Number(18446744073709549568.0)),
+            bytes("1bfffffffffffff800")
+        );
+    }
+
+    #[test]
+    fn big_integers_are_encoded_and_decoded_exactly() {
+        for (text, hex) in [
+            ("9007199254740993", "1b0020000000000001"),
+            ("18446744073709551615", "1bffffffffffffffff"),
+            ("-18446744073709551616", "3bffffffffffffffff"),
+        ] {
+            let value = parse(text).unwrap();
+
+            assert_eq!(json_to_cbor(&value), bytes(hex), "{text}");
+            assert_eq!(cbor_to_json(&bytes(hex)), Ok(value), "{text}");
+        }
+        assert_eq!(
+            json_to_cbor(&parse("18446744073709551616").unwrap()),
+            bytes("fb43f0000000000000")
+        );
+    }
+
+    #[test]
+    fn it_decodes_the_rfc_8949_floats() {
+        let cases = [
+            ("f90000", 0.0),
+            ("f98000", -0.0),
+            ("f93c00", 1.0),
+            ("f93e00", 1.5),
+            ("f97bff", 65504.0),
+            ("fa47c35000", 100000.0),
+            ("fa7f7fffff", 3.4028234663852886e38),
+            ("f90001", 5.960464477539063e-8),
+            ("f90400", 0.00006103515625),
+            ("f9c400", -4.0),
+            ("f97c00", f64::INFINITY),
+            ("f9fc00", f64::NEG_INFINITY),
+            ("fa7f800000", f64::INFINITY),
+            ("fbfff0000000000000", f64::NEG_INFINITY),
+        ];
+
+        for (hex, number) in cases {
+            let decoded = decoded(hex).unwrap().as_f64().unwrap();
+            assert_eq!(decoded, number, "{hex}");
+            assert_eq!(
+                decoded.is_sign_negative(),
+                number.is_sign_negative(),
+                "{hex}"
+            );
+        }
+        for hex in ["f97e00", "fa7fc00000", "fb7ff8000000000000"] {
+            assert!(decoded(hex).unwrap().as_f64().unwrap().is_nan(), "{hex}");
+        }
+    }
+
+    #[test]
+    fn it_decodes_indefinite_lengths() {
+        let cases = [
+            ("7f657374726561646d696e67ff", r#""streaming""#),
+            ("9fff", "[]"),
+            ("9f018202039f0405ffff", "[1, [2, 3], [4, 5]]"),
+            ("83018202039f0405ff", "[1, [2, 3], [4, 5]]"),
+            ("bf61610161629f0203ffff", r#"{"a": 1, "b": [2, 3]}"#),
+            ("826161bf61626163ff", r#"["a", {"b": "c"}]"#),
+            ("bf6346756ef563416d7421ff", r#"{"Fun": true, "Amt": -2}"#),
+        ];
+
+        for (hex, json) in cases {
+            assert_eq!(decoded(hex), Ok(parse(json).unwrap()), "{hex}");
+        }
+    }
+
+    #[test]
+    fn it_rejects_what_json_cannot_hold() {
+        assert_eq!(
+            decoded("c249010000000000000000"),
+            Err(CborError::Tag { offset: 0, tag: 2 })
+        );
+        assert_eq!(
+            decoded("8201d82076"),
+            Err(CborError::Tag { offset: 2, tag: 32 })
+        );
+        assert_eq!(decoded("4401020304"), Err(CborError::ByteString(0)));
+        assert_eq!(decoded("a201020304"), Err(CborError::NonStringKey(1)));
+        assert_eq!(
+            decoded("f7"),
+            Err(CborError::SimpleValue {
+                offset: 0,
+                value: 23
+            })
+        );
+        assert_eq!(
+            decoded("f8ff"),
+            Err(CborError::SimpleValue {
+                offset: 0,
+                value: 255
+            })
+        );
+        assert_eq!(
+            decoded("a161610ff0").unwrap_err().to_string(),
+            "ERROR: Invalid CBOR - unexpected bytes after the item at 4"
+        );
+    }
+
+    #[test]
+    fn it_rejects_malformed_input() {
+        let cases = [
+            ("", CborError::UnexpectedEnd),
+            ("19 01", CborError::UnexpectedEnd),
+            ("6361", CborError::UnexpectedEnd),
+            ("9f01", CborError::UnexpectedEnd),
+            ("1bffffffffffffffff00", CborError::TrailingBytes(9)),
+            ("7bffffffffffffffff", CborError::UnexpectedEnd),
+            ("1c", CborError::Malformed(0)),
+            ("1f", CborError::Malformed(0)),
+            ("ff", CborError::Malformed(0)),
+            ("8121ff", CborError::TrailingBytes(2)),
+            ("7f6161016162ff", CborError::Malformed(3)),
+            ("7f7f6161ffff", CborError::Malformed(1)),
+            ("62c328", CborError::InvalidUtf8(0)),
+            ("7f61c361a9ff", CborError::InvalidUtf8(1)),
+        ];
+
+        for (hex, error) in cases {
+            assert_eq!(decoded(&hex.replace(' ', "")), Err(error), "{hex}");
+        }
+    }
+
+    #[test]
+    fn it_rejects_items_nested_too_deeply() {
+        let nested = |depth: usize, item: &[u8]| {
+            let mut bytes = vec![0x81; depth];
+            bytes.extend(item);
+            cbor_to_json(&bytes)
+        };
+
+        let mut deepest = Json::Null;
+        for _ in 0..MAX_DEPTH {
+            deepest = Json::Array(vec![deepest]);
+        }
+        assert_eq!(nested(MAX_DEPTH, &[0xf6]), Ok(deepest));
+        assert_eq!(
+            nested(MAX_DEPTH, &[0xa0]),
+            Err(CborError::TooDeep(MAX_DEPTH))
+        );
+        assert_eq!(nested(200_000, &[0xf6]), Err(CborError::TooDeep(MAX_DEPTH)));
+        let mut indefinite = vec![0x9f; 200_000];
+        indefinite.push(0xf6);
+        assert_eq!(
+            cbor_to_json(&indefinite).unwrap_err().to_string(),
+            "ERROR: Invalid CBOR - item nested more than 256 deep at 256"
+        );
+    }
+
+    #[test]
+    fn byte_strings_can_be_decoded_as_base64() {
+        let decode = |hex: &str, alphabet| {
+            let options = CborOptions {
+                byte_strings_as_base64: Some(alphabet),
+            };
+            cbor_to_json_with_options(&bytes(hex), &options)
+        };
+
+        assert_eq!(decode("40", Alphabet::Standard), Ok("".to_json()));
+        assert_eq!(
+            decode("4401020304", Alphabet::Standard),
+            Ok("AQIDBA==".to_json())
+        );
+        assert_eq!(
+            decode("5f42010243030405ff", Alphabet::UrlSafe),
+            Ok("AQIDBAU".to_json())
+        );
+        assert_eq!(
+            decode("a1616143fbff00", Alphabet::UrlSafe),
+            Ok(Json::object().set("a", "-_8A"))
+        );
+        assert_eq!(
+            decode("5f4101610200ff", Alphabet::Standard),
+            Err(CborError::Malformed(3))
+        );
+    }
+
+    #[test]
+    fn values_round_trip() {
+        let document = parse(
+            r#"{
+                "name": "café 😀",
+                "counts": [0, 23, 24, 255, 256, 65535, 65536, 4294967296, -1, -24, -25, -4294967297],
+                "ratios": [0.5, -0.0, 1e-300, 1.7976931348623157e308, 9007199254740993],
+                "flags": [true, false, null],
+                "nested": {"": {"a": []}, "b": {}},
+                "long": "0123456789012345678901234567890123456789"
+            }"#,
+        )
+        .unwrap();
+
+        let round_tripped = cbor_to_json(&json_to_cbor(&document)).unwrap();
+
+        assert_eq!(round_tripped, document);
+        assert_eq!(
+            round_tripped.keys().collect::<Vec<_>>(),
+            ["name", "counts", "ratios", "flags", "nested", "long"]
+        );
+        assert!(round_tripped["ratios"][1]
+            .as_f64()
+            .unwrap()
+            .is_sign_negative());
+    }
+}
@@ -0,0 +1,152 @@
+//! Lossy conversions for cleaning up data which encodes values loosely, such
+//! as booleans as `"1"` and numbers as `"42"`. Unlike the `as_*` accessors,
+//! these accept values of more than one type, and nothing applies them
+//! implicitly.
+
+use crate::{
+    ast::Json,
+    parser::parse,
+    printer::{json_to_string_with_options, NonFinitePolicy, PrintOptions, PrintStyle},
+};
+
+impl Json {
+    /// Reads a boolean, the string `true`, `false`, `1` or `0` in any case,
+    /// or the number 1 or 0 as a boolean.
+    pub fn coerce_bool(&self) -> Option<bool> {
+        match self {
+            Json::Boolean(boolean) => Some(*boolean),
+            Json::String(string) if string == "1" || string.eq_ignore_ascii_case("true") => {
+                Some(true)
+            }
+            Json::String(string) if string == "0" || string.eq_ignore_ascii_case("false") => {
+                Some(false)
+            }
+            Json::Number(number) if *number == 1.0 => Some(true),
+            Json::Number(number) if *number == 0.0 => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Reads a number, or a string holding a JSON number, which may be
+    /// surrounded by whitespace, as a number.
+    pub fn coerce_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(_) | Json::BigInteger(_) => self.as_f64(),
+            Json::String(string) => parse(string).ok()?.as_f64(),
+            _ => None,
+        }
+    }
+
+    /// Gives a string as it is, and any other value as the printer formats
+    /// it compactly, with NaN and the infinities as `NaN`, `Infinity` and
+    /// `-Infinity`.
+    pub fn coerce_string(&self) -> String {
+        let options = PrintOptions::new()
+            .style(PrintStyle::Compact)
+            .non_finite(NonFinitePolicy::String);
+        match self {
+            Json::String(string) => string.clone(),
+            Json::Number(number) if number.is_nan() => "NaN".to_owned(),
+            Json::Number(number) if number.is_infinite() && *number > 0.0 => "Infinity".to_owned(),
+            Json::Number(number) if number.is_infinite() => "-Infinity".to_owned(),
+            value => json_to_string_with_options(value, &options)
+                .expect("Every value can be printed with these options"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Json, dsl::ToJson, parser::parse};
+
+    #[test]
+    fn coerce_bool_accepts_loosely_encoded_booleans() {
+        let cases = [
+            ("true", Some(true)),
+            ("false", Some(false)),
+            (r#""true""#, Some(true)),
+            (r#""TRUE""#, Some(true)),
+            (r#""False""#, Some(false)),
+            (r#""1""#, Some(true)),
+            (r#""0""#, Some(false)),
+            ("1", Some(true)),
+            ("1.0", Some(true)),
+            ("0", Some(false)),
+            ("-0", Some(false)),
+            ("2", None),
+            ("0.5", None),
+            (r#""yes""#, None),
+            (r#"" true""#, None),
+            (r#""01""#, None),
+            (r#""""#, None),
+            ("null", None),
+            ("[true]", None),
+            (r#"{"a": true}"#, None),
+        ];
+
+        for (json, expected) in cases {
+            assert_eq!(parse(json).unwrap().coerce_bool(), expected, "{json}");
+        }
+    }
+
+    #[test]
+    fn coerce_f64_accepts_numbers_and_strings_of_json_numbers() {
+        let cases = [
+            ("42", Some(42.0)),
+            ("-1.5e3", Some(-1500.0)),
+            (r#""42""#, Some(42.0)),
+            (r#""-0.25""#, Some(-0.25)),
+            (r#""1E2""#, Some(100.0)),
+            (r#"" 7 ""#, Some(7.0)),
+            (r#""""#, None),
+            (r#""-""#, None),
+            (r#""+1""#, None),
+            (r#""01""#, None),
+            (r#""1.""#, None),
+            (r#"".5""#, None),
+            (r#""NaN""#, None),
+            (r#""Infinity""#, None),
+            (r#""0x10""#, None),
+            (r#""1,000""#, None),
+            (r#""[1]""#, None),
+            ("true", None),
+            ("null", None),
+            ("[1]", None),
+        ];
+
+        for (json, expected) in cases {
+            assert_eq!(parse(json).unwrap().coerce_f64(), expected, "{json}");
+        }
+    }
+
+    #[test]
+    fn coerce_string_formats_values_like_the_printer() {
+        let cases = [
+            (r#""text""#.to_json(), "\"text\""),
+            ("text".to_json(), "text"),
+            ("".to_json(), ""),
+            (Json::Null, "null"),
+            (true.to_json(), "true"),
+            (2.5.to_json(), "2.5"),
+            (1e21.to_json(), "1000000000000000000000"),
+            (f64::NAN.to_json(), "NaN"),
+            (f64::INFINITY.to_json(), "Infinity"),
+            (f64::NEG_INFINITY.to_json(), "-Infinity"),
+            (vec![1, 2].to_json(), "[1,2]"),
+            (Json::object().set("a", f64::NAN), r#"{"a":"NaN"}"#),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(value.coerce_string(), expected, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn strict_accessors_do_not_coerce() {
+        let value = "1".to_json();
+
+        assert_eq!(value.as_bool(), None);
+        assert_eq!(value.as_f64(), None);
+        assert_eq!(1.to_json().as_str(), None);
+    }
+}
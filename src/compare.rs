@@ -0,0 +1,303 @@
+//! Comparing values more loosely than `==`, for checking documents which
+//! are only expected to match in part.
+
+use crate::ast::{escape_pointer_token, unescape_pointer_token, Json};
+
+/// A JSON Pointer whose tokens may be `*`, matching any one key or index, or
+/// `**`, matching any number of them. Text which isn't a pointer matches
+/// nothing.
+struct Pattern(Option<Vec<String>>);
+
+impl Pattern {
+    fn parse(pattern: &str) -> Pattern {
+        if pattern.is_empty() {
+            return Pattern(Some(Vec::new()));
+        }
+        let tokens = pattern.strip_prefix('/').map(|tokens| tokens.split('/'));
+        Pattern(tokens.map(|tokens| tokens.map(unescape_pointer_token).collect()))
+    }
+
+    fn matches(&self, path: &[String]) -> bool {
+        fn matches(pattern: &[String], path: &[String]) -> bool {
+            match (pattern.split_first(), path.split_first()) {
+                (None, None) => true,
+                (Some((token, rest)), _) if token == "**" => {
+                    matches(rest, path) || (!path.is_empty() && matches(pattern, &path[1..]))
+                }
+                (Some((token, rest)), Some((segment, path))) => {
+                    (token == "*" || token == segment) && matches(rest, path)
+                }
+                _ => false,
+            }
+        }
+        self.0
+            .as_ref()
+            .is_some_and(|pattern| matches(pattern, path))
+    }
+}
+
+/// The first difference `Json::approx_diff` found between two values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApproxMismatch {
+    /// The JSON Pointer of the differing values.
+    pub pointer: String,
+    /// The numbers found at the pointer, if both values are numbers.
+    pub numbers: Option<(f64, f64)>,
+}
+
+impl Json {
+    /// Compares two values like `==`, except that two numbers `a` and `b` are
+    /// equal if `|a - b| <= epsilon`, or `|a - b| <= epsilon * max(|a|, |b|)`,
+    /// so that `epsilon` is both an absolute and a relative tolerance. Arrays of
+    /// different lengths, and objects with different keys, are never equal.
+    pub fn approx_eq(&self, other: &Json, epsilon: f64) -> bool {
+        self.approx_diff(other, epsilon).is_none()
+    }
+
+    /// Like `approx_eq`, but gives the first difference found, in document
+    /// order, if there is one.
+    pub fn approx_diff(&self, other: &Json, epsilon: f64) -> Option<ApproxMismatch> {
+        let approx_equal = |a: &Json, b: &Json| match (a.as_f64(), b.as_f64()) {
+            (Some(x), Some(y)) => {
+                let difference = (x - y).abs();
+                a == b || difference <= epsilon || difference <= epsilon * x.abs().max(y.abs())
+            }
+            _ => a == b,
+        };
+
+        let pointer = difference_pointer(self, other, &[], &approx_equal)?;
+        let numbers = match (self.pointer(&pointer), other.pointer(&pointer)) {
+            (Some(a), Some(b)) => a.as_f64().zip(b.as_f64()),
+            _ => None,
+        };
+        Some(ApproxMismatch { pointer, numbers })
+    }
+
+    /// Compares two values, treating those at the ignored JSON Pointers as
+    /// always equal, even if one of them is missing. A `*` token in an
+    /// ignored pointer matches any one key or index, and `**` matches any
+    /// number of them, so `/items/*/id` ignores the id of each item and
+    /// `/**/id` ignores every id.
+    pub fn deep_eq_ignoring(&self, other: &Json, ignored: &[&str]) -> bool {
+        self.diff_ignoring(other, ignored).is_none()
+    }
+
+    /// Like `deep_eq_ignoring`, but gives the JSON Pointer of the first
+    /// difference found, in document order, if there is one.
+    pub fn diff_ignoring(&self, other: &Json, ignored: &[&str]) -> Option<String> {
+        let patterns: Vec<_> = ignored
+            .iter()
+            .map(|pattern| Pattern::parse(pattern))
+            .collect();
+        difference_pointer(self, other, &patterns, &|a, b| a == b)
+    }
+}
+
+/// Finds the JSON Pointer of the first difference between two values outside
+/// the ignored paths, comparing values other than arrays and objects with
+/// `scalars_equal`.
+fn difference_pointer(
+    a: &Json,
+    b: &Json,
+    ignored: &[Pattern],
+    scalars_equal: &impl Fn(&Json, &Json) -> bool,
+) -> Option<String> {
+    let mut path = Vec::new();
+    first_difference(Some(a), Some(b), &mut path, ignored, scalars_equal).then(|| {
+        path.iter()
+            .map(|segment| format!("/{}", escape_pointer_token(segment)))
+            .collect()
+    })
+}
+
+/// Finds whether the values differ outside the ignored paths, leaving the
+/// path of the first difference in `path` if they do.
+fn first_difference(
+    a: Option<&Json>,
+    b: Option<&Json>,
+    path: &mut Vec<String>,
+    ignored: &[Pattern],
+    scalars_equal: &impl Fn(&Json, &Json) -> bool,
+) -> bool {
+    if ignored.iter().any(|pattern| pattern.matches(path)) {
+        return false;
+    }
+    let mut children_differ = |children: Vec<(String, Option<&Json>, Option<&Json>)>| {
+        for (segment, a, b) in children {
+            path.push(segment);
+            if first_difference(a, b, path, ignored, scalars_equal) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    };
+
+    match (a, b) {
+        (Some(Json::Object(a)), Some(Json::Object(b))) => {
+            let only_in_b = b.keys().filter(|key| !a.contains_key(key));
+            let keys: Vec<_> = a.keys().chain(only_in_b).collect();
+            children_differ(
+                keys.into_iter()
+                    .map(|key| (key.clone(), a.get(key), b.get(key)))
+                    .collect(),
+            )
+        }
+        (Some(Json::Array(a)), Some(Json::Array(b))) => children_differ(
+            (0..a.len().max(b.len()))
+                .map(|index| (index.to_string(), a.get(index), b.get(index)))
+                .collect(),
+        ),
+        (Some(a), Some(b)) => !scalars_equal(a, b),
+        (a, b) => a != b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{compare::ApproxMismatch, dsl::ToJson, parser::parse};
+
+    fn response() -> &'static str {
+        r#"{
+            "meta": {"timestamp": 1700000000, "version": 2},
+            "items": [
+                {"id": 17, "name": "a", "tags": [{"id": 1}]},
+                {"id": 18, "name": "b"}
+            ]
+        }"#
+    }
+
+    fn golden() -> &'static str {
+        r#"{
+            "meta": {"timestamp": 0, "version": 2},
+            "items": [
+                {"id": 1, "name": "a", "tags": [{"id": 2}]},
+                {"id": 2, "name": "b"}
+            ]
+        }"#
+    }
+
+    fn diff(a: &str, b: &str, ignored: &[&str]) -> Option<String> {
+        parse(a).unwrap().diff_ignoring(&parse(b).unwrap(), ignored)
+    }
+
+    #[test]
+    fn it_compares_everything_but_the_ignored_paths() {
+        let (response, golden) = (parse(response()).unwrap(), parse(golden()).unwrap());
+
+        assert!(!response.deep_eq_ignoring(&golden, &[]));
+        assert!(!response.deep_eq_ignoring(&golden, &["/meta/timestamp"]));
+        assert!(response.deep_eq_ignoring(&golden, &["/meta/timestamp", "/**/id"]));
+        assert!(response.deep_eq_ignoring(&golden, &["/meta", "/items/*/id", "/items/0/tags"]));
+        assert!(response.deep_eq_ignoring(&golden, &[""]));
+        assert!(!response.deep_eq_ignoring(&golden, &["meta", "**"]));
+    }
+
+    #[test]
+    fn it_gives_the_pointer_of_the_first_difference() {
+        assert_eq!(
+            diff(response(), golden(), &[]),
+            Some("/meta/timestamp".to_owned())
+        );
+        assert_eq!(
+            diff(response(), golden(), &["/meta/timestamp"]),
+            Some("/items/0/id".to_owned())
+        );
+        assert_eq!(
+            diff(response(), golden(), &["/meta/timestamp", "/items/*/id"]),
+            Some("/items/0/tags/0/id".to_owned())
+        );
+        assert_eq!(
+            diff(r#"{"a/b": [1]}"#, r#"{"a/b": [2]}"#, &[]),
+            Some("/a~1b/0".to_owned())
+        );
+        assert_eq!(diff("[1]", r#"{"0": 1}"#, &[]), Some("".to_owned()));
+        assert_eq!(diff("1", "1.0", &[]), None);
+    }
+
+    #[test]
+    fn ignored_keys_may_be_missing_from_either_side() {
+        assert_eq!(diff(r#"{"a": 1, "id": 2}"#, r#"{"a": 1}"#, &["/id"]), None);
+        assert_eq!(
+            diff(r#"{"a": 1}"#, r#"{"id": 2, "a": 1}"#, &["/**/id"]),
+            None
+        );
+        assert_eq!(
+            diff(r#"{"a": 1}"#, r#"{"a": 1, "b": 2}"#, &["/id"]),
+            Some("/b".to_owned())
+        );
+        assert_eq!(
+            diff(r#"{"a": 1, "b": 2}"#, r#"{"a": 1}"#, &[]),
+            Some("/b".to_owned())
+        );
+    }
+
+    #[test]
+    fn arrays_are_compared_item_by_item_outside_ignored_wildcards() {
+        assert_eq!(diff("[1, 2]", "[1, 2, 3]", &[]), Some("/2".to_owned()));
+        assert_eq!(diff(r#"{"x": [1, 2]}"#, r#"{"x": [3]}"#, &["/x/*"]), None);
+        assert_eq!(
+            diff(r#"{"x": [1, 2]}"#, r#"{"x": [1]}"#, &["/x/0"]),
+            Some("/x/1".to_owned())
+        );
+        assert_eq!(
+            diff(
+                r#"[{"x": [1]}, {"x": [2]}]"#,
+                r#"[{"x": [3, 4]}, {"x": []}]"#,
+                &["/**/x/*"]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn approx_eq_allows_numbers_within_the_tolerance() {
+        let a = parse(r#"{"sum": 0.30000000000000004, "big": 1e20, "list": [1, 2.0001]}"#).unwrap();
+        let b = parse(r#"{"sum": 0.3, "big": 1.0000001e20, "list": [1, 2]}"#).unwrap();
+
+        assert_ne!(a, b);
+        assert!(a.approx_eq(&b, 1e-3));
+        assert!(!a.approx_eq(&b, 1e-9));
+        for number in [f64::INFINITY, f64::NAN] {
+            assert!(number.to_json().approx_eq(&number.to_json(), 0.0));
+        }
+    }
+
+    #[test]
+    fn approx_diff_gives_the_first_mismatched_numbers() {
+        let a = parse(r#"{"a": [1, 2.5], "b": 3}"#).unwrap();
+        let b = parse(r#"{"a": [1, 2.6], "b": 4}"#).unwrap();
+
+        assert_eq!(
+            a.approx_diff(&b, 0.01),
+            Some(ApproxMismatch {
+                pointer: "/a/1".to_owned(),
+                numbers: Some((2.5, 2.6)),
+            })
+        );
+        assert_eq!(
+            a.approx_diff(&b, 0.1).map(|mismatch| mismatch.pointer),
+            Some("/b".to_owned())
+        );
+        assert_eq!(a.approx_diff(&b, 1.0), None);
+    }
+
+    #[test]
+    fn approx_eq_still_requires_the_same_structure() {
+        let approx_diff = |a: &str, b: &str| parse(a).unwrap().approx_diff(&parse(b).unwrap(), 1e9);
+        let structural = |pointer: &str| {
+            Some(ApproxMismatch {
+                pointer: pointer.to_owned(),
+                numbers: None,
+            })
+        };
+
+        assert_eq!(approx_diff("[1, 2]", "[1, 2, 3]"), structural("/2"));
+        assert_eq!(approx_diff(r#"{"a": 1}"#, r#"{"b": 1}"#), structural("/a"));
+        assert_eq!(
+            approx_diff(r#"{"a": 1}"#, r#"{"a": "1"}"#),
+            structural("/a")
+        );
+        assert_eq!(approx_diff("[[1]]", "[1]"), structural("/0"));
+    }
+}
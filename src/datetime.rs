@@ -0,0 +1,263 @@
+//! Converting durations and times to JSON, and with the `chrono` and `time`
+//! features, the date and time types of those crates to and from RFC 3339
+//! strings.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(any(feature = "chrono", feature = "time"))]
+use crate::dsl::FromJsonError;
+use crate::{
+    ast::Json,
+    dsl::{ToJson, MAX_SAFE_INTEGER},
+};
+
+#[cfg(feature = "chrono")]
+mod chrono;
+#[cfg(feature = "time")]
+mod time;
+
+/// Converts a duration to a number of seconds, such as `1.5`. This is the
+/// nearest `f64` to the duration, so long durations lose precision: a year
+/// is only precise to about 4 nanoseconds, and `Duration::MAX` to about an
+/// hour. Use `DurationParts` to keep every nanosecond.
+impl ToJson for Duration {
+    fn to_json(&self) -> Json {
+        Json::Number(self.as_secs_f64())
+    }
+}
+
+/// Converts a time to a number of seconds since the Unix epoch, negative for
+/// times before it, with the same precision as a `Duration`.
+impl ToJson for SystemTime {
+    fn to_json(&self) -> Json {
+        match self.duration_since(UNIX_EPOCH) {
+            Ok(since) => Json::Number(since.as_secs_f64()),
+            Err(error) => Json::Number(-error.duration().as_secs_f64()),
+        }
+    }
+}
+
+/// Converts a duration to an object of whole seconds and nanoseconds, such
+/// as `{"secs": 1, "nanos": 500000000}`, which is exact. Seconds beyond
+/// `MAX_SAFE_INTEGER` are written as a string of digits rather than rounded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DurationParts(pub Duration);
+
+impl ToJson for DurationParts {
+    fn to_json(&self) -> Json {
+        let secs = self.0.as_secs();
+        let secs = if secs <= MAX_SAFE_INTEGER as u64 {
+            secs.to_json()
+        } else {
+            Json::String(secs.to_string())
+        };
+        Json::object()
+            .set("secs", secs)
+            .set("nanos", self.0.subsec_nanos())
+    }
+}
+
+/// Converts a time to an RFC 3339 string in UTC, such as
+/// `2024-02-29T13:05:09.25Z`, with as many digits of fractional seconds as
+/// are needed, up to nine. Years after 9999 can't be written in RFC 3339, so
+/// they are written in the expanded ISO 8601 form, with a sign and at least
+/// six digits, as in `+010000-01-01T00:00:00Z`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rfc3339(pub SystemTime);
+
+impl ToJson for Rfc3339 {
+    fn to_json(&self) -> Json {
+        let (seconds, nanos) = match self.0.duration_since(UNIX_EPOCH) {
+            Ok(since) => (i128::from(since.as_secs()), since.subsec_nanos()),
+            Err(error) => {
+                let before = error.duration();
+                match before.subsec_nanos() {
+                    0 => (-i128::from(before.as_secs()), 0),
+                    nanos => (-i128::from(before.as_secs()) - 1, 1_000_000_000 - nanos),
+                }
+            }
+        };
+        let days = seconds.div_euclid(86_400);
+        let second_of_day = seconds.rem_euclid(86_400) as u32;
+        let (year, month, day) = civil_from_days(days);
+
+        let mut formatted = format_date(year, month, day);
+        push_time(
+            &mut formatted,
+            second_of_day / 3600,
+            second_of_day / 60 % 60,
+            second_of_day % 60,
+            nanos,
+        );
+        formatted.push('Z');
+        Json::String(formatted)
+    }
+}
+
+/// Formats a date as RFC 3339 does, or in the expanded form of ISO 8601 for
+/// years it can't represent.
+fn format_date(year: i128, month: u32, day: u32) -> String {
+    if (0..=9999).contains(&year) {
+        format!("{year:04}-{month:02}-{day:02}")
+    } else {
+        format!("{year:+07}-{month:02}-{day:02}")
+    }
+}
+
+/// Appends a time of day to a date, with as many digits of fractional
+/// seconds as are needed.
+fn push_time(formatted: &mut String, hour: u32, minute: u32, second: u32, nanos: u32) {
+    formatted.push_str(&format!("T{hour:02}:{minute:02}:{second:02}"));
+    if nanos > 0 {
+        let fraction = format!("{nanos:09}");
+        formatted.push('.');
+        formatted.push_str(fraction.trim_end_matches('0'));
+    }
+}
+
+/// Appends an offset from UTC of a whole number of minutes, as `Z` if it
+/// is zero.
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn push_offset(formatted: &mut String, offset_seconds: i32) {
+    if offset_seconds == 0 {
+        formatted.push('Z');
+    } else {
+        let sign = if offset_seconds < 0 { '-' } else { '+' };
+        let minutes = offset_seconds.unsigned_abs() / 60;
+        formatted.push_str(&format!("{sign}{:02}:{:02}", minutes / 60, minutes % 60));
+    }
+}
+
+/// Converts a string with a parser from another crate, failing with the
+/// string and the parser's message if it doesn't parse.
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn parse_string<T, E: std::fmt::Display>(
+    json: &Json,
+    expected: &str,
+    parse: impl FnOnce(&str) -> Result<T, E>,
+) -> Result<T, FromJsonError> {
+    let text = json
+        .as_str()
+        .ok_or_else(|| FromJsonError::new(json, expected))?;
+    parse(text).map_err(|error| FromJsonError {
+        pointer: String::new(),
+        expected: expected.to_owned(),
+        found: format!("{text:?} ({error})"),
+    })
+}
+
+/// Finds the date in the proleptic Gregorian calendar of a number of days
+/// since 1970-01-01, using Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: i128) -> (i128, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i128::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::{
+        ast::Json,
+        datetime::{DurationParts, Rfc3339},
+        dsl::ToJson,
+    };
+
+    fn rfc3339(seconds: i64, nanos: u32) -> String {
+        let offset = Duration::new(seconds.unsigned_abs(), 0);
+        let time = if seconds < 0 {
+            UNIX_EPOCH - offset
+        } else {
+            UNIX_EPOCH + offset
+        } + Duration::from_nanos(nanos.into());
+        Rfc3339(time).to_json().as_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn a_duration_is_converted_to_fractional_seconds() {
+        assert_eq!(Duration::from_millis(1500).to_json(), Json::Number(1.5));
+        assert_eq!(Duration::from_nanos(1).to_json(), Json::Number(1e-9));
+        assert_eq!(Duration::ZERO.to_json(), Json::Number(0.0));
+    }
+
+    #[test]
+    fn a_long_duration_is_rounded_to_the_nearest_f64() {
+        let precise = Duration::new(1000, 1);
+        let imprecise = Duration::new(365 * 24 * 3600, 1);
+
+        let read_back =
+            |duration: Duration| Duration::from_secs_f64(duration.to_json().as_f64().unwrap());
+
+        assert_eq!(read_back(precise), precise);
+        assert_ne!(read_back(imprecise), imprecise);
+        assert_eq!(
+            Duration::MAX.to_json(),
+            Json::Number(18446744073709552000.0)
+        );
+    }
+
+    #[test]
+    fn duration_parts_are_exact() {
+        assert_eq!(
+            DurationParts(Duration::new(1, 500_000_000)).to_json(),
+            Json::object().set("secs", 1).set("nanos", 500_000_000)
+        );
+        assert_eq!(
+            DurationParts(Duration::MAX).to_json(),
+            Json::object()
+                .set("secs", "18446744073709551615")
+                .set("nanos", 999_999_999)
+        );
+    }
+
+    #[test]
+    fn a_system_time_is_converted_to_seconds_since_the_epoch() {
+        let after = UNIX_EPOCH + Duration::from_millis(1_700_000_000_250);
+        let before = UNIX_EPOCH - Duration::from_millis(1500);
+
+        assert_eq!(after.to_json(), Json::Number(1_700_000_000.25));
+        assert_eq!(before.to_json(), Json::Number(-1.5));
+        assert_eq!(UNIX_EPOCH.to_json(), Json::Number(0.0));
+        assert!(SystemTime::now().to_json().as_f64().unwrap() > 1.7e9);
+    }
+
+    #[test]
+    fn rfc3339_formats_times_in_utc() {
+        assert_eq!(rfc3339(0, 0), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            rfc3339(1_709_211_909, 250_000_000),
+            "2024-02-29T13:05:09.25Z"
+        );
+        assert_eq!(rfc3339(951_782_400, 1), "2000-02-29T00:00:00.000000001Z");
+        assert_eq!(rfc3339(4_107_542_399, 0), "2100-02-28T23:59:59Z");
+        assert_eq!(rfc3339(253_402_300_799, 0), "9999-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn rfc3339_formats_times_before_the_epoch() {
+        assert_eq!(rfc3339(-1, 0), "1969-12-31T23:59:59Z");
+        assert_eq!(rfc3339(-1, 500_000_000), "1969-12-31T23:59:59.5Z");
+        assert_eq!(rfc3339(-86_400, 0), "1969-12-31T00:00:00Z");
+        assert_eq!(rfc3339(-2_208_988_800, 0), "1900-01-01T00:00:00Z");
+        assert_eq!(rfc3339(-62_167_219_200, 0), "0000-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn rfc3339_expands_years_it_cannot_represent() {
+        assert_eq!(rfc3339(253_402_300_800, 0), "+010000-01-01T00:00:00Z");
+        assert_eq!(rfc3339(-62_167_219_201, 0), "-000001-12-31T23:59:59Z");
+    }
+}
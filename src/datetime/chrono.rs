@@ -0,0 +1,202 @@
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, Timelike, Utc};
+
+use super::{format_date, parse_string, push_offset, push_time};
+use crate::{
+    ast::Json,
+    dsl::{FromJson, FromJsonError, ToJson},
+};
+
+/// Formats a date and time, with a leap second as second 60.
+fn format_date_time(value: NaiveDateTime) -> String {
+    let (date, time) = (value.date(), value.time());
+    let (second, nanos) = match time.nanosecond() {
+        leap @ 1_000_000_000.. => (time.second() + 1, leap - 1_000_000_000),
+        nanos => (time.second(), nanos),
+    };
+    let mut formatted = format_date(date.year().into(), date.month(), date.day());
+    push_time(&mut formatted, time.hour(), time.minute(), second, nanos);
+    formatted
+}
+
+/// Converts to an RFC 3339 string such as `2024-02-29T13:05:09.25Z`.
+impl ToJson for DateTime<Utc> {
+    fn to_json(&self) -> Json {
+        let mut formatted = format_date_time(self.naive_utc());
+        push_offset(&mut formatted, 0);
+        Json::String(formatted)
+    }
+}
+
+/// Converts to an RFC 3339 string with the offset, such as
+/// `2024-02-29T13:05:09.25+05:30`, or in UTC if the offset isn't a whole
+/// number of minutes, which RFC 3339 can't write.
+impl ToJson for DateTime<FixedOffset> {
+    fn to_json(&self) -> Json {
+        let offset = self.offset().local_minus_utc();
+        if offset % 60 != 0 {
+            return self.with_timezone(&Utc).to_json();
+        }
+        let mut formatted = format_date_time(self.naive_local());
+        push_offset(&mut formatted, offset);
+        Json::String(formatted)
+    }
+}
+
+/// Converts to an RFC 3339 date such as `2024-02-29`.
+impl ToJson for NaiveDate {
+    fn to_json(&self) -> Json {
+        Json::String(format_date(self.year().into(), self.month(), self.day()))
+    }
+}
+
+/// Converts to an RFC 3339 date and time without an offset, such as
+/// `2024-02-29T13:05:09.25`.
+impl ToJson for NaiveDateTime {
+    fn to_json(&self) -> Json {
+        Json::String(format_date_time(*self))
+    }
+}
+
+/// Parses an RFC 3339 string with any offset, converting it to UTC.
+impl FromJson for DateTime<Utc> {
+    fn from_json(json: &Json) -> Result<DateTime<Utc>, FromJsonError> {
+        DateTime::<FixedOffset>::from_json(json).map(|date_time| date_time.with_timezone(&Utc))
+    }
+}
+
+impl FromJson for DateTime<FixedOffset> {
+    fn from_json(json: &Json) -> Result<DateTime<FixedOffset>, FromJsonError> {
+        parse_string(json, "RFC 3339 date-time", DateTime::parse_from_rfc3339)
+    }
+}
+
+impl FromJson for NaiveDate {
+    fn from_json(json: &Json) -> Result<NaiveDate, FromJsonError> {
+        parse_string(json, "RFC 3339 date", str::parse)
+    }
+}
+
+impl FromJson for NaiveDateTime {
+    fn from_json(json: &Json) -> Result<NaiveDateTime, FromJsonError> {
+        parse_string(json, "date-time without an offset", str::parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+    use crate::{
+        ast::Json,
+        dsl::{FromJson, ToJson},
+        from_json_str,
+    };
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn date_times_convert_to_rfc_3339_strings() {
+        let india = FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap();
+        let new_york = FixedOffset::west_opt(5 * 3600).unwrap();
+        let time = utc("2024-02-29T13:05:09.25Z");
+
+        assert_eq!(time.to_json(), "2024-02-29T13:05:09.25Z".to_json());
+        assert_eq!(
+            time.with_timezone(&india).to_json(),
+            "2024-02-29T18:35:09.25+05:30".to_json()
+        );
+        assert_eq!(
+            time.with_timezone(&new_york).to_json(),
+            "2024-02-29T08:05:09.25-05:00".to_json()
+        );
+        assert_eq!(
+            time.with_timezone(&FixedOffset::east_opt(0).unwrap())
+                .to_json(),
+            "2024-02-29T13:05:09.25Z".to_json()
+        );
+        assert_eq!(
+            utc("1969-12-31T23:59:59.000000001Z").to_json(),
+            "1969-12-31T23:59:59.000000001Z".to_json()
+        );
+        assert_eq!(
+            Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap().to_json(),
+            "2000-01-01T00:00:00Z".to_json()
+        );
+    }
+
+    #[test]
+    fn offsets_with_seconds_are_written_in_utc() {
+        let offset = FixedOffset::east_opt(3600 + 1).unwrap();
+
+        let time = utc("2024-01-01T00:00:00Z").with_timezone(&offset);
+
+        assert_eq!(time.to_json(), "2024-01-01T00:00:00Z".to_json());
+    }
+
+    #[test]
+    fn naive_dates_and_times_have_no_offset() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let time = date.and_hms_nano_opt(13, 5, 9, 120_000_000).unwrap();
+
+        assert_eq!(date.to_json(), "2024-02-29".to_json());
+        assert_eq!(time.to_json(), "2024-02-29T13:05:09.12".to_json());
+        assert_eq!(
+            date.and_hms_opt(0, 0, 0).unwrap().to_json(),
+            "2024-02-29T00:00:00".to_json()
+        );
+    }
+
+    #[test]
+    fn values_round_trip_with_sub_second_precision_and_offsets() {
+        let offset = FixedOffset::east_opt(-(9 * 3600 + 30 * 60)).unwrap();
+        let times = [
+            utc("2024-02-29T13:05:09.123456789+14:00").with_timezone(&offset),
+            utc("1999-12-31T23:59:59.5Z").fixed_offset(),
+            DateTime::parse_from_rfc3339("2016-12-31T23:59:60.25Z").unwrap(),
+        ];
+        let dates = NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+        let local = dates.and_hms_micro_opt(1, 2, 3, 4).unwrap();
+
+        for time in times {
+            assert_eq!(
+                DateTime::<FixedOffset>::from_json(&time.to_json()),
+                Ok(time)
+            );
+            assert_eq!(
+                DateTime::<Utc>::from_json(&time.to_json()),
+                Ok(time.with_timezone(&Utc))
+            );
+        }
+        assert_eq!(NaiveDate::from_json(&dates.to_json()), Ok(dates));
+        assert_eq!(NaiveDateTime::from_json(&local.to_json()), Ok(local));
+        assert_eq!(
+            times[0].to_json(),
+            "2024-02-28T13:35:09.123456789-09:30".to_json()
+        );
+        assert_eq!(times[2].to_json(), "2016-12-31T23:59:60.25Z".to_json());
+    }
+
+    #[test]
+    fn parse_errors_show_the_string_and_the_reason() {
+        let error = from_json_str::<Vec<DateTime<Utc>>>(
+            r#"["2024-01-01T00:00:00Z", "2024-13-01T00:00:00Z"]"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            r#"ERROR: Cannot convert JSON - expected RFC 3339 date-time at /1, found "2024-13-01T00:00:00Z" (input is out of range)"#
+        );
+        assert_eq!(
+            NaiveDate::from_json(&Json::Number(2024.0))
+                .unwrap_err()
+                .to_string(),
+            "ERROR: Cannot convert JSON - expected RFC 3339 date at the root, found number 2024"
+        );
+        assert!(NaiveDateTime::from_json(&"2024-01-01T00:00:00Z".to_json()).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, Date, OffsetDateTime,
+    PrimitiveDateTime, Time, UtcDateTime,
+};
+
+use super::{format_date, parse_string, push_offset, push_time};
+use crate::{
+    ast::Json,
+    dsl::{FromJson, FromJsonError, ToJson},
+};
+
+fn format_date_time(date: Date, time: Time) -> String {
+    let mut formatted = date_string(date);
+    push_time(
+        &mut formatted,
+        time.hour().into(),
+        time.minute().into(),
+        time.second().into(),
+        time.nanosecond(),
+    );
+    formatted
+}
+
+fn date_string(date: Date) -> String {
+    format_date(
+        date.year().into(),
+        u8::from(date.month()).into(),
+        date.day().into(),
+    )
+}
+
+/// Converts to an RFC 3339 string with the offset, such as
+/// `2024-02-29T13:05:09.25+05:30`, or in UTC if the offset isn't a whole
+/// number of minutes, which RFC 3339 can't write.
+impl ToJson for OffsetDateTime {
+    fn to_json(&self) -> Json {
+        let offset = self.offset().whole_seconds();
+        if offset % 60 != 0 {
+            return self.to_utc().to_json();
+        }
+        let mut formatted = format_date_time(self.date(), self.time());
+        push_offset(&mut formatted, offset);
+        Json::String(formatted)
+    }
+}
+
+/// Converts to an RFC 3339 string such as `2024-02-29T13:05:09.25Z`.
+impl ToJson for UtcDateTime {
+    fn to_json(&self) -> Json {
+        let mut formatted = format_date_time(self.date(), self.time());
+        push_offset(&mut formatted, 0);
+        Json::String(formatted)
+    }
+}
+
+/// Converts to an RFC 3339 date such as `2024-02-29`.
+impl ToJson for Date {
+    fn to_json(&self) -> Json {
+        Json::String(date_string(*self))
+    }
+}
+
+/// Converts to an RFC 3339 date and time without an offset, such as
+/// `2024-02-29T13:05:09.25`.
+impl ToJson for PrimitiveDateTime {
+    fn to_json(&self) -> Json {
+        Json::String(format_date_time(self.date(), self.time()))
+    }
+}
+
+impl FromJson for OffsetDateTime {
+    fn from_json(json: &Json) -> Result<OffsetDateTime, FromJsonError> {
+        parse_string(json, "RFC 3339 date-time", |text| {
+            OffsetDateTime::parse(text, &Rfc3339)
+        })
+    }
+}
+
+/// Parses an RFC 3339 string with any offset, converting it to UTC.
+impl FromJson for UtcDateTime {
+    fn from_json(json: &Json) -> Result<UtcDateTime, FromJsonError> {
+        OffsetDateTime::from_json(json).map(OffsetDateTime::to_utc)
+    }
+}
+
+impl FromJson for Date {
+    fn from_json(json: &Json) -> Result<Date, FromJsonError> {
+        parse_string(json, "RFC 3339 date", |text| {
+            Date::parse(text, format_description!("[year]-[month]-[day]"))
+        })
+    }
+}
+
+impl FromJson for PrimitiveDateTime {
+    fn from_json(json: &Json) -> Result<PrimitiveDateTime, FromJsonError> {
+        parse_string(json, "date-time without an offset", |text| {
+            PrimitiveDateTime::parse(
+                text,
+                format_description!(
+                    "[year]-[month]-[day]T[hour]:[minute]:[second][optional [.[subsecond]]]"
+                ),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{
+        format_description::well_known::Rfc3339, Date, Month, OffsetDateTime, PrimitiveDateTime,
+        Time, UtcDateTime, UtcOffset,
+    };
+
+    use crate::{
+        ast::Json,
+        dsl::{FromJson, ToJson},
+        from_json_str,
+    };
+
+    fn offset_date_time(text: &str) -> OffsetDateTime {
+        OffsetDateTime::parse(text, &Rfc3339).unwrap()
+    }
+
+    #[test]
+    fn date_times_convert_to_rfc_3339_strings() {
+        let india = UtcOffset::from_hms(5, 30, 0).unwrap();
+        let new_york = UtcOffset::from_hms(-5, 0, 0).unwrap();
+        let time = offset_date_time("2024-02-29T13:05:09.25Z");
+
+        assert_eq!(time.to_json(), "2024-02-29T13:05:09.25Z".to_json());
+        assert_eq!(time.to_utc().to_json(), "2024-02-29T13:05:09.25Z".to_json());
+        assert_eq!(
+            time.to_offset(india).to_json(),
+            "2024-02-29T18:35:09.25+05:30".to_json()
+        );
+        assert_eq!(
+            time.to_offset(new_york).to_json(),
+            "2024-02-29T08:05:09.25-05:00".to_json()
+        );
+        assert_eq!(
+            OffsetDateTime::UNIX_EPOCH.to_json(),
+            "1970-01-01T00:00:00Z".to_json()
+        );
+    }
+
+    #[test]
+    fn offsets_with_seconds_are_written_in_utc() {
+        let offset = UtcOffset::from_hms(1, 0, 1).unwrap();
+
+        let time = offset_date_time("2024-01-01T00:00:00Z").to_offset(offset);
+
+        assert_eq!(time.to_json(), "2024-01-01T00:00:00Z".to_json());
+    }
+
+    #[test]
+    fn dates_and_primitive_date_times_have_no_offset() {
+        let date = Date::from_calendar_date(2024, Month::February, 29).unwrap();
+        let time =
+            PrimitiveDateTime::new(date, Time::from_hms_nano(13, 5, 9, 120_000_000).unwrap());
+
+        assert_eq!(date.to_json(), "2024-02-29".to_json());
+        assert_eq!(time.to_json(), "2024-02-29T13:05:09.12".to_json());
+        assert_eq!(date.midnight().to_json(), "2024-02-29T00:00:00".to_json());
+    }
+
+    #[test]
+    fn values_round_trip_with_sub_second_precision_and_offsets() {
+        let offset = UtcOffset::from_hms(-9, -30, 0).unwrap();
+        let times = [
+            offset_date_time("2024-02-29T13:05:09.123456789+14:00").to_offset(offset),
+            offset_date_time("1999-12-31T23:59:59.5Z"),
+            offset_date_time("0001-01-01T00:00:00.000001+01:00"),
+        ];
+        let date = Date::from_calendar_date(1, Month::January, 1).unwrap();
+        let local = date.with_hms_micro(1, 2, 3, 4).unwrap();
+
+        for time in times {
+            assert_eq!(OffsetDateTime::from_json(&time.to_json()), Ok(time));
+            assert_eq!(
+                OffsetDateTime::from_json(&time.to_json()).unwrap().offset(),
+                time.offset()
+            );
+            assert_eq!(UtcDateTime::from_json(&time.to_json()), Ok(time.to_utc()));
+        }
+        assert_eq!(Date::from_json(&date.to_json()), Ok(date));
+        assert_eq!(PrimitiveDateTime::from_json(&local.to_json()), Ok(local));
+        assert_eq!(
+            PrimitiveDateTime::from_json(&date.midnight().to_json()),
+            Ok(date.midnight())
+        );
+        assert_eq!(
+            times[0].to_json(),
+            "2024-02-28T13:35:09.123456789-09:30".to_json()
+        );
+    }
+
+    #[test]
+    fn parse_errors_show_the_string_and_the_reason() {
+        let error = from_json_str::<Vec<OffsetDateTime>>(
+            r#"["2024-01-01T00:00:00Z", "2024-13-01T00:00:00Z"]"#,
+        )
+        .unwrap_err();
+
+        assert!(error.to_string().starts_with(
+            r#"ERROR: Cannot convert JSON - expected RFC 3339 date-time at /1, found "2024-13-01T00:00:00Z" ("#
+        ));
+        assert_eq!(
+            Date::from_json(&Json::Number(2024.0))
+                .unwrap_err()
+                .to_string(),
+            "ERROR: Cannot convert JSON - expected RFC 3339 date at the root, found number 2024"
+        );
+        assert!(PrimitiveDateTime::from_json(&"2024-01-01T00:00:00Z".to_json()).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Summarising the shape of a document: which paths it has values at, and
+//! of which types.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+};
+
+use crate::{
+    ast::{escape_pointer_token, Json},
+    dsl::ToJson,
+    map::Map,
+    walk::PathSegment,
+};
+
+/// The types of the values at each path in a document, from
+/// `Json::describe`.
+///
+/// Displays as a line for each path, with the types aligned, and converts
+/// to an object of paths with `ToJson`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Description {
+    /// One for each distinct path, sorted by path.
+    pub paths: Vec<PathDescription>,
+}
+
+/// The types of the values found at a path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathDescription {
+    /// A JSON Pointer with each array index replaced by `[]` after the
+    /// array's path, as in `/users[]/email`. The document itself is `""`.
+    pub path: String,
+    /// Each type name, as from `Json::type_name`, with the number of values
+    /// of that type, most frequent first.
+    pub types: Vec<(&'static str, usize)>,
+}
+
+impl PathDescription {
+    /// Whether values at the path have more than one type other than
+    /// `null`, such as both numbers and strings.
+    pub fn has_conflict(&self) -> bool {
+        self.types
+            .iter()
+            .filter(|(name, _)| *name != "null")
+            .count()
+            > 1
+    }
+}
+
+impl Json {
+    /// Counts the values of each type at each path in the document, treating
+    /// every item of an array as at the same path.
+    pub fn describe(&self) -> Description {
+        let mut counts: BTreeMap<String, BTreeMap<&'static str, usize>> = BTreeMap::new();
+        self.walk(|path, value| {
+            let mut collapsed = String::new();
+            for segment in path.segments() {
+                match segment {
+                    PathSegment::Key(key) => {
+                        collapsed.push('/');
+                        collapsed.push_str(&escape_pointer_token(key));
+                    }
+                    PathSegment::Index(_) => collapsed.push_str("[]"),
+                }
+            }
+            *counts
+                .entry(collapsed)
+                .or_default()
+                .entry(value.type_name())
+                .or_default() += 1;
+        });
+
+        let paths = counts
+            .into_iter()
+            .map(|(path, counts)| {
+                let mut types: Vec<_> = counts.into_iter().collect();
+                types.sort_by(|(_, a), (_, b)| b.cmp(a));
+                PathDescription { path, types }
+            })
+            .collect();
+        Description { paths }
+    }
+}
+
+/// Writes `path: type ×count, …` lines, marking those with conflicting
+/// types. The document itself is written as `(root)`.
+impl fmt::Display for Description {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = |path: &str| {
+            let path = if path.is_empty() { "(root)" } else { path };
+            format!("{path}:")
+        };
+        let width = self
+            .paths
+            .iter()
+            .map(|description| label(&description.path).chars().count())
+            .max()
+            .unwrap_or(0);
+
+        for description in &self.paths {
+            formatter.write_fmt(format_args!("{:width$}", label(&description.path)))?;
+            for (index, (type_name, count)) in description.types.iter().enumerate() {
+                let separator = if index == 0 { " " } else { ", " };
+                formatter.write_fmt(format_args!("{separator}{type_name} ×{count}"))?;
+            }
+            if description.has_conflict() {
+                formatter.write_str("  <- conflicting types")?;
+            }
+            formatter.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+/// An object with a property for each path, holding an object of the count
+/// of each type as `types` and whether they conflict as `conflict`.
+impl ToJson for Description {
+    fn to_json(&self) -> Json {
+        let mut paths = Map::new();
+        for description in &self.paths {
+            let mut types = Map::new();
+            for (type_name, count) in &description.types {
+                types.insert(type_name.to_string(), count.to_json());
+            }
+            paths.insert(
+                description.path.clone(),
+                Json::object()
+                    .set("types", Json::Object(types))
+                    .set("conflict", description.has_conflict()),
+            );
+        }
+        Json::Object(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{describe::PathDescription, dsl::ToJson, parser::parse};
+
+    const USERS: &str = r#"{"users": [
+        {"id": 1, "email": "a@example.com", "tags": ["x"]},
+        {"id": "2", "email": null, "admin": true},
+        {"id": 3, "email": "c@example.com", "tags": [], "address": {"city": "Oslo"}},
+        {"id": 4, "email": "d@example.com", "tags": ["y", 5]}
+    ], "a/b": 1}"#;
+
+    #[test]
+    fn it_merges_the_paths_of_heterogeneous_objects_in_arrays() {
+        let description = parse(USERS).unwrap().describe();
+        let paths: Vec<_> = description
+            .paths
+            .iter()
+            .map(|PathDescription { path, types }| (path.as_str(), types.clone()))
+            .collect();
+
+        assert_eq!(
+            paths,
+            [
+                ("", vec![("object", 1)]),
+                ("/a~1b", vec![("number", 1)]),
+                ("/users", vec![("array", 1)]),
+                ("/users[]", vec![("object", 4)]),
+                ("/users[]/address", vec![("object", 1)]),
+                ("/users[]/address/city", vec![("string", 1)]),
+                ("/users[]/admin", vec![("boolean", 1)]),
+                ("/users[]/email", vec![("string", 3), ("null", 1)]),
+                ("/users[]/id", vec![("number", 3), ("string", 1)]),
+                ("/users[]/tags", vec![("array", 3)]),
+                ("/users[]/tags[]", vec![("string", 2), ("number", 1)]),
+            ]
+        );
+        let conflicts: Vec<_> = description
+            .paths
+            .iter()
+            .filter(|description| description.has_conflict())
+            .map(|description| description.path.as_str())
+            .collect();
+        assert_eq!(conflicts, ["/users[]/id", "/users[]/tags[]"]);
+    }
+
+    #[test]
+    fn it_displays_aligned_lines_marking_conflicts() {
+        assert_eq!(
+            parse(USERS).unwrap().describe().to_string(),
+            "\
+(root):                object ×1
+/a~1b:                 number ×1
+/users:                array ×1
+/users[]:              object ×4
+/users[]/address:      object ×1
+/users[]/address/city: string ×1
+/users[]/admin:        boolean ×1
+/users[]/email:        string ×3, null ×1
+/users[]/id:           number ×3, string ×1  <- conflicting types
+/users[]/tags:         array ×3
+/users[]/tags[]:       string ×2, number ×1  <- conflicting types
+"
+        );
+    }
+
+    #[test]
+    fn it_converts_to_an_object_of_paths() {
+        let description = parse(r#"[{"n": 1}, {"n": "x"}, {"n": null}, 2]"#)
+            .unwrap()
+            .describe();
+
+        assert_eq!(
+            description.to_json(),
+            parse(
+                r#"{
+                    "": {"types": {"array": 1}, "conflict": false},
+                    "[]": {"types": {"object": 3, "number": 1}, "conflict": true},
+                    "[]/n": {"types": {"null": 1, "number": 1, "string": 1}, "conflict": true}
+                }"#
+            )
+            .unwrap()
+        );
+    }
+}
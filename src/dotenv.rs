@@ -0,0 +1,344 @@
+//! Writing an object as `KEY=value` lines for an environment file, which a
+//! POSIX shell can source.
+
+use alloc::{
+    borrow::ToOwned,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::{
+    ast::{push_pointer_token, Json},
+    printer::json_to_compact_string,
+};
+
+/// How `json_to_env_with_options` writes nested values.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EnvOptions {
+    /// Write the properties of nested objects, and the items of arrays, with
+    /// their keys or indexes joined to the key of their parent by `_`, as in
+    /// `DB_HOST=x` for `{"db": {"host": "x"}}`, rather than failing.
+    pub flatten: bool,
+}
+
+/// An error from writing a value which an environment file can't hold.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EnvError {
+    /// The value has this type, but must be an object.
+    NotAnObject(&'static str),
+    /// There is an array or object at this JSON Pointer, and nested values
+    /// aren't being flattened.
+    Nested(String),
+    /// The values at the two JSON Pointers would both be the variable.
+    Collision {
+        pointers: (String, String),
+        name: String,
+    },
+    /// The number at this JSON Pointer is NaN or an infinity, which JSON
+    /// can't represent.
+    NonFiniteNumber(String),
+    /// The string at this JSON Pointer contains a NUL character, which a
+    /// shell variable can't hold.
+    NulCharacter(String),
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("ERROR: Cannot convert JSON to environment variables - ")?;
+        match self {
+            EnvError::NotAnObject(found) => {
+                formatter.write_fmt(format_args!("expected an object, found {found}"))
+            }
+            EnvError::Nested(pointer) => formatter.write_fmt(format_args!(
+                "the value at {pointer:?} is not a string, number, boolean or null"
+            )),
+            EnvError::Collision {
+                pointers: (first, second),
+                name,
+            } => formatter.write_fmt(format_args!(
+                "{first:?} and {second:?} would both be {name}"
+            )),
+            EnvError::NonFiniteNumber(pointer) => formatter.write_fmt(format_args!(
+                "the number at {pointer:?} is not a valid JSON number"
+            )),
+            EnvError::NulCharacter(pointer) => formatter.write_fmt(format_args!(
+                "the string at {pointer:?} contains a NUL character"
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for EnvError {}
+
+/// Writes each property of an object as a `KEY=value` line, failing on
+/// nested arrays and objects.
+///
+/// Keys are uppercased, with each character other than an ASCII letter or
+/// digit replaced by `_`, and `_` before a key which would start with a
+/// digit. Strings with characters a shell would interpret are single-quoted,
+/// numbers and booleans are written as JSON, and `null` as an empty value.
+pub fn json_to_env(value: &Json) -> Result<String, EnvError> {
+    json_to_env_with_options(value, &EnvOptions::default())
+}
+
+pub fn json_to_env_with_options(value: &Json, options: &EnvOptions) -> Result<String, EnvError> {
+    let properties = value
+        .as_object()
+        .ok_or(EnvError::NotAnObject(value.type_name()))?;
+    let mut writer = EnvWriter {
+        options,
+        output: String::new(),
+        pointers: BTreeMap::new(),
+    };
+    for (key, value) in properties {
+        let mut pointer = String::new();
+        push_pointer_token(&mut pointer, key);
+        writer.variable(variable_name(key), value, &pointer)?;
+    }
+    Ok(writer.output)
+}
+
+struct EnvWriter<'a> {
+    options: &'a EnvOptions,
+    output: String,
+    /// The JSON Pointer of the value written as each variable so far.
+    pointers: BTreeMap<String, String>,
+}
+
+impl EnvWriter<'_> {
+    fn variable(&mut self, name: String, value: &Json, pointer: &str) -> Result<(), EnvError> {
+        let children: Option<Vec<(String, &Json)>> = match value {
+            Json::Array(items) => Some(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| (index.to_string(), item))
+                    .collect(),
+            ),
+            Json::Object(properties) => Some(
+                properties
+                    .iter()
+                    .map(|(key, property)| (key.clone(), property))
+                    .collect(),
+            ),
+            _ => None,
+        };
+        if let Some(children) = children {
+            if !self.options.flatten {
+                return Err(EnvError::Nested(pointer.to_owned()));
+            }
+            for (key, child) in children {
+                let mut child_pointer = pointer.to_owned();
+                push_pointer_token(&mut child_pointer, &key);
+                let child_name = format!("{name}_{}", variable_name(&key).trim_start_matches('_'));
+                self.variable(child_name, child, &child_pointer)?;
+            }
+            return Ok(());
+        }
+
+        if let Some(first) = self.pointers.get(&name) {
+            return Err(EnvError::Collision {
+                pointers: (first.clone(), pointer.to_owned()),
+                name,
+            });
+        }
+        self.output.push_str(&name);
+        self.output.push('=');
+        match value {
+            Json::Null => {}
+            Json::String(string) if string.contains('\0') => {
+                return Err(EnvError::NulCharacter(pointer.to_owned()))
+            }
+            Json::String(string) => push_shell_word(string, &mut self.output),
+            scalar => self.output.push_str(
+                &json_to_compact_string(scalar)
+                    .map_err(|_| EnvError::NonFiniteNumber(pointer.to_owned()))?,
+            ),
+        }
+        self.output.push('\n');
+        self.pointers.insert(name, pointer.to_owned());
+        Ok(())
+    }
+}
+
+/// Converts a key to the name of a variable: uppercase ASCII letters, digits
+/// and `_`, not starting with a digit.
+fn variable_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Appends a string as it is if a shell would read it back unchanged, or
+/// else in single quotes, in which only a single quote needs escaping.
+fn push_shell_word(string: &str, output: &mut String) {
+    let is_plain = string
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-.,/:@%+".contains(c));
+    if is_plain {
+        output.push_str(string);
+    } else {
+        output.push('\'');
+        output.push_str(&string.replace('\'', r"'\''"));
+        output.push('\'');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use crate::{
+        ast::Json,
+        dotenv::{json_to_env, json_to_env_with_options, EnvError, EnvOptions},
+        dsl::ToJson,
+        parser::parse,
+    };
+
+    /// Sources the environment file in `sh`, giving the values of the
+    /// variables.
+    fn sourced(env: &str, names: &[&str]) -> Vec<String> {
+        let printed: Vec<_> = names
+            .iter()
+            .map(|name| format!("printf '%s\\0' \"${name}\""))
+            .collect();
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("set -eu\n{env}{}", printed.join("\n")))
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let mut values: Vec<_> = String::from_utf8(output.stdout)
+            .unwrap()
+            .split('\0')
+            .map(str::to_owned)
+            .collect();
+        values.pop();
+        values
+    }
+
+    #[test]
+    fn it_writes_a_line_for_each_property() {
+        let document = parse(
+            r#"{"name": "api", "port": 8080, "ratio": 0.25, "debug": false, "token": null,
+                "log-level": "info", "2fa": true, "": "empty key", "url": "https://x.io/a?b=c"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            json_to_env(&document).unwrap(),
+            "NAME=api\nPORT=8080\nRATIO=0.25\nDEBUG=false\nTOKEN=\nLOG_LEVEL=info\n\
+             _2FA=true\n_='empty key'\nURL='https://x.io/a?b=c'\n"
+        );
+    }
+
+    #[test]
+    fn tricky_values_survive_being_sourced_by_sh() {
+        let values = [
+            "plain",
+            "two words",
+            "it's \"quoted\"",
+            "$HOME and ${PATH} and `id` and $(id)",
+            "line one\nline two\n",
+            "back\\slash \\n",
+            "'",
+            "''",
+            "*.rs ~ ~/x [a] #comment; a && b | c > d < e",
+            "tab\tand\r carriage",
+            "",
+            "é 😀",
+        ];
+        let document: Json = values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (format!("v{index}"), value.to_json()))
+            .collect();
+        let names: Vec<_> = (0..values.len()).map(|index| format!("V{index}")).collect();
+        let names: Vec<_> = names.iter().map(String::as_str).collect();
+
+        let env = json_to_env(&document).unwrap();
+
+        assert_eq!(sourced(&env, &names), values);
+        assert!(env.contains(r#"V2='it'\''s "quoted"'"#));
+    }
+
+    #[test]
+    fn nested_values_fail_or_are_flattened() {
+        let document = parse(
+            r#"{"db": {"host": "localhost", "port": 5432, "replica": {"hosts": ["a", "b c"]}}, "x": 1}"#,
+        )
+        .unwrap();
+        let flatten = EnvOptions { flatten: true };
+
+        assert_eq!(
+            json_to_env(&document),
+            Err(EnvError::Nested("/db".to_owned()))
+        );
+        let env = json_to_env_with_options(&document, &flatten).unwrap();
+        assert_eq!(
+            env,
+            "DB_HOST=localhost\nDB_PORT=5432\nDB_REPLICA_HOSTS_0=a\nDB_REPLICA_HOSTS_1='b c'\nX=1\n"
+        );
+        assert_eq!(
+            sourced(&env, &["DB_REPLICA_HOSTS_1", "DB_PORT"]),
+            ["b c", "5432"]
+        );
+    }
+
+    #[test]
+    fn names_which_collide_fail() {
+        let collide = |json: &str, options: &EnvOptions| {
+            json_to_env_with_options(&parse(json).unwrap(), options).unwrap_err()
+        };
+
+        assert_eq!(
+            collide(r#"{"api-key": 1, "API_KEY": 2}"#, &EnvOptions::default()),
+            EnvError::Collision {
+                pointers: ("/api-key".to_owned(), "/API_KEY".to_owned()),
+                name: "API_KEY".to_owned(),
+            }
+        );
+        assert_eq!(
+            collide(
+                r#"{"db": {"host": "a"}, "db_host": "b"}"#,
+                &EnvOptions { flatten: true }
+            )
+            .to_string(),
+            r#"ERROR: Cannot convert JSON to environment variables - "/db/host" and "/db_host" would both be DB_HOST"#
+        );
+    }
+
+    #[test]
+    fn it_fails_on_values_a_shell_cannot_hold() {
+        assert_eq!(
+            json_to_env(&vec![1].to_json()),
+            Err(EnvError::NotAnObject("array"))
+        );
+        assert_eq!(
+            json_to_env(&Json::object().set("n", f64::NAN)),
+            Err(EnvError::NonFiniteNumber("/n".to_owned()))
+        );
+        assert_eq!(
+            json_to_env(&Json::object().set("a/b", "x\0y")),
+            Err(EnvError::NulCharacter("/a~1b".to_owned()))
+        );
+    }
+}
@@ -1,11 +1,102 @@
-use std::collections::{BTreeMap, HashMap};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6},
+};
+#[cfg(feature = "std")]
+use std::{
+    collections::{HashMap, HashSet},
+    error,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
 
-use crate::ast::Json;
+use crate::{
+    ast::{escape_pointer_token, integer_to_json, Json},
+    map::Map,
+};
 
 pub trait ToJson {
     fn to_json(&self) -> Json;
 }
 
+/// Implements `ToJson` for a struct, converting it to an object with a
+/// property for each listed field, in the order listed. A field can be
+/// renamed with `=> "name"`, and a field marked with `?`, which must be an
+/// `Option`, is left out when it is `None`, as `Json::set_opt` does, rather
+/// than being `null`.
+///
+/// ```
+/// use json_pretty_printer::{dsl::ToJson, impl_to_json, parser::parse};
+///
+/// struct ServerConfig {
+///     host: String,
+///     port: u16,
+///     tls_enabled: bool,
+///     cert_path: Option<String>,
+/// }
+///
+/// impl_to_json!(struct ServerConfig { host, port => "listen_port", tls_enabled, cert_path? });
+///
+/// let config = ServerConfig {
+///     host: "localhost".to_owned(),
+///     port: 8080,
+///     tls_enabled: false,
+///     cert_path: None,
+/// };
+///
+/// assert_eq!(
+///     config.to_json(),
+///     parse(r#"{"host": "localhost", "listen_port": 8080, "tls_enabled": false}"#).unwrap()
+/// );
+/// ```
+#[macro_export]
+macro_rules! impl_to_json {
+    (struct $type:ident { $($fields:tt)* }) => {
+        impl $crate::dsl::ToJson for $type {
+            fn to_json(&self) -> $crate::ast::Json {
+                let mut json = $crate::ast::Json::object();
+                $crate::impl_to_json!(@fields self json $($fields)*);
+                json
+            }
+        }
+    };
+    (@fields $self:ident $json:ident) => {};
+    (@fields $self:ident $json:ident
+        $field:ident ? $(=> $name:literal)? $(, $($rest:tt)*)?) => {
+        $json = $json.set_opt(
+            $crate::impl_to_json!(@name $field $($name)?),
+            $self.$field.as_ref(),
+        );
+        $crate::impl_to_json!(@fields $self $json $($($rest)*)?);
+    };
+    (@fields $self:ident $json:ident
+        $field:ident $(=> $name:literal)? $(, $($rest:tt)*)?) => {
+        $json = $json.set($crate::impl_to_json!(@name $field $($name)?), &$self.$field);
+        $crate::impl_to_json!(@fields $self $json $($($rest)*)?);
+    };
+    (@name $field:ident) => {
+        stringify!($field)
+    };
+    (@name $field:ident $name:literal) => {
+        $name
+    };
+}
+
+/// The largest integer such that it and every integer nearer zero are held
+/// exactly by an `f64`, and so by a JSON number: 2^53 - 1.
+pub const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
 impl ToJson for Json {
     fn to_json(&self) -> Json {
         self.clone()
@@ -30,12 +121,111 @@ impl ToJson for str {
     }
 }
 
-impl ToJson for &str {
+/// Converts an OS string to a string, replacing anything which isn't valid
+/// UTF-8 with U+FFFD, like `OsStr::to_string_lossy`.
+#[cfg(feature = "std")]
+impl ToJson for OsStr {
+    fn to_json(&self) -> Json {
+        Json::String(self.to_string_lossy().into_owned())
+    }
+}
+
+/// Like `OsStr`, replaces anything which isn't valid UTF-8 with U+FFFD.
+#[cfg(feature = "std")]
+impl ToJson for OsString {
+    fn to_json(&self) -> Json {
+        self.as_os_str().to_json()
+    }
+}
+
+/// Converts a path to a string as it is, separators included, replacing
+/// anything which isn't valid UTF-8 with U+FFFD like `OsStr`.
+#[cfg(feature = "std")]
+impl ToJson for Path {
+    fn to_json(&self) -> Json {
+        self.as_os_str().to_json()
+    }
+}
+
+/// Like `Path`, replaces anything which isn't valid UTF-8 with U+FFFD.
+#[cfg(feature = "std")]
+impl ToJson for PathBuf {
+    fn to_json(&self) -> Json {
+        self.as_os_str().to_json()
+    }
+}
+
+/// Converts an address to a string in the form its `Display` gives, such as
+/// `192.168.1.1` or `::1`, which its `FromStr` reads back.
+impl ToJson for IpAddr {
+    fn to_json(&self) -> Json {
+        Json::String(self.to_string())
+    }
+}
+
+impl ToJson for Ipv4Addr {
+    fn to_json(&self) -> Json {
+        Json::String(self.to_string())
+    }
+}
+
+impl ToJson for Ipv6Addr {
     fn to_json(&self) -> Json {
         Json::String(self.to_string())
     }
 }
 
+/// Converts an address to a string in the form its `Display` gives, such as
+/// `10.0.0.1:53`, or `[::1]:8080` with the IPv6 address in brackets, which its
+/// `FromStr` reads back.
+impl ToJson for SocketAddr {
+    fn to_json(&self) -> Json {
+        Json::String(self.to_string())
+    }
+}
+
+impl ToJson for SocketAddrV4 {
+    fn to_json(&self) -> Json {
+        Json::String(self.to_string())
+    }
+}
+
+impl ToJson for SocketAddrV6 {
+    fn to_json(&self) -> Json {
+        Json::String(self.to_string())
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Rc<T> {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Arc<T> {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ToOwned + ?Sized> ToJson for Cow<'_, T> {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
 impl ToJson for f64 {
     fn to_json(&self) -> Json {
         Json::Number(*self)
@@ -54,18 +244,121 @@ impl ToJson for i32 {
     }
 }
 
+impl ToJson for i8 {
+    fn to_json(&self) -> Json {
+        Json::Number(f64::from(*self))
+    }
+}
+
+impl ToJson for u8 {
+    fn to_json(&self) -> Json {
+        Json::Number(f64::from(*self))
+    }
+}
+
+impl ToJson for i16 {
+    fn to_json(&self) -> Json {
+        Json::Number(f64::from(*self))
+    }
+}
+
+impl ToJson for u16 {
+    fn to_json(&self) -> Json {
+        Json::Number(f64::from(*self))
+    }
+}
+
+impl ToJson for u32 {
+    fn to_json(&self) -> Json {
+        Json::Number(f64::from(*self))
+    }
+}
+
+/// Converts integers no bigger than `MAX_SAFE_INTEGER` either way to numbers,
+/// and any other integer to a `Json::BigInteger`, so that none are rounded.
+impl ToJson for i64 {
+    fn to_json(&self) -> Json {
+        integer_to_json((*self).into())
+    }
+}
+
+/// Like `i64`, keeps integers bigger than `MAX_SAFE_INTEGER` exactly.
+impl ToJson for u64 {
+    fn to_json(&self) -> Json {
+        integer_to_json((*self).into())
+    }
+}
+
+/// Like `i64`, keeps integers bigger than `MAX_SAFE_INTEGER` either way exactly.
+impl ToJson for isize {
+    fn to_json(&self) -> Json {
+        integer_to_json(*self as i128)
+    }
+}
+
+/// Like `i64`, keeps integers bigger than `MAX_SAFE_INTEGER` exactly.
+impl ToJson for usize {
+    fn to_json(&self) -> Json {
+        integer_to_json(*self as i128)
+    }
+}
+
 impl<T: ToJson> ToJson for [T] {
     fn to_json(&self) -> Json {
         Json::Array(self.iter().map(ToJson::to_json).collect())
     }
 }
 
+impl<T: ToJson, const N: usize> ToJson for [T; N] {
+    fn to_json(&self) -> Json {
+        Json::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
 impl<T: ToJson> ToJson for Vec<T> {
     fn to_json(&self) -> Json {
         Json::Array(self.iter().map(ToJson::to_json).collect())
     }
 }
 
+/// Converts a set to an array in the set's order.
+impl<T: ToJson> ToJson for BTreeSet<T> {
+    fn to_json(&self) -> Json {
+        Json::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+/// Converts a set to an array sorted in the order of `Json::total_cmp`, so
+/// that the order doesn't depend on the hasher.
+#[cfg(feature = "std")]
+impl<T: ToJson, S> ToJson for HashSet<T, S> {
+    fn to_json(&self) -> Json {
+        let mut items: Vec<_> = self.iter().map(ToJson::to_json).collect();
+        items.sort_unstable();
+        Json::Array(items)
+    }
+}
+
+/// Converts a tuple to an array. Pairs aren't converted, so that a slice of
+/// `(&str, T)` pairs can be converted to an object instead.
+impl<A: ToJson, B: ToJson, C: ToJson> ToJson for (A, B, C) {
+    fn to_json(&self) -> Json {
+        Json::Array(vec![self.0.to_json(), self.1.to_json(), self.2.to_json()])
+    }
+}
+
+/// Converts a tuple to an array.
+impl<A: ToJson, B: ToJson, C: ToJson, D: ToJson> ToJson for (A, B, C, D) {
+    fn to_json(&self) -> Json {
+        Json::Array(vec![
+            self.0.to_json(),
+            self.1.to_json(),
+            self.2.to_json(),
+            self.3.to_json(),
+        ])
+    }
+}
+
 impl<T: ToJson> ToJson for Option<T> {
     fn to_json(&self) -> Json {
         match self {
@@ -85,31 +378,481 @@ impl<T: ToJson> ToJson for [(&str, T)] {
     }
 }
 
-impl<T: ToJson> ToJson for BTreeMap<&str, T> {
+/// Converts a map with keys such as `String` or `&str` to an object with its
+/// properties in the map's order.
+impl<K: AsRef<str>, T: ToJson> ToJson for BTreeMap<K, T> {
     fn to_json(&self) -> Json {
         Json::Object(
             self.iter()
-                .map(|(key, value)| ((*key).to_owned(), value.to_json()))
+                .map(|(key, value)| (key.as_ref().to_owned(), value.to_json()))
                 .collect(),
         )
     }
 }
 
-impl<T: ToJson> ToJson for HashMap<&str, T> {
+/// Converts a map with keys such as `String` or `&str` to an object with its
+/// properties sorted by key, so that the order doesn't depend on the hasher.
+#[cfg(feature = "std")]
+impl<K: AsRef<str>, T: ToJson, S> ToJson for HashMap<K, T, S> {
     fn to_json(&self) -> Json {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.as_ref().cmp(b.as_ref()));
         Json::Object(
-            self.iter()
-                .map(|(key, value)| ((*key).to_owned(), value.to_json()))
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.as_ref().to_owned(), value.to_json()))
                 .collect(),
         )
     }
 }
 
+/// The `From` conversions give the same values as `ToJson`. Vectors and
+/// options convert through `Into<Json>`, so they cover `Vec<Json>` and
+/// `Option<Json>` too.
+impl From<bool> for Json {
+    fn from(value: bool) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<f64> for Json {
+    fn from(value: f64) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<f32> for Json {
+    fn from(value: f32) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<i8> for Json {
+    fn from(value: i8) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<u8> for Json {
+    fn from(value: u8) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<i16> for Json {
+    fn from(value: i16) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<u16> for Json {
+    fn from(value: u16) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<i32> for Json {
+    fn from(value: i32) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<u32> for Json {
+    fn from(value: u32) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<i64> for Json {
+    fn from(value: i64) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<u64> for Json {
+    fn from(value: u64) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<isize> for Json {
+    fn from(value: isize) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Json {
+        value.to_json()
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Json {
+        Json::String(value.to_owned())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Json {
+        Json::String(value)
+    }
+}
+
+impl From<Map> for Json {
+    fn from(properties: Map) -> Json {
+        Json::Object(properties)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(items: Vec<T>) -> Json {
+        Json::Array(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Json {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+/// Formats a value which converts to JSON with `Display` as `Json` does,
+/// indented by two spaces, or by the width, as in `{:4}`, and with `Debug`
+/// on one line, as in `println!("{}", Pretty(&config))`. Like `Json`, it
+/// writes non-finite numbers as `null`.
+#[derive(Clone, Copy)]
+pub struct Pretty<T>(pub T);
+
+impl<T: ToJson> Pretty<T> {
+    /// Sets the number of spaces to indent by.
+    pub fn with_indent(self, indent: u64) -> PrettyIndented<T> {
+        PrettyIndented {
+            value: self.0,
+            indent,
+        }
+    }
+}
+
+impl<T: ToJson> fmt::Display for Pretty<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0.to_json(), formatter)
+    }
+}
+
+impl<T: ToJson> fmt::Debug for Pretty<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_fmt(format_args!("{:#}", self.0.to_json()))
+    }
+}
+
+/// A `Pretty` value with its own indent.
+#[derive(Clone, Copy)]
+pub struct PrettyIndented<T> {
+    value: T,
+    indent: u64,
+}
+
+impl<T: ToJson> fmt::Display for PrettyIndented<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let indent = self.indent as usize;
+        formatter.write_fmt(format_args!("{:indent$}", self.value.to_json()))
+    }
+}
+
+impl<T: ToJson> fmt::Debug for PrettyIndented<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_fmt(format_args!("{:#}", self.value.to_json()))
+    }
+}
+
+/// Like `Pretty`, but formats on one line with both `Display` and `Debug`.
+#[derive(Clone, Copy)]
+pub struct Compact<T>(pub T);
+
+impl<T: ToJson> fmt::Display for Compact<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_fmt(format_args!("{:#}", self.0.to_json()))
+    }
+}
+
+impl<T: ToJson> fmt::Debug for Compact<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_fmt(format_args!("{:#}", self.0.to_json()))
+    }
+}
+
+/// Converting a value back out of JSON, the reverse of `ToJson`.
+pub trait FromJson: Sized {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError>;
+}
+
+/// An error from converting JSON to a value of another type, giving the JSON
+/// Pointer of the part which didn't fit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FromJsonError {
+    pub pointer: String,
+    /// What the value should have been, such as `string` or `u8`.
+    pub expected: String,
+    /// What the value was: its type, or the number if it was out of range.
+    pub found: String,
+}
+
+impl FromJsonError {
+    pub(crate) fn new(json: &Json, expected: impl Into<String>) -> FromJsonError {
+        let found = match json {
+            Json::Number(number) => format!("number {number}"),
+            Json::BigInteger(integer) => format!("number {integer}"),
+            other => other.type_name().to_owned(),
+        };
+        FromJsonError {
+            pointer: String::new(),
+            expected: expected.into(),
+            found,
+        }
+    }
+
+    /// Moves the error down into the child of a value with the key or index.
+    fn within(mut self, segment: impl fmt::Display) -> FromJsonError {
+        let segment = escape_pointer_token(&segment.to_string());
+        self.pointer = format!("/{segment}{}", self.pointer);
+        self
+    }
+}
+
+impl fmt::Display for FromJsonError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let location = match self.pointer.as_str() {
+            "" => "the root",
+            pointer => pointer,
+        };
+        formatter.write_fmt(format_args!(
+            "ERROR: Cannot convert JSON - expected {} at {location}, found {}",
+            self.expected, self.found
+        ))
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for FromJsonError {}
+
+impl FromJson for Json {
+    fn from_json(json: &Json) -> Result<Json, FromJsonError> {
+        Ok(json.clone())
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(json: &Json) -> Result<bool, FromJsonError> {
+        json.as_bool()
+            .ok_or_else(|| FromJsonError::new(json, "boolean"))
+    }
+}
+
+impl FromJson for String {
+    fn from_json(json: &Json) -> Result<String, FromJsonError> {
+        json.as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| FromJsonError::new(json, "string"))
+    }
+}
+
+impl FromJson for f64 {
+    fn from_json(json: &Json) -> Result<f64, FromJsonError> {
+        json.as_f64()
+            .ok_or_else(|| FromJsonError::new(json, "number"))
+    }
+}
+
+impl FromJson for i8 {
+    fn from_json(json: &Json) -> Result<i8, FromJsonError> {
+        json.as_i64()
+            .and_then(|integer| integer.try_into().ok())
+            .ok_or_else(|| FromJsonError::new(json, "i8"))
+    }
+}
+
+impl FromJson for u8 {
+    fn from_json(json: &Json) -> Result<u8, FromJsonError> {
+        json.as_i64()
+            .and_then(|integer| integer.try_into().ok())
+            .ok_or_else(|| FromJsonError::new(json, "u8"))
+    }
+}
+
+impl FromJson for i16 {
+    fn from_json(json: &Json) -> Result<i16, FromJsonError> {
+        json.as_i64()
+            .and_then(|integer| integer.try_into().ok())
+            .ok_or_else(|| FromJsonError::new(json, "i16"))
+    }
+}
+
+impl FromJson for u16 {
+    fn from_json(json: &Json) -> Result<u16, FromJsonError> {
+        json.as_i64()
+            .and_then(|integer| integer.try_into().ok())
+            .ok_or_else(|| FromJsonError::new(json, "u16"))
+    }
+}
+
+impl FromJson for i32 {
+    fn from_json(json: &Json) -> Result<i32, FromJsonError> {
+        json.as_i64()
+            .and_then(|integer| integer.try_into().ok())
+            .ok_or_else(|| FromJsonError::new(json, "i32"))
+    }
+}
+
+impl FromJson for u32 {
+    fn from_json(json: &Json) -> Result<u32, FromJsonError> {
+        json.as_i64()
+            .and_then(|integer| integer.try_into().ok())
+            .ok_or_else(|| FromJsonError::new(json, "u32"))
+    }
+}
+
+impl FromJson for i64 {
+    fn from_json(json: &Json) -> Result<i64, FromJsonError> {
+        json.as_i64().ok_or_else(|| FromJsonError::new(json, "i64"))
+    }
+}
+
+impl FromJson for u64 {
+    fn from_json(json: &Json) -> Result<u64, FromJsonError> {
+        json.as_u64().ok_or_else(|| FromJsonError::new(json, "u64"))
+    }
+}
+
+impl FromJson for isize {
+    fn from_json(json: &Json) -> Result<isize, FromJsonError> {
+        json.as_i64()
+            .and_then(|integer| integer.try_into().ok())
+            .ok_or_else(|| FromJsonError::new(json, "isize"))
+    }
+}
+
+impl FromJson for usize {
+    fn from_json(json: &Json) -> Result<usize, FromJsonError> {
+        json.as_usize()
+            .ok_or_else(|| FromJsonError::new(json, "usize"))
+    }
+}
+
+/// Converts `null` to `None`.
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(json: &Json) -> Result<Option<T>, FromJsonError> {
+        match json {
+            Json::Null => Ok(None),
+            json => T::from_json(json).map(Some),
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(json: &Json) -> Result<Vec<T>, FromJsonError> {
+        let items = json
+            .as_array()
+            .ok_or_else(|| FromJsonError::new(json, "array"))?;
+        items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| T::from_json(item).map_err(|error| error.within(index)))
+            .collect()
+    }
+}
+
+impl<T: FromJson> FromJson for BTreeMap<String, T> {
+    fn from_json(json: &Json) -> Result<BTreeMap<String, T>, FromJsonError> {
+        let properties = json
+            .as_object()
+            .ok_or_else(|| FromJsonError::new(json, "object"))?;
+        properties
+            .iter()
+            .map(|(key, value)| match T::from_json(value) {
+                Ok(value) => Ok((key.clone(), value)),
+                Err(error) => Err(error.within(key)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: FromJson> FromJson for HashMap<String, T> {
+    fn from_json(json: &Json) -> Result<HashMap<String, T>, FromJsonError> {
+        BTreeMap::from_json(json).map(|properties| properties.into_iter().collect())
+    }
+}
+
+/// Gets the items of an array which should have a fixed length.
+fn tuple_items(json: &Json, length: usize) -> Result<&[Json], FromJsonError> {
+    let expected = || format!("array of {length} items");
+    match json {
+        Json::Array(items) if items.len() == length => Ok(items),
+        Json::Array(items) => Err(FromJsonError {
+            found: format!("array of {} items", items.len()),
+            ..FromJsonError::new(json, expected())
+        }),
+        json => Err(FromJsonError::new(json, expected())),
+    }
+}
+
+/// Converts an array of exactly 2 items to a tuple.
+impl<A: FromJson, B: FromJson> FromJson for (A, B) {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let items = tuple_items(json, 2)?;
+        Ok((
+            A::from_json(&items[0]).map_err(|error| error.within(0))?,
+            B::from_json(&items[1]).map_err(|error| error.within(1))?,
+        ))
+    }
+}
+
+/// Converts an array of exactly 3 items to a tuple.
+impl<A: FromJson, B: FromJson, C: FromJson> FromJson for (A, B, C) {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let items = tuple_items(json, 3)?;
+        Ok((
+            A::from_json(&items[0]).map_err(|error| error.within(0))?,
+            B::from_json(&items[1]).map_err(|error| error.within(1))?,
+            C::from_json(&items[2]).map_err(|error| error.within(2))?,
+        ))
+    }
+}
+
+/// Converts an array of exactly 4 items to a tuple.
+impl<A: FromJson, B: FromJson, C: FromJson, D: FromJson> FromJson for (A, B, C, D) {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let items = tuple_items(json, 4)?;
+        Ok((
+            A::from_json(&items[0]).map_err(|error| error.within(0))?,
+            B::from_json(&items[1]).map_err(|error| error.within(1))?,
+            C::from_json(&items[2]).map_err(|error| error.within(2))?,
+            D::from_json(&items[3]).map_err(|error| error.within(3))?,
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::collections::{BTreeMap, HashMap};
+    use std::{
+        borrow::Cow,
+        collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+        ffi::{OsStr, OsString},
+        net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6},
+        path::{Path, PathBuf},
+        rc::Rc,
+        sync::Arc,
+    };
 
-    use crate::{ast::Json, dsl::ToJson};
+    use crate::{
+        ast::Json,
+        dsl::{Compact, FromJson, FromJsonError, Pretty, ToJson, MAX_SAFE_INTEGER},
+        from_json_str,
+        map::Map,
+        printer::{json_to_compact_string, json_to_string},
+        to_compact_json_string,
+    };
 
     #[test]
     fn a_bool_is_converted_to_a_json_boolean() {
@@ -133,6 +876,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn references_and_smart_pointers_convert_like_their_contents() {
+        let json = Json::object().set("a", 1);
+
+        assert_eq!((&&5).to_json(), Json::Number(5.0));
+        assert_eq!(Box::new(true).to_json(), Json::Boolean(true));
+        assert_eq!(Rc::new(json.clone()).to_json(), json);
+        assert_eq!(Arc::<str>::from("shared").to_json(), "shared".to_json());
+        assert_eq!(Cow::Borrowed("cow").to_json(), "cow".to_json());
+        assert_eq!(
+            Cow::<str>::Owned("cow".to_owned()).to_json(),
+            "cow".to_json()
+        );
+        assert_eq!(Box::<[i32]>::from([1, 2]).to_json(), vec![1, 2].to_json());
+    }
+
+    #[test]
+    fn containers_of_references_convert_naturally() {
+        let json = Json::object().set("a", 1);
+        let strings = vec!["a", "b"];
+        let values = vec![&json, &json];
+        let name = "x".to_owned();
+        let names = BTreeMap::from([("first", Some(&name))]);
+
+        assert_eq!(strings.to_json(), Json::array().push("a").push("b"));
+        assert_eq!(values.to_json(), Json::array().push(&json).push(&json));
+        assert_eq!(names.to_json(), Json::object().set("first", "x"));
+    }
+
     #[test]
     fn a_f64_is_converted_to_a_json_number() {
         assert_eq!(123.456.to_json(), Json::Number(123.456));
@@ -140,12 +912,58 @@ mod tests {
 
     #[test]
     fn a_f32_is_converted_to_a_json_number() {
-        assert_eq!((123.0 as f32).to_json(), Json::Number(123.0));
+        assert_eq!(123.0_f32.to_json(), Json::Number(123.0));
     }
 
     #[test]
     fn an_i32_is_converted_to_a_json_number() {
-        assert_eq!((123 as i32).to_json(), Json::Number(123.0));
+        assert_eq!(123_i32.to_json(), Json::Number(123.0));
+    }
+
+    #[test]
+    fn small_integer_types_are_converted_exactly() {
+        assert_eq!(i8::MIN.to_json(), Json::Number(-128.0));
+        assert_eq!(u8::MAX.to_json(), Json::Number(255.0));
+        assert_eq!(i16::MIN.to_json(), Json::Number(-32768.0));
+        assert_eq!(u16::MAX.to_json(), Json::Number(65535.0));
+        assert_eq!(u32::MAX.to_json(), Json::Number(4294967295.0));
+        assert_eq!(i32::MIN.to_json(), Json::Number(-2147483648.0));
+    }
+
+    #[test]
+    fn large_integer_types_are_converted_within_the_safe_range() {
+        let max_safe = MAX_SAFE_INTEGER;
+
+        assert_eq!(42_u64.to_json(), Json::Number(42.0));
+        assert_eq!((-7_isize).to_json(), Json::Number(-7.0));
+        assert_eq!(3_usize.to_json(), Json::Number(3.0));
+        assert_eq!(max_safe.to_json(), Json::Number(9007199254740991.0));
+        assert_eq!((-max_safe).to_json(), Json::Number(-9007199254740991.0));
+        assert_eq!(
+            (max_safe as u64).to_json(),
+            Json::Number(9007199254740991.0)
+        );
+        assert_eq!((max_safe - 1).to_json().as_i64(), Some(max_safe - 1));
+    }
+
+    #[test]
+    fn large_integer_types_are_kept_exactly_outside_the_safe_range() {
+        let max_safe = MAX_SAFE_INTEGER;
+
+        assert_eq!((max_safe + 1).to_json(), Json::BigInteger(1 << 53));
+        assert_eq!(
+            (max_safe as u64 + 2).to_json(),
+            Json::BigInteger((1 << 53) + 1)
+        );
+        assert_eq!((-max_safe - 1).to_json(), Json::BigInteger(-(1 << 53)));
+        assert_eq!(u64::MAX.to_json(), Json::BigInteger(u64::MAX.into()));
+        assert_eq!(i64::MIN.to_json(), Json::BigInteger(i64::MIN.into()));
+        assert_eq!(usize::MAX.to_json(), Json::BigInteger(usize::MAX as i128));
+        assert_eq!(
+            json_to_compact_string(&u64::MAX.to_json()).unwrap(),
+            "18446744073709551615"
+        );
+        assert_eq!(u64::from_json(&u64::MAX.to_json()), Ok(u64::MAX));
     }
 
     #[test]
@@ -160,6 +978,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn a_fixed_size_array_satisfies_a_to_json_bound() {
+        fn convert<T: ToJson>(value: T) -> Json {
+            value.to_json()
+        }
+
+        assert_eq!(convert([true; 3]), vec![true, true, true].to_json());
+        assert_eq!(convert([1_u8; 4]), vec![1, 1, 1, 1].to_json());
+        assert_eq!(
+            convert([[1, 2], [3, 4]]),
+            vec![vec![1, 2], vec![3, 4]].to_json()
+        );
+        assert_eq!(convert::<[i32; 0]>([]), Json::array());
+    }
+
     #[test]
     fn a_vec_is_converted_to_a_json_array() {
         assert_eq!(
@@ -187,7 +1020,7 @@ mod tests {
     fn an_array_of_key_value_pairs_is_converted_to_a_json_object() {
         assert_eq!(
             [("foo", 12), ("bar", 34),].to_json(),
-            Json::Object(BTreeMap::from([
+            Json::Object(Map::from([
                 ("foo".to_owned(), Json::Number(12.0)),
                 ("bar".to_owned(), Json::Number(34.0))
             ]))
@@ -198,7 +1031,7 @@ mod tests {
     fn a_btree_map_with_string_keys_is_converted_to_a_json_object() {
         assert_eq!(
             BTreeMap::from([("foo", 12), ("bar", 34)]).to_json(),
-            Json::Object(BTreeMap::from([
+            Json::Object(Map::from([
                 ("foo".to_owned(), Json::Number(12.0)),
                 ("bar".to_owned(), Json::Number(34.0))
             ]))
@@ -209,10 +1042,455 @@ mod tests {
     fn a_hash_map_with_string_keys_is_converted_to_a_json_object() {
         assert_eq!(
             HashMap::from([("foo", 12), ("bar", 34)]).to_json(),
-            Json::Object(BTreeMap::from([
+            Json::Object(Map::from([
                 ("foo".to_owned(), Json::Number(12.0)),
                 ("bar".to_owned(), Json::Number(34.0))
             ]))
         );
     }
+
+    #[test]
+    fn maps_with_owned_string_keys_are_converted_to_json_objects() {
+        let btree = BTreeMap::from([("b".to_owned(), true), ("a".to_owned(), false)]);
+        let hash = HashMap::from([("b".to_owned(), 2), ("a".to_owned(), 1)]);
+
+        assert_eq!(
+            btree.to_json(),
+            Json::object().set("a", false).set("b", true)
+        );
+        assert_eq!(hash.to_json(), Json::object().set("a", 1).set("b", 2));
+    }
+
+    #[test]
+    fn hash_maps_are_converted_with_their_keys_sorted() {
+        let keys = ["m", "z", "a", "k", "b", "y", "c", "x"];
+        let map: HashMap<_, _> = keys.iter().map(|key| (*key, 0)).collect();
+
+        let json = map.to_json();
+
+        let converted: Vec<_> = json.keys().collect();
+        assert_eq!(converted, ["a", "b", "c", "k", "m", "x", "y", "z"]);
+    }
+
+    #[test]
+    fn nested_maps_are_converted_through_composition() {
+        let daily = BTreeMap::from([("mon", vec![1, 2]), ("tue", vec![])]);
+        let metrics = HashMap::from([("daily".to_owned(), daily)]);
+        let document = BTreeMap::from([("metrics", metrics)]);
+
+        assert_eq!(
+            document.to_json(),
+            Json::object().set(
+                "metrics",
+                Json::object().set(
+                    "daily",
+                    Json::object()
+                        .set("mon", vec![1, 2])
+                        .set("tue", Json::array())
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn a_btree_set_is_converted_to_a_json_array_in_order() {
+        assert_eq!(
+            BTreeSet::from(["b", "c", "a"]).to_json(),
+            vec!["a", "b", "c"].to_json()
+        );
+    }
+
+    #[test]
+    fn a_hash_set_is_converted_to_a_json_array_sorted_by_value() {
+        let tags: HashSet<_> = ["rust", "json", "cli", "fmt", "api", "zip"].into();
+        let numbers: HashSet<_> = [10, -3, 7, 0, 255].into();
+        let options: HashSet<_> = [Some(2), None, Some(1)].into();
+
+        assert_eq!(
+            tags.to_json(),
+            vec!["api", "cli", "fmt", "json", "rust", "zip"].to_json()
+        );
+        assert_eq!(numbers.to_json(), vec![-3, 0, 7, 10, 255].to_json());
+        assert_eq!(
+            options.to_json(),
+            Json::array().push(Json::Null).push(1).push(2)
+        );
+        assert_eq!(
+            tags.to_json().to_string(),
+            tags.iter()
+                .copied()
+                .collect::<HashSet<_>>()
+                .to_json()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn scalars_are_converted_from_json_of_their_type() {
+        assert_eq!(bool::from_json(&true.to_json()), Ok(true));
+        assert_eq!(String::from_json(&"a".to_json()), Ok("a".to_owned()));
+        assert_eq!(f64::from_json(&1.5.to_json()), Ok(1.5));
+        assert_eq!(u8::from_json(&255.to_json()), Ok(255));
+        assert_eq!(i64::from_json(&(-3).to_json()), Ok(-3));
+        assert_eq!(Json::from_json(&Json::Null), Ok(Json::Null));
+        assert_eq!(Option::<bool>::from_json(&Json::Null), Ok(None));
+        assert_eq!(Option::<bool>::from_json(&false.to_json()), Ok(Some(false)));
+    }
+
+    #[test]
+    fn containers_are_converted_from_json_arrays_and_objects() {
+        let json = Json::object().set("a", vec![1, 2]).set("b", Json::array());
+
+        assert_eq!(
+            BTreeMap::<String, Vec<u32>>::from_json(&json),
+            Ok(BTreeMap::from([
+                ("a".to_owned(), vec![1, 2]),
+                ("b".to_owned(), vec![])
+            ]))
+        );
+        assert_eq!(
+            HashMap::<String, Json>::from_json(&json).map(|map| map.len()),
+            Ok(2)
+        );
+        assert_eq!(
+            <(String, bool)>::from_json(&Json::array().push("x").push(true)),
+            Ok(("x".to_owned(), true))
+        );
+        assert_eq!(
+            <(u8, u8, u8, u8)>::from_json(&vec![1, 2, 3, 4].to_json()),
+            Ok((1, 2, 3, 4))
+        );
+    }
+
+    #[test]
+    fn tuples_other_than_pairs_are_converted_to_json_arrays() {
+        assert_eq!(
+            ("a", 1, true).to_json(),
+            Json::array().push("a").push(1).push(true)
+        );
+        assert_eq!((1, 2, 3, 4).to_json(), vec![1, 2, 3, 4].to_json());
+    }
+
+    #[test]
+    fn from_json_errors_point_at_the_value_which_did_not_fit() {
+        let error = |result: Result<(), FromJsonError>| result.unwrap_err().to_string();
+        let json = Json::object().set("a/b", vec![Json::Null, "x".to_json()]);
+
+        assert_eq!(
+            error(u8::from_json(&256.to_json()).map(|_| ())),
+            "ERROR: Cannot convert JSON - expected u8 at the root, found number 256"
+        );
+        assert_eq!(
+            error(u32::from_json(&1.5.to_json()).map(|_| ())),
+            "ERROR: Cannot convert JSON - expected u32 at the root, found number 1.5"
+        );
+        assert_eq!(
+            error(BTreeMap::<String, Vec<Option<f64>>>::from_json(&json).map(|_| ())),
+            "ERROR: Cannot convert JSON - expected number at /a~1b/1, found string"
+        );
+        assert_eq!(
+            error(<(bool, bool)>::from_json(&vec![true].to_json()).map(|_| ())),
+            "ERROR: Cannot convert JSON - expected array of 2 items at the root, found array of 1 items"
+        );
+        assert_eq!(
+            error(
+                Vec::<(bool, String)>::from_json(
+                    &Json::array().push(Json::array().push(true).push(2))
+                )
+                .map(|_| ())
+            ),
+            "ERROR: Cannot convert JSON - expected string at /0/1, found number 2"
+        );
+        assert_eq!(
+            String::from_json(&Json::object()),
+            Err(FromJsonError {
+                pointer: "".to_owned(),
+                expected: "string".to_owned(),
+                found: "object".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn paths_are_converted_to_json_strings() {
+        let path = Path::new("/srv/données/日本/report.json");
+
+        assert_eq!(path.to_json(), "/srv/données/日本/report.json".to_json());
+        assert_eq!(path.to_path_buf().to_json(), path.to_json());
+        assert_eq!(path.as_os_str().to_json(), path.to_json());
+        assert_eq!(OsString::from("ünï").to_json(), "ünï".to_json());
+        assert_eq!(
+            vec![Path::new("a"), Path::new("b/c")].to_json(),
+            vec!["a", "b/c"].to_json()
+        );
+    }
+
+    #[test]
+    fn backslash_separators_survive_printing_and_parsing() {
+        let path = PathBuf::from(r"C:\Users\ada\Документы");
+
+        let printed = to_compact_json_string(&path).unwrap();
+
+        assert_eq!(printed, r#""C:\\Users\\ada\\Документы""#);
+        assert_eq!(
+            from_json_str::<String>(&printed).unwrap(),
+            r"C:\Users\ada\Документы"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_are_converted_lossily() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = Path::new(OsStr::from_bytes(b"/tmp/a\xffb"));
+
+        assert_eq!(path.to_json(), "/tmp/a\u{FFFD}b".to_json());
+    }
+
+    #[test]
+    fn ip_addresses_are_converted_to_their_display_form() {
+        let v4 = Ipv4Addr::new(192, 168, 1, 1);
+        let v6: Ipv6Addr = "2001:0db8:0000:0000:0000:0000:0000:0001".parse().unwrap();
+
+        assert_eq!(v4.to_json(), "192.168.1.1".to_json());
+        assert_eq!(IpAddr::from(v4).to_json(), "192.168.1.1".to_json());
+        assert_eq!(Ipv6Addr::LOCALHOST.to_json(), "::1".to_json());
+        assert_eq!(IpAddr::from(v6).to_json(), "2001:db8::1".to_json());
+        assert_eq!(
+            Ipv4Addr::LOCALHOST.to_ipv6_mapped().to_json(),
+            "::ffff:127.0.0.1".to_json()
+        );
+    }
+
+    #[test]
+    fn socket_addresses_bracket_ipv6_addresses() {
+        let v4: SocketAddr = "10.0.0.1:53".parse().unwrap();
+        let v6: SocketAddr = "[::1]:8080".parse().unwrap();
+        let zoned = SocketAddrV6::new("fe80::1".parse().unwrap(), 443, 0, 3);
+
+        assert_eq!(v4.to_json(), "10.0.0.1:53".to_json());
+        assert_eq!(v6.to_json(), "[::1]:8080".to_json());
+        assert_eq!(v6.ip().to_json(), "::1".to_json());
+        assert_eq!(zoned.to_json(), "[fe80::1%3]:443".to_json());
+    }
+
+    #[test]
+    fn addresses_round_trip_through_from_str() {
+        let zoned = SocketAddr::V6(SocketAddrV6::new("fe80::1".parse().unwrap(), 443, 0, 3));
+        let addresses: Vec<SocketAddr> = vec![
+            "10.0.0.1:53".parse().unwrap(),
+            "[2001:db8::1]:8080".parse().unwrap(),
+            zoned,
+        ];
+
+        let json = addresses.to_json();
+        let read_back: Vec<SocketAddr> = json
+            .members()
+            .map(|address| address.as_str().unwrap().parse().unwrap())
+            .collect();
+
+        assert_eq!(read_back, addresses);
+    }
+
+    #[test]
+    fn addresses_compose_with_containers() {
+        let hosts = BTreeMap::from([
+            (
+                "10.0.0.1:53".parse::<SocketAddr>().unwrap().to_string(),
+                Some(Ipv4Addr::new(10, 0, 0, 1)),
+            ),
+            ("[::1]:80".parse::<SocketAddr>().unwrap().to_string(), None),
+        ]);
+
+        assert_eq!(
+            hosts.to_json(),
+            Json::object()
+                .set("10.0.0.1:53", "10.0.0.1")
+                .set("[::1]:80", Json::Null)
+        );
+    }
+
+    #[test]
+    fn values_convert_into_json_like_to_json() {
+        let text: Json = "hello".into();
+        let flags: Json = vec![true, false].into();
+        let nested: Json = vec![Some(1.5), None].into();
+        let items: Json = vec![Json::Null, 2.into()].into();
+
+        assert_eq!(text, "hello".to_json());
+        assert_eq!(Json::from("hello".to_owned()), "hello".to_json());
+        assert_eq!(flags, vec![true, false].to_json());
+        assert_eq!(nested, vec![Some(1.5), None].to_json());
+        assert_eq!(items, vec![Json::Null, 2.to_json()].to_json());
+        assert_eq!(Json::from(Some("x")), "x".to_json());
+        assert_eq!(Json::from(None::<Json>), Json::Null);
+        assert_eq!(Json::from(Map::new()), Json::object());
+        assert_eq!(Json::from(-7i8), (-7i8).to_json());
+        assert_eq!(Json::from(u32::MAX), u32::MAX.to_json());
+        assert_eq!(Json::from(0.1f32), 0.1f32.to_json());
+    }
+
+    #[test]
+    fn conversions_fit_apis_bounded_on_into_json() {
+        fn labelled(label: &str, value: impl Into<Json>) -> Json {
+            Json::object().set(label, value.into())
+        }
+
+        assert_eq!(labelled("n", 3u8), Json::object().set("n", 3));
+        assert_eq!(
+            labelled("tags", vec!["a", "b"]),
+            Json::object().set("tags", vec!["a", "b"])
+        );
+    }
+
+    #[test]
+    fn wide_integers_convert_with_from() {
+        assert_eq!(Json::from(MAX_SAFE_INTEGER), MAX_SAFE_INTEGER.to_json());
+        assert_eq!(Json::from(7usize), 7.to_json());
+        assert_eq!(Json::from(u64::MAX), Json::BigInteger(u64::MAX.into()));
+        assert_eq!(Json::from(isize::MIN), Json::BigInteger(isize::MIN as i128));
+    }
+
+    #[test]
+    fn impl_to_json_matches_a_hand_written_impl() {
+        struct Limits {
+            connections: u32,
+            timeout: Option<f64>,
+        }
+        crate::impl_to_json!(struct Limits { connections, timeout? => "timeout_secs" });
+
+        struct ServerConfig {
+            host: String,
+            port: u16,
+            tls_enabled: bool,
+            cert: Option<String>,
+            limits: Limits,
+            tags: Vec<&'static str>,
+        }
+        crate::impl_to_json!(struct ServerConfig {
+            host,
+            port => "listen_port",
+            tls_enabled,
+            cert?,
+            limits,
+            tags,
+        });
+
+        fn by_hand(config: &ServerConfig) -> Json {
+            let mut limits = Json::object().set("connections", config.limits.connections);
+            if let Some(timeout) = config.limits.timeout {
+                limits = limits.set("timeout_secs", timeout);
+            }
+            let mut json = Json::object()
+                .set("host", &config.host)
+                .set("listen_port", config.port)
+                .set("tls_enabled", config.tls_enabled);
+            if let Some(cert) = &config.cert {
+                json = json.set("cert", cert);
+            }
+            json.set("limits", limits).set("tags", &config.tags)
+        }
+
+        let mut config = ServerConfig {
+            host: "example.com".to_owned(),
+            port: 443,
+            tls_enabled: true,
+            cert: Some("/etc/cert.pem".to_owned()),
+            limits: Limits {
+                connections: 100,
+                timeout: None,
+            },
+            tags: vec!["a", "b"],
+        };
+
+        assert_eq!(config.to_json(), by_hand(&config));
+        assert_eq!(
+            config.to_json().keys().collect::<Vec<_>>(),
+            [
+                "host",
+                "listen_port",
+                "tls_enabled",
+                "cert",
+                "limits",
+                "tags"
+            ]
+        );
+        assert_eq!(config.to_json()["limits"].keys().count(), 1);
+
+        config.cert = None;
+        config.limits.timeout = Some(2.5);
+        assert_eq!(config.to_json(), by_hand(&config));
+        assert_eq!(config.to_json().get("cert"), None);
+        assert_eq!(config.to_json()["limits"]["timeout_secs"], 2.5.to_json());
+    }
+
+    #[test]
+    fn impl_to_json_writes_null_for_unmarked_options() {
+        struct Entry {
+            note: Option<&'static str>,
+        }
+        crate::impl_to_json!(struct Entry { note });
+
+        assert_eq!(
+            Entry { note: None }.to_json(),
+            Json::object().set("note", Json::Null)
+        );
+        assert_eq!(
+            vec![Entry { note: Some("x") }].to_json(),
+            vec![Json::object().set("note", "x")].to_json()
+        );
+    }
+
+    #[test]
+    fn pretty_displays_like_json_to_string() {
+        let values = [
+            Json::object()
+                .set("name", "api")
+                .set("ports", vec![80, 443])
+                .set("tls", Json::object().set("cert", None::<&str>))
+                .set("empty", Json::array()),
+            vec![Some(vec![1.5]), None].to_json(),
+            "text".to_json(),
+        ];
+
+        for value in &values {
+            assert_eq!(Pretty(value).to_string(), json_to_string(value, 2).unwrap());
+            assert_eq!(
+                format!("{:4}", Pretty(value)),
+                json_to_string(value, 4).unwrap()
+            );
+            for indent in [0, 3] {
+                assert_eq!(
+                    Pretty(value).with_indent(indent).to_string(),
+                    json_to_string(value, indent).unwrap()
+                );
+            }
+        }
+        assert_eq!(
+            Pretty(Some(vec![(1, "a", true)])).to_string(),
+            json_to_string(&vec![(1, "a", true)].to_json(), 2).unwrap()
+        );
+    }
+
+    #[test]
+    fn debug_and_compact_format_on_one_line() {
+        let value = BTreeMap::from([("b", vec![1, 2]), ("a", vec![])]);
+        let compact = json_to_compact_string(&value.to_json()).unwrap();
+
+        assert_eq!(format!("{:?}", Pretty(&value)), compact);
+        assert_eq!(format!("{:?}", Pretty(&value).with_indent(8)), compact);
+        assert_eq!(Compact(&value).to_string(), compact);
+        assert_eq!(format!("{:?}", Compact(&value)), compact);
+        assert_eq!(compact, r#"{"a":[],"b":[1,2]}"#);
+    }
+
+    #[test]
+    fn non_finite_numbers_display_as_null() {
+        let values = [f64::NAN, f64::INFINITY];
+
+        assert_eq!(Pretty(&values).to_string(), "[\n  null,\n  null\n]");
+        assert_eq!(format!("{:?}", Pretty(&values)), "[null,null]");
+        assert_eq!(Compact(f64::NEG_INFINITY).to_string(), "null");
+    }
 }
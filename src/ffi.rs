@@ -0,0 +1,162 @@
+//! C functions for formatting JSON from other languages, behind the `ffi`
+//! feature, declared in `include/json_pretty_printer.h`. Build a library to
+//! link against with
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or
+//! `staticlib`).
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic, ptr,
+};
+
+use crate::{parser::parse, printer::json_to_string};
+
+/// Formats a NUL-terminated JSON document, indenting by the number of
+/// spaces, and returns the output as a new NUL-terminated string. On failure,
+/// returns null and, if `out_err` isn't null, points it at a new string
+/// describing the error. It is pointed at null on success. Strings returned
+/// either way must be freed with `json_pretty_free`.
+///
+/// # Safety
+///
+/// `input` must be null or point to a NUL-terminated string, and `out_err`
+/// must be null or valid to write a pointer to.
+#[no_mangle]
+pub unsafe extern "C" fn json_pretty_format(
+    input: *const c_char,
+    indent: u32,
+    out_err: *mut *mut c_char,
+) -> *mut c_char {
+    let formatted = if input.is_null() {
+        Err("ERROR: Invalid JSON - the input is null".to_owned())
+    } else {
+        let input = CStr::from_ptr(input);
+        panic::catch_unwind(|| format(input, indent)).unwrap_or_else(|_| {
+            Err("ERROR: Failed to format JSON - the formatter panicked".to_owned())
+        })
+    };
+
+    let (output, error) = match formatted {
+        Ok(output) => (into_c_string(output), ptr::null_mut()),
+        Err(error) => (ptr::null_mut(), into_c_string(error)),
+    };
+    if !out_err.is_null() {
+        *out_err = error;
+    } else if !error.is_null() {
+        json_pretty_free(error);
+    }
+    output
+}
+
+/// Frees a string returned by `json_pretty_format`. Does nothing if `string`
+/// is null.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by `json_pretty_format` which
+/// hasn't already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn json_pretty_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+fn format(input: &CStr, indent: u32) -> Result<String, String> {
+    let input = input
+        .to_str()
+        .map_err(|error| format!("ERROR: Invalid JSON - the input is not UTF-8: {error}"))?;
+    let json = parse(input).map_err(|error| error.to_string())?;
+    json_to_string(&json, indent.into()).map_err(|error| error.to_string())
+}
+
+/// Converts a string for C, which can't hold NUL characters, so any are
+/// written as `\u0000`. Neither printed JSON nor messages about input which
+/// ends at its first NUL should hold one, but this must not panic if they do.
+fn into_c_string(string: String) -> *mut c_char {
+    let string = match CString::new(string) {
+        Ok(string) => string,
+        Err(error) => {
+            let escaped = String::from_utf8_lossy(&error.into_vec()).replace('\0', "\\u0000");
+            CString::new(escaped).expect("NUL characters have been escaped")
+        }
+    };
+    string.into_raw()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{c_char, CStr},
+        ptr,
+    };
+
+    use crate::ffi::{json_pretty_format, json_pretty_free};
+
+    /// Calls `json_pretty_format`, returning its output or error and freeing
+    /// both.
+    fn format(input: &CStr, indent: u32) -> Result<String, String> {
+        let mut error: *mut c_char = ptr::null_mut();
+        unsafe {
+            let output = json_pretty_format(input.as_ptr().cast(), indent, &mut error);
+            let take = |string: *mut c_char| {
+                let text = CStr::from_ptr(string).to_str().unwrap().to_owned();
+                json_pretty_free(string);
+                text
+            };
+            match (output.is_null(), error.is_null()) {
+                (false, true) => Ok(take(output)),
+                (true, false) => Err(take(error)),
+                _ => panic!("exactly one of the output and error should be set"),
+            }
+        }
+    }
+
+    #[test]
+    fn it_formats_a_document() {
+        assert_eq!(
+            format(c"{\"a\": [1, \"\\u0000\"]}", 2),
+            Ok("{\n  \"a\": [\n    1,\n    \"\\u0000\"\n  ]\n}".to_owned())
+        );
+        assert_eq!(format(c"null", 0), Ok("null".to_owned()));
+    }
+
+    #[test]
+    fn it_reports_parse_errors() {
+        assert_eq!(
+            format(c"[1,", 2),
+            Err("ERROR: Invalid JSON - Unexpected end of input at line 1, column 3".to_owned())
+        );
+        assert_eq!(
+            format(c"[1e999]", 2),
+            Err("ERROR: Invalid JSON - Number too large for an f64 at line 1, column 2".to_owned())
+        );
+    }
+
+    #[test]
+    fn it_rejects_input_which_is_not_utf_8() {
+        assert_eq!(
+            format(c"[\"\xff\"]", 2),
+            Err(
+                "ERROR: Invalid JSON - the input is not UTF-8: invalid utf-8 sequence of 1 bytes from index 2"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn null_pointers_are_handled() {
+        unsafe {
+            let mut error: *mut c_char = ptr::null_mut();
+            assert!(json_pretty_format(ptr::null(), 2, &mut error).is_null());
+            assert_eq!(
+                CStr::from_ptr(error).to_str(),
+                Ok("ERROR: Invalid JSON - the input is null")
+            );
+            json_pretty_free(error);
+
+            assert!(json_pretty_format(c"[".as_ptr(), 2, ptr::null_mut()).is_null());
+            json_pretty_free(ptr::null_mut());
+        }
+    }
+}
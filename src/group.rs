@@ -0,0 +1,271 @@
+//! Indexing an array of objects by one of their properties.
+
+use std::{error, fmt};
+
+use crate::{ast::Json, map::Map, printer::json_to_compact_string};
+
+/// What `Json::group_by_with` does when two elements have the same key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Fail with `GroupByError::DuplicateKey`.
+    #[default]
+    Error,
+    /// Keep the first element with the key.
+    KeepFirst,
+    /// Keep the last element with the key.
+    KeepLast,
+    /// Put every group in an array, even those with only one element.
+    Collect,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GroupByOptions {
+    pub duplicates: DuplicateKeys,
+    /// The key to group elements without the property under. If this is
+    /// `None`, such elements are an error.
+    pub missing_key_bucket: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GroupByError {
+    /// The value isn't an array.
+    NotAnArray,
+    /// The element at this index isn't an object.
+    NotAnObject(usize),
+    /// The element at this index doesn't have the property.
+    MissingKey(usize),
+    /// The element at this index has a property which isn't a string, number
+    /// or boolean, so can't be used as a key. This holds its type name.
+    InvalidKey(usize, &'static str),
+    /// The element at this index has the same key as an earlier one.
+    DuplicateKey(usize, String),
+}
+
+impl fmt::Display for GroupByError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("ERROR: Cannot group JSON - ")?;
+        match self {
+            GroupByError::NotAnArray => formatter.write_str("the value is not an array"),
+            GroupByError::NotAnObject(index) => {
+                formatter.write_fmt(format_args!("element {index} is not an object"))
+            }
+            GroupByError::MissingKey(index) => {
+                formatter.write_fmt(format_args!("element {index} does not have the key"))
+            }
+            GroupByError::InvalidKey(index, type_name) => formatter.write_fmt(format_args!(
+                "element {index} has a key of type {type_name}"
+            )),
+            GroupByError::DuplicateKey(index, key) => formatter.write_fmt(format_args!(
+                "element {index} has the same key as an earlier element: {key:?}"
+            )),
+        }
+    }
+}
+
+impl error::Error for GroupByError {}
+
+impl Json {
+    /// Turns an array of objects into an object of those objects, keyed by
+    /// their value of the `key` property, failing if two have the same value
+    /// or one doesn't have the property.
+    pub fn group_by(&self, key: &str) -> Result<Json, GroupByError> {
+        self.group_by_with(key, &GroupByOptions::default())
+    }
+
+    /// Like `group_by`, with a choice of what to do with elements which have
+    /// the same key or don't have one. Numbers are turned into keys in the
+    /// compact form the printer would use, such as `1` and `2.5`.
+    pub fn group_by_with(&self, key: &str, options: &GroupByOptions) -> Result<Json, GroupByError> {
+        let Json::Array(items) = self else {
+            return Err(GroupByError::NotAnArray);
+        };
+
+        let mut groups = Map::new();
+        for (index, item) in items.iter().enumerate() {
+            let Json::Object(properties) = item else {
+                return Err(GroupByError::NotAnObject(index));
+            };
+            let group_key = match (properties.get(key), &options.missing_key_bucket) {
+                (Some(Json::String(string)), _) => string.clone(),
+                (Some(value @ (Json::Number(_) | Json::BigInteger(_) | Json::Boolean(_))), _) => {
+                    json_to_compact_string(value)
+                        .map_err(|_| GroupByError::InvalidKey(index, value.type_name()))?
+                }
+                (Some(value), _) => return Err(GroupByError::InvalidKey(index, value.type_name())),
+                (None, Some(bucket)) => bucket.clone(),
+                (None, None) => return Err(GroupByError::MissingKey(index)),
+            };
+
+            let is_duplicate = groups.contains_key(&group_key);
+            match options.duplicates {
+                DuplicateKeys::Error if is_duplicate => {
+                    return Err(GroupByError::DuplicateKey(index, group_key));
+                }
+                DuplicateKeys::KeepFirst if is_duplicate => {}
+                DuplicateKeys::Collect => {
+                    if !is_duplicate {
+                        groups.insert(group_key.clone(), Json::array());
+                    }
+                    if let Some(Json::Array(group)) = groups.get_mut(&group_key) {
+                        group.push(item.clone());
+                    }
+                }
+                _ => {
+                    groups.insert(group_key, item.clone());
+                }
+            }
+        }
+        Ok(Json::Object(groups))
+    }
+
+    /// Turns an object made by `group_by` back into an array of its values,
+    /// in the order of the object's keys. Arrays of values, as made by
+    /// `DuplicateKeys::Collect`, are flattened. Returns `None` for a value
+    /// which isn't an object.
+    pub fn ungroup(&self) -> Option<Json> {
+        let mut items = Vec::new();
+        for value in self.as_object()?.values() {
+            match value {
+                Json::Array(group) => items.extend(group.iter().cloned()),
+                other => items.push(other.clone()),
+            }
+        }
+        Some(Json::Array(items))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        group::{DuplicateKeys, GroupByError, GroupByOptions},
+        parser::parse,
+    };
+
+    fn people() -> Json {
+        parse(
+            r#"[
+                {"id": "b", "team": 2, "name": "Bo"},
+                {"id": "a", "team": 1.5, "name": "Al"},
+                {"id": "c", "team": 2, "name": "Cy"},
+                {"name": "Di"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn options(duplicates: DuplicateKeys) -> GroupByOptions {
+        GroupByOptions {
+            duplicates,
+            missing_key_bucket: Some("none".to_owned()),
+        }
+    }
+
+    #[test]
+    fn it_indexes_objects_by_a_string_property_in_array_order() {
+        let mut people = people();
+        people.remove_index(3);
+
+        let grouped = people.group_by("id").unwrap();
+
+        assert_eq!(
+            grouped,
+            parse(
+                r#"{
+                    "b": {"id": "b", "team": 2, "name": "Bo"},
+                    "a": {"id": "a", "team": 1.5, "name": "Al"},
+                    "c": {"id": "c", "team": 2, "name": "Cy"}
+                }"#
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            grouped.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["b", "a", "c"]
+        );
+        assert_eq!(grouped.ungroup(), Some(people));
+    }
+
+    #[test]
+    fn it_handles_duplicate_keys_by_the_chosen_policy() {
+        let names = |duplicates| {
+            let grouped = people()
+                .group_by_with("team", &options(duplicates))
+                .unwrap();
+            grouped
+                .as_object()
+                .unwrap()
+                .iter()
+                .map(|(key, value)| {
+                    let group = match value {
+                        Json::Array(group) => group.iter().collect(),
+                        item => vec![item],
+                    };
+                    let names = group.iter().map(|item| item["name"].as_str().unwrap());
+                    (key.clone(), names.collect::<Vec<_>>().join(","))
+                })
+                .collect::<Vec<_>>()
+        };
+        let expect =
+            |pairs: [(&str, &str); 3]| pairs.map(|(key, names)| (key.to_owned(), names.to_owned()));
+
+        assert_eq!(
+            people().group_by_with("team", &options(DuplicateKeys::Error)),
+            Err(GroupByError::DuplicateKey(2, "2".to_owned()))
+        );
+        assert_eq!(
+            names(DuplicateKeys::KeepFirst),
+            expect([("2", "Bo"), ("1.5", "Al"), ("none", "Di")])
+        );
+        assert_eq!(
+            names(DuplicateKeys::KeepLast),
+            expect([("2", "Cy"), ("1.5", "Al"), ("none", "Di")])
+        );
+        assert_eq!(
+            names(DuplicateKeys::Collect),
+            expect([("2", "Bo,Cy"), ("1.5", "Al"), ("none", "Di")])
+        );
+    }
+
+    #[test]
+    fn ungroup_flattens_collected_groups() {
+        let grouped = people()
+            .group_by_with("team", &options(DuplicateKeys::Collect))
+            .unwrap();
+
+        let ungrouped = grouped.ungroup().unwrap();
+
+        let names: Vec<_> = ungrouped
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["Bo", "Cy", "Al", "Di"]);
+        assert_eq!(Json::array().ungroup(), None);
+    }
+
+    #[test]
+    fn it_fails_on_elements_it_cannot_key() {
+        assert_eq!(people().group_by("id"), Err(GroupByError::MissingKey(3)));
+        assert_eq!(
+            parse(r#"[{"id": 1}, 2]"#).unwrap().group_by("id"),
+            Err(GroupByError::NotAnObject(1))
+        );
+        assert_eq!(
+            parse(r#"[{"id": null}]"#).unwrap().group_by("id"),
+            Err(GroupByError::InvalidKey(0, "null"))
+        );
+        assert_eq!(
+            parse(r#"[{"id": true}, {"id": false}]"#)
+                .unwrap()
+                .group_by("id"),
+            Ok(parse(r#"{"true": {"id": true}, "false": {"id": false}}"#).unwrap())
+        );
+        assert_eq!(Json::object().group_by("id"), Err(GroupByError::NotAnArray));
+        assert_eq!(
+            GroupByError::MissingKey(3).to_string(),
+            "ERROR: Cannot group JSON - element 3 does not have the key"
+        );
+    }
+}
@@ -0,0 +1,566 @@
+use std::mem;
+
+use crate::{
+    ast::Json,
+    map::Map,
+    parser::{fail, number_from_text, number_is_too_large, JsonParseError},
+};
+
+/// A parser which is given its input in chunks, for use when the whole
+/// document isn't available at once (e.g. when reading a request body from a
+/// socket).
+///
+/// Only the parts of the document which can't yet be turned into a value are
+/// buffered between calls to `feed`, such as a number or string which is split
+/// across two chunks. Syntax errors are returned from `feed` as soon as the
+/// input seen so far can't be the start of a valid document, after which the
+/// parser keeps returning the same error.
+#[derive(Debug, Default)]
+pub struct IncrementalParser {
+    containers: Vec<Container>,
+    state: State,
+    parsed: Option<Json>,
+    error: Option<JsonParseError>,
+}
+
+#[derive(Debug)]
+enum Container {
+    Array(Vec<Json>),
+    Object { properties: Map, key: String },
+}
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Value,
+    FirstArrayItem,
+    FirstObjectKey,
+    ObjectKey,
+    Colon,
+    AfterValue,
+    Literal {
+        literal: &'static str,
+        matched: usize,
+        value: Json,
+    },
+    Number {
+        text: String,
+        part: NumberPart,
+    },
+    String(PartialString),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum NumberPart {
+    Sign,
+    Zero,
+    Integer,
+    Point,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits,
+}
+
+#[derive(Debug)]
+struct PartialString {
+    text: String,
+    is_key: bool,
+    escape: Escape,
+    high_surrogate: Option<u16>,
+}
+
+#[derive(Debug)]
+enum Escape {
+    None,
+    Backslash,
+    Unicode { codepoint: u16, digits: u8 },
+}
+
+impl IncrementalParser {
+    pub fn new() -> IncrementalParser {
+        IncrementalParser::default()
+    }
+
+    pub fn feed(&mut self, chunk: &str) -> Result<(), JsonParseError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+
+        for c in chunk.chars() {
+            if let Err(error) = self.push_char(c) {
+                self.error = Some(error.clone());
+                return Err(error);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<Json, JsonParseError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        if let State::Number { text, part } = mem::take(&mut self.state) {
+            self.end_number(text, part, None)?;
+        }
+
+        match self.parsed {
+            Some(json) => Ok(json),
+            None => fail("Unexpected end of input"),
+        }
+    }
+
+    fn push_char(&mut self, c: char) -> Result<(), JsonParseError> {
+        match mem::take(&mut self.state) {
+            State::Literal {
+                literal,
+                matched,
+                value,
+            } => self.push_literal_char(c, literal, matched, value),
+            State::Number { text, part } => self.push_number_char(c, text, part),
+            State::String(string) => self.push_string_char(c, string),
+            state if " \n\r\t".contains(c) => {
+                self.state = state;
+                Ok(())
+            }
+            State::Value => self.start_value(c),
+            State::FirstArrayItem if c == ']' => self.end_container(),
+            State::FirstArrayItem => self.start_value(c),
+            State::FirstObjectKey if c == '}' => self.end_container(),
+            State::FirstObjectKey | State::ObjectKey => self.start_key(c),
+            State::Colon if c == ':' => {
+                self.state = State::Value;
+                Ok(())
+            }
+            State::Colon => fail("Missing colon after object key"),
+            State::AfterValue => self.push_separator(c),
+        }
+    }
+
+    fn start_value(&mut self, c: char) -> Result<(), JsonParseError> {
+        self.state = match c {
+            'n' => State::Literal {
+                literal: "null",
+                matched: 1,
+                value: Json::Null,
+            },
+            't' => State::Literal {
+                literal: "true",
+                matched: 1,
+                value: Json::Boolean(true),
+            },
+            'f' => State::Literal {
+                literal: "false",
+                matched: 1,
+                value: Json::Boolean(false),
+            },
+            '-' | '0'..='9' => State::Number {
+                text: c.to_string(),
+                part: match c {
+                    '-' => NumberPart::Sign,
+                    '0' => NumberPart::Zero,
+                    _ => NumberPart::Integer,
+                },
+            },
+            '"' => State::String(PartialString::new(false)),
+            '[' => {
+                self.containers.push(Container::Array(Vec::new()));
+                State::FirstArrayItem
+            }
+            '{' => {
+                self.containers.push(Container::Object {
+                    properties: Map::new(),
+                    key: String::new(),
+                });
+                State::FirstObjectKey
+            }
+            unexpected_char => return fail(format!("Unexpected character: {unexpected_char}")),
+        };
+
+        Ok(())
+    }
+
+    fn start_key(&mut self, c: char) -> Result<(), JsonParseError> {
+        if c != '"' {
+            return fail(format!("Expected a string, found '{c}'"));
+        }
+
+        self.state = State::String(PartialString::new(true));
+        Ok(())
+    }
+
+    fn push_literal_char(
+        &mut self,
+        c: char,
+        literal: &'static str,
+        matched: usize,
+        value: Json,
+    ) -> Result<(), JsonParseError> {
+        let expected_char = char::from(literal.as_bytes()[matched]);
+
+        if c != expected_char {
+            return fail(format!("Expected '{expected_char}', but found '{c}'"));
+        }
+
+        if matched + 1 == literal.len() {
+            self.complete_value(value);
+        } else {
+            self.state = State::Literal {
+                literal,
+                matched: matched + 1,
+                value,
+            };
+        }
+
+        Ok(())
+    }
+
+    fn push_number_char(
+        &mut self,
+        c: char,
+        mut text: String,
+        part: NumberPart,
+    ) -> Result<(), JsonParseError> {
+        match part.next(c) {
+            Some(next_part) => {
+                text.push(c);
+                self.state = State::Number {
+                    text,
+                    part: next_part,
+                };
+                Ok(())
+            }
+            None => {
+                self.end_number(text, part, Some(c))?;
+                self.push_char(c)
+            }
+        }
+    }
+
+    fn end_number(
+        &mut self,
+        text: String,
+        part: NumberPart,
+        next_char: Option<char>,
+    ) -> Result<(), JsonParseError> {
+        match (part, next_char) {
+            (NumberPart::Sign, Some(c)) => fail(format!("Unexpected character in number: {c}")),
+            (NumberPart::Sign, None) => fail("Unexpected end of input"),
+            (NumberPart::Point, _) => fail("Missing digits after point in number"),
+            (NumberPart::Exponent | NumberPart::ExponentSign, _) => {
+                fail("Missing digits after exponent in number")
+            }
+            _ if number_is_too_large(&text) => fail("Number too large for an f64"),
+            _ => {
+                self.complete_value(number_from_text(&text)?);
+                Ok(())
+            }
+        }
+    }
+
+    fn push_string_char(
+        &mut self,
+        c: char,
+        mut string: PartialString,
+    ) -> Result<(), JsonParseError> {
+        match string.escape {
+            Escape::None if string.high_surrogate.is_some() && c != '\\' => {
+                return fail("Unpaired UTF-16 surrogate in string")
+            }
+            Escape::None => match c {
+                '"' => return self.end_string(string),
+                '\\' => string.escape = Escape::Backslash,
+                regular_char => string.text.push(regular_char),
+            },
+            Escape::Backslash if c == 'u' => {
+                string.escape = Escape::Unicode {
+                    codepoint: 0,
+                    digits: 0,
+                }
+            }
+            Escape::Backslash if string.high_surrogate.is_some() => {
+                return fail("Unpaired UTF-16 surrogate in string")
+            }
+            Escape::Backslash => {
+                string.escape = Escape::None;
+                string.text.push(match c {
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    'b' => '\u{8}',
+                    'f' => '\u{C}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    _ => return fail("Invalid escape sequence in string"),
+                });
+            }
+            Escape::Unicode { codepoint, digits } => {
+                let Some(digit) = c.to_digit(16) else {
+                    return fail("Invalid hex digit in unicode escape sequence");
+                };
+                let codepoint = codepoint * 16 + digit as u16;
+
+                if digits < 3 {
+                    string.escape = Escape::Unicode {
+                        codepoint,
+                        digits: digits + 1,
+                    };
+                } else {
+                    string.escape = Escape::None;
+                    string.push_utf16(codepoint)?;
+                }
+            }
+        }
+
+        self.state = State::String(string);
+        Ok(())
+    }
+
+    fn end_string(&mut self, string: PartialString) -> Result<(), JsonParseError> {
+        if !string.is_key {
+            self.complete_value(Json::String(string.text));
+            return Ok(());
+        }
+
+        if let Some(Container::Object { key, .. }) = self.containers.last_mut() {
+            *key = string.text;
+        }
+        self.state = State::Colon;
+        Ok(())
+    }
+
+    fn push_separator(&mut self, c: char) -> Result<(), JsonParseError> {
+        match (self.containers.last(), c) {
+            (Some(Container::Array(_)), ',') => self.state = State::Value,
+            (Some(Container::Object { .. }), ',') => self.state = State::ObjectKey,
+            (Some(Container::Array(_)), ']') | (Some(Container::Object { .. }), '}') => {
+                return self.end_container()
+            }
+            (Some(Container::Array(_)), unexpected_char) => {
+                return fail(format!("Expected ',' or ']', found '{unexpected_char}'"))
+            }
+            (Some(Container::Object { .. }), unexpected_char) => {
+                return fail(format!("Expected ',' or '}}', found '{unexpected_char}'"))
+            }
+            (None, unexpected_char) => {
+                return fail(format!("Unexpected character: {unexpected_char}"))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn end_container(&mut self) -> Result<(), JsonParseError> {
+        match self.containers.pop() {
+            Some(Container::Array(items)) => self.complete_value(Json::Array(items)),
+            Some(Container::Object { properties, .. }) => {
+                self.complete_value(Json::Object(properties))
+            }
+            None => return fail("Unexpected closing bracket"),
+        }
+
+        Ok(())
+    }
+
+    fn complete_value(&mut self, value: Json) {
+        match self.containers.last_mut() {
+            Some(Container::Array(items)) => items.push(value),
+            Some(Container::Object { properties, key }) => {
+                properties.insert(mem::take(key), value);
+            }
+            None => self.parsed = Some(value),
+        }
+
+        self.state = State::AfterValue;
+    }
+}
+
+impl NumberPart {
+    fn next(self, c: char) -> Option<NumberPart> {
+        match (self, c) {
+            (NumberPart::Sign, '0') => Some(NumberPart::Zero),
+            (NumberPart::Sign | NumberPart::Integer, '0'..='9') => Some(NumberPart::Integer),
+            (NumberPart::Zero | NumberPart::Integer, '.') => Some(NumberPart::Point),
+            (NumberPart::Point | NumberPart::Fraction, '0'..='9') => Some(NumberPart::Fraction),
+            (NumberPart::Zero | NumberPart::Integer | NumberPart::Fraction, 'e' | 'E') => {
+                Some(NumberPart::Exponent)
+            }
+            (NumberPart::Exponent, '+' | '-') => Some(NumberPart::ExponentSign),
+            (
+                NumberPart::Exponent | NumberPart::ExponentSign | NumberPart::ExponentDigits,
+                '0'..='9',
+            ) => Some(NumberPart::ExponentDigits),
+            _ => None,
+        }
+    }
+}
+
+impl PartialString {
+    fn new(is_key: bool) -> PartialString {
+        PartialString {
+            text: String::new(),
+            is_key,
+            escape: Escape::None,
+            high_surrogate: None,
+        }
+    }
+
+    fn push_utf16(&mut self, codepoint: u16) -> Result<(), JsonParseError> {
+        let decoded = match (self.high_surrogate.take(), codepoint) {
+            (None, 0xD800..=0xDBFF) => {
+                self.high_surrogate = Some(codepoint);
+                return Ok(());
+            }
+            (Some(high_surrogate), 0xDC00..=0xDFFF) => {
+                char::decode_utf16([high_surrogate, codepoint]).next()
+            }
+            (Some(_), _) => None,
+            (None, _) => char::decode_utf16([codepoint]).next(),
+        };
+
+        match decoded {
+            Some(Ok(decoded_char)) => {
+                self.text.push(decoded_char);
+                Ok(())
+            }
+            _ => fail("Unpaired UTF-16 surrogate in string"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IncrementalParser;
+    use crate::{ast::Json, dsl::ToJson, parser::parse};
+
+    const DOCUMENTS: [&str; 12] = [
+        "null",
+        " true ",
+        "false",
+        "-12.5e+3",
+        "0",
+        r#""\uD83D\uDE02 and \u0041\n""#,
+        r#""😃 or 🙁?""#,
+        "[]",
+        "[1, [2, [3, []]], {}]",
+        r#"{"name": "Andrew", "age": 63, "tags": ["a", "b"], "nested": {"x": null}}"#,
+        " { \"key\"\t:\n\"value\"   }\n",
+        r#"[-0, 10E23, 1e-2, 0.5, 123]"#,
+    ];
+
+    const INVALID_DOCUMENTS: [&str; 17] = [
+        "",
+        "nul",
+        "truee",
+        "-x",
+        "00",
+        "67.",
+        "1e",
+        r#""\uD83D""#,
+        r#""\uDE02""#,
+        r#""\u00G0""#,
+        r#""unterminated"#,
+        "[1,]",
+        "[1 2]",
+        r#"{"a" 1}"#,
+        r#"{"a": 1,}"#,
+        "{} {}",
+        "[1e400]",
+    ];
+
+    fn parse_in_chunks(chunks: &[&str]) -> Result<Json, crate::parser::JsonParseError> {
+        let mut parser = IncrementalParser::new();
+        for chunk in chunks {
+            parser.feed(chunk)?;
+        }
+        parser.finish()
+    }
+
+    #[test]
+    fn it_parses_a_document_fed_in_one_chunk() {
+        for document in DOCUMENTS {
+            assert_eq!(parse_in_chunks(&[document]), parse(document), "{document}");
+        }
+    }
+
+    #[test]
+    fn it_parses_a_document_split_at_every_boundary() {
+        for document in DOCUMENTS {
+            for split in (0..=document.len()).filter(|i| document.is_char_boundary(*i)) {
+                let (first, second) = document.split_at(split);
+                assert_eq!(
+                    parse_in_chunks(&[first, second]),
+                    parse(document),
+                    "{document} split at {split}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_parses_a_document_fed_one_char_at_a_time() {
+        for document in DOCUMENTS {
+            let chars: Vec<String> = document.chars().map(String::from).collect();
+            let chunks: Vec<&str> = chars.iter().map(String::as_str).collect();
+            assert_eq!(parse_in_chunks(&chunks), parse(document), "{document}");
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_documents_split_at_every_boundary() {
+        for document in INVALID_DOCUMENTS {
+            assert!(parse(document).is_err(), "{document}");
+            for split in (0..=document.len()).filter(|i| document.is_char_boundary(*i)) {
+                let (first, second) = document.split_at(split);
+                assert!(
+                    parse_in_chunks(&[first, second]).is_err(),
+                    "{document} split at {split}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_reports_errors_as_soon_as_they_are_detectable() {
+        let mut parser = IncrementalParser::new();
+        assert!(parser.feed("[1, 2,").is_ok());
+        assert!(parser.feed(" ]").is_err());
+
+        let mut parser = IncrementalParser::new();
+        assert!(parser.feed(r#"{"a" "#).is_ok());
+        assert!(parser.feed("1").is_err());
+    }
+
+    #[test]
+    fn it_keeps_returning_the_first_error() {
+        let mut parser = IncrementalParser::new();
+        let error = parser.feed("[1 2").unwrap_err();
+
+        assert_eq!(parser.feed("]"), Err(error.clone()));
+        assert_eq!(parser.finish(), Err(error));
+    }
+
+    #[test]
+    fn it_rejects_a_number_cut_off_at_the_end_of_the_input() {
+        assert!(parse_in_chunks(&["-"]).is_err());
+        assert!(parse_in_chunks(&["1."]).is_err());
+        assert!(parse_in_chunks(&["1e+"]).is_err());
+    }
+
+    #[test]
+    fn it_rejects_a_number_too_large_for_an_f64() {
+        assert!(parse_in_chunks(&["[1e3", "09]"]).is_err());
+        assert!(parse_in_chunks(&["[1e3", "08]"]).is_ok());
+    }
+
+    #[test]
+    fn it_completes_a_number_at_the_end_of_the_input() {
+        let mut parser = IncrementalParser::new();
+        parser.feed("12").unwrap();
+        parser.feed("34").unwrap();
+        assert_eq!(parser.finish(), Ok(1234.to_json()));
+    }
+}
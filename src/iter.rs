@@ -0,0 +1,316 @@
+//! Iterating the contents of a value without matching on its variant. Each
+//! iterator is empty for values which aren't of the type it iterates.
+//! Iterators of values collect into arrays, and of properties into objects.
+
+use alloc::string::String;
+use core::slice;
+
+use crate::{ast::Json, map};
+
+/// One of the children `&Json` iterates: an item of an array, or a property
+/// of an object.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JsonItem<'a> {
+    Member(&'a Json),
+    Entry(&'a str, &'a Json),
+}
+
+impl<'a> JsonItem<'a> {
+    /// The item, or the property's value.
+    pub fn value(self) -> &'a Json {
+        match self {
+            JsonItem::Member(value) | JsonItem::Entry(_, value) => value,
+        }
+    }
+
+    /// The property's key, or `None` for an item of an array.
+    pub fn key(self) -> Option<&'a str> {
+        match self {
+            JsonItem::Member(_) => None,
+            JsonItem::Entry(key, _) => Some(key),
+        }
+    }
+}
+
+impl Json {
+    /// The keys of an object, in the map's order.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.as_object()
+            .into_iter()
+            .flat_map(map::Map::keys)
+            .map(String::as_str)
+    }
+
+    /// The property values of an object, in the map's order.
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &Json> {
+        self.as_object().into_iter().flat_map(map::Map::values)
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Json> {
+        self.as_object_mut()
+            .into_iter()
+            .flat_map(map::Map::values_mut)
+    }
+
+    /// The properties of an object, in the map's order.
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = (&str, &Json)> {
+        self.as_object()
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// The items of an array.
+    pub fn members(&self) -> impl DoubleEndedIterator<Item = &Json> {
+        self.as_array().into_iter().flatten()
+    }
+
+    pub fn members_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Json> {
+        self.as_array_mut().into_iter().flatten()
+    }
+}
+
+/// The iterator over the items of an array or properties of an object.
+#[derive(Clone)]
+pub struct Items<'a>(ItemsState<'a>);
+
+#[derive(Clone)]
+enum ItemsState<'a> {
+    Members(slice::Iter<'a, Json>),
+    Entries(map::Iter<'a>),
+    Empty,
+}
+
+impl<'a> Iterator for Items<'a> {
+    type Item = JsonItem<'a>;
+
+    fn next(&mut self) -> Option<JsonItem<'a>> {
+        match &mut self.0 {
+            ItemsState::Members(items) => items.next().map(JsonItem::Member),
+            ItemsState::Entries(entries) => entries
+                .next()
+                .map(|(key, value)| JsonItem::Entry(key, value)),
+            ItemsState::Empty => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            ItemsState::Members(items) => items.size_hint(),
+            ItemsState::Entries(entries) => entries.size_hint(),
+            ItemsState::Empty => (0, Some(0)),
+        }
+    }
+}
+
+impl ExactSizeIterator for Items<'_> {}
+
+impl<'a> IntoIterator for &'a Json {
+    type Item = JsonItem<'a>;
+    type IntoIter = Items<'a>;
+
+    /// Iterates the items of an array or the properties of an object, or
+    /// nothing for any other value.
+    fn into_iter(self) -> Items<'a> {
+        Items(match self {
+            Json::Array(items) => ItemsState::Members(items.iter()),
+            Json::Object(properties) => ItemsState::Entries(properties.iter()),
+            _ => ItemsState::Empty,
+        })
+    }
+}
+
+impl FromIterator<Json> for Json {
+    fn from_iter<I: IntoIterator<Item = Json>>(items: I) -> Json {
+        Json::Array(items.into_iter().collect())
+    }
+}
+
+/// Collects properties into an object. A repeated key replaces the value
+/// from earlier, keeping the first position.
+impl FromIterator<(String, Json)> for Json {
+    fn from_iter<I: IntoIterator<Item = (String, Json)>>(properties: I) -> Json {
+        Json::Object(properties.into_iter().collect())
+    }
+}
+
+/// Appends items to an array, like `push`, leaving a value which isn't an
+/// array unchanged without taking any items.
+impl Extend<Json> for Json {
+    fn extend<I: IntoIterator<Item = Json>>(&mut self, items: I) {
+        if let Json::Array(array) = self {
+            array.extend(items);
+        }
+    }
+}
+
+/// Sets properties of an object, like `set`, leaving a value which isn't an
+/// object unchanged without taking any properties.
+impl Extend<(String, Json)> for Json {
+    fn extend<I: IntoIterator<Item = (String, Json)>>(&mut self, properties: I) {
+        if let Json::Object(object) = self {
+            object.extend(properties);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Json, dsl::ToJson, iter::JsonItem, parser::parse};
+
+    #[test]
+    fn it_sums_an_array_of_numbers() {
+        let document = parse(r#"{"scores": [1, 2.5, 3]}"#).unwrap();
+
+        let total: f64 = document["scores"].members().filter_map(Json::as_f64).sum();
+
+        assert_eq!(total, 6.5);
+    }
+
+    #[test]
+    fn it_collects_the_keys_and_values_of_an_object_in_order() {
+        let document = parse(r#"{"b": 1, "a": [2], "c": null}"#).unwrap();
+
+        assert_eq!(document.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert_eq!(
+            document.values().map(Json::type_name).collect::<Vec<_>>(),
+            ["number", "array", "null"]
+        );
+        assert_eq!(document.entries().next_back(), Some(("c", &Json::Null)));
+    }
+
+    #[test]
+    fn iterators_are_empty_for_other_variants() {
+        let values = [
+            Json::Null,
+            true.to_json(),
+            "text".to_json(),
+            1.to_json(),
+            vec![1].to_json(),
+            Json::object().set("a", 1),
+        ];
+
+        let counts = values.map(|value| {
+            (
+                value.keys().count(),
+                value.values().count(),
+                value.entries().count(),
+                value.members().count(),
+                value.into_iter().count(),
+            )
+        });
+
+        assert_eq!(
+            counts,
+            [
+                (0, 0, 0, 0, 0),
+                (0, 0, 0, 0, 0),
+                (0, 0, 0, 0, 0),
+                (0, 0, 0, 0, 0),
+                (0, 0, 0, 1, 1),
+                (1, 1, 1, 0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_mutable_iterators_change_values_in_place() {
+        let mut array = vec![1, 2].to_json();
+        let mut object = Json::object().set("a", 1).set("b", 2);
+
+        array
+            .members_mut()
+            .for_each(|item| *item = item.to_string().to_json());
+        object.values_mut().for_each(|value| *value = Json::Null);
+        assert_eq!(Json::Null.members_mut().count(), 0);
+
+        assert_eq!(array, vec!["1", "2"].to_json());
+        assert_eq!(object, parse(r#"{"a": null, "b": null}"#).unwrap());
+    }
+
+    #[test]
+    fn a_reference_iterates_items_or_entries() {
+        let object = parse(r#"{"x": 1, "y": [2]}"#).unwrap();
+        let array = parse(r#"["x", 3]"#).unwrap();
+
+        let entries: Vec<_> = (&object).into_iter().collect();
+        let mut keys = Vec::new();
+        let mut total = 0.0;
+        for item in &array {
+            keys.extend(item.key());
+            total += item.value().as_f64().unwrap_or(0.0);
+        }
+
+        assert_eq!(
+            entries,
+            [
+                JsonItem::Entry("x", &1.to_json()),
+                JsonItem::Entry("y", &vec![2].to_json())
+            ]
+        );
+        assert_eq!(keys, Vec::<&str>::new());
+        assert_eq!(total, 3.0);
+        assert_eq!((&object).into_iter().len(), 2);
+    }
+
+    #[test]
+    fn iterators_collect_into_arrays_and_objects() {
+        struct Row {
+            name: String,
+            score: f64,
+        }
+        let rows = [
+            Row {
+                name: "ada".to_owned(),
+                score: 3.5,
+            },
+            Row {
+                name: "bo".to_owned(),
+                score: 1.0,
+            },
+        ];
+
+        let numbers = (0..10).map(|i| i.to_json()).collect::<Json>();
+        let scores = rows
+            .iter()
+            .map(|r| (r.name.clone(), r.score.to_json()))
+            .collect::<Json>();
+
+        assert_eq!(numbers, (0..10).collect::<Vec<_>>().to_json());
+        assert_eq!(scores, parse(r#"{"ada": 3.5, "bo": 1}"#).unwrap());
+        assert_eq!(std::iter::empty::<Json>().collect::<Json>(), Json::array());
+    }
+
+    #[test]
+    fn collecting_a_repeated_key_keeps_the_last_value_in_the_first_place() {
+        let object: Json = [("a", 1), ("b", 2), ("a", 3)]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.to_json()))
+            .collect();
+
+        assert_eq!(object.entries().next(), Some(("a", &3.to_json())));
+        assert_eq!(object.keys().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn containers_extend_and_other_values_stay_unchanged() {
+        let mut array = vec![1].to_json();
+        let mut object = Json::object().set("a", 1);
+        let mut text = "text".to_json();
+        let mut taken = 0;
+
+        array.extend([2.to_json(), 3.to_json()]);
+        object.extend([("b".to_owned(), Json::Null), ("a".to_owned(), 2.to_json())]);
+        text.extend((0..3).map(|i| {
+            taken += 1;
+            i.to_json()
+        }));
+        object.extend(std::iter::once(Json::Null));
+        array.extend(std::iter::once(("c".to_owned(), Json::Null)));
+
+        assert_eq!(array, vec![1, 2, 3].to_json());
+        assert_eq!(object, parse(r#"{"a": 2, "b": null}"#).unwrap());
+        assert_eq!(text, "text".to_json());
+        assert_eq!(taken, 0);
+    }
+}
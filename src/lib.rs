@@ -0,0 +1,167 @@
+//! Parsing, building and pretty-printing JSON.
+//!
+//! Without the default `std` feature, the library needs only `alloc`: it
+//! parses, builds and prints to strings, but has none of the modules beyond
+//! those, nor `write_json`, nor conversions of types from `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::{
+    dsl::{FromJson, FromJsonError, ToJson},
+    parser::{parse, JsonParseError},
+    printer::{json_to_string_with_options, PrintError, PrintOptions, PrintStyle},
+};
+
+#[cfg(feature = "std")]
+pub mod access;
+pub mod ast;
+pub mod base64;
+pub mod canonical;
+pub mod cbor;
+#[cfg(feature = "std")]
+pub mod coerce;
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "std")]
+pub mod datetime;
+#[cfg(feature = "std")]
+pub mod describe;
+pub mod dotenv;
+pub mod dsl;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod group;
+#[cfg(feature = "std")]
+pub mod incremental;
+pub mod iter;
+pub mod map;
+pub mod msgpack;
+pub mod parser;
+#[cfg(feature = "std")]
+pub mod patch;
+pub mod printer;
+#[cfg(feature = "std")]
+pub mod query;
+#[cfg(feature = "std")]
+pub mod redact;
+#[cfg(feature = "std")]
+pub mod rename;
+pub mod schema;
+#[cfg(feature = "serde")]
+mod serde;
+mod sha256;
+#[cfg(feature = "std")]
+pub mod streaming;
+pub mod theme;
+pub mod toml;
+pub mod tree;
+pub mod urlencoded;
+#[cfg(feature = "std")]
+pub mod walk;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod xml;
+
+/// Formats any value which converts to JSON.
+///
+/// ```
+/// use json_pretty_printer::{
+///     from_json_str,
+///     printer::{PrintOptions, PrintStyle},
+///     to_json_string,
+/// };
+///
+/// let scores = vec![("ada".to_owned(), 3, true), ("bo".to_owned(), 1, false)];
+///
+/// let text = to_json_string(&scores, &PrintOptions::new().style(PrintStyle::OneLine)).unwrap();
+///
+/// assert_eq!(text, r#"[["ada", 3, true], ["bo", 1, false]]"#);
+/// assert_eq!(from_json_str::<Vec<(String, u32, bool)>>(&text).unwrap(), scores);
+/// ```
+pub fn to_json_string<T: ToJson + ?Sized>(
+    value: &T,
+    options: &PrintOptions,
+) -> Result<String, PrintError> {
+    json_to_string_with_options(&value.to_json(), options)
+}
+
+/// Formats any value which converts to JSON on a single line without spaces.
+///
+/// ```
+/// use json_pretty_printer::to_compact_json_string;
+///
+/// assert_eq!(
+///     to_compact_json_string(&[("id", 7), ("count", 2)][..]).unwrap(),
+///     r#"{"count":2,"id":7}"#
+/// );
+/// ```
+pub fn to_compact_json_string<T: ToJson + ?Sized>(value: &T) -> Result<String, PrintError> {
+    to_json_string(value, &PrintOptions::new().style(PrintStyle::Compact))
+}
+
+/// Parses JSON and converts it to a value of any type which converts from
+/// JSON.
+///
+/// ```
+/// use json_pretty_printer::{from_json_str, Error};
+///
+/// let pairs: Vec<(String, Option<f64>)> = from_json_str(r#"[["a", 1.5], ["b", null]]"#).unwrap();
+/// assert_eq!(pairs, [("a".to_owned(), Some(1.5)), ("b".to_owned(), None)]);
+///
+/// let error = from_json_str::<Vec<(String, u8)>>(r#"[["a", 300]]"#).unwrap_err();
+/// assert!(matches!(error, Error::Convert(_)));
+/// assert_eq!(
+///     error.to_string(),
+///     "ERROR: Cannot convert JSON - expected u8 at /0/1, found number 300"
+/// );
+/// ```
+pub fn from_json_str<T: FromJson>(input: &str) -> Result<T, Error> {
+    Ok(T::from_json(&parse(input)?)?)
+}
+
+/// An error from `from_json_str`: either the input isn't JSON, or it is but
+/// doesn't convert to the type asked for. The error it wraps is its source.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    Parse(JsonParseError),
+    Convert(FromJsonError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(error) => error.fmt(formatter),
+            Error::Convert(error) => error.fmt(formatter),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Parse(error) => Some(error),
+            Error::Convert(error) => Some(error),
+        }
+    }
+}
+
+impl From<JsonParseError> for Error {
+    fn from(error: JsonParseError) -> Error {
+        Error::Parse(error)
+    }
+}
+
+impl From<FromJsonError> for Error {
+    fn from(error: FromJsonError) -> Error {
+        Error::Convert(error)
+    }
+}
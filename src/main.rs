@@ -1,29 +1,163 @@
-mod ast;
-mod dsl;
-mod parser;
-mod printer;
-
 use std::{
-    io::{stdin, Read},
+    env, fs,
+    io::{self, stdin, stdout, BufWriter, Read, Write},
     process,
 };
 
-use parser::parse;
+use json_pretty_printer::{
+    dotenv::json_to_env,
+    dsl::ToJson,
+    parser::parse,
+    printer::{write_json, PrintOptions},
+    schema::infer_schema,
+    tree::{json_to_tree_with_options, TreeGlyphs, TreeOptions},
+};
+
+/// What to write for the JSON read from the files given, or else STDIN.
+enum Output {
+    /// The JSON, pretty-printed.
+    Json,
+    /// `KEY=value` lines for an environment file.
+    Env,
+    /// A line for each value, with connectors drawn in these glyphs.
+    Tree(TreeGlyphs),
+    /// The types found at each path, as text or else as JSON.
+    Describe { as_json: bool },
+    /// A JSON Schema which every document is valid against.
+    InferSchema,
+    /// The SHA-256 digest of the canonical form, in hex.
+    Hash,
+}
 
 fn main() {
-    let mut input: String = String::new();
+    let mut output_kind = Output::Json;
+    let mut max_string_length = None;
+    let mut paths = Vec::new();
+    for argument in env::args().skip(1) {
+        match argument.as_str() {
+            "--env" => output_kind = Output::Env,
+            "--tree" => output_kind = Output::Tree(TreeGlyphs::Unicode),
+            "--tree=ascii" => output_kind = Output::Tree(TreeGlyphs::Ascii),
+            "--describe" => output_kind = Output::Describe { as_json: false },
+            "--describe=json" => output_kind = Output::Describe { as_json: true },
+            "--infer-schema" => output_kind = Output::InferSchema,
+            "--hash" => output_kind = Output::Hash,
+            _ if argument.starts_with("--max-string-length=") => {
+                match argument["--max-string-length=".len()..].parse() {
+                    Ok(length) => max_string_length = Some(length),
+                    Err(_) => {
+                        eprintln!("ERROR: Invalid argument - {argument}");
+                        process::exit(2);
+                    }
+                }
+            }
+            _ if argument.starts_with('-') => {
+                eprintln!("ERROR: Unknown argument - {argument}");
+                process::exit(2);
+            }
+            _ => paths.push(argument),
+        }
+    }
+    if max_string_length.is_some() && !matches!(output_kind, Output::Tree(_)) {
+        eprintln!("ERROR: Invalid argument - --max-string-length only applies to --tree");
+        process::exit(2);
+    }
+    if paths.len() > 1 && !matches!(output_kind, Output::InferSchema) {
+        eprintln!("ERROR: Too many arguments - only --infer-schema reads more than one file");
+        process::exit(2);
+    }
 
-    stdin()
-        .read_to_string(&mut input)
-        .expect("Failed to read STDIN.");
+    let mut inputs = Vec::new();
+    if paths.is_empty() {
+        let mut input: String = String::new();
 
-    match parse(&input) {
-        Ok(json) => {
-            println!("{json}");
+        stdin()
+            .read_to_string(&mut input)
+            .expect("Failed to read STDIN.");
+        inputs.push(input);
+    }
+    for path in &paths {
+        match fs::read_to_string(path) {
+            Ok(input) => inputs.push(input),
+            Err(error) => {
+                eprintln!("ERROR: Failed to read {path} - {error}");
+                process::exit(1);
+            }
         }
-        Err(error) => {
+    }
+
+    let documents: Vec<_> = inputs
+        .iter()
+        .map(|input| match parse(input) {
+            Ok(json) => json,
+            Err(error) => {
+                eprintln!("{error}");
+                process::exit(1);
+            }
+        })
+        .collect();
+    let json = &documents[0];
+
+    let mut output = BufWriter::new(stdout().lock());
+    let written = match output_kind {
+        Output::Json => write_json(json, &mut output, &PrintOptions::default())
+            .and_then(|_| output.write_all(b"\n")),
+        Output::Env => match json_to_env(json) {
+            Ok(env) => output.write_all(env.as_bytes()),
+            Err(error) => {
+                eprintln!("{error}");
+                process::exit(1);
+            }
+        },
+        Output::Tree(glyphs) => {
+            let mut options = TreeOptions {
+                glyphs,
+                ..TreeOptions::default()
+            };
+            if max_string_length.is_some() {
+                options.max_string_length = max_string_length;
+            }
+            match json_to_tree_with_options(json, &options) {
+                Ok(tree) => output.write_all(tree.as_bytes()),
+                Err(error) => {
+                    eprintln!("{error}");
+                    process::exit(1);
+                }
+            }
+        }
+        Output::Describe { as_json: false } => write!(output, "{}", json.describe()),
+        Output::Describe { as_json: true } => write_json(
+            &json.describe().to_json(),
+            &mut output,
+            &PrintOptions::default(),
+        )
+        .and_then(|_| output.write_all(b"\n")),
+        Output::InferSchema => write_json(
+            &infer_schema(&documents),
+            &mut output,
+            &PrintOptions::default(),
+        )
+        .and_then(|_| output.write_all(b"\n")),
+        Output::Hash => match json.canonical_hash_hex() {
+            Ok(hash) => writeln!(output, "{hash}"),
+            Err(error) => {
+                eprintln!("{error}");
+                process::exit(1);
+            }
+        },
+    }
+    .and_then(|_| output.flush());
+
+    match written {
+        Ok(()) => {}
+        // The printer's own errors, such as for a non-finite number.
+        Err(error) if error.kind() == io::ErrorKind::InvalidData => {
             eprintln!("{error}");
             process::exit(1);
         }
-    };
+        Err(error) => {
+            eprintln!("ERROR: Failed to write JSON - {error}");
+            process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,312 @@
+use alloc::{
+    collections::{btree_map, BTreeMap},
+    string::String,
+    vec::{self, Vec},
+};
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    slice,
+};
+
+use crate::ast::Json;
+
+/// The properties of a JSON object, which remember the order they were first
+/// inserted in.
+///
+/// Two maps are equal if they have the same properties, in any order.
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(String, Json)>,
+    /// The position of each key in `entries`.
+    index: BTreeMap<String, usize>,
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        let position = *self.index.get(key)?;
+        Some(&self.entries[position].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Json> {
+        let position = *self.index.get(key)?;
+        Some(&mut self.entries[position].1)
+    }
+
+    /// Sets the value of a key, returning its previous value. A new key is
+    /// added at the end, while an existing key keeps its position.
+    pub fn insert(&mut self, key: String, value: Json) -> Option<Json> {
+        match self.index.get(&key) {
+            Some(&position) => Some(core::mem::replace(&mut self.entries[position].1, value)),
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes a key, returning its value. The keys after it keep their order.
+    pub fn remove(&mut self, key: &str) -> Option<Json> {
+        let position = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(position);
+
+        for later_position in self.index.values_mut() {
+            if *later_position > position {
+                *later_position -= 1;
+            }
+        }
+
+        Some(value)
+    }
+
+    /// The entries in insertion order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.entries.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(self.entries.iter_mut())
+    }
+
+    /// The entries in lexicographic order of their keys.
+    pub fn iter_sorted(&self) -> SortedIter<'_> {
+        SortedIter {
+            keys: self.index.iter(),
+            entries: &self.entries,
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &String> + ExactSizeIterator {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &Json> + ExactSizeIterator {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Json> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+
+    /// Reorders the entries, keeping the order of those the comparison finds equal.
+    pub fn sort_by(&mut self, mut compare: impl FnMut(&str, &str) -> Ordering) {
+        self.entries.sort_by(|(a, _), (b, _)| compare(a, b));
+
+        for (position, (key, _)) in self.entries.iter().enumerate() {
+            *self.index.get_mut(key).expect("Every entry is indexed") = position;
+        }
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Map) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl Eq for Map {}
+
+/// Hashes the entries in key order, so that maps which are equal hash the same.
+impl Hash for Map {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for entry in self.iter_sorted() {
+            entry.hash(state);
+        }
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<(String, Json)> for Map {
+    fn from_iter<I: IntoIterator<Item = (String, Json)>>(entries: I) -> Map {
+        let mut map = Map::new();
+        map.extend(entries);
+        map
+    }
+}
+
+impl Extend<(String, Json)> for Map {
+    fn extend<I: IntoIterator<Item = (String, Json)>>(&mut self, entries: I) {
+        for (key, value) in entries {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<const N: usize> From<[(String, Json); N]> for Map {
+    fn from(entries: [(String, Json); N]) -> Map {
+        entries.into_iter().collect()
+    }
+}
+
+impl From<BTreeMap<String, Json>> for Map {
+    fn from(entries: BTreeMap<String, Json>) -> Map {
+        entries.into_iter().collect()
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (String, Json);
+    type IntoIter = vec::IntoIter<(String, Json)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = (&'a String, &'a Json);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Map {
+    type Item = (&'a String, &'a mut Json);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
+
+#[derive(Clone)]
+pub struct Iter<'a>(slice::Iter<'a, (String, Json)>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a Json);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+pub struct IterMut<'a>(slice::IterMut<'a, (String, Json)>);
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a String, &'a mut Json);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+#[derive(Clone)]
+pub struct SortedIter<'a> {
+    keys: btree_map::Iter<'a, String, usize>,
+    entries: &'a [(String, Json)],
+}
+
+impl<'a> Iterator for SortedIter<'a> {
+    type Item = (&'a String, &'a Json);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, &position) = self.keys.next()?;
+        Some((key, &self.entries[position].1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.keys.size_hint()
+    }
+}
+
+impl ExactSizeIterator for SortedIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Json, dsl::ToJson, map::Map};
+
+    fn abc() -> Map {
+        Map::from([
+            ("b".to_owned(), 1.to_json()),
+            ("c".to_owned(), 2.to_json()),
+            ("a".to_owned(), 3.to_json()),
+        ])
+    }
+
+    #[test]
+    fn it_iterates_in_insertion_order() {
+        assert_eq!(abc().keys().collect::<Vec<_>>(), ["b", "c", "a"]);
+        assert_eq!(
+            abc().iter_sorted().map(|(key, _)| key).collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn it_keeps_the_position_of_a_key_which_is_inserted_again() {
+        let mut map = abc();
+
+        assert_eq!(map.insert("c".to_owned(), Json::Null), Some(2.to_json()));
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["b", "c", "a"]);
+        assert_eq!(map.get("c"), Some(&Json::Null));
+    }
+
+    #[test]
+    fn it_keeps_the_order_of_the_other_keys_when_one_is_removed() {
+        let mut map = abc();
+
+        assert_eq!(map.remove("b"), Some(1.to_json()));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["c", "a"]);
+        assert_eq!(map.get("a"), Some(&3.to_json()));
+        assert_eq!(map.get("c"), Some(&2.to_json()));
+    }
+
+    #[test]
+    fn maps_with_the_same_entries_in_a_different_order_are_equal() {
+        let mut sorted = abc();
+        sorted.sort_by(str::cmp);
+
+        assert_eq!(sorted.keys().collect::<Vec<_>>(), ["a", "b", "c"]);
+        assert_eq!(sorted.get("b"), Some(&1.to_json()));
+        assert_eq!(sorted, abc());
+        assert_ne!(sorted, Map::from([("a".to_owned(), 3.to_json())]));
+    }
+}
@@ -0,0 +1,299 @@
+//! Encoding values as MessagePack, a binary format with the same types as
+//! JSON, using the smallest format the specification allows for each value.
+
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::ast::{is_whole_number, Json};
+
+/// An error from encoding a value which MessagePack can't hold.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MsgpackError {
+    /// The value contains NaN or an infinity, which JSON can't represent.
+    NonFiniteNumber(f64),
+    /// A string has more bytes, or an array or object more items, than a
+    /// 32-bit length can count.
+    TooLong(usize),
+}
+
+impl fmt::Display for MsgpackError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MsgpackError::NonFiniteNumber(number) => formatter.write_fmt(format_args!(
+                "ERROR: Cannot encode MessagePack - {number} is not a valid JSON number"
+            )),
+            MsgpackError::TooLong(length) => formatter.write_fmt(format_args!(
+                "ERROR: Cannot encode MessagePack - a length of {length} doesn't fit in 32 bits"
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for MsgpackError {}
+
+/// Encodes a value as MessagePack. Whole numbers which fit in an `i64` or
+/// `u64` use the smallest int format which holds them, and other numbers,
+/// including `-0`, are float 64. Properties are written in the map's order.
+pub fn json_to_msgpack(value: &Json) -> Result<Vec<u8>, MsgpackError> {
+    let mut output = Vec::new();
+    encode(value, &mut output)?;
+    Ok(output)
+}
+
+fn encode(value: &Json, output: &mut Vec<u8>) -> Result<(), MsgpackError> {
+    match value {
+        Json::Null => output.push(0xc0),
+        Json::Boolean(false) => output.push(0xc2),
+        Json::Boolean(true) => output.push(0xc3),
+        Json::Number(number) => encode_number(*number, output)?,
+        Json::BigInteger(integer) => {
+            if !encode_integer(*integer, output) {
+                encode_number(*integer as f64, output)?;
+            }
+        }
+        Json::String(string) => {
+            encode_length(string.len(), [0xa0, 0xd9, 0xda, 0xdb], 31, output)?;
+            output.extend_from_slice(string.as_bytes());
+        }
+        Json::Array(items) => {
+            encode_length(items.len(), [0x90, 0, 0xdc, 0xdd], 15, output)?;
+            for item in items {
+                encode(item, output)?;
+            }
+        }
+        Json::Object(properties) => {
+            encode_length(properties.len(), [0x80, 0, 0xde, 0xdf], 15, output)?;
+            for (key, value) in properties {
+                encode_length(key.len(), [0xa0, 0xd9, 0xda, 0xdb], 31, output)?;
+                output.extend_from_slice(key.as_bytes());
+                encode(value, output)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn encode_number(number: f64, output: &mut Vec<u8>) -> Result<(), MsgpackError> {
+    if !number.is_finite() {
+        return Err(MsgpackError::NonFiniteNumber(number));
+    }
+    // Every whole `f64` which fits in a `u64` or `i64` is exactly an `i128`.
+    let is_integer = is_whole_number(number) && !(number == 0.0 && number.is_sign_negative());
+    if !(is_integer && encode_integer(number as i128, output)) {
+        output.push(0xcb);
+        output.extend(number.to_be_bytes());
+    }
+    Ok(())
+}
+
+/// Writes an integer in the smallest int format which holds it, returning
+/// whether it fits in one.
+fn encode_integer(integer: i128, output: &mut Vec<u8>) -> bool {
+    if let Ok(integer) = u64::try_from(integer) {
+        match integer {
+            0..=0x7f => output.push(integer as u8),
+            0x80..=0xff => output.extend([0xcc, integer as u8]),
+            0x100..=0xffff => {
+                output.push(0xcd);
+                output.extend((integer as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                output.push(0xce);
+                output.extend((integer as u32).to_be_bytes());
+            }
+            _ => {
+                output.push(0xcf);
+                output.extend(integer.to_be_bytes());
+            }
+        }
+    } else if let Ok(integer) = i64::try_from(integer) {
+        match integer {
+            -32..=-1 => output.push(integer as u8),
+            -0x80..=-33 => output.extend([0xd0, integer as u8]),
+            -0x8000..=-0x81 => {
+                output.push(0xd1);
+                output.extend((integer as i16).to_be_bytes());
+            }
+            -0x8000_0000..=-0x8001 => {
+                output.push(0xd2);
+                output.extend((integer as i32).to_be_bytes());
+            }
+            _ => {
+                output.push(0xd3);
+                output.extend(integer.to_be_bytes());
+            }
+        }
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Writes the header of a string, array or map: the fix format holding the
+/// length in its low bits if it is at most `fix_max`, or else the 8-bit,
+/// 16-bit or 32-bit format, of which arrays and maps have no 8-bit one,
+/// marked by 0.
+fn encode_length(
+    length: usize,
+    [fix, format8, format16, format32]: [u8; 4],
+    fix_max: usize,
+    output: &mut Vec<u8>,
+) -> Result<(), MsgpackError> {
+    match length {
+        _ if length <= fix_max => output.push(fix | length as u8),
+        0..=0xff if format8 != 0 => output.extend([format8, length as u8]),
+        0..=0xffff => {
+            output.push(format16);
+            output.extend((length as u16).to_be_bytes());
+        }
+        _ => {
+            let length = u32::try_from(length).map_err(|_| MsgpackError::TooLong(length))?;
+            output.push(format32);
+            output.extend(length.to_be_bytes());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        dsl::ToJson,
+        msgpack::{json_to_msgpack, MsgpackError},
+        parser::parse,
+    };
+
+    fn encoded(value: impl ToJson) -> Vec<u8> {
+        json_to_msgpack(&value.to_json()).unwrap()
+    }
+
+    /// The header of an encoded value, without the items or bytes after it.
+    fn header(value: impl ToJson, length: usize) -> Vec<u8> {
+        let bytes = encoded(value);
+        bytes[..length].to_vec()
+    }
+
+    #[test]
+    fn it_encodes_scalars() {
+        assert_eq!(encoded(Json::Null), [0xc0]);
+        assert_eq!(encoded(false), [0xc2]);
+        assert_eq!(encoded(true), [0xc3]);
+        assert_eq!(
+            encoded(1.5),
+            [0xcb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            encoded(-0.0),
+            [0xcb, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(encoded(""), [0xa0]);
+        assert_eq!(encoded("hé"), [0xa3, b'h', 0xc3, 0xa9]);
+    }
+
+    #[test]
+    fn it_uses_the_smallest_int_format_at_each_boundary() {
+        let cases: [(f64, &[u8]); 20] = [
+            (0.0, &[0x00]),
+            (127.0, &[0x7f]),
+            (128.0, &[0xcc, 0x80]),
+            (255.0, &[0xcc, 0xff]),
+            (256.0, &[0xcd, 0x01, 0x00]),
+            (65535.0, &[0xcd, 0xff, 0xff]),
+            (65536.0, &[0xce, 0x00, 0x01, 0x00, 0x00]),
+            (4294967295.0, &[0xce, 0xff, 0xff, 0xff, 0xff]),
+            (4294967296.0, &[0xcf, 0, 0, 0, 0x01, 0, 0, 0, 0]),
+            (
+                1e19,
+                &[0xcf, 0x8a, 0xc7, 0x23, 0x04, 0x89, 0xe8, 0x00, 0x00],
+            ),
+            (-1.0, &[0xff]),
+            (-32.0, &[0xe0]),
+            (-33.0, &[0xd0, 0xdf]),
+            (-128.0, &[0xd0, 0x80]),
+            (-129.0, &[0xd1, 0xff, 0x7f]),
+            (-32768.0, &[0xd1, 0x80, 0x00]),
+            (-32769.0, &[0xd2, 0xff, 0xff, 0x7f, 0xff]),
+            (-2147483648.0, &[0xd2, 0x80, 0x00, 0x00, 0x00]),
+            (
+                -2147483649.0,
+                &[0xd3, 0xff, 0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff],
+            ),
+            (-9223372036854775808.0, &[0xd3, 0x80, 0, 0, 0, 0, 0, 0, 0]),
+        ];
+
+        for (number, bytes) in cases {
+            assert_eq!(encoded(number), bytes, "{number}");
+        }
+    }
+
+    #[test]
+    fn whole_numbers_outside_the_int_formats_are_floats() {
+        for number in [18446744073709551616.0, -9223372036854777856.0, 1e300] {
+            assert_eq!(encoded(Json::Number(number))[0], 0xcb, "{number}");
+        }
+    }
+
+    #[test]
+    fn it_uses_the_smallest_string_format_at_each_boundary() {
+        let string = |length: usize| "x".repeat(length);
+
+        assert_eq!(header(string(31), 1), [0xbf]);
+        assert_eq!(header(string(32), 2), [0xd9, 0x20]);
+        assert_eq!(header(string(255), 2), [0xd9, 0xff]);
+        assert_eq!(header(string(256), 3), [0xda, 0x01, 0x00]);
+        assert_eq!(header(string(65535), 3), [0xda, 0xff, 0xff]);
+        assert_eq!(header(string(65536), 5), [0xdb, 0x00, 0x01, 0x00, 0x00]);
+        assert_eq!(encoded(string(65536)).len(), 5 + 65536);
+    }
+
+    #[test]
+    fn it_uses_the_smallest_array_and_map_formats_at_each_boundary() {
+        let array = |length: usize| vec![Json::Null; length];
+        let object = |length: usize| {
+            (0..length)
+                .map(|index| (index.to_string(), Json::Null))
+                .collect::<Json>()
+        };
+
+        assert_eq!(encoded(array(0)), [0x90]);
+        assert_eq!(header(array(15), 1), [0x9f]);
+        assert_eq!(header(array(16), 3), [0xdc, 0x00, 0x10]);
+        assert_eq!(header(array(65535), 3), [0xdc, 0xff, 0xff]);
+        assert_eq!(header(array(65536), 5), [0xdd, 0x00, 0x01, 0x00, 0x00]);
+        assert_eq!(encoded(Json::object()), [0x80]);
+        assert_eq!(header(object(15), 1), [0x8f]);
+        assert_eq!(header(object(16), 3), [0xde, 0x00, 0x10]);
+        assert_eq!(header(object(65535), 3), [0xde, 0xff, 0xff]);
+        assert_eq!(header(object(65536), 5), [0xdf, 0x00, 0x01, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn it_encodes_nested_values_in_order() {
+        let document = parse(r#"{"b": [1, "a"], "a": {"c": null}}"#).unwrap();
+
+        assert_eq!(
+            json_to_msgpack(&document).unwrap(),
+            [0x82, 0xa1, b'b', 0x92, 0x01, 0xa1, b'a', 0xa1, b'a', 0x81, 0xa1, b'c', 0xc0]
+        );
+    }
+
+    #[test]
+    fn it_fails_on_non_finite_numbers() {
+        let document = vec![1.0, f64::NAN].to_json();
+
+        assert!(matches!(
+            json_to_msgpack(&document),
+            Err(MsgpackError::NonFiniteNumber(number)) if number.is_nan()
+        ));
+        assert_eq!(
+            json_to_msgpack(&f64::NEG_INFINITY.to_json())
+                .unwrap_err()
+                .to_string(),
+            "ERROR: Cannot encode MessagePack - -inf is not a valid JSON number"
+        );
+    }
+}
@@ -1,45 +1,668 @@
-use std::{collections::BTreeMap, error, fmt, iter::Peekable, str::Chars};
-
-use crate::ast::Json;
-
-#[derive(Debug, PartialEq)]
+use alloc::{
+    borrow::ToOwned,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cmp::Ordering, fmt, iter::Peekable, str::Chars};
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::{
+    ast::{
+        escape_pointer_token, parse_array_index, unescape_pointer_token, Comment, Comments, Json,
+    },
+    dsl::MAX_SAFE_INTEGER,
+    map::Map,
+};
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct JsonParseError {
     pub message: String,
+    /// Where in the input the error was found, if known.
+    pub position: Option<Position>,
+}
+
+/// A location in the input, pointing at the character which caused an error.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Position {
+    /// The byte offset from the start of the input.
+    pub offset: usize,
+    /// The line number, starting from 1.
+    pub line: usize,
+    /// The column number in characters, starting from 1.
+    pub column: usize,
 }
 
 impl fmt::Display for JsonParseError {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-        formatter.write_fmt(format_args!("ERROR: Invalid JSON - {}", self.message))
+        formatter.write_fmt(format_args!("ERROR: Invalid JSON - {}", self.message))?;
+
+        if let Some(Position { line, column, .. }) = self.position {
+            formatter.write_fmt(format_args!(" at line {line}, column {column}"))?;
+        }
+
+        Ok(())
     }
 }
 
+#[cfg(feature = "std")]
 impl error::Error for JsonParseError {}
 
+/// Relaxations of the JSON grammar which the parser can be asked to accept.
+///
+/// All of them are disabled by default, giving strict RFC 8259 parsing.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ParseOptions {
+    /// Accept `_` between two digits of a number, as in `1_000_000`. The
+    /// underscores are ignored when converting the number.
+    pub allow_numeric_underscores: bool,
+    /// Accept object keys which are identifiers without quotes, as JSON5
+    /// allows, e.g. `{name: "x"}`. An identifier is made of ASCII letters,
+    /// digits, `_` and `$`, and doesn't start with a digit.
+    pub allow_unquoted_keys: bool,
+    /// Accept strings and keys quoted with `'` rather than `"`, as JSON5
+    /// allows, and the escape `\'` in any string.
+    pub allow_single_quotes: bool,
+    /// Accept a comma after the last item of an array or object, as JSON5
+    /// allows, e.g. `[1, 2,]`.
+    pub allow_trailing_commas: bool,
+}
+
 pub fn parse(json: &str) -> Result<Json, JsonParseError> {
-    let mut rest = json.chars().peekable();
-    let parsed = parse_value(&mut rest)?;
-
-    if let Some(unexpected_char) = rest.peek().map(|c| c.to_owned()) {
-        fail(format!(
-            "Unexpected character: {unexpected_char}, {} chars remaining",
-            rest.count()
-        ))
+    parse_with_options(json, &ParseOptions::default())
+}
+
+pub fn parse_with_options(json: &str, options: &ParseOptions) -> Result<Json, JsonParseError> {
+    let mut rest = Input::new(json);
+    rest.options = *options;
+
+    parse_document(&mut rest)
+}
+
+/// Checks that `json` is a valid document, without building any values.
+///
+/// This runs exactly the same grammar as `parse`, but allocates nothing for
+/// the values it reads, so it's much cheaper when the parsed value would be
+/// thrown away.
+pub fn validate(json: &str) -> Result<(), JsonParseError> {
+    parse_document(&mut Input::new(json))
+}
+
+/// Parses a JSONC document, in which `//` and `/* */` comments may appear
+/// anywhere whitespace can.
+///
+/// Each comment is attached to the value (or object entry) which follows it,
+/// or, if there is none, to the enclosing array or object as a dangling comment.
+pub fn parse_with_comments(json: &str) -> Result<(Json, Comments), JsonParseError> {
+    let mut rest = Input::new(json);
+    rest.comments = Some(CommentCollector::default());
+
+    let parsed = parse_document(&mut rest)?;
+    let mut collector = rest.comments.take().unwrap_or_default();
+    collector.comments.trailing = collector.pending;
+
+    Ok((parsed, collector.comments))
+}
+
+fn parse_document<V: ParsedValue>(rest: &mut Input) -> Result<V, JsonParseError> {
+    let parsed = parse_value(rest).map_err(|error| rest.locate(error))?;
+
+    if let Some(unexpected_char) = rest.next() {
+        let error = JsonParseError {
+            message: format!(
+                "Unexpected character: {unexpected_char}, {} chars remaining",
+                rest.count() + 1
+            ),
+            position: None,
+        };
+        Err(rest.locate(error))
     } else {
         Ok(parsed)
     }
 }
 
-fn fail<T>(message: impl Into<String>) -> Result<T, JsonParseError> {
+/// The values produced by the parser. Parsing to `Json` builds the value, and
+/// parsing to `()` checks the syntax without allocating anything, so that
+/// `validate` and `parse` share one implementation of the grammar.
+trait ParsedValue: Sized {
+    type Text: ParsedText;
+    type Items: Default;
+    type Properties: Default;
+
+    fn null() -> Self;
+    fn boolean(value: bool) -> Self;
+    fn number(text: &Self::Text) -> Result<Self, JsonParseError>;
+    fn string(text: Self::Text) -> Self;
+    fn array(items: Self::Items) -> Self;
+    fn object(properties: Self::Properties) -> Self;
+    fn push_item(items: &mut Self::Items, item: Self);
+    fn insert_property(properties: &mut Self::Properties, key: Self::Text, value: Self);
+}
+
+trait ParsedText: Default {
+    fn push(&mut self, c: char);
+    fn as_str(&self) -> &str;
+}
+
+impl ParsedValue for Json {
+    type Text = String;
+    type Items = Vec<Json>;
+    type Properties = Map;
+
+    fn null() -> Json {
+        Json::Null
+    }
+    fn boolean(value: bool) -> Json {
+        Json::Boolean(value)
+    }
+    fn number(text: &String) -> Result<Json, JsonParseError> {
+        number_from_text(text)
+    }
+    fn string(text: String) -> Json {
+        Json::String(text)
+    }
+    fn array(items: Vec<Json>) -> Json {
+        Json::Array(items)
+    }
+    fn object(properties: Map) -> Json {
+        Json::Object(properties)
+    }
+    fn push_item(items: &mut Vec<Json>, item: Json) {
+        items.push(item);
+    }
+    fn insert_property(properties: &mut Map, key: String, value: Json) {
+        properties.insert(key, value);
+    }
+}
+
+impl ParsedValue for () {
+    type Text = ();
+    type Items = ();
+    type Properties = ();
+
+    fn null() {}
+    fn boolean(_: bool) {}
+    fn number(_: &()) -> Result<(), JsonParseError> {
+        Ok(())
+    }
+    fn string(_: ()) {}
+    fn array(_: ()) {}
+    fn object(_: ()) {}
+    fn push_item(_: &mut (), _: ()) {}
+    fn insert_property(_: &mut (), _: (), _: ()) {}
+}
+
+impl ParsedText for String {
+    fn push(&mut self, c: char) {
+        String::push(self, c);
+    }
+    fn as_str(&self) -> &str {
+        self
+    }
+}
+
+impl ParsedText for () {
+    fn push(&mut self, _: char) {}
+    fn as_str(&self) -> &str {
+        ""
+    }
+}
+
+struct Input<'a> {
+    chars: Peekable<Chars<'a>>,
+    options: ParseOptions,
+    comments: Option<CommentCollector>,
+    next_position: Position,
+    last_position: Position,
+}
+
+#[derive(Default)]
+struct CommentCollector {
+    comments: Comments,
+    pending: Vec<Comment>,
+    path: Vec<String>,
+}
+
+impl<'a> Input<'a> {
+    fn new(json: &'a str) -> Input<'a> {
+        Input {
+            chars: json.chars().peekable(),
+            options: ParseOptions::default(),
+            comments: None,
+            next_position: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            last_position: Position::default(),
+        }
+    }
+
+    /// Records the position of the last character read as the location of
+    /// `error`, unless it already has one.
+    fn locate(&self, mut error: JsonParseError) -> JsonParseError {
+        if error.position.is_none() {
+            error.position = Some(if self.last_position.line == 0 {
+                self.next_position
+            } else {
+                self.last_position
+            });
+        }
+        error
+    }
+
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+
+    fn next_if(&mut self, predicate: impl FnOnce(&char) -> bool) -> Option<char> {
+        match self.chars.peek() {
+            Some(next_char) if predicate(next_char) => self.next(),
+            _ => None,
+        }
+    }
+
+    fn enter_index(&mut self, index: usize) {
+        if let Some(collector) = &mut self.comments {
+            collector.path.push(index.to_string());
+        }
+    }
+
+    fn enter_key(&mut self, key: &str) {
+        if let Some(collector) = &mut self.comments {
+            collector.path.push(escape_pointer_token(key));
+        }
+    }
+
+    fn leave(&mut self) {
+        if let Some(collector) = &mut self.comments {
+            collector.path.pop();
+        }
+    }
+
+    fn attach_leading_comments(&mut self) {
+        if let Some(collector) = &mut self.comments {
+            collector.attach_pending(|comments| &mut comments.leading);
+        }
+    }
+
+    fn attach_dangling_comments(&mut self) {
+        if let Some(collector) = &mut self.comments {
+            collector.attach_pending(|comments| &mut comments.dangling);
+        }
+    }
+}
+
+impl Iterator for Input<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let next_char = self.chars.next()?;
+
+        self.last_position = self.next_position;
+        self.next_position.offset += next_char.len_utf8();
+        if next_char == '\n' {
+            self.next_position.line += 1;
+            self.next_position.column = 1;
+        } else {
+            self.next_position.column += 1;
+        }
+
+        Some(next_char)
+    }
+}
+
+impl CommentCollector {
+    fn attach_pending(
+        &mut self,
+        position: impl FnOnce(&mut Comments) -> &mut BTreeMap<String, Vec<Comment>>,
+    ) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let pointer: String = self.path.iter().map(|token| format!("/{token}")).collect();
+
+        position(&mut self.comments)
+            .entry(pointer)
+            .or_default()
+            .append(&mut self.pending);
+    }
+}
+
+/// Parses only the value addressed by the JSON Pointer (RFC 6901) `pointer`,
+/// skipping over everything before it without building any `Json` for it.
+///
+/// Returns `Ok(None)` if the pointer does not address a value in the document.
+/// Parsing stops as soon as the addressed value has been read, so any content
+/// after it is not examined. If an object contains the same key more than
+/// once, the first occurrence is used.
+pub fn parse_pointer(json: &str, pointer: &str) -> Result<Option<Json>, JsonParseError> {
+    let tokens = parse_pointer_tokens(pointer)?;
+    let mut rest = Input::new(json);
+
+    parse_value_at_pointer(&mut rest, &tokens).map_err(|error| rest.locate(error))
+}
+
+fn parse_pointer_tokens(pointer: &str) -> Result<Vec<String>, JsonParseError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    match pointer.strip_prefix('/') {
+        Some(tokens) => Ok(tokens.split('/').map(unescape_pointer_token).collect()),
+        None => fail(format!("JSON pointer must start with '/': {pointer}")),
+    }
+}
+
+fn parse_value_at_pointer(
+    rest: &mut Input,
+    tokens: &[String],
+) -> Result<Option<Json>, JsonParseError> {
+    let Some((token, remaining_tokens)) = tokens.split_first() else {
+        return parse_value(rest).map(Some);
+    };
+
+    skip_whitespace(rest)?;
+
+    match peek_or_fail(rest)? {
+        '[' => parse_array_item_at_pointer(rest, token, remaining_tokens),
+        '{' => parse_object_property_at_pointer(rest, token, remaining_tokens),
+        _ => {
+            skip_value(rest)?;
+            Ok(None)
+        }
+    }
+}
+
+fn parse_array_item_at_pointer(
+    rest: &mut Input,
+    token: &str,
+    remaining_tokens: &[String],
+) -> Result<Option<Json>, JsonParseError> {
+    let Some(target_index) = parse_array_index(token) else {
+        skip_value(rest)?;
+        return Ok(None);
+    };
+
+    next_or_fail(rest)?;
+    skip_whitespace(rest)?;
+
+    if peek_or_fail(rest)? == ']' {
+        rest.next();
+        return Ok(None);
+    }
+
+    let mut index = 0;
+
+    loop {
+        if index == target_index {
+            return parse_value_at_pointer(rest, remaining_tokens);
+        }
+
+        skip_value(rest)?;
+        index += 1;
+
+        match next_or_fail(rest)? {
+            ']' => return Ok(None),
+            ',' => continue,
+            unexpected_char => {
+                return fail(format!("Expected ',' or ']', found '{unexpected_char}'"))
+            }
+        }
+    }
+}
+
+fn parse_object_property_at_pointer(
+    rest: &mut Input,
+    token: &str,
+    remaining_tokens: &[String],
+) -> Result<Option<Json>, JsonParseError> {
+    next_or_fail(rest)?;
+    skip_whitespace(rest)?;
+
+    if peek_or_fail(rest)? == '}' {
+        rest.next();
+        return Ok(None);
+    }
+
+    loop {
+        let key: String = parse_string(rest)?;
+        skip_whitespace(rest)?;
+
+        if next_or_fail(rest)? != ':' {
+            return fail("Missing colon after object key");
+        }
+
+        if key == token {
+            return parse_value_at_pointer(rest, remaining_tokens);
+        }
+
+        skip_value(rest)?;
+
+        match next_or_fail(rest)? {
+            '}' => return Ok(None),
+            ',' => {
+                skip_whitespace(rest)?;
+                continue;
+            }
+            unexpected_char => {
+                return fail(format!("Expected ',' or '}}', found '{unexpected_char}'"))
+            }
+        }
+    }
+}
+
+fn skip_value(rest: &mut Input) -> Result<(), JsonParseError> {
+    skip_whitespace(rest)?;
+
+    match peek_or_fail(rest)? {
+        '"' => {
+            rest.next();
+            skip_string_contents(rest)?;
+        }
+        '[' | '{' => skip_container(rest)?,
+        _ => parse_value(rest)?,
+    }
+
+    skip_whitespace(rest)?;
+
+    Ok(())
+}
+
+fn skip_container(rest: &mut Input) -> Result<(), JsonParseError> {
+    let mut expected_closing_brackets = Vec::new();
+
+    loop {
+        match next_or_fail(rest)? {
+            '[' => expected_closing_brackets.push(']'),
+            '{' => expected_closing_brackets.push('}'),
+            closing_bracket @ (']' | '}') => {
+                if expected_closing_brackets.pop() != Some(closing_bracket) {
+                    return fail(format!("Unexpected character: {closing_bracket}"));
+                }
+                if expected_closing_brackets.is_empty() {
+                    return Ok(());
+                }
+            }
+            '"' => skip_string_contents(rest)?,
+            _ => {}
+        }
+    }
+}
+
+fn skip_string_contents(rest: &mut Input) -> Result<(), JsonParseError> {
+    loop {
+        match next_or_fail(rest)? {
+            '"' => return Ok(()),
+            '\\' => {
+                next_or_fail(rest)?;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Converts the text of a number which matches the grammar and isn't too large
+/// for an `f64`, as `number_is_too_large` decides. An integer beyond
+/// `MAX_SAFE_INTEGER` either way, which an `f64` might round, is kept exactly
+/// if it fits in an `i128`.
+pub(crate) fn number_from_text(text: &str) -> Result<Json, JsonParseError> {
+    match text.parse::<i128>() {
+        Ok(integer) if integer.unsigned_abs() > MAX_SAFE_INTEGER as u128 => {
+            return Ok(Json::BigInteger(integer))
+        }
+        _ => {}
+    }
+    match text.parse::<f64>() {
+        Ok(number) => Ok(Json::Number(number)),
+        Err(_) => fail(format!("Expected number, found: {text}")),
+    }
+}
+
+/// Whether the text of a number which matches the grammar would round to
+/// infinity as an `f64`, which can't be printed back as JSON.
+pub(crate) fn number_is_too_large(text: &str) -> bool {
+    let mut magnitude = NumberMagnitude::default();
+    text.chars().for_each(|c| magnitude.push(c));
+    magnitude.is_too_large()
+}
+
+/// The significant digits of `2^1024 - 2^970`, halfway between `f64::MAX` and
+/// `2^1024`, which is the smallest number that rounds to infinity.
+const OVERFLOW_DIGITS: &[u8] = b"179769313486231580793728971405303415079934132710037826936173778980444968292764750946649017977587207096330286416692887910946555547851940402630657488671505820681908902000708383676273854845817711531764475730270069855571366959622842914819860834936475292719074168444365510704342711559699508093042880177904174497792";
+
+/// The power of ten of the first of `OVERFLOW_DIGITS`.
+const OVERFLOW_EXPONENT: i64 = 308;
+
+/// Follows the characters of a number as they're read, to tell whether it's
+/// too large for an `f64` without keeping its text. This lets `validate`
+/// reject exactly the numbers which `parse` does.
+struct NumberMagnitude {
+    in_fraction: bool,
+    in_exponent: bool,
+    negative_exponent: bool,
+    exponent: i64,
+    /// Whether a non-zero digit has been read before the exponent.
+    significant: bool,
+    /// The power of ten of the first non-zero digit, ignoring the exponent.
+    leading_exponent: i64,
+    /// How the significant digits read so far compare with `OVERFLOW_DIGITS`.
+    ordering: Ordering,
+    compared: usize,
+}
+
+impl Default for NumberMagnitude {
+    fn default() -> NumberMagnitude {
+        NumberMagnitude {
+            in_fraction: false,
+            in_exponent: false,
+            negative_exponent: false,
+            exponent: 0,
+            significant: false,
+            leading_exponent: 0,
+            ordering: Ordering::Equal,
+            compared: 0,
+        }
+    }
+}
+
+impl NumberMagnitude {
+    fn push(&mut self, c: char) {
+        match c {
+            '.' => self.in_fraction = true,
+            'e' | 'E' => self.in_exponent = true,
+            '-' if self.in_exponent => self.negative_exponent = true,
+            '-' | '+' => {}
+            digit if self.in_exponent => {
+                let value = digit.to_digit(10).unwrap_or(0);
+                self.exponent = self
+                    .exponent
+                    .saturating_mul(10)
+                    .saturating_add(value.into());
+            }
+            digit => self.push_significand_digit(digit as u8),
+        }
+    }
+
+    fn push_significand_digit(&mut self, digit: u8) {
+        if self.significant {
+            if !self.in_fraction {
+                self.leading_exponent += 1;
+            }
+        } else {
+            if self.in_fraction {
+                self.leading_exponent -= 1;
+            }
+            if digit == b'0' {
+                return;
+            }
+            self.significant = true;
+        }
+
+        if self.ordering == Ordering::Equal {
+            self.ordering = match OVERFLOW_DIGITS.get(self.compared) {
+                Some(overflow_digit) => digit.cmp(overflow_digit),
+                None if digit == b'0' => Ordering::Equal,
+                None => Ordering::Greater,
+            };
+            self.compared += 1;
+        }
+    }
+
+    fn is_too_large(&self) -> bool {
+        if !self.significant {
+            return false;
+        }
+        let exponent = if self.negative_exponent {
+            -self.exponent
+        } else {
+            self.exponent
+        };
+        match self
+            .leading_exponent
+            .saturating_add(exponent)
+            .cmp(&OVERFLOW_EXPONENT)
+        {
+            Ordering::Less => false,
+            Ordering::Greater => true,
+            // Fewer digits than `OVERFLOW_DIGITS` which match it so far are
+            // smaller, as its remaining digits aren't all zero.
+            Ordering::Equal => match self.ordering {
+                Ordering::Equal => self.compared >= OVERFLOW_DIGITS.len(),
+                ordering => ordering == Ordering::Greater,
+            },
+        }
+    }
+}
+
+/// The text of a number being parsed, along with how large it is.
+#[derive(Default)]
+struct NumberText<T> {
+    text: T,
+    magnitude: NumberMagnitude,
+}
+
+impl<T: ParsedText> ParsedText for NumberText<T> {
+    fn push(&mut self, c: char) {
+        self.text.push(c);
+        self.magnitude.push(c);
+    }
+    fn as_str(&self) -> &str {
+        self.text.as_str()
+    }
+}
+
+pub(crate) fn fail<T>(message: impl Into<String>) -> Result<T, JsonParseError> {
     Err(JsonParseError {
         message: message.into(),
+        position: None,
     })
 }
 
-fn consume(
-    rest: &mut Peekable<Chars>,
-    literal: &str,
-    json_value: Json,
-) -> Result<Json, JsonParseError> {
+fn consume<V>(rest: &mut Input, literal: &str, json_value: V) -> Result<V, JsonParseError> {
     for expected_char in literal.chars() {
         match rest.next() {
             None => return fail("Unexpected end of input".to_owned()),
@@ -54,115 +677,182 @@ fn consume(
     Ok(json_value)
 }
 
-fn peek_or_fail(rest: &mut Peekable<Chars>) -> Result<char, JsonParseError> {
+fn peek_or_fail(rest: &mut Input) -> Result<char, JsonParseError> {
     match rest.peek() {
         Some(c) => Ok(*c),
         None => fail("Unexpected end of input"),
     }
 }
 
-fn next_or_fail(rest: &mut Peekable<Chars>) -> Result<char, JsonParseError> {
+fn next_or_fail(rest: &mut Input) -> Result<char, JsonParseError> {
     match rest.next() {
         Some(c) => Ok(c),
         None => fail("Unexpected end of input"),
     }
 }
 
-fn skip_whitespace(rest: &mut Peekable<Chars>) {
+fn skip_whitespace(rest: &mut Input) -> Result<(), JsonParseError> {
     while let Some(next_char) = rest.peek() {
         if " \n\r\t".contains(*next_char) {
             rest.next();
             continue;
         }
+        if *next_char == '/' && rest.comments.is_some() {
+            let comment = parse_comment(rest)?;
+            if let Some(collector) = &mut rest.comments {
+                collector.pending.push(comment);
+            }
+            continue;
+        }
         break;
     }
+    Ok(())
+}
+
+fn parse_comment(rest: &mut Input) -> Result<Comment, JsonParseError> {
+    next_or_fail(rest)?;
+
+    let mut text = String::new();
+
+    match next_or_fail(rest)? {
+        '/' => {
+            while let Some(next_char) = rest.next_if(|c| *c != '\n') {
+                text.push(next_char);
+            }
+            if text.ends_with('\r') {
+                text.pop();
+            }
+            Ok(Comment::Line(text))
+        }
+        '*' => loop {
+            match next_or_fail(rest)? {
+                '*' if rest.next_if(|c| *c == '/').is_some() => return Ok(Comment::Block(text)),
+                next_char => text.push(next_char),
+            }
+        },
+        unexpected_char => fail(format!(
+            "Expected '/' or '*', but found '{unexpected_char}'"
+        )),
+    }
 }
 
-fn parse_value(rest: &mut Peekable<Chars>) -> Result<Json, JsonParseError> {
-    skip_whitespace(rest);
+fn parse_value<V: ParsedValue>(rest: &mut Input) -> Result<V, JsonParseError> {
+    skip_whitespace(rest)?;
+    rest.attach_leading_comments();
 
     let value = match peek_or_fail(rest)? {
-        'n' => consume(rest, "null", Json::Null),
-        't' => consume(rest, "true", Json::Boolean(true)),
-        'f' => consume(rest, "false", Json::Boolean(false)),
+        'n' => consume(rest, "null", V::null()),
+        't' => consume(rest, "true", V::boolean(true)),
+        'f' => consume(rest, "false", V::boolean(false)),
         '-' | '0'..='9' => parse_number(rest),
         '"' => parse_string_value(rest),
+        '\'' if rest.options.allow_single_quotes => parse_string_value(rest),
         '[' => parse_array(rest),
         '{' => parse_object(rest),
-        unexpected_char => fail(format!("Unexpected character: {unexpected_char}")),
+        unexpected_char => {
+            rest.next();
+            fail(format!("Unexpected character: {unexpected_char}"))
+        }
     };
 
-    skip_whitespace(rest);
+    skip_whitespace(rest)?;
 
     value
 }
 
-fn parse_number(rest: &mut Peekable<Chars>) -> Result<Json, JsonParseError> {
-    let mut number_string = String::new();
+fn parse_number<V: ParsedValue>(rest: &mut Input) -> Result<V, JsonParseError> {
+    let start = rest.next_position;
+    let mut number_string = NumberText::<V::Text>::default();
 
-    let mut advance_if = |predicate: fn(char) -> bool| -> bool {
-        match rest.next_if(|arg0: &char| predicate(*arg0)) {
-            Some(next_char) => {
-                number_string.push(next_char);
-                true
-            }
-            None => false,
-        }
-    };
+    advance_if(rest, &mut number_string, |c| c == '-');
 
-    advance_if(|c| c == '-');
-
-    if !advance_if(|c| c == '0') {
-        if !advance_if(|c| "123456789".contains(c)) {
+    if !advance_if(rest, &mut number_string, |c| c == '0') {
+        if !advance_if(rest, &mut number_string, |c| "123456789".contains(c)) {
             return fail(format!(
                 "Unexpected character in number: {}",
-                rest.peek().unwrap()
+                peek_or_fail(rest)?
             ));
         }
 
-        while advance_if(|c| "0123456789".contains(c)) {}
+        advance_digits(rest, &mut number_string)?;
     }
 
-    if advance_if(|c| c == '.') {
-        if !advance_if(|c| "0123456789".contains(c)) {
+    if advance_if(rest, &mut number_string, |c| c == '.') {
+        if !advance_if(rest, &mut number_string, |c| "0123456789".contains(c)) {
             return fail("Missing digits after point in number");
         }
-        while advance_if(|c| "0123456789".contains(c)) {}
+        advance_digits(rest, &mut number_string)?;
     }
 
-    if advance_if(|c| c == 'e' || c == 'E') {
-        advance_if(|c| c == '-' || c == '+');
+    if advance_if(rest, &mut number_string, |c| c == 'e' || c == 'E') {
+        advance_if(rest, &mut number_string, |c| c == '-' || c == '+');
 
-        if !advance_if(|c| "0123456789".contains(c)) {
+        if !advance_if(rest, &mut number_string, |c| "0123456789".contains(c)) {
             return fail("Missing digits after exponent in number");
         }
-        while advance_if(|c| "0123456789".contains(c)) {}
+        advance_digits(rest, &mut number_string)?;
     }
 
-    return match number_string.parse::<f64>() {
-        Ok(number) => Ok(Json::Number(number)),
-        Err(_) => fail(format!("Expected number, found: {number_string}")),
+    let number = if number_string.magnitude.is_too_large() {
+        fail("Number too large for an f64")
+    } else {
+        V::number(&number_string.text)
     };
+    number.map_err(|error| JsonParseError {
+        position: Some(start),
+        ..error
+    })
+}
+
+fn advance_if(
+    rest: &mut Input,
+    number_string: &mut impl ParsedText,
+    predicate: fn(char) -> bool,
+) -> bool {
+    match rest.next_if(|next_char| predicate(*next_char)) {
+        Some(next_char) => {
+            number_string.push(next_char);
+            true
+        }
+        None => false,
+    }
+}
+
+fn advance_digits(
+    rest: &mut Input,
+    number_string: &mut impl ParsedText,
+) -> Result<(), JsonParseError> {
+    loop {
+        while advance_if(rest, number_string, |c| "0123456789".contains(c)) {}
+
+        if !rest.options.allow_numeric_underscores || rest.next_if(|c| *c == '_').is_none() {
+            return Ok(());
+        }
+
+        if !advance_if(rest, number_string, |c| "0123456789".contains(c)) {
+            return fail("Underscores in numbers must be between digits");
+        }
+    }
 }
 
-fn parse_string_value(rest: &mut Peekable<Chars>) -> Result<Json, JsonParseError> {
+fn parse_string_value<V: ParsedValue>(rest: &mut Input) -> Result<V, JsonParseError> {
     let parsed_string = parse_string(rest)?;
 
-    Ok(Json::String(parsed_string))
+    Ok(V::string(parsed_string))
 }
 
-fn parse_string(rest: &mut Peekable<Chars>) -> Result<String, JsonParseError> {
-    let mut parsed_string = String::new();
+fn parse_string<T: ParsedText>(rest: &mut Input) -> Result<T, JsonParseError> {
+    let mut parsed_string = T::default();
 
-    let first_char = next_or_fail(rest)?;
-    if first_char != '"' {
-        return fail(format!("Expected a string, found '{}'", first_char));
+    let quote = next_or_fail(rest)?;
+    if quote != '"' && !(quote == '\'' && rest.options.allow_single_quotes) {
+        return fail(format!("Expected a string, found '{}'", quote));
     }
 
     loop {
         match next_or_fail(rest)? {
-            '"' => break,
-            '\\' => parsed_string.push_str(&parse_string_escape_char(rest)?),
+            closing_quote if closing_quote == quote => break,
+            '\\' => parse_string_escape_char(rest, &mut parsed_string)?,
             regular_char => parsed_string.push(regular_char),
         }
     }
@@ -170,11 +860,30 @@ fn parse_string(rest: &mut Peekable<Chars>) -> Result<String, JsonParseError> {
     Ok(parsed_string)
 }
 
-fn parse_string_escape_char(rest: &mut Peekable<Chars>) -> Result<String, JsonParseError> {
-    let mut codepoints = Vec::new();
+fn parse_string_escape_char(
+    rest: &mut Input,
+    parsed_string: &mut impl ParsedText,
+) -> Result<(), JsonParseError> {
+    let mut high_surrogate = None;
 
     loop {
-        codepoints.push(parse_string_escape_as_codepoint(rest)?);
+        let codepoint = parse_string_escape_as_codepoint(rest)?;
+
+        let decoded = match (high_surrogate.take(), codepoint) {
+            (None, 0xD800..=0xDBFF) => {
+                high_surrogate = Some(codepoint);
+                None
+            }
+            (Some(high), 0xDC00..=0xDFFF) => Some(char::decode_utf16([high, codepoint]).next()),
+            (Some(_), _) => Some(None),
+            (None, _) => Some(char::decode_utf16([codepoint]).next()),
+        };
+
+        match decoded {
+            Some(Some(Ok(decoded_char))) => parsed_string.push(decoded_char),
+            Some(_) => return fail("Unpaired UTF-16 surrogate in string"),
+            None => {}
+        }
 
         if peek_or_fail(rest)? == '\\' {
             next_or_fail(rest)?;
@@ -184,21 +893,17 @@ fn parse_string_escape_char(rest: &mut Peekable<Chars>) -> Result<String, JsonPa
         }
     }
 
-    let mut decoded = String::new();
-
-    for decoding_result in char::decode_utf16(codepoints) {
-        match decoding_result {
-            Ok(decoded_char) => decoded.push(decoded_char),
-            Err(_) => return fail("Unpaired UTF-16 surrogate in string"),
-        }
+    if high_surrogate.is_some() {
+        return fail("Unpaired UTF-16 surrogate in string");
     }
 
-    Ok(decoded)
+    Ok(())
 }
 
-fn parse_string_escape_as_codepoint(rest: &mut Peekable<Chars>) -> Result<u16, JsonParseError> {
+fn parse_string_escape_as_codepoint(rest: &mut Input) -> Result<u16, JsonParseError> {
     match next_or_fail(rest)? {
         '"' => Ok(34),
+        '\'' if rest.options.allow_single_quotes => Ok(39),
         '\\' => Ok(92),
         '/' => Ok(47),
         'b' => Ok(8),
@@ -211,39 +916,50 @@ fn parse_string_escape_as_codepoint(rest: &mut Peekable<Chars>) -> Result<u16, J
     }
 }
 
-fn parse_utf16_hex_escaped_codepoint(rest: &mut Peekable<Chars>) -> Result<u16, JsonParseError> {
-    let mut hex_digits = String::new();
+fn parse_utf16_hex_escaped_codepoint(rest: &mut Input) -> Result<u16, JsonParseError> {
+    let mut codepoint = 0;
 
     for _ in 0..4 {
-        let next_char = next_or_fail(rest)?;
-        if next_char.is_ascii_hexdigit() {
-            hex_digits.push(next_char);
-        } else {
-            return fail("Invalid hex digit in unicode escape sequence");
+        match next_or_fail(rest)?.to_digit(16) {
+            Some(digit) => codepoint = codepoint * 16 + digit as u16,
+            None => return fail("Invalid hex digit in unicode escape sequence"),
         }
     }
 
-    u16::from_str_radix(&hex_digits, 16).or_else(|_| fail("Invalid hex codepoint"))
+    Ok(codepoint)
 }
 
-fn parse_array(rest: &mut Peekable<Chars>) -> Result<Json, JsonParseError> {
+fn parse_array<V: ParsedValue>(rest: &mut Input) -> Result<V, JsonParseError> {
     if next_or_fail(rest)? != '[' {
         return fail("Expected array");
     }
 
-    skip_whitespace(rest);
+    skip_whitespace(rest)?;
 
-    let mut items = Vec::new();
+    let mut items = V::Items::default();
 
     if peek_or_fail(rest)? == ']' {
+        rest.attach_dangling_comments();
         rest.next();
     } else {
-        loop {
+        for index in 0.. {
+            rest.enter_index(index);
             let item = parse_value(rest)?;
-            items.push(item);
+            rest.leave();
+            V::push_item(&mut items, item);
 
             match next_or_fail(rest)? {
-                ']' => break,
+                ']' => {
+                    rest.attach_dangling_comments();
+                    break;
+                }
+                ',' if rest.options.allow_trailing_commas => {
+                    skip_whitespace(rest)?;
+                    if rest.next_if(|c| *c == ']').is_some() {
+                        rest.attach_dangling_comments();
+                        break;
+                    }
+                }
                 ',' => continue,
                 unexpected_char => {
                     return fail(format!("Expected ',' or ']', found '{unexpected_char}'"))
@@ -252,38 +968,47 @@ fn parse_array(rest: &mut Peekable<Chars>) -> Result<Json, JsonParseError> {
         }
     }
 
-    Ok(Json::Array(items))
+    Ok(V::array(items))
 }
 
-fn parse_object(rest: &mut Peekable<Chars>) -> Result<Json, JsonParseError> {
+fn parse_object<V: ParsedValue>(rest: &mut Input) -> Result<V, JsonParseError> {
     if next_or_fail(rest)? != '{' {
         return fail("Expected array");
     }
 
-    skip_whitespace(rest);
+    skip_whitespace(rest)?;
 
-    let mut properties = BTreeMap::new();
+    let mut properties = V::Properties::default();
 
     if peek_or_fail(rest)? == '}' {
+        rest.attach_dangling_comments();
         rest.next();
     } else {
         loop {
-            let key = parse_string(rest)?;
-            skip_whitespace(rest);
+            let key: V::Text = parse_key(rest)?;
+            rest.enter_key(key.as_str());
+            skip_whitespace(rest)?;
 
             if next_or_fail(rest)? != ':' {
                 return fail("Missing colon after object key");
             }
 
             let value = parse_value(rest)?;
+            rest.leave();
 
-            properties.insert(key, value);
+            V::insert_property(&mut properties, key, value);
 
             match next_or_fail(rest)? {
-                '}' => break,
+                '}' => {
+                    rest.attach_dangling_comments();
+                    break;
+                }
                 ',' => {
-                    skip_whitespace(rest);
-                    continue;
+                    skip_whitespace(rest)?;
+                    if rest.options.allow_trailing_commas && rest.next_if(|c| *c == '}').is_some() {
+                        rest.attach_dangling_comments();
+                        break;
+                    }
                 }
                 unexpected_char => {
                     return fail(format!("Expected ',' or '}}', found '{unexpected_char}'"))
@@ -292,13 +1017,36 @@ fn parse_object(rest: &mut Peekable<Chars>) -> Result<Json, JsonParseError> {
         }
     }
 
-    Ok(Json::Object(properties))
+    Ok(V::object(properties))
+}
+
+fn parse_key<T: ParsedText>(rest: &mut Input) -> Result<T, JsonParseError> {
+    let is_identifier_start = |c: &char| c.is_ascii_alphabetic() || *c == '_' || *c == '$';
+    if !rest.options.allow_unquoted_keys || !rest.peek().is_some_and(is_identifier_start) {
+        return parse_string(rest);
+    }
+
+    let mut key = T::default();
+    while let Some(next_char) =
+        rest.next_if(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '$')
+    {
+        key.push(next_char);
+    }
+    Ok(key)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse;
-    use crate::{ast::Json, dsl::ToJson};
+    use std::collections::BTreeMap;
+
+    use super::{
+        parse, parse_pointer, parse_with_comments, parse_with_options, validate, ParseOptions,
+        Position,
+    };
+    use crate::{
+        ast::{Comment, Comments, Json},
+        dsl::ToJson,
+    };
 
     #[test]
     fn it_parses_null() {
@@ -329,6 +1077,32 @@ mod tests {
         assert_eq!(parse("123"), Ok(123.to_json()));
     }
 
+    #[test]
+    fn it_keeps_integers_beyond_53_bits_exactly() {
+        assert_eq!(
+            parse("9007199254740991"),
+            Ok(Json::Number(9007199254740991.0))
+        );
+        assert_eq!(
+            parse("-9007199254740993"),
+            Ok(Json::BigInteger(-9007199254740993))
+        );
+        assert_eq!(
+            parse("170141183460469231731687303715884105727"),
+            Ok(Json::BigInteger(i128::MAX))
+        );
+        assert_eq!(
+            parse("170141183460469231731687303715884105728"),
+            Ok(Json::Number(2f64.powi(127)))
+        );
+        assert_eq!(parse("-0"), Ok(Json::Number(-0.0)));
+        assert_eq!(
+            parse("9007199254740993.0"),
+            Ok(Json::Number(9007199254740992.0))
+        );
+        assert_eq!(parse("1e17"), Ok(Json::Number(1e17)));
+    }
+
     #[test]
     fn it_parses_a_decimal() {
         assert_eq!(parse("120.056"), Ok(120.056.to_json()));
@@ -363,6 +1137,76 @@ mod tests {
         assert!(parse("67.").is_err());
     }
 
+    #[test]
+    fn it_rejects_numeric_underscores_by_default() {
+        assert!(parse("1_000").is_err());
+        assert!(parse("0.000_1").is_err());
+        assert!(parse("1e1_0").is_err());
+    }
+
+    #[test]
+    fn it_parses_numeric_underscores_if_allowed() {
+        let options = ParseOptions {
+            allow_numeric_underscores: true,
+            ..ParseOptions::default()
+        };
+
+        assert_eq!(
+            parse_with_options("1_000_000", &options),
+            Ok(1000000.to_json())
+        );
+        assert_eq!(
+            parse_with_options("-12_3.4_56e1_0", &options),
+            Ok((-123.456e10).to_json())
+        );
+        assert_eq!(
+            parse_with_options(r#"{"max_bytes": 1_000}"#, &options),
+            Ok([("max_bytes", 1000)].to_json())
+        );
+    }
+
+    #[test]
+    fn it_rejects_misplaced_numeric_underscores_even_if_allowed() {
+        let options = ParseOptions {
+            allow_numeric_underscores: true,
+            ..ParseOptions::default()
+        };
+
+        for invalid in [
+            "_1", "1_", "1__0", "1_.5", "1._5", "1.5_", "1_e5", "1e_5", "1e5_", "-_1", "0_1",
+        ] {
+            assert!(parse_with_options(invalid, &options).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn it_rejects_json5_syntax_by_default() {
+        for invalid in ["{a: 1}", "'a'", r#"["\'"]"#, "[1,]", r#"{"a": 1,}"#] {
+            assert!(parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn it_parses_json5_syntax_if_allowed() {
+        let options = ParseOptions {
+            allow_unquoted_keys: true,
+            allow_single_quotes: true,
+            allow_trailing_commas: true,
+            ..ParseOptions::default()
+        };
+
+        assert_eq!(
+            parse_with_options(r#"{$id_2: 'it\'s "x"', 'b': ["\'", ], _c: {}, }"#, &options),
+            Ok(Json::object()
+                .set("$id_2", r#"it's "x""#)
+                .set("b", vec!["'"])
+                .set("_c", Json::object()))
+        );
+        for invalid in ["{2d: 1}", "{a-b: 1}", "[,]", "{,}", "[1,,]", "'a\"", "[1,"] {
+            assert!(parse_with_options(invalid, &options).is_err(), "{invalid}");
+        }
+    }
+
     #[test]
     fn it_parses_inputs_with_leading_whitespace() {
         assert_eq!(parse("   null"), Ok(Json::Null));
@@ -572,4 +1416,241 @@ mod tests {
         assert!(parse(r#"{"no value"}"#).is_err());
         assert!(parse(r#"{"missing": "comma" "between": "properties"}"#).is_err());
     }
+
+    #[test]
+    fn parse_pointer_returns_the_whole_document_for_the_empty_pointer() {
+        assert_eq!(
+            parse_pointer(r#"{"a": [1, 2]}"#, ""),
+            Ok(Some(Json::object().set("a", [1, 2].to_json())))
+        );
+    }
+
+    #[test]
+    fn parse_pointer_returns_a_nested_value() {
+        let json = r#"{"results": [{"id": 7, "tags": ["x"]}, {"id": 8}], "total": 2}"#;
+
+        assert_eq!(parse_pointer(json, "/results/0/id"), Ok(Some(7.to_json())));
+        assert_eq!(parse_pointer(json, "/results/1/id"), Ok(Some(8.to_json())));
+        assert_eq!(
+            parse_pointer(json, "/results/0/tags"),
+            Ok(Some(["x"].to_json()))
+        );
+        assert_eq!(parse_pointer(json, "/total"), Ok(Some(2.to_json())));
+    }
+
+    #[test]
+    fn parse_pointer_skips_over_strings_containing_brackets_and_escaped_quotes() {
+        let json = r#"[{"skip": "]}\"[{"}, "target"]"#;
+
+        assert_eq!(parse_pointer(json, "/1"), Ok(Some("target".to_json())));
+    }
+
+    #[test]
+    fn parse_pointer_unescapes_pointer_tokens() {
+        let json = r#"{"a/b": 1, "m~n": 2}"#;
+
+        assert_eq!(parse_pointer(json, "/a~1b"), Ok(Some(1.to_json())));
+        assert_eq!(parse_pointer(json, "/m~0n"), Ok(Some(2.to_json())));
+    }
+
+    #[test]
+    fn parse_pointer_returns_none_if_the_path_does_not_exist() {
+        let json = r#"{"a": [1, 2], "b": "text"}"#;
+
+        assert_eq!(parse_pointer(json, "/c"), Ok(None));
+        assert_eq!(parse_pointer(json, "/a/2"), Ok(None));
+        assert_eq!(parse_pointer(json, "/a/01"), Ok(None));
+        assert_eq!(parse_pointer(json, "/a/x"), Ok(None));
+        assert_eq!(parse_pointer(json, "/b/0"), Ok(None));
+        assert_eq!(parse_pointer("[]", "/0"), Ok(None));
+        assert_eq!(parse_pointer("{}", "/a"), Ok(None));
+    }
+
+    #[test]
+    fn parse_pointer_rejects_invalid_syntax_on_the_path() {
+        assert!(parse_pointer(r#"{"a" 1}"#, "/a").is_err());
+        assert!(parse_pointer(r#"[1 2]"#, "/1").is_err());
+        assert!(parse_pointer(r#"[[1}, 2]"#, "/1").is_err());
+        assert!(parse_pointer(r#"["unterminated, 2]"#, "/1").is_err());
+        assert!(parse_pointer(r#"{"a": nul}"#, "/a").is_err());
+    }
+
+    #[test]
+    fn parse_pointer_rejects_a_pointer_without_a_leading_slash() {
+        assert!(parse_pointer("{}", "a").is_err());
+    }
+
+    #[test]
+    fn it_rejects_comments_outside_of_comment_preserving_mode() {
+        assert!(parse("// comment\nnull").is_err());
+        assert!(parse("[1 /* comment */]").is_err());
+    }
+
+    #[test]
+    fn parse_with_comments_attaches_comments_to_the_following_value() {
+        let (json, comments) = parse_with_comments(
+            "// root\n{\n  // first\n  \"a\": 1, /* after a */\n  \"b\": [\n    true, // one\n    false\n  ]\n}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            json,
+            Json::object().set("a", 1).set("b", [true, false].to_json())
+        );
+        assert_eq!(
+            comments,
+            Comments {
+                leading: BTreeMap::from([
+                    ("".to_owned(), vec![Comment::Line(" root".to_owned())]),
+                    ("/a".to_owned(), vec![Comment::Line(" first".to_owned())]),
+                    (
+                        "/b".to_owned(),
+                        vec![Comment::Block(" after a ".to_owned())]
+                    ),
+                    ("/b/1".to_owned(), vec![Comment::Line(" one".to_owned())]),
+                ]),
+                ..Comments::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parse_with_comments_attaches_dangling_and_trailing_comments() {
+        let (json, comments) =
+            parse_with_comments("{\"a/b\": [1 /* last */], \"c\": {/* empty */}} // end").unwrap();
+
+        assert_eq!(
+            json,
+            Json::object()
+                .set("a/b", [1].to_json())
+                .set("c", Json::object())
+        );
+        assert_eq!(
+            comments,
+            Comments {
+                dangling: BTreeMap::from([
+                    (
+                        "/a~1b".to_owned(),
+                        vec![Comment::Block(" last ".to_owned())]
+                    ),
+                    ("/c".to_owned(), vec![Comment::Block(" empty ".to_owned())]),
+                ]),
+                trailing: vec![Comment::Line(" end".to_owned())],
+                ..Comments::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parse_with_comments_rejects_malformed_comments() {
+        assert!(parse_with_comments("/ not a comment\nnull").is_err());
+        assert!(parse_with_comments("[1 /* unterminated ]").is_err());
+    }
+
+    #[test]
+    fn validate_accepts_everything_parse_accepts() {
+        for valid in [
+            "null",
+            " [1, -2.5e3, \"\\uD83D\\uDE02\", {\"a\": [true, false]}] ",
+            r#"{"nested": {"deeply": [[], {}]}}"#,
+        ] {
+            assert_eq!(validate(valid), Ok(()), "{valid}");
+            assert!(parse(valid).is_ok(), "{valid}");
+        }
+    }
+
+    #[test]
+    fn validate_rejects_everything_parse_rejects() {
+        for invalid in [
+            "",
+            "nul",
+            "00",
+            "67.",
+            "-",
+            "-x",
+            r#""\uD83D""#,
+            r#""\uDE02""#,
+            r#""\d""#,
+            "[true,]",
+            r#"{"a" 1}"#,
+            "{} {}",
+        ] {
+            assert_eq!(validate(invalid), parse(invalid).map(|_| ()), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn it_reports_the_position_of_an_error() {
+        let error = parse("[1,\n  2,\n  x]").unwrap_err();
+        assert_eq!(
+            error.position,
+            Some(Position {
+                offset: 11,
+                line: 3,
+                column: 3
+            })
+        );
+        assert_eq!(
+            error.to_string(),
+            "ERROR: Invalid JSON - Unexpected character: x at line 3, column 3"
+        );
+
+        assert_eq!(
+            validate("[1 2]").unwrap_err().position,
+            Some(Position {
+                offset: 3,
+                line: 1,
+                column: 4
+            })
+        );
+        assert_eq!(
+            parse("").unwrap_err().position,
+            Some(Position {
+                offset: 0,
+                line: 1,
+                column: 1
+            })
+        );
+    }
+
+    #[test]
+    fn it_rejects_numbers_too_large_for_an_f64_at_their_start() {
+        let error = parse("[1,\n -1e400]").unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "ERROR: Invalid JSON - Number too large for an f64 at line 2, column 2"
+        );
+        assert!(parse("1e308").is_ok());
+        assert!(validate("1e400").is_err());
+    }
+
+    #[test]
+    fn it_rejects_exactly_the_numbers_which_round_to_infinity() {
+        let halfway = "179769313486231580793728971405303415079934132710037826936173778980444968292764750946649017977587207096330286416692887910946555547851940402630657488671505820681908902000708383676273854845817711531764475730270069855571366959622842914819860834936475292719074168444365510704342711559699508093042880177904174497792";
+        let below_halfway = format!("{}1", &halfway[..halfway.len() - 1]);
+        let cases = [
+            "1.7976931348623157e308",
+            "1.7976931348623158e308",
+            "1.7976931348623159e308",
+            "-1.8e308",
+            "0.000017976931348623157e313",
+            "0.000017976931348623159e313",
+            "17976931348623157e292",
+            "1e-400",
+            "0e999999999999999999999",
+            "1e999999999999999999999",
+            halfway,
+            &below_halfway,
+            &format!("{halfway}.0000"),
+            &format!("{below_halfway}.9999"),
+            &format!("0.{halfway}e309"),
+        ];
+
+        for text in cases {
+            let finite = text.parse::<f64>().unwrap().is_finite();
+            assert_eq!(parse(text).is_ok(), finite, "{text}");
+            assert_eq!(validate(text).is_ok(), finite, "{text}");
+        }
+    }
 }
@@ -0,0 +1,593 @@
+//! JSON Patch (RFC 6902): applying a list of operations to a value, and
+//! finding the operations which turn one value into another.
+
+use std::{error, fmt};
+
+use crate::ast::{
+    parse_array_index, push_pointer_token, unescape_pointer_token, Json, JsonPathError,
+};
+
+/// An error from `Json::apply_patch`, identifying the operation which failed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatchError {
+    /// The position of the operation in the patch.
+    pub index: usize,
+    /// The operation's `op`, or empty if it doesn't have one.
+    pub op: String,
+    /// The pointer which the operation couldn't use: its `from` when that was
+    /// the problem, otherwise its `path`.
+    pub pointer: String,
+    pub kind: PatchErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatchErrorKind {
+    /// The patch isn't an array of operations.
+    NotAnArray,
+    /// The operation isn't an object with a known `op` and the members that
+    /// op requires.
+    InvalidOperation(String),
+    /// The pointer doesn't address a value the operation can use.
+    Path(JsonPathError),
+    /// A `test` operation found a different value.
+    TestFailed,
+    /// A `move` operation tried to move a value into one of its own children.
+    MoveIntoChild,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kind == PatchErrorKind::NotAnArray {
+            return formatter.write_str("ERROR: Invalid JSON patch - patch must be an array");
+        }
+
+        formatter.write_fmt(format_args!(
+            "ERROR: Invalid JSON patch - operation {} ({} {:?}): ",
+            self.index, self.op, self.pointer
+        ))?;
+        match &self.kind {
+            PatchErrorKind::NotAnArray => Ok(()),
+            PatchErrorKind::InvalidOperation(message) => formatter.write_str(message),
+            PatchErrorKind::Path(error) => {
+                let (message, pointer) = error.describe();
+                formatter.write_fmt(format_args!("{message}: {pointer:?}"))
+            }
+            PatchErrorKind::TestFailed => formatter.write_str("test failed"),
+            PatchErrorKind::MoveIntoChild => {
+                formatter.write_str("cannot move a value into one of its children")
+            }
+        }
+    }
+}
+
+impl error::Error for PatchError {}
+
+impl Json {
+    /// Applies a JSON Patch (RFC 6902): an array of `add`, `remove`,
+    /// `replace`, `move`, `copy` and `test` operations. If any operation
+    /// fails, the value is left as it was.
+    pub fn apply_patch(&mut self, patch: &Json) -> Result<(), PatchError> {
+        let Json::Array(operations) = patch else {
+            return Err(PatchError {
+                index: 0,
+                op: String::new(),
+                pointer: String::new(),
+                kind: PatchErrorKind::NotAnArray,
+            });
+        };
+
+        let mut patched = self.clone();
+        for (index, operation) in operations.iter().enumerate() {
+            apply_operation(&mut patched, operation).map_err(|(pointer, kind)| PatchError {
+                index,
+                op: operation
+                    .get("op")
+                    .and_then(Json::as_str)
+                    .unwrap_or_default()
+                    .to_owned(),
+                pointer,
+                kind,
+            })?;
+        }
+        *self = patched;
+        Ok(())
+    }
+}
+
+/// Finds a JSON Patch which turns `old` into `new` when applied to it.
+///
+/// Objects are compared property by property. Arrays are compared item by
+/// item after skipping the items they start and end with in common, with any
+/// extra items removed or added at the end of the differing part.
+pub fn json_diff(old: &Json, new: &Json) -> Json {
+    let mut operations = Vec::new();
+    diff_into(old, new, String::new(), &mut operations);
+    Json::Array(operations)
+}
+
+fn diff_into(old: &Json, new: &Json, path: String, operations: &mut Vec<Json>) {
+    if old == new {
+        return;
+    }
+
+    match (old, new) {
+        (Json::Object(old_properties), Json::Object(new_properties)) => {
+            for key in old_properties.keys() {
+                if !new_properties.contains_key(key) {
+                    let mut path = path.clone();
+                    push_pointer_token(&mut path, key);
+                    operations.push(operation("remove", &path));
+                }
+            }
+            for (key, new_value) in new_properties {
+                let mut path = path.clone();
+                push_pointer_token(&mut path, key);
+                match old_properties.get(key) {
+                    Some(old_value) => diff_into(old_value, new_value, path, operations),
+                    None => {
+                        operations.push(operation("add", &path).set("value", new_value.clone()))
+                    }
+                }
+            }
+        }
+        (Json::Array(old_items), Json::Array(new_items)) => {
+            let prefix = old_items
+                .iter()
+                .zip(new_items)
+                .take_while(|(old_item, new_item)| old_item == new_item)
+                .count();
+            let suffix = old_items[prefix..]
+                .iter()
+                .rev()
+                .zip(new_items[prefix..].iter().rev())
+                .take_while(|(old_item, new_item)| old_item == new_item)
+                .count();
+            let old_middle = &old_items[prefix..old_items.len() - suffix];
+            let new_middle = &new_items[prefix..new_items.len() - suffix];
+
+            for (offset, (old_item, new_item)) in old_middle.iter().zip(new_middle).enumerate() {
+                diff_into(
+                    old_item,
+                    new_item,
+                    format!("{path}/{}", prefix + offset),
+                    operations,
+                );
+            }
+            let end = prefix + old_middle.len().min(new_middle.len());
+            for _ in new_middle.len()..old_middle.len() {
+                operations.push(operation("remove", &format!("{path}/{end}")));
+            }
+            for (offset, new_item) in new_middle.iter().enumerate().skip(old_middle.len()) {
+                let path = format!("{path}/{}", prefix + offset);
+                operations.push(operation("add", &path).set("value", new_item.clone()));
+            }
+        }
+        _ => operations.push(operation("replace", &path).set("value", new.clone())),
+    }
+}
+
+fn operation(op: &str, path: &str) -> Json {
+    Json::object().set("op", op).set("path", path)
+}
+
+/// A failure of one operation, with the pointer it failed at.
+type OperationError = (String, PatchErrorKind);
+
+fn path_error(pointer: &str) -> impl FnOnce(JsonPathError) -> OperationError + '_ {
+    move |error| (pointer.to_owned(), PatchErrorKind::Path(error))
+}
+
+fn not_found(pointer: &str) -> OperationError {
+    path_error(pointer)(JsonPathError::NotFound(pointer.to_owned()))
+}
+
+fn apply_operation(document: &mut Json, operation: &Json) -> Result<(), OperationError> {
+    let member = |name: &str| {
+        operation.get(name).ok_or_else(|| {
+            (
+                String::new(),
+                PatchErrorKind::InvalidOperation(format!("missing {name:?}")),
+            )
+        })
+    };
+    let string_member = |name: &str| {
+        member(name)?.as_str().ok_or_else(|| {
+            (
+                String::new(),
+                PatchErrorKind::InvalidOperation(format!("{name:?} must be a string")),
+            )
+        })
+    };
+    let op = string_member("op")?;
+    let path = string_member("path")?;
+
+    match op {
+        "add" => add(document, path, member("value")?.clone()).map_err(path_error(path)),
+        "remove" => document
+            .remove_path(path)
+            .map(|_| ())
+            .map_err(path_error(path)),
+        "replace" => {
+            let value = member("value")?.clone();
+            let target = document.pointer_mut(path).ok_or_else(|| not_found(path))?;
+            *target = value;
+            Ok(())
+        }
+        "move" => {
+            let from = string_member("from")?;
+            if path == from {
+                return document
+                    .pointer(from)
+                    .map(|_| ())
+                    .ok_or_else(|| not_found(from));
+            }
+            if path.starts_with(from) && path[from.len()..].starts_with('/') {
+                return Err((from.to_owned(), PatchErrorKind::MoveIntoChild));
+            }
+            let value = document.remove_path(from).map_err(path_error(from))?;
+            add(document, path, value).map_err(path_error(path))
+        }
+        "copy" => {
+            let from = string_member("from")?;
+            let value = document
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| not_found(from))?;
+            add(document, path, value).map_err(path_error(path))
+        }
+        "test" => {
+            let value = member("value")?;
+            let target = document.pointer(path).ok_or_else(|| not_found(path))?;
+            if target == value {
+                Ok(())
+            } else {
+                Err((path.to_owned(), PatchErrorKind::TestFailed))
+            }
+        }
+        _ => Err((
+            path.to_owned(),
+            PatchErrorKind::InvalidOperation(format!("unknown op {op:?}")),
+        )),
+    }
+}
+
+/// Adds a value at a pointer whose parent must already exist, inserting it
+/// into an array at the index rather than replacing the item there.
+fn add(document: &mut Json, pointer: &str, value: Json) -> Result<(), JsonPathError> {
+    if pointer.is_empty() {
+        *document = value;
+        return Ok(());
+    }
+    let (parent_pointer, token) = match pointer.rsplit_once('/') {
+        Some(split) if pointer.starts_with('/') => split,
+        _ => return Err(JsonPathError::InvalidPointer(pointer.to_owned())),
+    };
+
+    let parent = document
+        .pointer_mut(parent_pointer)
+        .ok_or_else(|| JsonPathError::NotFound(parent_pointer.to_owned()))?;
+    match parent {
+        Json::Object(properties) => {
+            properties.insert(unescape_pointer_token(token), value);
+        }
+        Json::Array(items) => {
+            let index = match token {
+                "-" => items.len(),
+                _ => parse_array_index(token)
+                    .ok_or_else(|| JsonPathError::InvalidIndex(parent_pointer.to_owned()))?,
+            };
+            if index > items.len() {
+                return Err(JsonPathError::IndexOutOfBounds(parent_pointer.to_owned()));
+            }
+            items.insert(index, value);
+        }
+        _ => return Err(JsonPathError::NotAContainer(parent_pointer.to_owned())),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Json, JsonPathError},
+        parser::parse,
+        patch::{json_diff, PatchError, PatchErrorKind},
+    };
+
+    fn patched(document: &str, patch: &str) -> Result<Json, PatchError> {
+        let mut document = parse(document).unwrap();
+        document.apply_patch(&parse(patch).unwrap())?;
+        Ok(document)
+    }
+
+    #[test]
+    fn it_passes_the_rfc_6902_examples() {
+        let examples = [
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#,
+                r#"{"baz": "qux", "foo": "bar"}"#,
+            ),
+            (
+                r#"{"foo": ["bar", "baz"]}"#,
+                r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#,
+                r#"{"foo": ["bar", "qux", "baz"]}"#,
+            ),
+            (
+                r#"{"baz": "qux", "foo": "bar"}"#,
+                r#"[{"op": "remove", "path": "/baz"}]"#,
+                r#"{"foo": "bar"}"#,
+            ),
+            (
+                r#"{"foo": ["bar", "qux", "baz"]}"#,
+                r#"[{"op": "remove", "path": "/foo/1"}]"#,
+                r#"{"foo": ["bar", "baz"]}"#,
+            ),
+            (
+                r#"{"baz": "qux", "foo": "bar"}"#,
+                r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#,
+                r#"{"baz": "boo", "foo": "bar"}"#,
+            ),
+            (
+                r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+                r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#,
+                r#"{"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}"#,
+            ),
+            (
+                r#"{"foo": ["all", "grass", "cows", "eat"]}"#,
+                r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#,
+                r#"{"foo": ["all", "cows", "eat", "grass"]}"#,
+            ),
+            (
+                r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#,
+                r#"[
+                    {"op": "test", "path": "/baz", "value": "qux"},
+                    {"op": "test", "path": "/foo/1", "value": 2}
+                ]"#,
+                r#"{"baz": "qux", "foo": ["a", 2, "c"]}"#,
+            ),
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/child", "value": {"grandchild": {}}}]"#,
+                r#"{"foo": "bar", "child": {"grandchild": {}}}"#,
+            ),
+            (
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/baz", "value": "qux", "xyz": 123}]"#,
+                r#"{"foo": "bar", "baz": "qux"}"#,
+            ),
+            (
+                r#"{"/": 9, "~1": 10}"#,
+                r#"[{"op": "test", "path": "/~01", "value": 10}]"#,
+                r#"{"/": 9, "~1": 10}"#,
+            ),
+            (
+                r#"{"foo": ["bar"]}"#,
+                r#"[{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]"#,
+                r#"{"foo": ["bar", ["abc", "def"]]}"#,
+            ),
+        ];
+
+        for (document, patch, result) in examples {
+            assert_eq!(
+                patched(document, patch),
+                Ok(parse(result).unwrap()),
+                "{patch}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_fails_on_the_rfc_6902_error_examples() {
+        assert_eq!(
+            patched(
+                r#"{"baz": "qux"}"#,
+                r#"[{"op": "test", "path": "/baz", "value": "bar"}]"#
+            ),
+            Err(PatchError {
+                index: 0,
+                op: "test".to_owned(),
+                pointer: "/baz".to_owned(),
+                kind: PatchErrorKind::TestFailed,
+            })
+        );
+        assert_eq!(
+            patched(
+                r#"{"foo": "bar"}"#,
+                r#"[{"op": "add", "path": "/baz/bat", "value": "qux"}]"#
+            ),
+            Err(PatchError {
+                index: 0,
+                op: "add".to_owned(),
+                pointer: "/baz/bat".to_owned(),
+                kind: PatchErrorKind::Path(JsonPathError::NotFound("/baz".to_owned())),
+            })
+        );
+        assert_eq!(
+            patched(
+                r#"{"/": 9, "~1": 10}"#,
+                r#"[{"op": "test", "path": "/~01", "value": "10"}]"#
+            )
+            .map_err(|error| error.kind),
+            Err(PatchErrorKind::TestFailed)
+        );
+    }
+
+    #[test]
+    fn it_copies_and_replaces_the_whole_document() {
+        assert_eq!(
+            patched(
+                r#"{"a": {"b": [1]}}"#,
+                r#"[
+                    {"op": "copy", "from": "/a/b", "path": "/c"},
+                    {"op": "add", "path": "/c/0", "value": 0},
+                    {"op": "move", "from": "/c", "path": "/c"}
+                ]"#
+            ),
+            Ok(parse(r#"{"a": {"b": [1]}, "c": [0, 1]}"#).unwrap())
+        );
+        assert_eq!(
+            patched(
+                r#"{"a": 1}"#,
+                r#"[{"op": "replace", "path": "", "value": [1]}]"#
+            ),
+            Ok(parse("[1]").unwrap())
+        );
+    }
+
+    #[test]
+    fn it_compares_values_rather_than_text_in_tests() {
+        assert_eq!(
+            patched(
+                r#"{"a": {"x": 1, "y": [1.0, {}]}}"#,
+                r#"[{"op": "test", "path": "/a", "value": {"y": [1e0, {}], "x": 10e-1}}]"#
+            ),
+            Ok(parse(r#"{"a": {"x": 1, "y": [1, {}]}}"#).unwrap())
+        );
+    }
+
+    #[test]
+    fn it_leaves_the_document_unchanged_if_any_operation_fails() {
+        let original = parse(r#"{"a": [1, 2], "b": {"c": 3}}"#).unwrap();
+        let mut document = original.clone();
+
+        let error = document
+            .apply_patch(
+                &parse(
+                    r#"[
+                        {"op": "add", "path": "/a/-", "value": 3},
+                        {"op": "remove", "path": "/b/c"},
+                        {"op": "replace", "path": "/a/0", "value": 0},
+                        {"op": "move", "from": "/a", "path": "/b/a"},
+                        {"op": "remove", "path": "/a"}
+                    ]"#,
+                )
+                .unwrap(),
+            )
+            .unwrap_err();
+
+        assert_eq!(error.index, 4);
+        assert_eq!(error.op, "remove");
+        assert_eq!(error.pointer, "/a");
+        assert_eq!(document, original);
+    }
+
+    #[test]
+    fn it_rejects_invalid_operations() {
+        let errors = [
+            (
+                r#"{"op": "add", "path": "/a"}"#,
+                PatchErrorKind::InvalidOperation(r#"missing "value""#.to_owned()),
+            ),
+            (
+                r#"{"op": "frobnicate", "path": "/a"}"#,
+                PatchErrorKind::InvalidOperation(r#"unknown op "frobnicate""#.to_owned()),
+            ),
+            (
+                r#"{"path": "/a"}"#,
+                PatchErrorKind::InvalidOperation(r#"missing "op""#.to_owned()),
+            ),
+            (
+                r#"{"op": "copy", "path": "/a", "from": 1}"#,
+                PatchErrorKind::InvalidOperation(r#""from" must be a string"#.to_owned()),
+            ),
+            (
+                r#"{"op": "move", "from": "/a", "path": "/a/b"}"#,
+                PatchErrorKind::MoveIntoChild,
+            ),
+            (
+                r#"{"op": "add", "path": "/a/3", "value": 1}"#,
+                PatchErrorKind::Path(JsonPathError::IndexOutOfBounds("/a".to_owned())),
+            ),
+            (
+                r#"{"op": "remove", "path": ""}"#,
+                PatchErrorKind::Path(JsonPathError::InvalidPointer("".to_owned())),
+            ),
+        ];
+
+        for (operation, kind) in errors {
+            assert_eq!(
+                patched(r#"{"a": [1, 2]}"#, &format!("[{operation}]")).map_err(|error| error.kind),
+                Err(kind),
+                "{operation}"
+            );
+        }
+        assert_eq!(
+            patched("{}", "{}").map_err(|error| error.kind),
+            Err(PatchErrorKind::NotAnArray)
+        );
+    }
+
+    #[test]
+    fn its_errors_name_the_failing_operation() {
+        let error = patched(r#"{"a": 1}"#, r#"[{"op": "remove", "path": "/b"}]"#).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            r#"ERROR: Invalid JSON patch - operation 0 (remove "/b"): no value at path: "/b""#
+        );
+    }
+
+    #[test]
+    fn a_diff_turns_the_old_value_into_the_new_one() {
+        let pairs = [
+            ("1", "1"),
+            ("1", "2"),
+            ("null", r#"{"a": 1}"#),
+            (r#"{"a": 1, "b": 2}"#, r#"{"a": 1}"#),
+            (r#"{"a": 1}"#, r#"{"a": 1, "b/c~d": [true]}"#),
+            (
+                r#"{"a": {"b": {"c": 1, "d": 2}}}"#,
+                r#"{"a": {"b": {"c": 1, "d": 3}}}"#,
+            ),
+            (r#"{"a": [1, 2, 3]}"#, r#"{"a": [1, 2, 4]}"#),
+            (r#"{"a": [[1, [2]]]}"#, r#"{"a": [[1, [3]]]}"#),
+            ("[1, 2, 3, 4]", "[1, 4]"),
+            ("[1, 4]", "[1, 2, 3, 4]"),
+            ("[1, 2, 3]", "[]"),
+            ("[]", "[1, 2, 3]"),
+            ("[1, 2, 3]", "[3, 2, 1]"),
+            ("[1, 1, 1]", "[1, 1]"),
+            ("[1, 2, 2, 3]", r#"[1, "x", "y", "z", 2, 3]"#),
+            (r#"[{"a": 1}, "b"]"#, r#"{"0": {"a": 1}, "1": "b"}"#),
+            (r#"{"": {"": 1}}"#, r#"{"": {"": 2, "x": null}}"#),
+        ];
+
+        for (old, new) in pairs {
+            let (old, new) = (parse(old).unwrap(), parse(new).unwrap());
+            let patch = json_diff(&old, &new);
+
+            let mut patched = old.clone();
+            patched.apply_patch(&patch).unwrap();
+            assert_eq!(patched, new, "{old:?} {new:?} {patch:?}");
+        }
+    }
+
+    #[test]
+    fn a_diff_only_touches_what_changed() {
+        let diff = |old, new| json_diff(&parse(old).unwrap(), &parse(new).unwrap());
+
+        assert_eq!(diff(r#"{"a": [1, 2]}"#, r#"{"a": [1, 2]}"#), Json::array());
+        assert_eq!(
+            diff(
+                r#"{"a": 1, "b": {"c": [1, 2, 3]}}"#,
+                r#"{"b": {"c": [1, 5, 3]}}"#
+            ),
+            parse(
+                r#"[
+                    {"op": "remove", "path": "/a"},
+                    {"op": "replace", "path": "/b/c/1", "value": 5}
+                ]"#
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            diff("[1, 2, 3, 4, 5]", "[1, 2, 9, 4, 5]"),
+            parse(r#"[{"op": "replace", "path": "/2", "value": 9}]"#).unwrap()
+        );
+        assert_eq!(
+            diff("[1, 2, 3, 4]", "[1, 4]"),
+            parse(r#"[{"op": "remove", "path": "/1"}, {"op": "remove", "path": "/1"}]"#).unwrap()
+        );
+    }
+}
@@ -1,359 +1,3627 @@
-use std::{
-    collections::BTreeMap,
+use alloc::{
+    borrow::ToOwned,
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec::{self, Vec},
+};
+use core::{
+    cell::Cell,
+    cmp::Ordering,
     fmt::{self, Write},
+    slice,
+};
+#[cfg(feature = "std")]
+use std::{
+    error,
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
 };
 
-use crate::ast::Json;
+use crate::{
+    ast::{escape_pointer_token, is_whole_number, Comment, Comments, Json},
+    canonical,
+    map::{self, Map},
+    theme::{Theme, Token, RESET},
+};
+
+/// Settings which control how the printer lays out its output.
+///
+/// `PrintOptions::default()` gives the standard pretty-printed output, with
+/// each array item and object entry on its own line, indented by two spaces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintOptions {
+    pub(crate) indent: String,
+    pub(crate) style: PrintStyle,
+    pub(crate) html_safe: bool,
+    pub(crate) width: usize,
+    pub(crate) scalar_arrays: ScalarArrayLayout,
+    pub(crate) max_output_bytes: Option<usize>,
+    pub(crate) non_finite: NonFinitePolicy,
+    pub(crate) float_format: FloatFormat,
+    pub(crate) key_order: KeyOrder,
+    pub(crate) max_depth: Option<u64>,
+    pub(crate) max_string_length: Option<usize>,
+    pub(crate) space_before_colon: bool,
+    pub(crate) space_after_colon: Option<bool>,
+    pub(crate) space_after_comma: Option<bool>,
+    pub(crate) align_keys: bool,
+    pub(crate) unquoted_keys: bool,
+    pub(crate) single_quotes: bool,
+    pub(crate) trailing_commas: bool,
+    pub(crate) theme: Option<Theme>,
+    pub(crate) cuddle_brackets: bool,
+    pub(crate) number_normalization: NumberNormalization,
+    pub(crate) max_indent_levels: Option<u64>,
+    pub(crate) big_int_as_string: bool,
+    pub(crate) max_depth_policy: MaxDepthPolicy,
+    /// Formats numbers and escapes for `json_to_canonical_string`.
+    pub(crate) canonical: bool,
+}
+
+impl Default for PrintOptions {
+    fn default() -> PrintOptions {
+        PrintOptions {
+            indent: "  ".to_owned(),
+            style: PrintStyle::default(),
+            html_safe: false,
+            width: 80,
+            scalar_arrays: ScalarArrayLayout::default(),
+            max_output_bytes: None,
+            non_finite: NonFinitePolicy::default(),
+            float_format: FloatFormat::default(),
+            key_order: KeyOrder::default(),
+            max_depth: None,
+            max_string_length: None,
+            space_before_colon: false,
+            space_after_colon: None,
+            space_after_comma: None,
+            align_keys: false,
+            unquoted_keys: false,
+            single_quotes: false,
+            trailing_commas: false,
+            theme: None,
+            cuddle_brackets: false,
+            number_normalization: NumberNormalization::default(),
+            max_indent_levels: None,
+            big_int_as_string: false,
+            max_depth_policy: MaxDepthPolicy::default(),
+            canonical: false,
+        }
+    }
+}
+
+/// How arrays and objects are laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PrintStyle {
+    /// Each array item and object entry on its own line, indented by nesting level.
+    #[default]
+    Pretty,
+    /// Everything on a single line, with a space after each comma and colon,
+    /// e.g. `{"a": 1, "b": [1, 2, 3]}`.
+    OneLine,
+    /// Like `Pretty`, but arrays and objects which fit within the width on a
+    /// single line are printed in the `OneLine` style.
+    Fit,
+    /// Everything on a single line with no whitespace, e.g. `{"a":[1,2]}`.
+    Compact,
+}
+
+/// How arrays containing only scalars are laid out by the `Pretty` and `Fit`
+/// styles. Arrays containing any array or object always follow the style.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ScalarArrayLayout {
+    /// Follow the style, like any other array.
+    #[default]
+    Expanded,
+    /// Print the whole array on one line, e.g. `[1, 2, 3]`.
+    OneLine,
+    /// Print the given number of items per line, with each following line
+    /// aligned to just after the opening bracket.
+    Wrap(usize),
+}
+
+/// How numbers which JSON can't represent, NaN and the infinities, are printed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NonFinitePolicy {
+    /// Fail with `PrintError::NonFiniteNumber`.
+    #[default]
+    Error,
+    /// Print `null`.
+    Null,
+    /// Print a string: `"NaN"`, `"Infinity"` or `"-Infinity"`.
+    String,
+}
+
+/// What happens to the contents of arrays and objects nested as deep as
+/// `max_depth`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MaxDepthPolicy {
+    /// Print the brackets around a single placeholder, as `["…"]` or
+    /// `{"…": "…"}`, so the output is still valid JSON.
+    #[default]
+    Elide,
+    /// Fail with `PrintError::MaxDepthExceeded`.
+    Error,
+}
+
+/// How numbers with a fractional part are printed. Integers are always printed
+/// without a decimal point.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FloatFormat {
+    /// The fewest digits which parse back to the same number, e.g. `3.141592653589793`.
+    #[default]
+    Shortest,
+    /// Exactly this many digits after the decimal point, keeping trailing zeros
+    /// so that columns line up, e.g. `3.14` and `2.50`.
+    FixedDecimals(u8),
+    /// Rounded to this many significant digits, dropping trailing zeros,
+    /// e.g. `3.14` and `2.5`. Zero is treated as one.
+    SignificantDigits(u8),
+}
+
+/// Rewrites of number text which make equal numbers print identically. None of
+/// them are applied by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NumberNormalization {
+    /// Print `-0` as `0`.
+    pub fold_negative_zero: bool,
+    /// Print an exponent marker `E` as `e`.
+    pub lowercase_exponent: bool,
+    /// Leave out a `+` after the exponent marker, as in `1e+5`.
+    pub strip_exponent_plus: bool,
+    /// Leave out a fractional part which is all zeros, as in `10.0`.
+    pub strip_zero_fraction: bool,
+}
+
+impl NumberNormalization {
+    /// Every normalization.
+    pub fn all() -> NumberNormalization {
+        NumberNormalization {
+            fold_negative_zero: true,
+            lowercase_exponent: true,
+            strip_exponent_plus: true,
+            strip_zero_fraction: true,
+        }
+    }
+}
+
+/// The order in which the entries of each object are printed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum KeyOrder {
+    /// The order the keys were inserted in, which for a parsed value is the
+    /// order they appeared in the document.
+    Document,
+    /// Sorted by comparing the keys' characters.
+    #[default]
+    Lexicographic,
+    /// Sorted like `Lexicographic`, but with runs of digits compared by their
+    /// numeric value, so `item2` comes before `item10`. See `natural_cmp`.
+    Natural,
+    /// Sorted by a comparison supplied by the caller. Keys which it finds equal
+    /// keep the order they were inserted in.
+    Custom(KeyComparator),
+}
+
+impl KeyOrder {
+    /// Sorts keys with the given comparison. See `KeyOrder::Custom`.
+    pub fn custom(compare: impl Fn(&str, &str) -> Ordering + 'static) -> KeyOrder {
+        KeyOrder::Custom(KeyComparator(Rc::new(compare)))
+    }
+
+    /// Puts the given keys first, in the order listed, followed by all other
+    /// keys in the `then` order.
+    pub fn prioritized<I>(keys: I, then: KeyOrder) -> KeyOrder
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let keys: Vec<String> = keys.into_iter().map(Into::into).collect();
+        let priority = move |key: &str| keys.iter().position(|priority| priority == key);
+
+        KeyOrder::custom(move |a, b| match (priority(a), priority(b)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => then.compare(a, b),
+        })
+    }
+
+    /// Compares two keys in this order. Every key is equal in `Document` order.
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            KeyOrder::Document => Ordering::Equal,
+            KeyOrder::Lexicographic => a.cmp(b),
+            KeyOrder::Natural => natural_cmp(a, b),
+            KeyOrder::Custom(KeyComparator(compare)) => compare(a, b),
+        }
+    }
+}
+
+/// A caller-supplied comparison of object keys, for `KeyOrder::Custom`.
+/// Comparators are only equal to clones of themselves.
+#[derive(Clone)]
+pub struct KeyComparator(Rc<CompareKeys>);
+
+type CompareKeys = dyn Fn(&str, &str) -> Ordering;
+
+impl PartialEq for KeyComparator {
+    fn eq(&self, other: &KeyComparator) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for KeyComparator {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("KeyComparator(..)")
+    }
+}
+
+impl PrintOptions {
+    pub fn new() -> PrintOptions {
+        PrintOptions::default()
+    }
+
+    /// The string written once per level of nesting at the start of each line,
+    /// e.g. `"    "` or `"\t"`.
+    pub fn indent(mut self, indent: impl Into<String>) -> PrintOptions {
+        self.indent = indent.into();
+        self
+    }
+
+    pub fn style(mut self, style: PrintStyle) -> PrintOptions {
+        self.style = style;
+        self
+    }
+
+    /// The maximum line length, in characters, used by the `Fit` style. A line
+    /// may still be longer if it holds a single scalar or key which doesn't fit.
+    pub fn width(mut self, width: usize) -> PrintOptions {
+        self.width = width;
+        self
+    }
+
+    pub fn scalar_arrays(mut self, layout: ScalarArrayLayout) -> PrintOptions {
+        self.scalar_arrays = layout;
+        self
+    }
+
+    /// Stops printing once this many bytes have been written, replacing the rest
+    /// of the current array or object with `"…truncated…"` and closing all open
+    /// brackets, so the output is still valid JSON. The output can exceed the
+    /// limit by the size of the last value written plus the closing brackets.
+    pub fn max_output_bytes(mut self, max_output_bytes: usize) -> PrintOptions {
+        self.max_output_bytes = Some(max_output_bytes);
+        self
+    }
+
+    /// Elides the contents of non-empty arrays and objects nested this many
+    /// levels deep, where the top-level value is at depth 0, following the
+    /// `max_depth_policy`.
+    pub fn max_depth(mut self, max_depth: u64) -> PrintOptions {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Writes a space between each key and its colon, e.g. `"key" : 1`. Off by
+    /// default.
+    pub fn space_before_colon(mut self, space: bool) -> PrintOptions {
+        self.space_before_colon = space;
+        self
+    }
+
+    /// Writes a space between each colon and its value. On by default, except
+    /// in the `Compact` style.
+    pub fn space_after_colon(mut self, space: bool) -> PrintOptions {
+        self.space_after_colon = Some(space);
+        self
+    }
+
+    /// Writes a space after each comma which isn't followed by a line break.
+    /// On by default, except in the `Compact` style.
+    pub fn space_after_comma(mut self, space: bool) -> PrintOptions {
+        self.space_after_comma = Some(space);
+        self
+    }
+
+    /// Pads the keys of each object printed over several lines with spaces, so
+    /// that their colons line up, e.g. `"name"    : "x"` above `"replicas": 3`.
+    /// Keys are measured in characters. Off by default.
+    pub fn align_keys(mut self, align_keys: bool) -> PrintOptions {
+        self.align_keys = align_keys;
+        self
+    }
+
+    /// Writes keys which are JavaScript identifiers without quotes, as JSON5
+    /// allows, e.g. `{name: "x", "not an identifier": 1}`. Only keys made of
+    /// ASCII letters, digits, `_` and `$`, not starting with a digit, are
+    /// unquoted. The output is not valid JSON, but parses with
+    /// `ParseOptions::allow_unquoted_keys`. Off by default.
+    pub fn unquoted_keys(mut self, unquoted_keys: bool) -> PrintOptions {
+        self.unquoted_keys = unquoted_keys;
+        self
+    }
+
+    /// Quotes strings and keys with `'` rather than `"`, as JSON5 allows,
+    /// escaping `'` instead of `"` within them. The output is not valid JSON,
+    /// but parses with `ParseOptions::allow_single_quotes`. Off by default.
+    pub fn single_quotes(mut self, single_quotes: bool) -> PrintOptions {
+        self.single_quotes = single_quotes;
+        self
+    }
+
+    /// Writes a comma after the last item of each array and object printed over
+    /// several lines, as JSON5 allows. The output is not valid JSON, but parses
+    /// with `ParseOptions::allow_trailing_commas`. Off by default.
+    pub fn trailing_commas(mut self, trailing_commas: bool) -> PrintOptions {
+        self.trailing_commas = trailing_commas;
+        self
+    }
+
+    /// Puts the closing bracket of an array or object printed over several
+    /// lines at the end of its last line, after a space, when each of its items
+    /// is printed on a single line, e.g. `"tags": [\n  "a",\n  "b" ]`. An array
+    /// or object with an item spread over several lines, or with comments after
+    /// its last item, still closes on its own line. Off by default.
+    pub fn cuddle_brackets(mut self, cuddle_brackets: bool) -> PrintOptions {
+        self.cuddle_brackets = cuddle_brackets;
+        self
+    }
+
+    /// Stops the indentation growing past this many levels. More deeply nested
+    /// items and brackets are still printed on their own lines, but indented
+    /// the same as the items at this level.
+    pub fn max_indent_levels(mut self, max_indent_levels: u64) -> PrintOptions {
+        self.max_indent_levels = Some(max_indent_levels);
+        self
+    }
+
+    /// The number of indents written before items at the given nesting level.
+    pub(crate) fn indent_levels(&self, level: u64) -> u64 {
+        self.max_indent_levels
+            .map_or(level, |max_indent_levels| level.min(max_indent_levels))
+    }
+
+    /// Prints integers above 2^53 - 1 in magnitude as strings, e.g.
+    /// `"9007199254740993"`, since JavaScript can't parse them exactly. This
+    /// applies to the `BigInteger`s the parser reads such integers as, which
+    /// hold every digit written. A `Number` is never quoted, since one this
+    /// large may have been written with a fraction or exponent. Off by
+    /// default.
+    pub fn big_int_as_string(mut self, big_int_as_string: bool) -> PrintOptions {
+        self.big_int_as_string = big_int_as_string;
+        self
+    }
+
+    pub fn number_normalization(mut self, normalization: NumberNormalization) -> PrintOptions {
+        self.number_normalization = normalization;
+        self
+    }
+
+    /// Applies every number normalization, so that numbers with the same value
+    /// are printed identically.
+    pub fn normalized_numbers(self) -> PrintOptions {
+        self.number_normalization(NumberNormalization::all())
+    }
+
+    /// Colours the output with ANSI escape codes, styling each kind of token as
+    /// the theme gives. Without the escape codes, the output is the same as
+    /// without a theme.
+    pub fn theme(mut self, theme: Theme) -> PrintOptions {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Shortens string values with more than this many characters, ending them
+    /// with `…` and the number of characters left out, e.g. `"abc… (+20 chars)"`.
+    /// Object keys are always printed in full.
+    pub fn max_string_length(mut self, max_string_length: usize) -> PrintOptions {
+        self.max_string_length = Some(max_string_length);
+        self
+    }
+
+    pub fn max_depth_policy(mut self, policy: MaxDepthPolicy) -> PrintOptions {
+        self.max_depth_policy = policy;
+        self
+    }
+
+    pub fn key_order(mut self, key_order: KeyOrder) -> PrintOptions {
+        self.key_order = key_order;
+        self
+    }
+
+    pub fn float_format(mut self, float_format: FloatFormat) -> PrintOptions {
+        self.float_format = float_format;
+        self
+    }
+
+    pub fn non_finite(mut self, policy: NonFinitePolicy) -> PrintOptions {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Escapes `<`, `>`, `&`, U+2028 and U+2029 in strings, so the output can be
+    /// embedded in a `<script>` tag or an HTML attribute.
+    pub fn html_safe(mut self, html_safe: bool) -> PrintOptions {
+        self.html_safe = html_safe;
+        self
+    }
+}
+
+/// An error which stopped the printer from writing a value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrintError {
+    /// The value contains NaN or an infinity, which JSON can't represent, and
+    /// the policy is `NonFinitePolicy::Error`.
+    NonFiniteNumber(f64),
+    /// The value has a non-empty array or object nested as deep as `max_depth`,
+    /// which is given, and the policy is `MaxDepthPolicy::Error`.
+    MaxDepthExceeded(u64),
+    /// The writer failed.
+    Write,
+}
+
+impl fmt::Display for PrintError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrintError::NonFiniteNumber(number) => formatter.write_fmt(format_args!(
+                "ERROR: Unprintable JSON - {number} is not a valid JSON number"
+            )),
+            PrintError::MaxDepthExceeded(max_depth) => formatter.write_fmt(format_args!(
+                "ERROR: Unprintable JSON - the value is nested more than {max_depth} levels deep"
+            )),
+            PrintError::Write => formatter.write_str("ERROR: Failed to write JSON"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for PrintError {}
 
+/// Formats a value with the default options, or in the `Compact` style with the
+/// alternate flag, as in `{:#}`. A width, as in `{:4}`, sets the number of
+/// spaces to indent by. As `Display` can't report why it failed, and
+/// `format!` panics if it does, non-finite numbers are written as `null`.
 impl fmt::Display for Json {
     fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        display_json(self, formatter, 2, 0)
+        let options = match formatter.width() {
+            _ if formatter.alternate() => PrintOptions::new().style(PrintStyle::Compact),
+            Some(width) => spaces(width as u64),
+            None => PrintOptions::default(),
+        }
+        .non_finite(NonFinitePolicy::Null);
+        display_json_with_options(self, formatter, &options).map(|_| ())
+    }
+}
+
+pub fn json_to_string(value: &Json, indent: u64) -> Result<String, PrintError> {
+    json_to_string_with_options(value, &spaces(indent))
+}
+
+pub fn json_to_string_with_options(
+    value: &Json,
+    options: &PrintOptions,
+) -> Result<String, PrintError> {
+    json_to_truncated_string(value, options).map(|(output, _)| output)
+}
+
+/// Measurements of the output written for a value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrintStats {
+    /// The number of bytes written.
+    pub bytes: usize,
+    /// The number of line breaks written, plus one.
+    pub lines: usize,
+    /// The greatest number of arrays and objects nested inside each other,
+    /// which is 0 for a scalar and 1 for an array of scalars.
+    pub max_depth: u64,
+}
+
+pub fn display_json_with_options<W: Write>(
+    value: &Json,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<PrintStats, fmt::Error> {
+    display_measured_json(value, output, options).map_err(|_| fmt::Error)
+}
+
+/// Writes a value to an `io::Write` sink as it is formatted, without building
+/// the whole output in memory first.
+#[cfg(feature = "std")]
+pub fn write_json<W: io::Write>(
+    value: &Json,
+    output: &mut W,
+    options: &PrintOptions,
+) -> io::Result<PrintStats> {
+    let mut adapter = IoWriter {
+        output,
+        error: None,
+    };
+
+    match display_measured_json(value, &mut adapter, options) {
+        Ok(stats) => Ok(stats),
+        Err(PrintError::Write) => Err(adapter
+            .error
+            .unwrap_or_else(|| io::Error::other("Failed to format JSON"))),
+        Err(error) => Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+    }
+}
+
+/// Writes a value to a new file at `path`, replacing any existing file.
+#[cfg(feature = "std")]
+pub fn write_json_to_file(
+    value: &Json,
+    path: impl AsRef<Path>,
+    options: &PrintOptions,
+) -> io::Result<PrintStats> {
+    let mut output = BufWriter::new(File::create(path)?);
+    let stats = write_json(value, &mut output, options)?;
+    io::Write::flush(&mut output)?;
+    Ok(stats)
+}
+
+/// Adapts an `io::Write` sink for the printer, keeping the `io::Error` which
+/// made a write fail, since `fmt::Error` carries no information.
+#[cfg(feature = "std")]
+struct IoWriter<'a, W> {
+    output: &'a mut W,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Write for IoWriter<'_, W> {
+    fn write_str(&mut self, string: &str) -> Result<(), fmt::Error> {
+        self.output.write_all(string.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
     }
 }
 
-pub fn json_to_string(value: &Json, indent: u64) -> String {
+/// Reports the values left out of output which reached `max_output_bytes`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Truncation {
+    /// The number of values which were not printed, including those nested
+    /// inside omitted arrays and objects.
+    pub omitted_values: usize,
+}
+
+/// Formats a value like `json_to_string_with_options`, also returning whether
+/// it was cut short by `max_output_bytes`.
+pub fn json_to_truncated_string(
+    value: &Json,
+    options: &PrintOptions,
+) -> Result<(String, Option<Truncation>), PrintError> {
     let mut output = String::new();
-    display_json(value, &mut output, indent, 0).expect("Failed to write JSON to string");
-    output
+    let (truncation, _) = display_truncated_json(value, &mut output, options)?;
+    Ok((output, truncation))
+}
+
+/// Prints a value, returning whether it was truncated and how deeply nested
+/// the printed arrays and objects were.
+fn display_truncated_json<W: Write>(
+    value: &Json,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(Option<Truncation>, u64), PrintError> {
+    let mut state = PrintState::new(options);
+
+    match state.start_budget() {
+        Some(written) => state.display(value, &mut CountingWriter { output, written }),
+        None => state.display(value, output),
+    }
+    .map_err(|fmt::Error| state.take_error())?;
+
+    Ok((state.truncation, state.max_depth))
 }
 
-fn display_json<W: Write>(
+fn display_measured_json<W: Write>(
     value: &Json,
     output: &mut W,
+    options: &PrintOptions,
+) -> Result<PrintStats, PrintError> {
+    let mut measured = MeasuringWriter {
+        output,
+        bytes: 0,
+        line_breaks: 0,
+    };
+    let (_, max_depth) = display_truncated_json(value, &mut measured, options)?;
+
+    Ok(PrintStats {
+        bytes: measured.bytes,
+        lines: measured.line_breaks + 1,
+        max_depth,
+    })
+}
+
+/// A writer which passes its input through, counting the bytes and line breaks.
+struct MeasuringWriter<'a, W> {
+    output: &'a mut W,
+    bytes: usize,
+    line_breaks: usize,
+}
+
+impl<W: Write> Write for MeasuringWriter<'_, W> {
+    fn write_str(&mut self, string: &str) -> Result<(), fmt::Error> {
+        self.bytes += string.len();
+        self.line_breaks += string.bytes().filter(|&byte| byte == b'\n').count();
+        self.output.write_str(string)
+    }
+}
+
+fn spaces(indent: u64) -> PrintOptions {
+    PrintOptions::default().indent(" ".repeat(indent as usize))
+}
+
+/// Formats a value on a single line with no whitespace, e.g. `{"a":[1,2]}`.
+pub fn json_to_compact_string(value: &Json) -> Result<String, PrintError> {
+    json_to_string_with_options(value, &PrintOptions::new().style(PrintStyle::Compact))
+}
+
+/// Formats a value on a single line with a space after each comma and colon,
+/// e.g. `{"a": [1, 2]}`.
+pub fn json_to_one_line_string(value: &Json) -> Result<String, PrintError> {
+    json_to_string_with_options(value, &PrintOptions::new().style(PrintStyle::OneLine))
+}
+
+/// Formats a value parsed by `parse_with_comments`, writing each comment on its
+/// own line before the value it is attached to.
+pub fn json_to_string_with_comments(
+    value: &Json,
+    comments: &Comments,
     indent: u64,
+) -> Result<String, PrintError> {
+    let mut output = String::new();
+    display_commented_json(value, comments, &mut output, &spaces(indent))?;
+    Ok(output)
+}
+
+/// Formats a value in chunks of at most `chunk_size` bytes, doing only as much
+/// work as each chunk needs, e.g. to stream it as an HTTP response body.
+///
+/// Concatenated, the chunks equal the output of `json_to_string_with_options`.
+/// Beyond the chunk being built, memory use grows only with the nesting depth
+/// and the longest single key or scalar.
+pub struct JsonChunks<'a> {
+    state: PrintState<'a>,
+    written: Option<Rc<Cell<usize>>>,
+    buffer: String,
+    chunk_size: usize,
+    finished: bool,
+}
+
+impl<'a> JsonChunks<'a> {
+    pub fn new(value: &'a Json, options: &'a PrintOptions) -> JsonChunks<'a> {
+        let mut state = PrintState::new(options);
+        state.pending = Some(value);
+        let written = state.start_budget();
+
+        JsonChunks {
+            state,
+            written,
+            buffer: String::new(),
+            chunk_size: 8 * 1024,
+            finished: false,
+        }
+    }
+
+    /// The maximum size of each chunk in bytes, 8 KiB by default. A chunk is
+    /// never empty, so holds a whole character even if that is larger.
+    pub fn chunk_size(mut self, chunk_size: usize) -> JsonChunks<'a> {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Whether the output has been cut short by `max_output_bytes`, which is
+    /// only known once the chunks have reached the point of truncation.
+    pub fn truncation(&self) -> Option<Truncation> {
+        self.state.truncation
+    }
+
+    fn fill(&mut self) -> Result<(), fmt::Error> {
+        while !self.finished && self.buffer.len() < self.chunk_size {
+            let more = match &self.written {
+                Some(written) => self.state.step(&mut CountingWriter {
+                    output: &mut self.buffer,
+                    written: Rc::clone(written),
+                }),
+                None => self.state.step(&mut self.buffer),
+            }?;
+            self.finished = !more;
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for JsonChunks<'_> {
+    type Item = Result<String, PrintError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(fmt::Error) = self.fill() {
+            self.finished = true;
+            self.buffer.clear();
+            return Some(Err(self.state.take_error()));
+        }
+
+        let first_char_length = self.buffer.chars().next()?.len_utf8();
+        let mut length = self.chunk_size.min(self.buffer.len());
+        while !self.buffer.is_char_boundary(length) {
+            length -= 1;
+        }
+
+        let chunk = self.buffer[..length.max(first_char_length)].to_owned();
+        self.buffer.drain(..chunk.len());
+        Some(Ok(chunk))
+    }
+}
+
+/// The state carried through the printer while it writes a single value.
+///
+/// Arrays and objects are printed from an explicit stack of frames rather than
+/// by recursion, so the depth of a value is limited only by the heap.
+struct PrintState<'a> {
+    options: &'a PrintOptions,
+    /// The theme to style tokens with, which is left out when measuring output.
+    theme: Option<&'a Theme>,
+    /// The indentation for the deepest level printed so far, which is sliced
+    /// to get the indentation for any shallower level.
+    indentation: String,
+    comments: Option<CommentCursor<'a>>,
+    /// Whether the current array or object is being printed on a single line.
+    inline: bool,
+    budget: Option<Budget>,
+    truncation: Option<Truncation>,
+    /// The greatest number of nested arrays and objects printed so far.
+    max_depth: u64,
+    /// Why the printer stopped, when it wasn't the writer which failed.
+    error: Option<PrintError>,
+    /// The top-level value, until `step` starts printing it.
+    pending: Option<&'a Json>,
+    /// The arrays and objects which have been opened but not yet closed.
+    stack: Vec<Frame<'a>>,
+}
+
+/// An array or object which is part way through being printed.
+struct Frame<'a> {
+    children: Children<'a>,
+    index: usize,
     level: u64,
-) -> Result<(), fmt::Error> {
-    match value {
-        Json::Null => output.write_str("null"),
-        Json::Boolean(true) => output.write_str("true"),
-        Json::Boolean(false) => output.write_str("false"),
-        Json::String(string) => output.write_str(&display_json_string(string)),
-        Json::Number(number) => output.write_fmt(format_args!("{number}")),
-        Json::Array(array) => display_json_array(array, output, indent, level),
-        Json::Object(object) => display_json_object(object, output, indent, level),
-    }
-}
-
-fn display_json_string(string: &str) -> String {
-    let mut escaped = String::new();
-
-    escaped.push('"');
-
-    for c in string.chars() {
-        match c {
-            '\\' => escaped.push_str("\\\\"),
-            '"' => escaped.push_str("\\\""),
-            '\n' => escaped.push_str("\\n"),
-            '\r' => escaped.push_str("\\r"),
-            '\t' => escaped.push_str("\\t"),
-            '\u{C}' => escaped.push_str("\\f"),
-            '\u{8}' => escaped.push_str("\\b"),
-            '\x00'..='\x1F' => {
-                let mut codepoints = [0 as u16; 2];
-                c.encode_utf16(&mut codepoints);
-                escaped.push_str(&format!("\\u{:04X}", codepoints[0]));
+    dangling_comments: &'a [Comment],
+    parent_inline: bool,
+    parent_path_length: usize,
+    /// The width to pad each key to, when aligning keys.
+    key_width: usize,
+    /// Whether any item has been printed over several lines.
+    has_multi_line_item: bool,
+}
+
+#[derive(Clone)]
+enum Children<'a> {
+    Array(slice::Iter<'a, Json>),
+    Object(map::Iter<'a>),
+    SortedObject(map::SortedIter<'a>),
+    ReorderedObject(vec::IntoIter<(&'a String, &'a Json)>),
+}
+
+impl<'a> Children<'a> {
+    fn object(object: &'a Map, order: &KeyOrder) -> Children<'a> {
+        match order {
+            KeyOrder::Document => Children::Object(object.iter()),
+            KeyOrder::Lexicographic => Children::SortedObject(object.iter_sorted()),
+            KeyOrder::Natural | KeyOrder::Custom(_) => {
+                let mut entries: Vec<_> = object.iter().collect();
+                entries.sort_by(|(a, _), (b, _)| order.compare(a, b));
+                Children::ReorderedObject(entries.into_iter())
             }
-            other => escaped.push(other),
         }
     }
 
-    escaped.push('"');
+    fn brackets(&self) -> (&'static str, &'static str) {
+        match self {
+            Children::Array(_) => ("[", "]"),
+            _ => ("{", "}"),
+        }
+    }
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = (Option<&'a String>, &'a Json);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, value) = match self {
+            Children::Array(items) => return items.next().map(|item| (None, item)),
+            Children::Object(entries) => entries.next(),
+            Children::SortedObject(entries) => entries.next(),
+            Children::ReorderedObject(entries) => entries.next(),
+        }?;
+        Some((Some(key), value))
+    }
 
-    escaped
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Children::Array(items) => items.size_hint(),
+            Children::Object(entries) => entries.size_hint(),
+            Children::SortedObject(entries) => entries.size_hint(),
+            Children::ReorderedObject(entries) => entries.size_hint(),
+        }
+    }
 }
 
-fn display_json_array<W: Write>(
-    items: &Vec<Json>,
-    output: &mut W,
-    indent: u64,
-    level: u64,
-) -> Result<(), fmt::Error> {
-    let child_level = level + 1;
+impl ExactSizeIterator for Children<'_> {}
+
+impl<'a> PrintState<'a> {
+    fn new(options: &'a PrintOptions) -> PrintState<'a> {
+        PrintState {
+            options,
+            theme: options.theme.as_ref(),
+            indentation: String::new(),
+            comments: None,
+            inline: false,
+            budget: None,
+            truncation: None,
+            max_depth: 0,
+            error: None,
+            pending: None,
+            stack: Vec::new(),
+        }
+    }
+
+    fn display<W: Write>(&mut self, value: &'a Json, output: &mut W) -> Result<(), fmt::Error> {
+        self.pending = Some(value);
+        while self.step(output)? {}
+        Ok(())
+    }
+
+    /// Starts printing the pending top-level value, or prints the next item of
+    /// the innermost open array or object, or closes it if it has no items left.
+    /// Returns `false` once every value is closed.
+    fn step<W: Write>(&mut self, output: &mut W) -> Result<bool, fmt::Error> {
+        if let Some(value) = self.pending.take() {
+            self.display_value(value, output, 0, 0, 0, 0)?;
+            return Ok(true);
+        }
+
+        let Some(frame) = self.stack.last() else {
+            return Ok(false);
+        };
+
+        let index = frame.index;
+        let child_level = frame.level + 1;
+        let is_object = !matches!(frame.children, Children::Array(_));
+        let remaining = frame.children.clone();
+
+        if remaining.len() == 0 {
+            self.close_container(output)?;
+            return Ok(true);
+        }
+
+        if let Some(marked) = self.truncate(remaining.map(|(_, value)| value)) {
+            if marked {
+                if index > 0 {
+                    self.display_punctuation(output, ",")?;
+                }
+                self.display_line_break(output, child_level, index > 0)?;
+                output.write_str(TRUNCATION_MARKER)?;
+                if is_object {
+                    self.display_punctuation(output, self.key_separator())?;
+                    output.write_str(TRUNCATION_MARKER)?;
+                }
+            }
+            self.close_container(output)?;
+            return Ok(true);
+        }
+
+        if index > 0 {
+            self.display_punctuation(output, ",")?;
+        }
+        self.display_line_break(output, child_level, index > 0)?;
+
+        let frame = self.stack.last_mut().expect("A frame is open");
+        let (key, value) = frame.children.next().expect("The frame has items left");
+        frame.index += 1;
+        let comma_width = usize::from(frame.children.len() > 0);
+        let aligned_key_width = frame.key_width;
+
+        let parent_path_length = self.enter_path(|| match key {
+            Some(key) => escape_pointer_token(key),
+            None => index.to_string(),
+        });
+        self.display_leading_comments(output, child_level)?;
+
+        let key_width = match key {
+            Some(key) => {
+                let written_key_width = self.display_styled(Token::Key, output, |output| {
+                    let mut counter = CharCounter { output, count: 0 };
+                    display_key(key, &mut counter, self.options)?;
+                    Ok(counter.count)
+                })?;
+                for _ in written_key_width..aligned_key_width {
+                    output.write_char(' ')?;
+                }
+                let separator = self.key_separator();
+                self.display_punctuation(output, separator)?;
+                written_key_width.max(aligned_key_width) + separator.len()
+            }
+            None => 0,
+        };
+
+        self.display_value(
+            value,
+            output,
+            child_level,
+            key_width,
+            comma_width,
+            parent_path_length,
+        )?;
+        Ok(true)
+    }
+
+    /// Prints a scalar, or opens an array or object for `step` to print its items.
+    ///
+    /// `prefix_width` and `suffix_width` are the number of characters before and
+    /// after the value on its line, other than the indentation, such as its key
+    /// and a trailing comma.
+    fn display_value<W: Write>(
+        &mut self,
+        value: &'a Json,
+        output: &mut W,
+        level: u64,
+        prefix_width: usize,
+        suffix_width: usize,
+        parent_path_length: usize,
+    ) -> Result<(), fmt::Error> {
+        let inline = self.inline
+            || matches!(
+                self.options.style,
+                PrintStyle::OneLine | PrintStyle::Compact
+            )
+            || self.options.style == PrintStyle::Fit
+                && self.comments.is_none()
+                && fits_on_line(value, self.options, level, prefix_width + suffix_width);
+
+        if !is_scalar(value) {
+            self.max_depth = self.max_depth.max(level + 1);
+        }
+
+        let children = match value {
+            Json::Array(_) | Json::Object(_)
+                if self
+                    .options
+                    .max_depth
+                    .is_some_and(|max_depth| level >= max_depth)
+                    && !is_empty(value) =>
+            {
+                self.leave_path(parent_path_length);
+                return self.display_elided(value, output, level);
+            }
+            Json::Array(items)
+                if !inline
+                    && self.comments.is_none()
+                    && self.options.scalar_arrays != ScalarArrayLayout::Expanded
+                    && items.iter().all(is_scalar) =>
+            {
+                return self.display_scalar_array(items, output, level, prefix_width);
+            }
+            Json::Array(items) => Children::Array(items.iter()),
+            Json::Object(object) => Children::object(object, &self.options.key_order),
+            scalar => {
+                self.leave_path(parent_path_length);
+                return self.display_scalar(scalar, output);
+            }
+        };
+
+        let (open, close) = children.brackets();
+        let dangling_comments = self.dangling_comments();
+
+        self.display_punctuation(output, open)?;
+
+        if children.len() == 0 && dangling_comments.is_empty() {
+            self.leave_path(parent_path_length);
+            return self.display_punctuation(output, close);
+        }
+
+        let key_width = match value {
+            Json::Object(object) if self.options.align_keys && !inline => object
+                .keys()
+                .map(|key| key_width(key, self.options))
+                .max()
+                .unwrap_or(0),
+            _ => 0,
+        };
+
+        if !inline {
+            if let Some(parent) = self.stack.last_mut() {
+                parent.has_multi_line_item = true;
+            }
+        }
+
+        self.stack.push(Frame {
+            children,
+            index: 0,
+            level,
+            dangling_comments,
+            parent_inline: self.inline,
+            parent_path_length,
+            key_width,
+            has_multi_line_item: false,
+        });
+        self.inline = inline;
+        Ok(())
+    }
+
+    fn display_elided<W: Write>(
+        &mut self,
+        value: &Json,
+        output: &mut W,
+        level: u64,
+    ) -> Result<(), fmt::Error> {
+        if self.options.max_depth_policy == MaxDepthPolicy::Error {
+            self.error = Some(PrintError::MaxDepthExceeded(level));
+            return Err(fmt::Error);
+        }
+
+        let placeholder = |output: &mut W| output.write_str("\"…\"");
+
+        if let Json::Array(_) = value {
+            self.display_punctuation(output, "[")?;
+            self.display_styled(Token::String, output, placeholder)?;
+            return self.display_punctuation(output, "]");
+        }
+        let compact = self.options.style == PrintStyle::Compact;
+        let separator = match (
+            self.options.space_before_colon,
+            self.options.space_after_colon,
+        ) {
+            (false, None) if compact => ":",
+            _ => self.key_separator(),
+        };
+        self.display_punctuation(output, "{")?;
+        self.display_styled(Token::Key, output, placeholder)?;
+        self.display_punctuation(output, separator)?;
+        self.display_styled(Token::String, output, placeholder)?;
+        self.display_punctuation(output, "}")
+    }
+
+    fn close_container<W: Write>(&mut self, output: &mut W) -> Result<(), fmt::Error> {
+        let frame = self.stack.pop().expect("A frame is open");
+
+        if self.options.trailing_commas && !self.inline && frame.index > 0 {
+            self.display_punctuation(output, ",")?;
+        }
+        for comment in frame.dangling_comments {
+            self.display_line_break(output, frame.level + 1, false)?;
+            display_comment(comment, output)?;
+        }
+        let cuddle = self.options.cuddle_brackets
+            && !self.inline
+            && !frame.has_multi_line_item
+            && frame.dangling_comments.is_empty()
+            && frame.index > 0;
+        if cuddle {
+            output.write_char(' ')?;
+        } else {
+            self.display_line_break(output, frame.level, false)?;
+        }
+
+        self.display_punctuation(output, frame.children.brackets().1)?;
+
+        self.inline = frame.parent_inline;
+        self.leave_path(frame.parent_path_length);
+        Ok(())
+    }
+
+    fn display_scalar_array<W: Write>(
+        &mut self,
+        items: &'a [Json],
+        output: &mut W,
+        level: u64,
+        prefix_width: usize,
+    ) -> Result<(), fmt::Error> {
+        let items_per_line = match self.options.scalar_arrays {
+            ScalarArrayLayout::Wrap(items_per_line) => items_per_line.max(1),
+            ScalarArrayLayout::Expanded | ScalarArrayLayout::OneLine => items.len(),
+        };
+        if items.len() > items_per_line {
+            if let Some(parent) = self.stack.last_mut() {
+                parent.has_multi_line_item = true;
+            }
+        }
+
+        self.display_punctuation(output, "[")?;
+
+        for (index, item) in items.iter().enumerate() {
+            if let Some(marked) = self.truncate(items[index..].iter()) {
+                if marked {
+                    if index > 0 {
+                        self.display_punctuation(output, ",")?;
+                        output.write_str(self.comma_spacing())?;
+                    }
+                    output.write_str(TRUNCATION_MARKER)?;
+                }
+                break;
+            }
+
+            if index > 0 && index % items_per_line == 0 {
+                self.display_punctuation(output, ",")?;
+                output.write_char('\n')?;
+                output.write_str(self.indentation(level))?;
+                for _ in 0..=prefix_width {
+                    output.write_char(' ')?;
+                }
+            } else if index > 0 {
+                self.display_punctuation(output, ",")?;
+                output.write_str(self.comma_spacing())?;
+            }
+            self.display_scalar(item, output)?;
+        }
+
+        self.display_punctuation(output, "]")
+    }
+
+    fn display_scalar<W: Write>(&mut self, value: &Json, output: &mut W) -> Result<(), fmt::Error> {
+        match value {
+            Json::Number(number)
+                if !number.is_finite() && self.options.non_finite == NonFinitePolicy::Error =>
+            {
+                self.error = Some(PrintError::NonFiniteNumber(*number));
+                Err(fmt::Error)
+            }
+            scalar => {
+                let token = match scalar {
+                    Json::Null => Token::Null,
+                    Json::Boolean(_) => Token::Boolean,
+                    Json::Number(_) | Json::BigInteger(_) => Token::Number,
+                    _ => Token::String,
+                };
+                self.display_styled(token, output, |output| {
+                    display_json_scalar(scalar, output, self.options)
+                })
+            }
+        }
+    }
+
+    /// Writes a token, wrapped in the escape codes for its style in the theme.
+    fn display_styled<W: Write, T>(
+        &self,
+        token: Token,
+        output: &mut W,
+        display: impl FnOnce(&mut W) -> Result<T, fmt::Error>,
+    ) -> Result<T, fmt::Error> {
+        let Some(style) = self.theme.and_then(|theme| theme.style(token)) else {
+            return display(output);
+        };
+        let mut start = String::new();
+        style.display_start(&mut start)?;
+        output.write_str(&start)?;
+        let result = display(output)?;
+        output.write_str(RESET)?;
+
+        // Escape codes don't count towards `max_output_bytes`, so that styling
+        // doesn't change where the output is truncated.
+        if let Some(budget) = &self.budget {
+            let written = budget.written.get() - start.len() - RESET.len();
+            budget.written.set(written);
+        }
+        Ok(result)
+    }
+
+    fn display_punctuation<W: Write>(
+        &self,
+        output: &mut W,
+        punctuation: &str,
+    ) -> Result<(), fmt::Error> {
+        self.display_styled(Token::Punctuation, output, |output| {
+            output.write_str(punctuation)
+        })
+    }
+
+    /// Starts counting the output against `max_output_bytes`, if it is set,
+    /// returning the count for a `CountingWriter` to add to.
+    fn start_budget(&mut self) -> Option<Rc<Cell<usize>>> {
+        let max_output_bytes = self.options.max_output_bytes?;
+        let written = Rc::new(Cell::new(0));
+        self.budget = Some(Budget {
+            max_output_bytes,
+            written: Rc::clone(&written),
+        });
+        Some(written)
+    }
+
+    fn take_error(&mut self) -> PrintError {
+        self.error.take().unwrap_or(PrintError::Write)
+    }
+
+    /// Starts a new line at the given level, or in a single-line container, writes
+    /// the space which separates one item from the next.
+    fn display_line_break<W: Write>(
+        &mut self,
+        output: &mut W,
+        level: u64,
+        after_item: bool,
+    ) -> Result<(), fmt::Error> {
+        if self.inline {
+            if after_item {
+                output.write_str(self.comma_spacing())?;
+            }
+            return Ok(());
+        }
+
+        output.write_char('\n')?;
+        output.write_str(self.indentation(level))
+    }
+
+    fn key_separator(&self) -> &'static str {
+        let compact = self.inline && self.options.style == PrintStyle::Compact;
+        let space_after = self.options.space_after_colon.unwrap_or(!compact);
+
+        match (self.options.space_before_colon, space_after) {
+            (false, false) => ":",
+            (false, true) => ": ",
+            (true, false) => " :",
+            (true, true) => " : ",
+        }
+    }
+
+    /// The space written after a comma when the next item is on the same line.
+    fn comma_spacing(&self) -> &'static str {
+        let compact = self.options.style == PrintStyle::Compact;
+        if self.options.space_after_comma.unwrap_or(!compact) {
+            " "
+        } else {
+            ""
+        }
+    }
+
+    fn display_leading_comments<W: Write>(
+        &mut self,
+        output: &mut W,
+        level: u64,
+    ) -> Result<(), fmt::Error> {
+        for comment in self.leading_comments() {
+            display_comment(comment, output)?;
+            output.write_char('\n')?;
+            output.write_str(self.indentation(level))?;
+        }
+        Ok(())
+    }
+
+    fn indentation(&mut self, level: u64) -> &str {
+        let length = self.options.indent.len() * self.options.indent_levels(level) as usize;
+
+        while self.indentation.len() < length {
+            self.indentation.push_str(&self.options.indent);
+        }
+
+        &self.indentation[..length]
+    }
+
+    /// Checks whether the rest of a container, starting at `remaining`, should
+    /// be left out, recording the values omitted if so. Returns `Some(true)`
+    /// when this is the first container cut short, which is the one given the
+    /// truncation marker.
+    fn truncate(&mut self, remaining: impl Iterator<Item = &'a Json>) -> Option<bool> {
+        let exhausted = self
+            .budget
+            .as_ref()
+            .is_some_and(|budget| budget.written.get() >= budget.max_output_bytes);
+
+        if !exhausted {
+            return None;
+        }
+
+        let omitted_values = count_values(remaining);
+        match &mut self.truncation {
+            Some(truncation) => {
+                truncation.omitted_values += omitted_values;
+                Some(false)
+            }
+            None => {
+                self.truncation = Some(Truncation { omitted_values });
+                Some(true)
+            }
+        }
+    }
+
+    fn leading_comments(&self) -> &'a [Comment] {
+        self.comments.as_ref().map_or(&[], CommentCursor::leading)
+    }
+
+    fn dangling_comments(&self) -> &'a [Comment] {
+        self.comments.as_ref().map_or(&[], CommentCursor::dangling)
+    }
+
+    fn enter_path(&mut self, token: impl FnOnce() -> String) -> usize {
+        match &mut self.comments {
+            Some(cursor) => {
+                let parent_path_length = cursor.path.len();
+                cursor.path.push('/');
+                cursor.path.push_str(&token());
+                parent_path_length
+            }
+            None => 0,
+        }
+    }
+
+    fn leave_path(&mut self, parent_path_length: usize) {
+        if let Some(cursor) = &mut self.comments {
+            cursor.path.truncate(parent_path_length);
+        }
+    }
+}
+
+struct Budget {
+    max_output_bytes: usize,
+    written: Rc<Cell<usize>>,
+}
+
+/// A writer which counts the bytes written through it, so the printer can
+/// check its output against `max_output_bytes`.
+struct CountingWriter<'a, W> {
+    output: &'a mut W,
+    written: Rc<Cell<usize>>,
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write_str(&mut self, string: &str) -> Result<(), fmt::Error> {
+        self.written.set(self.written.get() + string.len());
+        self.output.write_str(string)
+    }
+}
+
+/// Compares keys in natural order: runs of ASCII digits are compared by their
+/// numeric value and other text is compared ignoring case, with digits before
+/// letters. Keys which are still equal, such as `a01` and `a1` or `A` and `a`,
+/// are then compared by their characters, so that only identical keys are equal.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_runs = Runs(a);
+    let mut b_runs = Runs(b);
+
+    loop {
+        let ordering = match (a_runs.next(), b_runs.next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a_run), Some(b_run)) => compare_runs(a_run, b_run),
+        };
+
+        if ordering.is_ne() {
+            return ordering;
+        }
+    }
+}
+
+fn compare_runs(a: &str, b: &str) -> Ordering {
+    let is_number = |run: &str| run.starts_with(|c: char| c.is_ascii_digit());
+
+    if is_number(a) && is_number(b) {
+        let a = a.trim_start_matches('0');
+        let b = b.trim_start_matches('0');
+        a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+    } else {
+        let lowercase = |run: &str| run.chars().flat_map(char::to_lowercase).collect::<Vec<_>>();
+        lowercase(a).cmp(&lowercase(b))
+    }
+}
+
+/// Splits a string into alternating runs of ASCII digits and other characters.
+struct Runs<'a>(&'a str);
+
+impl<'a> Iterator for Runs<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let first = self.0.chars().next()?;
+        let length = self
+            .0
+            .find(|c: char| c.is_ascii_digit() != first.is_ascii_digit())
+            .unwrap_or(self.0.len());
+        let (run, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Some(run)
+    }
+}
+
+fn count_values<'a>(values: impl Iterator<Item = &'a Json>) -> usize {
+    let mut pending: Vec<&Json> = values.collect();
+    let mut count = 0;
+
+    while let Some(value) = pending.pop() {
+        count += 1;
+        match value {
+            Json::Array(items) => pending.extend(items),
+            Json::Object(object) => pending.extend(object.values()),
+            _ => {}
+        }
+    }
+
+    count
+}
+
+const TRUNCATION_MARKER: &str = "\"…truncated…\"";
+
+struct CommentCursor<'a> {
+    comments: &'a Comments,
+    path: String,
+}
+
+impl<'a> CommentCursor<'a> {
+    fn leading(&self) -> &'a [Comment] {
+        self.comments
+            .leading
+            .get(&self.path)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    fn dangling(&self) -> &'a [Comment] {
+        self.comments
+            .dangling
+            .get(&self.path)
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+fn display_commented_json<W: Write>(
+    value: &Json,
+    comments: &Comments,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(), PrintError> {
+    let mut state = PrintState::new(options);
+    state.comments = Some(CommentCursor {
+        comments,
+        path: String::new(),
+    });
+
+    state
+        .display_leading_comments(output, 0)
+        .and_then(|()| state.display(value, output))
+        .map_err(|fmt::Error| state.take_error())?;
+
+    for comment in &comments.trailing {
+        output
+            .write_char('\n')
+            .map_err(|fmt::Error| PrintError::Write)?;
+        display_comment(comment, output).map_err(|fmt::Error| PrintError::Write)?;
+    }
+
+    Ok(())
+}
+
+fn is_scalar(value: &Json) -> bool {
+    !matches!(value, Json::Array(_) | Json::Object(_))
+}
+
+fn is_empty(container: &Json) -> bool {
+    match container {
+        Json::Array(items) => items.is_empty(),
+        Json::Object(object) => object.is_empty(),
+        _ => false,
+    }
+}
+
+fn fits_on_line(value: &Json, options: &PrintOptions, level: u64, reserved: usize) -> bool {
+    let indentation = options.indent.chars().count() * options.indent_levels(level) as usize;
+    let mut limit = WidthLimit {
+        remaining: options.width.saturating_sub(indentation + reserved),
+    };
+    let mut state = PrintState::new(options);
+    state.inline = true;
+    state.theme = None;
+    state.display(value, &mut limit).is_ok()
+}
+
+/// A writer which discards its input and fails once more than `remaining`
+/// characters have been written, so measuring a large value stops early.
+struct WidthLimit {
+    remaining: usize,
+}
+
+impl Write for WidthLimit {
+    fn write_str(&mut self, string: &str) -> Result<(), fmt::Error> {
+        let width = string.chars().count();
+        if width > self.remaining {
+            return Err(fmt::Error);
+        }
+        self.remaining -= width;
+        Ok(())
+    }
+}
+
+/// A writer which passes its input through, counting the characters written.
+struct CharCounter<'a, W> {
+    output: &'a mut W,
+    count: usize,
+}
+
+impl<W: Write> Write for CharCounter<'_, W> {
+    fn write_str(&mut self, string: &str) -> Result<(), fmt::Error> {
+        self.count += string.chars().count();
+        self.output.write_str(string)
+    }
+}
+
+/// The number of characters in a key once it is quoted and escaped.
+fn key_width(key: &str, options: &PrintOptions) -> usize {
+    let mut counter = CharCounter {
+        output: &mut Discard,
+        count: 0,
+    };
+    display_key(key, &mut counter, options).expect("Discarding output can't fail");
+    counter.count
+}
+
+/// A writer which ignores its input.
+struct Discard;
+
+impl Write for Discard {
+    fn write_str(&mut self, _: &str) -> Result<(), fmt::Error> {
+        Ok(())
+    }
+}
+
+pub(crate) fn display_json_scalar<W: Write>(
+    value: &Json,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(), fmt::Error> {
+    match value {
+        Json::Null => output.write_str("null"),
+        Json::Boolean(true) => output.write_str("true"),
+        Json::Boolean(false) => output.write_str("false"),
+        Json::String(string) => display_string_value(string, output, options),
+        Json::Number(number) => display_json_number(*number, output, options),
+        Json::BigInteger(integer) => display_big_integer(*integer, output, options),
+        Json::Array(_) | Json::Object(_) => unreachable!("Arrays and objects are not scalars"),
+    }
+}
+
+pub fn display_json_compact<W: Write>(value: &Json, output: &mut W) -> Result<(), fmt::Error> {
+    let options = PrintOptions::new().style(PrintStyle::Compact);
+    PrintState::new(&options).display(value, output)
+}
+
+fn display_json_number<W: Write>(
+    number: f64,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(), fmt::Error> {
+    if number.is_finite() && options.number_normalization != NumberNormalization::default() {
+        let mut text = String::new();
+        display_finite_number(number, &mut text, options)?;
+        return output.write_str(&normalize_number(&text, options.number_normalization));
+    }
+    if number.is_finite() {
+        return display_finite_number(number, output, options);
+    }
+
+    match options.non_finite {
+        NonFinitePolicy::Error => Err(fmt::Error),
+        NonFinitePolicy::Null => output.write_str("null"),
+        NonFinitePolicy::String if number.is_nan() => output.write_str("\"NaN\""),
+        NonFinitePolicy::String if number > 0.0 => output.write_str("\"Infinity\""),
+        NonFinitePolicy::String => output.write_str("\"-Infinity\""),
+    }
+}
+
+fn display_big_integer<W: Write>(
+    integer: i128,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(), fmt::Error> {
+    if options.big_int_as_string {
+        let quote = quote(options);
+        output.write_fmt(format_args!("{quote}{integer}{quote}"))
+    } else if options.canonical {
+        canonical::display_number(integer as f64, output)
+    } else {
+        output.write_fmt(format_args!("{integer}"))
+    }
+}
+
+fn display_finite_number<W: Write>(
+    number: f64,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(), fmt::Error> {
+    match options.float_format {
+        _ if options.canonical => canonical::display_number(number, output),
+        // Unlike `{number}`, which pads the shortest digits with zeros, this
+        // writes every digit of a large whole number, so that it parses back
+        // as the same number rather than a `BigInteger` near it.
+        _ if is_whole_number(number) => output.write_fmt(format_args!("{number:.0}")),
+        FloatFormat::Shortest => output.write_fmt(format_args!("{number}")),
+        FloatFormat::FixedDecimals(decimals) => {
+            output.write_fmt(format_args!("{number:.*}", usize::from(decimals)))
+        }
+        FloatFormat::SignificantDigits(digits) => {
+            // Rounding through scientific notation and printing the result
+            // normally avoids an exponent in the output.
+            let precision = usize::from(digits.max(1) - 1);
+            let rounded: f64 = format!("{number:.precision$e}")
+                .parse()
+                .expect("Rust formats floats in a form it can parse");
+            output.write_fmt(format_args!("{rounded}"))
+        }
+    }
+}
+
+/// Rewrites the text of a JSON number as the normalization gives.
+pub(crate) fn normalize_number(text: &str, normalization: NumberNormalization) -> String {
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(marker) => text.split_at(marker),
+        None => (text, ""),
+    };
+    let mut mantissa = mantissa;
+    let mut exponent = exponent.to_owned();
+
+    if normalization.fold_negative_zero
+        && mantissa.starts_with('-')
+        && mantissa
+            .bytes()
+            .all(|byte| matches!(byte, b'-' | b'0' | b'.'))
+    {
+        mantissa = &mantissa[1..];
+    }
+    if normalization.strip_zero_fraction {
+        if let Some((whole, fraction)) = mantissa.split_once('.') {
+            if fraction.bytes().all(|byte| byte == b'0') {
+                mantissa = whole;
+            }
+        }
+    }
+    if normalization.lowercase_exponent {
+        exponent = exponent.replace('E', "e");
+    }
+    if normalization.strip_exponent_plus {
+        exponent = exponent.replace('+', "");
+    }
+
+    mantissa.to_owned() + &exponent
+}
+
+pub(crate) fn display_key<W: Write>(
+    key: &str,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(), fmt::Error> {
+    if options.unquoted_keys && is_identifier(key) {
+        output.write_str(key)
+    } else {
+        display_json_string(key, output, options)
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let is_identifier_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$';
+    key.starts_with(|c: char| !c.is_ascii_digit()) && key.chars().all(is_identifier_char)
+}
+
+fn display_json_string<W: Write>(
+    string: &str,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(), fmt::Error> {
+    let quote = quote(options);
+    output.write_char(quote)?;
+    escape_string_into(string, output, options)?;
+    output.write_char(quote)
+}
+
+fn quote(options: &PrintOptions) -> char {
+    if options.single_quotes {
+        '\''
+    } else {
+        '"'
+    }
+}
+
+/// Writes a string which is a value, rather than a key, shortening it to the
+/// `max_string_length`.
+pub(crate) fn display_string_value<W: Write>(
+    string: &str,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(), fmt::Error> {
+    match options.max_string_length {
+        Some(max_length) => display_shortened_json_string(string, max_length, output, options),
+        None => display_json_string(string, output, options),
+    }
+}
+
+fn display_shortened_json_string<W: Write>(
+    string: &str,
+    max_length: usize,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(), fmt::Error> {
+    let Some((end, _)) = string.char_indices().nth(max_length) else {
+        return display_json_string(string, output, options);
+    };
+    let omitted = string[end..].chars().count();
+
+    let quote = quote(options);
+    output.write_char(quote)?;
+    escape_string_into(&string[..end], output, options)?;
+    output.write_fmt(format_args!("… (+{omitted} chars){quote}"))
+}
+
+/// Writes the escaped contents of a JSON string, without the surrounding quotes.
+pub fn escape_into<W: Write>(string: &str, output: &mut W) -> Result<(), fmt::Error> {
+    escape_string_into(string, output, &PrintOptions::default())
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+const LOWERCASE_HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+fn escape_string_into<W: Write>(
+    string: &str,
+    output: &mut W,
+    options: &PrintOptions,
+) -> Result<(), fmt::Error> {
+    let bytes = string.as_bytes();
+    let mut unescaped_start = 0;
+
+    for (index, &byte) in bytes.iter().enumerate() {
+        let escape = match byte {
+            b'\\' => "\\\\",
+            b'"' if !options.single_quotes => "\\\"",
+            b'\'' if options.single_quotes => "\\'",
+            b'\n' => "\\n",
+            b'\r' => "\\r",
+            b'\t' => "\\t",
+            0x0C => "\\f",
+            0x08 => "\\b",
+            0x00..=0x1F => "\\u00",
+            b'<' if options.html_safe => "\\u003C",
+            b'>' if options.html_safe => "\\u003E",
+            b'&' if options.html_safe => "\\u0026",
+            // U+2028 and U+2029 are encoded as E2 80 A8 and E2 80 A9.
+            0xE2 if options.html_safe && bytes[index + 1..].starts_with(&[0x80, 0xA8]) => "\\u2028",
+            0xE2 if options.html_safe && bytes[index + 1..].starts_with(&[0x80, 0xA9]) => "\\u2029",
+            _ => continue,
+        };
+
+        output.write_str(&string[unescaped_start..index])?;
+        output.write_str(escape)?;
+        unescaped_start = index + 1;
+
+        if escape == "\\u00" {
+            let hex_digits = if options.canonical {
+                LOWERCASE_HEX_DIGITS
+            } else {
+                HEX_DIGITS
+            };
+            output.write_char(char::from(hex_digits[usize::from(byte >> 4)]))?;
+            output.write_char(char::from(hex_digits[usize::from(byte & 0xF)]))?;
+        } else if byte == 0xE2 {
+            unescaped_start += 2;
+        }
+    }
+
+    output.write_str(&string[unescaped_start..])
+}
+
+fn display_comment<W: Write>(comment: &Comment, output: &mut W) -> Result<(), fmt::Error> {
+    match comment {
+        Comment::Line(text) => output.write_fmt(format_args!("//{text}")),
+        Comment::Block(text) => output.write_fmt(format_args!("/*{text}*/")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::{
+        ast::Json,
+        canonical::json_to_canonical_string,
+        dsl::ToJson,
+        map::Map,
+        parser::{parse, parse_with_comments, parse_with_options, ParseOptions},
+        printer::{
+            display_json_with_options, escape_into, json_to_compact_string,
+            json_to_one_line_string, json_to_string, json_to_string_with_comments,
+            json_to_string_with_options, json_to_truncated_string, natural_cmp, normalize_number,
+            write_json, write_json_to_file, FloatFormat, JsonChunks, KeyOrder, MaxDepthPolicy,
+            NonFinitePolicy, NumberNormalization, PrintError, PrintOptions, PrintStats, PrintStyle,
+            ScalarArrayLayout, Truncation,
+        },
+        theme::{Color, Style, Theme},
+    };
+
+    #[test]
+    fn it_prints_null() {
+        assert_eq!(json_to_string(&Json::Null, 2).unwrap(), "null");
+    }
+
+    #[test]
+    fn it_prints_booleans() {
+        assert_eq!(json_to_string(&Json::Boolean(true), 2).unwrap(), "true");
+        assert_eq!(json_to_string(&Json::Boolean(false), 2).unwrap(), "false");
+    }
+
+    #[test]
+    fn it_prints_numbers() {
+        assert_eq!(json_to_string(&Json::Number(0.0), 2).unwrap(), "0");
+        assert_eq!(json_to_string(&Json::Number(234.0), 2).unwrap(), "234");
+        assert_eq!(json_to_string(&Json::Number(-234.0), 2).unwrap(), "-234");
+        assert_eq!(
+            json_to_string(&Json::Number(123.456), 2).unwrap(),
+            "123.456"
+        );
+        assert_eq!(
+            json_to_string(&Json::Number(10000.00001), 2).unwrap(),
+            "10000.00001"
+        );
+        assert_eq!(
+            json_to_string(&Json::Number(0.00000000001), 2).unwrap(),
+            "0.00000000001"
+        );
+        assert_eq!(
+            json_to_string(&Json::Number(2405946039048539.0), 2).unwrap(),
+            "2405946039048539"
+        );
+    }
+
+    #[test]
+    fn it_prints_ascii_strings() {
+        assert_eq!(
+            json_to_string(&Json::String("This is a string.".to_owned()), 2).unwrap(),
+            r#""This is a string.""#
+        );
+    }
+
+    #[test]
+    fn it_prints_non_ascii_strings() {
+        assert_eq!(
+            json_to_string(&Json::String("😃 or 🙁?".to_owned()), 2).unwrap(),
+            r#""😃 or 🙁?""#
+        );
+    }
+
+    #[test]
+    fn it_prints_a_string_with_an_escaped_double_quote() {
+        assert_eq!(
+            json_to_string(&Json::String("double \" quote".to_owned()), 2).unwrap(),
+            r#""double \" quote""#
+        );
+    }
+
+    #[test]
+    fn it_prints_a_string_with_an_escaped_backslash() {
+        assert_eq!(
+            json_to_string(&Json::String("back \\ slash".to_owned()), 2).unwrap(),
+            r#""back \\ slash""#
+        );
+    }
+
+    #[test]
+    fn it_prints_a_string_with_a_solidus_without_escaping_it() {
+        assert_eq!(
+            json_to_string(&Json::String("forward / slash".to_owned()), 2).unwrap(),
+            r#""forward / slash""#
+        );
+    }
+
+    #[test]
+    fn it_prints_a_string_with_an_escaped_backspace() {
+        assert_eq!(
+            json_to_string(&Json::String("back \x08 space".to_owned()), 2).unwrap(),
+            r#""back \b space""#
+        );
+    }
+
+    #[test]
+    fn it_prints_a_string_with_an_escaped_formfeed() {
+        assert_eq!(
+            json_to_string(&Json::String("form \x0C feed".to_owned()), 2).unwrap(),
+            r#""form \f feed""#,
+        );
+    }
+
+    #[test]
+    fn it_prints_a_string_with_an_escaped_linefeed() {
+        assert_eq!(
+            json_to_string(&Json::String("line \n feed".to_owned()), 2).unwrap(),
+            r#""line \n feed""#,
+        );
+    }
+
+    #[test]
+    fn it_prints_a_string_with_an_escaped_carriage_return() {
+        assert_eq!(
+            json_to_string(&Json::String("carriage \r return".to_owned()), 2).unwrap(),
+            r#""carriage \r return""#,
+        );
+    }
+
+    #[test]
+    fn it_prints_a_string_with_an_escaped_tab() {
+        assert_eq!(
+            json_to_string(&Json::String("horizontal \t tab".to_owned()), 2).unwrap(),
+            r#""horizontal \t tab""#,
+        );
+    }
+
+    #[test]
+    fn it_prints_a_string_with_an_escaped_control_characters() {
+        assert_eq!(
+            json_to_string(&Json::String("null \x00 character".to_owned()), 2).unwrap(),
+            r#""null \u0000 character""#,
+        );
+        assert_eq!(
+            json_to_string(&Json::String("unit \x1F separator".to_owned()), 2).unwrap(),
+            r#""unit \u001F separator""#,
+        );
+    }
+
+    #[test]
+    fn it_escapes_a_string_into_a_writer_without_quotes() {
+        let mut output = String::new();
+        escape_into("a\"b\\c\u{0}\u{1F} é\u{2028}<", &mut output).unwrap();
+        assert_eq!(output, "a\\\"b\\\\c\\u0000\\u001F é\u{2028}<");
+    }
+
+    #[test]
+    fn it_escapes_every_control_character_as_before() {
+        for code in 0..0x20_u8 {
+            let c = char::from(code);
+            let expected = match c {
+                '\n' => "\\n".to_owned(),
+                '\r' => "\\r".to_owned(),
+                '\t' => "\\t".to_owned(),
+                '\u{C}' => "\\f".to_owned(),
+                '\u{8}' => "\\b".to_owned(),
+                _ => format!("\\u{:04X}", code),
+            };
+            assert_eq!(
+                json_to_string(&format!("x{c}y").to_json(), 2).unwrap(),
+                format!("\"x{expected}y\"")
+            );
+        }
+    }
+
+    #[test]
+    fn it_prints_an_array_with_one_element_per_line_with_2_space_indent() {
+        assert_eq!(
+            json_to_string(
+                &Json::Array(vec!(Json::Null, Json::Boolean(true), Json::Boolean(false))),
+                2
+            )
+            .unwrap(),
+            "[\n  null,\n  true,\n  false\n]",
+        );
+    }
+
+    #[test]
+    fn it_prints_an_empty_array_on_one_line() {
+        assert_eq!(json_to_string(&Json::Array(vec!()), 2).unwrap(), "[]",);
+    }
+
+    #[test]
+    fn it_prints_an_array_with_one_element_per_line_with_4_space_indent() {
+        assert_eq!(
+            json_to_string(
+                &Json::Array(vec!(Json::Null, Json::Boolean(true), Json::Boolean(false))),
+                4
+            )
+            .unwrap(),
+            "[\n    null,\n    true,\n    false\n]",
+        );
+    }
+
+    #[test]
+    fn it_prints_a_nested_array_with_increasing_levels_of_indentation() {
+        assert_eq!(
+            json_to_string(
+                &Json::Array(vec!(Json::Null, Json::Array(vec!(Json::Array(vec!()))))),
+                2
+            )
+            .unwrap(),
+            "[\n  null,\n  [\n    []\n  ]\n]",
+        );
+    }
+
+    #[test]
+    fn it_returns_to_shallower_indentation_after_printing_a_deeper_value() {
+        assert_eq!(
+            json_to_string(
+                &Json::Array(vec!(
+                    Json::Array(vec!(Json::Array(vec!(Json::Null)))),
+                    Json::Array(vec!(Json::Boolean(true)))
+                )),
+                2
+            )
+            .unwrap(),
+            "[\n  [\n    [\n      null\n    ]\n  ],\n  [\n    true\n  ]\n]",
+        );
+    }
+
+    #[test]
+    fn it_prints_an_empty_object_on_one_line() {
+        assert_eq!(
+            json_to_string(&Json::Object(Map::from([])), 2).unwrap(),
+            "{}",
+        );
+    }
+
+    #[test]
+    fn it_prints_an_object_with_one_key_per_line_with_2_space_indent() {
+        assert_eq!(
+            json_to_string(
+                &Json::Object(Map::from([
+                    ("key1".to_owned(), Json::String("value1".to_owned())),
+                    ("key2".to_owned(), Json::String("value2".to_owned()))
+                ])),
+                2
+            )
+            .unwrap(),
+            "{\n  \"key1\": \"value1\",\n  \"key2\": \"value2\"\n}",
+        );
+    }
+
+    #[test]
+    fn it_prints_an_object_with_one_key_per_line_with_4_space_indent() {
+        assert_eq!(
+            json_to_string(
+                &Json::Object(Map::from([
+                    ("key1".to_owned(), Json::String("value1".to_owned())),
+                    ("key2".to_owned(), Json::String("value2".to_owned()))
+                ])),
+                4
+            )
+            .unwrap(),
+            "{\n    \"key1\": \"value1\",\n    \"key2\": \"value2\"\n}",
+        );
+    }
+
+    #[test]
+    fn it_prints_a_nested_object_with_increasing_levels_of_indentation() {
+        assert_eq!(
+            json_to_string(
+                &Json::Object(Map::from([(
+                    "deeply".to_owned(),
+                    Json::Object(Map::from([(
+                        "nested".to_owned(),
+                        Json::Object(Map::from([(
+                            "object".to_owned(),
+                            Json::Object(Map::from([]))
+                        )]))
+                    )]))
+                )])),
+                2
+            )
+            .unwrap(),
+            "{\n  \"deeply\": {\n    \"nested\": {\n      \"object\": {}\n    }\n  }\n}",
+        );
+    }
+
+    #[test]
+    fn it_prints_comments_before_the_values_they_are_attached_to() {
+        let settings = r#"// Editor settings
+{
+    // Font
+    "editor.fontSize": 14, // Tabs
+    /* Width */
+    "editor.tabSize": 4,
+    "files.exclude": {
+        "**/.git": true
+        // more to come
+    },
+    "empty": [
+        // nothing yet
+    ]
+}
+// end"#;
+
+        let (json, comments) = parse_with_comments(settings).unwrap();
+
+        assert_eq!(
+            json_to_string_with_comments(&json, &comments, 4).unwrap(),
+            r#"// Editor settings
+{
+    // Font
+    "editor.fontSize": 14,
+    // Tabs
+    /* Width */
+    "editor.tabSize": 4,
+    "empty": [
+        // nothing yet
+    ],
+    "files.exclude": {
+        "**/.git": true
+        // more to come
+    }
+}
+// end"#
+        );
+    }
+
+    #[test]
+    fn it_prints_comments_idempotently() {
+        let settings = "[ /* a */ 1, // b\n {\"x\": /* c */ null /* d */}, [ ] ] // e";
+
+        let (json, comments) = parse_with_comments(settings).unwrap();
+        let formatted = json_to_string_with_comments(&json, &comments, 2).unwrap();
+
+        let (reparsed_json, reparsed_comments) = parse_with_comments(&formatted).unwrap();
+        assert_eq!(reparsed_json, json);
+        assert_eq!(reparsed_comments, comments);
+        assert_eq!(
+            json_to_string_with_comments(&reparsed_json, &reparsed_comments, 2).unwrap(),
+            formatted
+        );
+    }
+
+    #[test]
+    fn it_prints_compact_output_without_any_whitespace() {
+        assert_eq!(
+            json_to_compact_string(
+                &Json::object()
+                    .set("a", [1, 2].to_json())
+                    .set("b", Json::object().set("c", Json::Null).set("d", true))
+            )
+            .unwrap(),
+            r#"{"a":[1,2],"b":{"c":null,"d":true}}"#
+        );
+    }
+
+    #[test]
+    fn it_prints_compact_scalars_and_empty_containers() {
+        assert_eq!(json_to_compact_string(&Json::Null).unwrap(), "null");
+        assert_eq!(json_to_compact_string(&12.5.to_json()).unwrap(), "12.5");
+        assert_eq!(json_to_compact_string(&Json::array()).unwrap(), "[]");
+        assert_eq!(json_to_compact_string(&Json::object()).unwrap(), "{}");
+        assert_eq!(
+            json_to_compact_string(&[Json::array(), Json::object()].to_json()).unwrap(),
+            "[[],{}]"
+        );
+    }
+
+    #[test]
+    fn it_prints_compact_strings_with_the_same_escapes_as_pretty_output() {
+        let value = Json::object().set("line\nbreak", "tab\t\"quote\" \x01");
+
+        assert_eq!(
+            json_to_compact_string(&value).unwrap(),
+            r#"{"line\nbreak":"tab\t\"quote\" \u0001"}"#
+        );
+    }
+
+    #[test]
+    fn compact_output_parses_back_to_an_equal_value() {
+        let value = Json::object()
+            .set("nested", [Json::array(), [1.5, -2e30].to_json()].to_json())
+            .set("😃", Json::object().set("\\", "\u{8}\u{c}"))
+            .set("empty", Json::object());
+
+        assert_eq!(parse(&json_to_compact_string(&value).unwrap()), Ok(value));
+    }
+
+    #[test]
+    fn it_prints_with_the_default_options() {
+        assert_eq!(
+            json_to_string_with_options(
+                &Json::object().set("a", [1].to_json()),
+                &PrintOptions::default()
+            )
+            .unwrap(),
+            "{\n  \"a\": [\n    1\n  ]\n}"
+        );
+    }
+
+    #[test]
+    fn it_prints_with_a_tab_indent() {
+        assert_eq!(
+            json_to_string_with_options(
+                &Json::object().set("a", [1].to_json()),
+                &PrintOptions::new().indent("\t")
+            )
+            .unwrap(),
+            "{\n\t\"a\": [\n\t\t1\n\t]\n}"
+        );
+    }
+
+    #[test]
+    fn it_prints_with_an_empty_indent() {
+        assert_eq!(
+            json_to_string_with_options(&[1, 2].to_json(), &PrintOptions::new().indent(""))
+                .unwrap(),
+            "[\n1,\n2\n]"
+        );
+    }
+
+    #[test]
+    fn it_prints_one_line_output_with_a_space_after_each_comma_and_colon() {
+        assert_eq!(
+            json_to_one_line_string(
+                &Json::object()
+                    .set("a", 1)
+                    .set("b", [1, 2, 3].to_json())
+                    .set(
+                        "c",
+                        Json::object().set("d", [Json::array(), Json::object()].to_json())
+                    )
+            )
+            .unwrap(),
+            r#"{"a": 1, "b": [1, 2, 3], "c": {"d": [[], {}]}}"#
+        );
+    }
+
+    #[test]
+    fn it_prints_one_line_output_without_newlines_at_any_depth() {
+        let mut value = Json::object().set("text", "multi\nline");
+        for _ in 0..10 {
+            value = Json::object().set("items", [value.clone(), value].to_json());
+        }
+
+        let output =
+            json_to_string_with_options(&value, &PrintOptions::new().style(PrintStyle::OneLine))
+                .unwrap();
+        assert!(!output.contains('\n'));
+        assert_eq!(parse(&output), Ok(value));
+    }
+
+    #[test]
+    fn it_escapes_html_sensitive_characters_when_html_safe() {
+        let value = Json::object().set("</script>", "a & b\u{2028}\u{2029}\n");
+
+        assert_eq!(
+            json_to_string_with_options(&value, &PrintOptions::new().html_safe(true)).unwrap(),
+            "{\n  \"\\u003C/script\\u003E\": \"a \\u0026 b\\u2028\\u2029\\n\"\n}"
+        );
+    }
+
+    #[test]
+    fn html_safe_output_parses_back_to_an_equal_value() {
+        let value = [
+            "<!-- & -->".to_json(),
+            Json::object().set("<&>", "\u{2028}é😃\u{2029}"),
+        ]
+        .to_json();
+        let options = PrintOptions::new()
+            .html_safe(true)
+            .style(PrintStyle::OneLine);
+
+        let output = json_to_string_with_options(&value, &options).unwrap();
+        assert!(!output.contains(['<', '>', '&', '\u{2028}', '\u{2029}']));
+        assert!(output.contains("é😃"));
+        assert_eq!(parse(&output), Ok(value));
+    }
+
+    #[test]
+    fn it_prints_containers_which_fit_within_the_width_on_one_line() {
+        let value = Json::object()
+            .set("point", Json::object().set("x", 1).set("y", 2))
+            .set(
+                "names",
+                ["a long name", "another long name", "yet another"].to_json(),
+            );
+
+        assert_eq!(
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new().style(PrintStyle::Fit).width(40)
+            )
+            .unwrap(),
+            r#"{
+  "names": [
+    "a long name",
+    "another long name",
+    "yet another"
+  ],
+  "point": {"x": 1, "y": 2}
+}"#
+        );
+    }
+
+    #[test]
+    fn it_counts_the_indentation_key_and_comma_towards_the_width() {
+        let value = Json::object()
+            .set("a", [1, 2].to_json())
+            .set("b", [3, 4].to_json());
+        let fit = |width| {
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new().style(PrintStyle::Fit).width(width),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(fit(26), r#"{"a": [1, 2], "b": [3, 4]}"#);
+        assert_eq!(fit(25), "{\n  \"a\": [1, 2],\n  \"b\": [3, 4]\n}");
+        assert_eq!(fit(14), "{\n  \"a\": [1, 2],\n  \"b\": [3, 4]\n}");
+        assert_eq!(
+            fit(13),
+            "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": [3, 4]\n}"
+        );
+    }
+
+    #[test]
+    fn fit_output_is_idempotent() {
+        let mut value = Json::object()
+            .set("id", 1)
+            .set("tags", ["x", "y"].to_json());
+        for depth in 0..6 {
+            value = Json::object()
+                .set("depth", depth)
+                .set("children", [value.clone(), value].to_json());
+        }
+        let options = PrintOptions::new().style(PrintStyle::Fit);
+
+        let output = json_to_string_with_options(&value, &options).unwrap();
+        let reparsed = parse(&output).unwrap();
+        assert_eq!(reparsed, value);
+        assert_eq!(
+            json_to_string_with_options(&reparsed, &options).unwrap(),
+            output
+        );
+        assert!(output.lines().all(|line| line.chars().count() <= 80));
+    }
+
+    #[test]
+    fn it_prints_arrays_of_scalars_on_one_line() {
+        let value = Json::object()
+            .set(
+                "mixed",
+                [1.to_json(), "two".to_json(), Json::Null, true.to_json()].to_json(),
+            )
+            .set("nested", [[1, 2].to_json()].to_json())
+            .set("numbers", [1, 2, 3].to_json());
+
+        assert_eq!(
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new().scalar_arrays(ScalarArrayLayout::OneLine)
+            )
+            .unwrap(),
+            r#"{
+  "mixed": [1, "two", null, true],
+  "nested": [
+    [1, 2]
+  ],
+  "numbers": [1, 2, 3]
+}"#
+        );
+    }
+
+    #[test]
+    fn it_wraps_arrays_of_scalars_aligned_to_the_opening_bracket() {
+        let value = Json::object()
+            .set("mixed", [1.to_json(), Json::object()].to_json())
+            .set(
+                "numbers",
+                (1..=7).map(f64::from).collect::<Vec<_>>().to_json(),
+            )
+            .set("short", ["a", "b"].to_json());
+
+        assert_eq!(
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new().scalar_arrays(ScalarArrayLayout::Wrap(3))
+            )
+            .unwrap(),
+            r#"{
+  "mixed": [
+    1,
+    {}
+  ],
+  "numbers": [1, 2, 3,
+              4, 5, 6,
+              7],
+  "short": ["a", "b"]
+}"#
+        );
+    }
+
+    #[test]
+    fn it_wraps_a_top_level_array_of_scalars() {
+        let options = PrintOptions::new().scalar_arrays(ScalarArrayLayout::Wrap(2));
+
+        assert_eq!(
+            json_to_string_with_options(&[1, 2, 3].to_json(), &options).unwrap(),
+            "[1, 2,\n 3]"
+        );
+        assert_eq!(
+            json_to_string_with_options(&Json::array(), &options).unwrap(),
+            "[]"
+        );
+    }
+
+    #[test]
+    fn it_replaces_the_rest_of_the_current_container_with_a_marker_once_truncated() {
+        let value = Json::object()
+            .set("a", [1, 2, 3].to_json())
+            .set("b", Json::object().set("c", [4, 5].to_json()));
+
+        assert_eq!(
+            json_to_truncated_string(&value, &PrintOptions::new().max_output_bytes(20)).unwrap(),
+            (
+                "{\n  \"a\": [\n    1,\n    2,\n    \"…truncated…\"\n  ]\n}".to_owned(),
+                Some(Truncation { omitted_values: 5 })
+            )
+        );
+        assert_eq!(
+            json_to_truncated_string(
+                &value,
+                &PrintOptions::new()
+                    .max_output_bytes(0)
+                    .style(PrintStyle::OneLine)
+            )
+            .unwrap(),
+            (
+                r#"{"…truncated…": "…truncated…"}"#.to_owned(),
+                Some(Truncation { omitted_values: 8 })
+            )
+        );
+    }
+
+    #[test]
+    fn it_does_not_report_truncation_for_output_within_the_limit() {
+        let value = Json::object().set("a", [1, 2, 3].to_json());
+        let options = PrintOptions::new().max_output_bytes(1000);
+
+        assert_eq!(
+            json_to_truncated_string(&value, &options).unwrap(),
+            (json_to_string(&value, 2).unwrap(), None)
+        );
+    }
+
+    #[test]
+    fn truncated_output_always_parses() {
+        let mut value = Json::object()
+            .set("id", 1)
+            .set("tags", ["x", "y"].to_json());
+        for depth in 0..4 {
+            value = Json::object()
+                .set("depth", depth)
+                .set("children", [value.clone(), Json::array(), value].to_json());
+        }
+        let total_values =
+            json_to_truncated_string(&value, &PrintOptions::new().max_output_bytes(0))
+                .unwrap()
+                .1
+                .unwrap()
+                .omitted_values;
+
+        for style in [PrintStyle::Pretty, PrintStyle::OneLine, PrintStyle::Fit] {
+            for layout in [ScalarArrayLayout::Expanded, ScalarArrayLayout::Wrap(1)] {
+                for max_output_bytes in 0..600 {
+                    let options = PrintOptions::new()
+                        .style(style)
+                        .scalar_arrays(layout)
+                        .max_output_bytes(max_output_bytes);
+                    let (output, truncation) = json_to_truncated_string(&value, &options).unwrap();
+
+                    let reparsed = parse(&output).unwrap();
+                    match truncation {
+                        Some(truncation) => assert!(truncation.omitted_values <= total_values),
+                        None => assert_eq!(reparsed, value),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_writes_to_an_io_sink_with_the_same_output_as_a_string() {
+        let value = Json::object().set("a", [1, 2].to_json()).set("b", "é\n");
+        let options = PrintOptions::new().style(PrintStyle::Fit);
+
+        let mut output = Vec::new();
+        write_json(&value, &mut output, &options).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            json_to_string_with_options(&value, &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_returns_the_error_from_an_io_sink_which_fails_partway() {
+        struct FailingWriter {
+            remaining: usize,
+        }
+
+        impl io::Write for FailingWriter {
+            fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+                if self.remaining == 0 {
+                    return Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"));
+                }
+                let written = buffer.len().min(self.remaining);
+                self.remaining -= written;
+                Ok(written)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let error = write_json(
+            &[1, 2, 3].to_json(),
+            &mut FailingWriter { remaining: 5 },
+            &PrintOptions::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(error.to_string(), "pipe closed");
+    }
+
+    #[test]
+    fn it_writes_to_a_file() {
+        let path = std::env::temp_dir().join(format!(
+            "json_pretty_printer_write_test_{}.json",
+            std::process::id()
+        ));
+        let value = Json::object().set("a", [1, 2].to_json());
+
+        write_json_to_file(&value, &path, &PrintOptions::default()).unwrap();
+        let written = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path).unwrap();
 
-    if items.is_empty() {
-        output.write_str("[]")?;
-        return Ok(());
+        assert_eq!(written.unwrap(), json_to_string(&value, 2).unwrap());
     }
 
-    output.write_str("[\n")?;
+    fn deeply_nested_array(depth: usize) -> Json {
+        let mut value = Json::array();
+        for level in 0..depth {
+            value = Json::Array(vec![value, Json::Number(level as f64)]);
+        }
+        value
+    }
 
-    for (index, item) in items.into_iter().enumerate() {
-        for _ in 0..(child_level * indent) {
-            output.write_char(' ')?;
+    fn drop_deeply_nested_array(mut value: Json) {
+        while let Json::Array(mut items) = value {
+            value = if items.is_empty() {
+                Json::Null
+            } else {
+                items.swap_remove(0)
+            };
         }
+    }
 
-        display_json(item, output, indent, child_level)?;
+    #[test]
+    fn it_prints_a_deeply_nested_value_without_overflowing_the_stack() {
+        let depth = 500_000;
+        let value = deeply_nested_array(depth);
 
-        if index < items.len() - 1 {
-            output.write_char(',')?;
+        let mut expected = "[".repeat(depth);
+        expected.push_str("[]");
+        for level in 0..depth {
+            expected.push_str(&format!(",{level}]"));
         }
+        assert_eq!(json_to_compact_string(&value).unwrap(), expected);
 
-        output.write_char('\n')?;
+        let pretty = json_to_string_with_options(&value, &PrintOptions::new().indent("")).unwrap();
+        assert!(pretty.starts_with("[\n[\n[\n"));
+        assert!(pretty.ends_with(&format!("\n],\n{}\n]", depth - 1)));
+        assert_eq!(pretty.lines().count(), 3 * depth + 1);
+
+        drop_deeply_nested_array(value);
     }
 
-    for _ in 0..(level * indent) {
-        output.write_char(' ')?;
+    #[test]
+    fn it_fails_on_non_finite_numbers_by_default() {
+        for number in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            for value in [
+                number.to_json(),
+                [1.to_json(), number.to_json()].to_json(),
+                Json::object().set("a", Json::object().set("b", number)),
+            ] {
+                for style in [PrintStyle::Pretty, PrintStyle::Fit, PrintStyle::Compact] {
+                    let error =
+                        json_to_string_with_options(&value, &PrintOptions::new().style(style))
+                            .unwrap_err();
+                    let PrintError::NonFiniteNumber(reported) = error else {
+                        panic!("Expected a non-finite number error, got {error:?}");
+                    };
+                    assert_eq!(reported.to_bits(), number.to_bits());
+                }
+            }
+        }
+        assert_eq!(
+            json_to_string(&f64::INFINITY.to_json(), 2)
+                .unwrap_err()
+                .to_string(),
+            "ERROR: Unprintable JSON - inf is not a valid JSON number"
+        );
     }
 
-    output.write_char(']')?;
-    Ok(())
-}
+    #[test]
+    fn it_prints_non_finite_numbers_as_null_when_asked_to() {
+        let options = PrintOptions::new()
+            .non_finite(NonFinitePolicy::Null)
+            .style(PrintStyle::OneLine);
 
-fn display_json_object<W: Write>(
-    object: &BTreeMap<String, Json>,
-    output: &mut W,
-    indent: u64,
-    level: u64,
-) -> Result<(), fmt::Error> {
-    let child_level = level + 1;
+        assert_eq!(
+            json_to_string_with_options(&f64::NAN.to_json(), &options).unwrap(),
+            "null"
+        );
+        assert_eq!(
+            json_to_string_with_options(
+                &Json::object()
+                    .set("a", [f64::INFINITY, f64::NEG_INFINITY, 1.5].to_json())
+                    .set("b", f64::NAN),
+                &options
+            )
+            .unwrap(),
+            r#"{"a": [null, null, 1.5], "b": null}"#
+        );
+    }
 
-    if object.is_empty() {
-        output.write_str("{}")?;
-        return Ok(());
+    #[test]
+    fn it_prints_non_finite_numbers_as_strings_when_asked_to() {
+        let options = PrintOptions::new()
+            .non_finite(NonFinitePolicy::String)
+            .style(PrintStyle::OneLine);
+
+        assert_eq!(
+            json_to_string_with_options(&f64::NEG_INFINITY.to_json(), &options).unwrap(),
+            r#""-Infinity""#
+        );
+        assert_eq!(
+            json_to_string_with_options(
+                &Json::object()
+                    .set("a", [f64::INFINITY, f64::NEG_INFINITY, 1.5].to_json())
+                    .set("b", f64::NAN),
+                &options
+            )
+            .unwrap(),
+            r#"{"a": ["Infinity", "-Infinity", 1.5], "b": "NaN"}"#
+        );
     }
 
-    output.write_str("{\n")?;
+    #[test]
+    fn it_returns_non_finite_numbers_from_io_sinks_as_invalid_data() {
+        let error = write_json(
+            &f64::NAN.to_json(),
+            &mut Vec::new(),
+            &PrintOptions::default(),
+        )
+        .unwrap_err();
 
-    for (index, (key, value)) in object.into_iter().enumerate() {
-        for _ in 0..(child_level * indent) {
-            output.write_char(' ')?;
-        }
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
 
-        output.write_str(&display_json_string(&key))?;
+    fn chunk_fixtures() -> Vec<Json> {
+        vec![
+            Json::Null,
+            "a string with é, 😃 and \u{1}".to_json(),
+            Json::array(),
+            Json::object()
+                .set("names", ["ünïcödé", "😃😃😃", "plain"].to_json())
+                .set(
+                    "nested",
+                    [Json::object().set("x", 1.5), Json::array()].to_json(),
+                )
+                .set("empty", Json::object()),
+            deeply_nested_array(50),
+        ]
+    }
 
-        output.write_str(": ")?;
+    #[test]
+    fn chunks_concatenate_to_the_formatted_output() {
+        for value in chunk_fixtures() {
+            for options in [
+                PrintOptions::default(),
+                PrintOptions::new().style(PrintStyle::Fit).width(20),
+                PrintOptions::new().scalar_arrays(ScalarArrayLayout::Wrap(2)),
+            ] {
+                let expected = json_to_string_with_options(&value, &options).unwrap();
 
-        display_json(value, output, indent, child_level)?;
+                for chunk_size in [1, 2, 3, 5, 64, 8192] {
+                    let chunks: Vec<String> = JsonChunks::new(&value, &options)
+                        .chunk_size(chunk_size)
+                        .collect::<Result<_, _>>()
+                        .unwrap();
 
-        if index < object.len() - 1 {
-            output.write_char(',')?;
+                    assert_eq!(chunks.concat(), expected);
+                    for chunk in &chunks {
+                        assert!(!chunk.is_empty());
+                        assert!(chunk.len() <= chunk_size || chunk.chars().count() == 1);
+                    }
+                }
+            }
         }
+    }
 
-        output.write_char('\n')?;
+    #[test]
+    fn chunks_buffer_only_as_much_output_as_the_chunk_size_needs() {
+        let value = (0..10_000)
+            .map(|id| Json::object().set("id", id).set("name", "a name"))
+            .collect::<Vec<_>>()
+            .to_json();
+        let options = PrintOptions::default();
+        let mut chunks = JsonChunks::new(&value, &options).chunk_size(64);
+
+        let mut length = 0;
+        while let Some(chunk) = chunks.next() {
+            length += chunk.unwrap().len();
+            assert!(chunks.buffer.capacity() <= 256);
+        }
+        assert_eq!(length, json_to_string(&value, 2).unwrap().len());
     }
 
-    for _ in 0..(level * indent) {
-        output.write_char(' ')?;
+    #[test]
+    fn chunks_end_with_an_error_for_unprintable_values() {
+        let value = [1.to_json(), f64::NAN.to_json()].to_json();
+        let options = PrintOptions::default();
+        let mut results: Vec<_> = JsonChunks::new(&value, &options).chunk_size(1).collect();
+
+        assert!(matches!(
+            results.pop(),
+            Some(Err(PrintError::NonFiniteNumber(_)))
+        ));
+        assert_eq!(
+            results.into_iter().collect::<Result<String, _>>(),
+            Ok("[\n  1".to_owned())
+        );
     }
 
-    output.write_char('}')?;
-    Ok(())
-}
+    #[test]
+    fn chunks_report_truncation() {
+        let value = (0..100).collect::<Vec<_>>().to_json();
+        let options = PrintOptions::new().max_output_bytes(50);
+        let mut chunks = JsonChunks::new(&value, &options).chunk_size(10);
 
-#[cfg(test)]
-mod tests {
-    use std::collections::BTreeMap;
+        let output: String = chunks.by_ref().map(Result::unwrap).collect();
+        assert_eq!(
+            (output, chunks.truncation()),
+            json_to_truncated_string(&value, &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_prints_floats_with_a_fixed_number_of_decimals() {
+        let options = PrintOptions::new()
+            .float_format(FloatFormat::FixedDecimals(2))
+            .style(PrintStyle::OneLine);
 
-    use crate::{ast::Json, printer::json_to_string};
+        assert_eq!(
+            json_to_string_with_options(
+                &[std::f64::consts::PI, 2.5, 0.001, -1.005, 3.0, 1e20].to_json(),
+                &options
+            )
+            .unwrap(),
+            "[3.14, 2.50, 0.00, -1.00, 3, 100000000000000000000]"
+        );
+    }
 
     #[test]
-    fn it_prints_null() {
-        assert_eq!(json_to_string(&Json::Null, 2), "null");
+    fn it_prints_floats_with_a_number_of_significant_digits() {
+        let options = PrintOptions::new()
+            .float_format(FloatFormat::SignificantDigits(3))
+            .style(PrintStyle::OneLine);
+
+        assert_eq!(
+            json_to_string_with_options(
+                &[
+                    std::f64::consts::PI,
+                    2.5,
+                    0.000123456,
+                    -1234.5678,
+                    3.0,
+                    0.1 + 0.2
+                ]
+                .to_json(),
+                &options
+            )
+            .unwrap(),
+            "[3.14, 2.5, 0.000123, -1230, 3, 0.3]"
+        );
     }
 
     #[test]
-    fn it_prints_booleans() {
-        assert_eq!(json_to_string(&Json::Boolean(true), 2), "true");
-        assert_eq!(json_to_string(&Json::Boolean(false), 2), "false");
+    fn formatted_floats_always_parse() {
+        let numbers = [
+            0.5,
+            -0.5,
+            1e-300,
+            1.7976931348623157e300 / 3.0,
+            123.456,
+            -0.0001,
+        ];
+
+        for format in [
+            FloatFormat::Shortest,
+            FloatFormat::FixedDecimals(0),
+            FloatFormat::FixedDecimals(5),
+            FloatFormat::SignificantDigits(0),
+            FloatFormat::SignificantDigits(17),
+        ] {
+            let options = PrintOptions::new().float_format(format);
+            for number in numbers {
+                let output = json_to_string_with_options(&number.to_json(), &options).unwrap();
+                assert!(!output.contains(['e', 'E']), "{output}");
+                assert!(!output.starts_with('.') && !output.starts_with("-."));
+                assert!(parse(&output).is_ok(), "{output}");
+            }
+        }
     }
 
     #[test]
-    fn it_prints_numbers() {
-        assert_eq!(json_to_string(&Json::Number(0.0), 2), "0");
-        assert_eq!(json_to_string(&Json::Number(234.0), 2), "234");
-        assert_eq!(json_to_string(&Json::Number(-234.0), 2), "-234");
-        assert_eq!(json_to_string(&Json::Number(123.456), 2), "123.456");
-        assert_eq!(json_to_string(&Json::Number(10000.00001), 2), "10000.00001");
+    fn it_prints_keys_in_the_chosen_order() {
+        let value = parse(r#"{"item10": 1, "item2": 2, "Item1": 3, "item1": 4}"#).unwrap();
+        let print = |key_order| {
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .key_order(key_order)
+                    .style(PrintStyle::Compact),
+            )
+            .unwrap()
+        };
+
         assert_eq!(
-            json_to_string(&Json::Number(0.00000000001), 2),
-            "0.00000000001"
+            print(KeyOrder::Document),
+            r#"{"item10":1,"item2":2,"Item1":3,"item1":4}"#
         );
         assert_eq!(
-            json_to_string(&Json::Number(2405946039048539.0), 2),
-            "2405946039048539"
+            print(KeyOrder::Lexicographic),
+            r#"{"Item1":3,"item1":4,"item10":1,"item2":2}"#
+        );
+        assert_eq!(
+            print(KeyOrder::Natural),
+            r#"{"Item1":3,"item1":4,"item2":2,"item10":1}"#
         );
     }
 
     #[test]
-    fn it_prints_ascii_strings() {
+    fn it_applies_the_key_order_at_every_level() {
+        let value = parse(r#"{"b": {"z": 1, "y": [{"d": 1, "c": 2}]}, "a": null}"#).unwrap();
+
         assert_eq!(
-            json_to_string(&Json::String("This is a string.".to_owned()), 2),
-            r#""This is a string.""#
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .key_order(KeyOrder::Document)
+                    .style(PrintStyle::Compact)
+            )
+            .unwrap(),
+            r#"{"b":{"z":1,"y":[{"d":1,"c":2}]},"a":null}"#
         );
     }
 
     #[test]
-    fn it_prints_non_ascii_strings() {
+    fn natural_cmp_orders_tricky_pairs() {
+        use std::cmp::Ordering::{Equal, Greater, Less};
+
+        for (a, b, expected) in [
+            ("item2", "item10", Less),
+            ("item10", "item10", Equal),
+            ("item1", "item1a", Less),
+            ("item01", "item1", Less),
+            ("item1", "item01", Greater),
+            ("item001", "item01", Less),
+            ("item01", "item2", Less),
+            ("2", "10", Less),
+            ("10", "9", Greater),
+            ("0", "00", Less),
+            ("007", "7", Less),
+            ("99999999999999999999999", "100000000000000000000000", Less),
+            ("a1b2", "a1b10", Less),
+            ("A", "a", Less),
+            ("a", "B", Less),
+            ("Item2", "item10", Less),
+            ("item", "item1", Less),
+            ("1abc", "abc", Less),
+            ("", "a", Less),
+            ("x-1", "x1", Greater),
+        ] {
+            assert_eq!(natural_cmp(a, b), expected, "{a} vs {b}");
+            assert_eq!(natural_cmp(b, a), expected.reverse(), "{b} vs {a}");
+        }
+    }
+
+    #[test]
+    fn it_prints_keys_in_a_custom_order_at_every_level() {
+        let value = parse(
+            r#"{"scripts": {"test": 1, "build": 2}, "version": "1.0", "author": null,
+                "name": "crate", "dependencies": {"name": "dependency", "alpha": 3}}"#,
+        )
+        .unwrap();
+        let options = PrintOptions::new()
+            .key_order(KeyOrder::prioritized(
+                ["name", "version", "description"],
+                KeyOrder::Lexicographic,
+            ))
+            .style(PrintStyle::Compact);
+
         assert_eq!(
-            json_to_string(&Json::String("😃 or 🙁?".to_owned()), 2),
-            r#""😃 or 🙁?""#
+            json_to_string_with_options(&value, &options).unwrap(),
+            r#"{"name":"crate","version":"1.0","author":null,"dependencies":{"name":"dependency","alpha":3},"scripts":{"build":2,"test":1}}"#
         );
     }
 
     #[test]
-    fn it_prints_a_string_with_an_escaped_double_quote() {
+    fn keys_which_a_custom_order_finds_equal_keep_their_document_order() {
+        let value = parse(r#"{"bb": 1, "a": 2, "ccc": 3, "dd": 4, "e": 5}"#).unwrap();
+        let by_length = KeyOrder::custom(|a, b| a.len().cmp(&b.len()));
+
         assert_eq!(
-            json_to_string(&Json::String("double \" quote".to_owned()), 2),
-            r#""double \" quote""#
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .key_order(by_length.clone())
+                    .style(PrintStyle::Compact)
+            )
+            .unwrap(),
+            r#"{"a":2,"e":5,"bb":1,"dd":4,"ccc":3}"#
         );
+        assert_eq!(by_length, by_length.clone());
+        assert_ne!(by_length, KeyOrder::custom(|a, b| a.len().cmp(&b.len())));
     }
 
     #[test]
-    fn it_prints_a_string_with_an_escaped_backslash() {
+    fn it_elides_the_contents_of_containers_at_the_max_depth() {
+        let value = parse(r#"{"a": [1, {"b": []}], "c": {"d": {"e": 1}}, "f": 2}"#).unwrap();
+        let print = |max_depth| {
+            json_to_string_with_options(&value, &PrintOptions::new().max_depth(max_depth)).unwrap()
+        };
+
+        assert_eq!(print(0), r#"{"…": "…"}"#);
         assert_eq!(
-            json_to_string(&Json::String("back \\ slash".to_owned()), 2),
-            r#""back \\ slash""#
+            print(1),
+            r#"{
+  "a": ["…"],
+  "c": {"…": "…"},
+  "f": 2
+}"#
+        );
+        assert_eq!(
+            print(2),
+            r#"{
+  "a": [
+    1,
+    {"…": "…"}
+  ],
+  "c": {
+    "d": {"…": "…"}
+  },
+  "f": 2
+}"#
+        );
+        assert_eq!(
+            print(3),
+            json_to_string_with_options(&value, &PrintOptions::new()).unwrap()
+        );
+        assert_eq!(
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new().max_depth(1).style(PrintStyle::Compact)
+            )
+            .unwrap(),
+            r#"{"a":["…"],"c":{"…":"…"},"f":2}"#
         );
     }
 
     #[test]
-    fn it_prints_a_string_with_a_solidus_without_escaping_it() {
+    fn it_keeps_empty_containers_and_scalars_at_the_max_depth() {
         assert_eq!(
-            json_to_string(&Json::String("forward / slash".to_owned()), 2),
-            r#""forward / slash""#
+            json_to_string_with_options(
+                &parse(r#"[[], {}, 1, "two"]"#).unwrap(),
+                &PrintOptions::new().max_depth(1).style(PrintStyle::OneLine)
+            )
+            .unwrap(),
+            r#"[[], {}, 1, "two"]"#
+        );
+        assert_eq!(
+            json_to_string_with_options(&1.to_json(), &PrintOptions::new().max_depth(0)).unwrap(),
+            "1"
         );
     }
 
     #[test]
-    fn it_prints_a_string_with_an_escaped_backspace() {
+    fn it_fails_on_containers_at_the_max_depth_with_the_error_policy() {
+        let options = PrintOptions::new()
+            .max_depth(2)
+            .max_depth_policy(MaxDepthPolicy::Error);
+
         assert_eq!(
-            json_to_string(&Json::String("back \x08 space".to_owned()), 2),
-            r#""back \b space""#
+            json_to_string_with_options(&parse(r#"[[1], [[]]]"#).unwrap(), &options),
+            Ok("[\n  [\n    1\n  ],\n  [\n    []\n  ]\n]".to_owned())
+        );
+        assert_eq!(
+            json_to_string_with_options(&parse(r#"[[1], [[2]]]"#).unwrap(), &options),
+            Err(PrintError::MaxDepthExceeded(2))
         );
     }
 
     #[test]
-    fn it_prints_a_string_with_an_escaped_formfeed() {
+    fn it_shortens_long_string_values_but_not_keys() {
+        let value = parse(r#"{"a long key": ["short", "exactly 10", "a bit longer", "é😀\n\"x"]}"#)
+            .unwrap();
+        let output = json_to_string_with_options(
+            &value,
+            &PrintOptions::new()
+                .max_string_length(10)
+                .style(PrintStyle::Compact),
+        )
+        .unwrap();
+
         assert_eq!(
-            json_to_string(&Json::String("form \x0C feed".to_owned()), 2),
-            r#""form \f feed""#,
+            output,
+            r#"{"a long key":["short","exactly 10","a bit long… (+2 chars)","é😀\n\"x"]}"#
         );
+        assert!(parse(&output).is_ok());
     }
 
     #[test]
-    fn it_prints_a_string_with_an_escaped_linefeed() {
+    fn it_shortens_strings_between_characters_and_escapes() {
+        let value = ["é😀\n\"x"].to_json();
+        let print = |max_string_length| {
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .max_string_length(max_string_length)
+                    .style(PrintStyle::Compact),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(print(0), r#"["… (+5 chars)"]"#);
+        assert_eq!(print(2), r#"["é😀… (+3 chars)"]"#);
+        assert_eq!(print(3), r#"["é😀\n… (+2 chars)"]"#);
+        assert_eq!(print(4), r#"["é😀\n\"… (+1 chars)"]"#);
+        for max_string_length in 0..6 {
+            assert!(parse(&print(max_string_length)).is_ok());
+        }
+    }
+
+    #[test]
+    fn it_spaces_colons_and_commas_as_configured() {
+        let value = parse(r#"{"a": [1, {"b": null}], "c": {}}"#).unwrap();
+
+        for space_before_colon in [false, true] {
+            for space_after_colon in [false, true] {
+                for space_after_comma in [false, true] {
+                    let colon = match (space_before_colon, space_after_colon) {
+                        (false, false) => ":",
+                        (false, true) => ": ",
+                        (true, false) => " :",
+                        (true, true) => " : ",
+                    };
+                    let comma = if space_after_comma { ", " } else { "," };
+                    let options = PrintOptions::new()
+                        .space_before_colon(space_before_colon)
+                        .space_after_colon(space_after_colon)
+                        .space_after_comma(space_after_comma);
+                    let print = |style| {
+                        json_to_string_with_options(&value, &options.clone().style(style)).unwrap()
+                    };
+
+                    assert_eq!(
+                        print(PrintStyle::Pretty),
+                        format!(
+                            "{{\n  \"a\"{colon}[\n    1,\n    {{\n      \"b\"{colon}null\n    }}\n  ],\n  \"c\"{colon}{{}}\n}}"
+                        )
+                    );
+                    for style in [PrintStyle::OneLine, PrintStyle::Compact] {
+                        assert_eq!(
+                            print(style),
+                            format!("{{\"a\"{colon}[1{comma}{{\"b\"{colon}null}}]{comma}\"c\"{colon}{{}}}}")
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_spaces_scalar_arrays_and_placeholders_as_configured() {
+        let value = parse(r#"{"a": [1, 2, 3], "b": {"c": {"d": 1}}}"#).unwrap();
+
         assert_eq!(
-            json_to_string(&Json::String("line \n feed".to_owned()), 2),
-            r#""line \n feed""#,
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .scalar_arrays(ScalarArrayLayout::OneLine)
+                    .space_before_colon(true)
+                    .space_after_comma(false)
+                    .max_depth(2)
+            )
+            .unwrap(),
+            "{\n  \"a\" : [1,2,3],\n  \"b\" : {\n    \"c\" : {\"…\" : \"…\"}\n  }\n}"
+        );
+        assert_eq!(
+            json_to_string_with_options(&value, &PrintOptions::new().style(PrintStyle::Compact))
+                .unwrap(),
+            r#"{"a":[1,2,3],"b":{"c":{"d":1}}}"#
         );
     }
 
     #[test]
-    fn it_prints_a_string_with_an_escaped_carriage_return() {
+    fn display_uses_the_alternate_flag_and_width_of_the_formatter() {
+        let value = parse(r#"{"a": [1, {"b": null}]}"#).unwrap();
+
         assert_eq!(
-            json_to_string(&Json::String("carriage \r return".to_owned()), 2),
-            r#""carriage \r return""#,
+            format!("{value}"),
+            "{\n  \"a\": [\n    1,\n    {\n      \"b\": null\n    }\n  ]\n}"
+        );
+        assert_eq!(format!("{value:#}"), r#"{"a":[1,{"b":null}]}"#);
+        assert_eq!(
+            format!("{value:4}"),
+            "{\n    \"a\": [\n        1,\n        {\n            \"b\": null\n        }\n    ]\n}"
         );
     }
 
     #[test]
-    fn it_prints_a_string_with_an_escaped_tab() {
+    fn display_writes_non_finite_numbers_as_null() {
+        let value = Json::Array(vec![Json::Number(f64::NAN), Json::Number(f64::INFINITY)]);
+
+        assert_eq!(format!("{value:#}"), "[null,null]");
+        assert_eq!(format!("{value:1}"), "[\n null,\n null\n]");
+        assert!(json_to_string(&value, 2).is_err());
+    }
+
+    #[test]
+    fn it_aligns_the_colons_of_each_object() {
+        let value = parse(
+            r#"{"name": "x", "replicas": 3, "é😀": {"a": 1, "longer": [], "b": {}}, "single": {"only": 1}}"#,
+        )
+        .unwrap();
+
         assert_eq!(
-            json_to_string(&Json::String("horizontal \t tab".to_owned()), 2),
-            r#""horizontal \t tab""#,
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .align_keys(true)
+                    .key_order(KeyOrder::Document)
+            )
+            .unwrap(),
+            r#"{
+  "name"    : "x",
+  "replicas": 3,
+  "é😀"      : {
+    "a"     : 1,
+    "longer": [],
+    "b"     : {}
+  },
+  "single"  : {
+    "only": 1
+  }
+}"#
         );
     }
 
     #[test]
-    fn it_prints_a_string_with_an_escaped_control_characters() {
+    fn it_does_not_align_keys_on_one_line_or_by_default() {
+        let value = parse(r#"{"a": 1, "bbb": {}}"#).unwrap();
+
         assert_eq!(
-            json_to_string(&Json::String("null \x00 character".to_owned()), 2),
-            r#""null \u0000 character""#,
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .align_keys(true)
+                    .style(PrintStyle::OneLine)
+            )
+            .unwrap(),
+            r#"{"a": 1, "bbb": {}}"#
         );
         assert_eq!(
-            json_to_string(&Json::String("unit \x1F separator".to_owned()), 2),
-            r#""unit \u001F separator""#,
+            json_to_string(&value, 2).unwrap(),
+            "{\n  \"a\": 1,\n  \"bbb\": {}\n}"
         );
     }
 
     #[test]
-    fn it_prints_an_array_with_one_element_per_line_with_2_space_indent() {
+    fn it_prints_json5_features_when_enabled() {
+        let value = parse(
+            r#"{"name": "it's \"quoted\"", "$id_2": [1, {}], "not-an-identifier": {"": null}, "2d": []}"#,
+        )
+        .unwrap();
+        let options = PrintOptions::new()
+            .unquoted_keys(true)
+            .single_quotes(true)
+            .trailing_commas(true)
+            .key_order(KeyOrder::Document);
+
         assert_eq!(
-            json_to_string(
-                &Json::Array(vec!(Json::Null, Json::Boolean(true), Json::Boolean(false))),
-                2
-            ),
-            "[\n  null,\n  true,\n  false\n]",
+            json_to_string_with_options(&value, &options).unwrap(),
+            r#"{
+  name: 'it\'s "quoted"',
+  $id_2: [
+    1,
+    {},
+  ],
+  'not-an-identifier': {
+    '': null,
+  },
+  '2d': [],
+}"#
+        );
+        assert_eq!(
+            json_to_string_with_options(&value, &options.style(PrintStyle::OneLine)).unwrap(),
+            r#"{name: 'it\'s "quoted"', $id_2: [1, {}], 'not-an-identifier': {'': null}, '2d': []}"#
         );
     }
 
     #[test]
-    fn it_prints_an_empty_array_on_one_line() {
-        assert_eq!(json_to_string(&Json::Array(vec!()), 2), "[]",);
+    fn json5_output_parses_back_to_the_same_value_with_the_json5_options() {
+        let value = parse(
+            r#"{"name": "it's \"quoted\"", "$id_2": [1, {"'": "\\'"}], "not-an-identifier": {"": null}, "2d": [[]]}"#,
+        )
+        .unwrap();
+        let parse_options = ParseOptions {
+            allow_unquoted_keys: true,
+            allow_single_quotes: true,
+            allow_trailing_commas: true,
+            ..ParseOptions::default()
+        };
+
+        for style in [PrintStyle::Pretty, PrintStyle::OneLine, PrintStyle::Compact] {
+            let printed = json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .unquoted_keys(true)
+                    .single_quotes(true)
+                    .trailing_commas(true)
+                    .style(style),
+            )
+            .unwrap();
+
+            assert_eq!(
+                parse_with_options(&printed, &parse_options),
+                Ok(value.clone())
+            );
+        }
     }
 
     #[test]
-    fn it_prints_an_array_with_one_element_per_line_with_4_space_indent() {
+    fn it_toggles_each_json5_feature_separately() {
+        let value = parse(r#"{"a": ["b"]}"#).unwrap();
+        let print = |options: PrintOptions| {
+            json_to_string_with_options(&value, &options.style(PrintStyle::Fit)).unwrap()
+        };
+
+        assert_eq!(print(PrintOptions::new()), r#"{"a": ["b"]}"#);
         assert_eq!(
-            json_to_string(
-                &Json::Array(vec!(Json::Null, Json::Boolean(true), Json::Boolean(false))),
-                4
-            ),
-            "[\n    null,\n    true,\n    false\n]",
+            print(PrintOptions::new().unquoted_keys(true)),
+            r#"{a: ["b"]}"#
+        );
+        assert_eq!(
+            print(PrintOptions::new().single_quotes(true)),
+            r#"{'a': ['b']}"#
+        );
+        assert_eq!(
+            print(PrintOptions::new().trailing_commas(true).width(5)),
+            "{\n  \"a\": [\n    \"b\",\n  ],\n}"
         );
     }
 
+    fn strip_escape_codes(output: &str) -> String {
+        let mut stripped = String::new();
+        let mut rest = output;
+        while let Some(start) = rest.find('\x1b') {
+            stripped.push_str(&rest[..start]);
+            let end = rest[start..].find('m').unwrap();
+            rest = &rest[start + end + 1..];
+        }
+        stripped.push_str(rest);
+        stripped
+    }
+
     #[test]
-    fn it_prints_a_nested_array_with_increasing_levels_of_indentation() {
+    fn it_colors_each_kind_of_token_with_the_theme() {
+        let value = parse(r#"{"a": [1, "b", true, null]}"#).unwrap();
+        let theme = Theme {
+            punctuation: Some(Style::default().dim()),
+            boolean: None,
+            number: Some(Style::color(Color::Rgb(1, 2, 3))),
+            ..Theme::default()
+        };
+
         assert_eq!(
-            json_to_string(
-                &Json::Array(vec!(Json::Null, Json::Array(vec!(Json::Array(vec!()))))),
-                2
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new().theme(theme).style(PrintStyle::Compact)
+            )
+            .unwrap(),
+            "\x1b[2m{\x1b[0m\x1b[1;34m\"a\"\x1b[0m\x1b[2m:\x1b[0m\x1b[2m[\x1b[0m\
+             \x1b[38;2;1;2;3m1\x1b[0m\x1b[2m,\x1b[0m\x1b[32m\"b\"\x1b[0m\x1b[2m,\x1b[0m\
+             true\x1b[2m,\x1b[0m\x1b[90mnull\x1b[0m\x1b[2m]\x1b[0m\x1b[2m}\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn colored_output_without_escape_codes_matches_the_plain_output() {
+        let all_styled = Theme {
+            punctuation: Some(Style::color(Color::Ansi256(200)).bold()),
+            ..Theme::default()
+        };
+        let option_sets = [
+            PrintOptions::new(),
+            PrintOptions::new().style(PrintStyle::OneLine),
+            PrintOptions::new().style(PrintStyle::Compact),
+            PrintOptions::new().style(PrintStyle::Fit).width(20),
+            PrintOptions::new().scalar_arrays(ScalarArrayLayout::Wrap(2)),
+            PrintOptions::new().align_keys(true).trailing_commas(true),
+            PrintOptions::new().max_depth(1),
+            PrintOptions::new().max_output_bytes(20),
+        ];
+
+        for value in chunk_fixtures() {
+            for options in &option_sets {
+                let plain = json_to_string_with_options(&value, options).unwrap();
+                for theme in [Theme::default(), all_styled.clone(), Theme::plain()] {
+                    let colored =
+                        json_to_string_with_options(&value, &options.clone().theme(theme)).unwrap();
+                    assert_eq!(strip_escape_codes(&colored), plain);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_returns_stats_matching_the_output() {
+        for (json, options, max_depth) in [
+            ("1", PrintOptions::new(), 0),
+            ("[]", PrintOptions::new(), 1),
+            (r#"{"a": {}}"#, PrintOptions::new(), 2),
+            (r#"["é😀", {"ключ": [[]]}]"#, PrintOptions::new(), 4),
+            (
+                r#"["é😀", {"ключ": [[]]}]"#,
+                PrintOptions::new().style(PrintStyle::OneLine),
+                4,
             ),
-            "[\n  null,\n  [\n    []\n  ]\n]",
+            (
+                r#"[[1, 2], [3]]"#,
+                PrintOptions::new().scalar_arrays(ScalarArrayLayout::OneLine),
+                2,
+            ),
+            (r#"[[[1]]]"#, PrintOptions::new().max_depth(1), 2),
+        ] {
+            let value = parse(json).unwrap();
+            let mut output = Vec::new();
+            let stats = write_json(&value, &mut output, &options).unwrap();
+            let output = String::from_utf8(output).unwrap();
+
+            assert_eq!(
+                stats,
+                PrintStats {
+                    bytes: output.len(),
+                    lines: output.lines().count(),
+                    max_depth,
+                },
+                "{output}"
+            );
+
+            let mut displayed = String::new();
+            assert_eq!(
+                display_json_with_options(&value, &mut displayed, &options),
+                Ok(stats)
+            );
+            assert_eq!(displayed, output);
+        }
+    }
+
+    #[test]
+    fn it_cuddles_closing_brackets_after_single_line_items() {
+        let value = parse(
+            r#"{"tags": ["a", "b"], "people": [{"name": "x", "ids": [1, 2]}, {"empty": {}}], "none": [], "nested": [[1], {"a": {"b": 1}}]}"#,
+        )
+        .unwrap();
+        let options = PrintOptions::new()
+            .cuddle_brackets(true)
+            .key_order(KeyOrder::Document);
+        let output = json_to_string_with_options(&value, &options).unwrap();
+
+        assert_eq!(
+            output,
+            r#"{
+  "tags": [
+    "a",
+    "b" ],
+  "people": [
+    {
+      "name": "x",
+      "ids": [
+        1,
+        2 ]
+    },
+    {
+      "empty": {} }
+  ],
+  "none": [],
+  "nested": [
+    [
+      1 ],
+    {
+      "a": {
+        "b": 1 }
+    }
+  ]
+}"#
+        );
+        assert_eq!(
+            json_to_string_with_options(&parse(&output).unwrap(), &options).unwrap(),
+            output
         );
     }
 
     #[test]
-    fn it_prints_an_empty_object_on_one_line() {
-        assert_eq!(json_to_string(&Json::Object(BTreeMap::from([])), 2), "{}",);
+    fn it_cuddles_brackets_only_after_single_line_scalar_arrays() {
+        let value = parse(r#"{"a": [1, 2, 3], "b": [[1, 2, 3]]}"#).unwrap();
+        let print = |layout| {
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .cuddle_brackets(true)
+                    .scalar_arrays(layout),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            print(ScalarArrayLayout::OneLine),
+            "{\n  \"a\": [1, 2, 3],\n  \"b\": [\n    [1, 2, 3] ]\n}"
+        );
+        assert_eq!(
+            print(ScalarArrayLayout::Wrap(2)),
+            "{\n  \"a\": [1, 2,\n        3],\n  \"b\": [\n    [1, 2,\n     3]\n  ]\n}"
+        );
     }
 
     #[test]
-    fn it_prints_an_object_with_one_key_per_line_with_2_space_indent() {
+    fn it_normalizes_numbers_when_asked() {
+        for (json, plain, normalized) in [
+            ("-0", "-0", "0"),
+            ("-0.0", "-0", "0"),
+            ("0", "0", "0"),
+            ("1e+5", "100000", "100000"),
+            ("2.50", "2.5", "2.5"),
+            ("1E2", "100", "100"),
+            ("-1.5", "-1.5", "-1.5"),
+        ] {
+            let value = parse(json).unwrap();
+            assert_eq!(json_to_string(&value, 2).unwrap(), plain, "{json}");
+            assert_eq!(
+                json_to_string_with_options(&value, &PrintOptions::new().normalized_numbers())
+                    .unwrap(),
+                normalized,
+                "{json}"
+            );
+        }
+
         assert_eq!(
-            json_to_string(
-                &Json::Object(BTreeMap::from([
-                    ("key1".to_owned(), Json::String("value1".to_owned())),
-                    ("key2".to_owned(), Json::String("value2".to_owned()))
-                ])),
-                2
-            ),
-            "{\n  \"key1\": \"value1\",\n  \"key2\": \"value2\"\n}",
+            json_to_string_with_options(
+                &(-0.0).to_json(),
+                &PrintOptions::new()
+                    .float_format(FloatFormat::FixedDecimals(2))
+                    .number_normalization(NumberNormalization {
+                        fold_negative_zero: true,
+                        ..NumberNormalization::default()
+                    })
+            )
+            .unwrap(),
+            "0"
         );
     }
 
     #[test]
-    fn it_prints_an_object_with_one_key_per_line_with_4_space_indent() {
+    fn normalize_number_rewrites_each_part_of_the_text_separately() {
+        let none = NumberNormalization::default();
+        let all = NumberNormalization::all();
+        let only = |change: fn(&mut NumberNormalization)| {
+            let mut normalization = none;
+            change(&mut normalization);
+            normalization
+        };
+
+        for (text, normalization, expected) in [
+            ("-0", all, "0"),
+            ("-0.000", all, "0"),
+            ("-0E+0", all, "0e0"),
+            ("1E+2", all, "1e2"),
+            ("10.0", all, "10"),
+            ("10.50", all, "10.50"),
+            ("1.0e-7", all, "1e-7"),
+            ("-0.0", none, "-0.0"),
+            ("-0.0", only(|n| n.fold_negative_zero = true), "0.0"),
+            ("-0.0", only(|n| n.strip_zero_fraction = true), "-0"),
+            ("1E+2", only(|n| n.lowercase_exponent = true), "1e+2"),
+            ("1E+2", only(|n| n.strip_exponent_plus = true), "1E2"),
+            ("-10", all, "-10"),
+        ] {
+            assert_eq!(normalize_number(text, normalization), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn it_caps_the_indentation_at_the_max_indent_levels() {
+        let value = deeply_nested_array(30);
+        let output = json_to_string_with_options(
+            &value,
+            &PrintOptions::new().max_indent_levels(8).width(40),
+        )
+        .unwrap();
+        drop_deeply_nested_array(value);
+
+        let leading_spaces = |line: &str| line.len() - line.trim_start().len();
+        assert_eq!(output.lines().map(leading_spaces).max(), Some(16));
+        assert_eq!(output.lines().count(), 91);
+        assert_eq!(output.matches('[').count(), 31);
+        assert_eq!(output.matches(']').count(), 31);
+
         assert_eq!(
-            json_to_string(
-                &Json::Object(BTreeMap::from([
-                    ("key1".to_owned(), Json::String("value1".to_owned())),
-                    ("key2".to_owned(), Json::String("value2".to_owned()))
-                ])),
-                4
-            ),
-            "{\n    \"key1\": \"value1\",\n    \"key2\": \"value2\"\n}",
+            json_to_string_with_options(
+                &parse(r#"[[[[1, 2]]], 3]"#).unwrap(),
+                &PrintOptions::new().max_indent_levels(1)
+            )
+            .unwrap(),
+            "[\n  [\n  [\n  [\n  1,\n  2\n  ]\n  ]\n  ],\n  3\n]"
         );
     }
 
     #[test]
-    fn it_prints_a_nested_object_with_increasing_levels_of_indentation() {
+    fn it_prints_integers_beyond_53_bits_as_strings_when_asked() {
+        let value = parse(
+            "[9007199254740991, 9007199254740992, 9007199254740993, -9007199254740993, \
+             9223372036854775807, 12345, 1.5, 9007199254740991.5, 1e17]",
+        )
+        .unwrap();
+
         assert_eq!(
-            json_to_string(
-                &Json::Object(BTreeMap::from([(
-                    "deeply".to_owned(),
-                    Json::Object(BTreeMap::from([(
-                        "nested".to_owned(),
-                        Json::Object(BTreeMap::from([(
-                            "object".to_owned(),
-                            Json::Object(BTreeMap::from([]))
-                        )]))
-                    )]))
-                )])),
-                2
-            ),
-            "{\n  \"deeply\": {\n    \"nested\": {\n      \"object\": {}\n    }\n  }\n}",
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .big_int_as_string(true)
+                    .style(PrintStyle::Compact)
+            )
+            .unwrap(),
+            r#"[9007199254740991,"9007199254740992","9007199254740993","-9007199254740993","9223372036854775807",12345,1.5,9007199254740992,100000000000000000]"#
+        );
+        assert_eq!(
+            json_to_string_with_options(
+                &value,
+                &PrintOptions::new()
+                    .big_int_as_string(true)
+                    .single_quotes(true)
+                    .style(PrintStyle::Compact)
+            )
+            .unwrap(),
+            "[9007199254740991,'9007199254740992','9007199254740993','-9007199254740993',\
+             '9223372036854775807',12345,1.5,9007199254740992,100000000000000000]"
+        );
+        assert_eq!(
+            json_to_string_with_options(
+                &f64::INFINITY.to_json(),
+                &PrintOptions::new()
+                    .big_int_as_string(true)
+                    .non_finite(NonFinitePolicy::Null)
+            )
+            .unwrap(),
+            "null"
+        );
+    }
+
+    #[test]
+    fn it_prints_integers_beyond_53_bits_exactly() {
+        let text = "[9007199254740993,-9007199254740993,9223372036854775807,\
+                    -170141183460469231731687303715884105728,123456789012345678901234567890]";
+        let value = parse(text).unwrap();
+
+        assert_eq!(json_to_compact_string(&value).unwrap(), text);
+        assert_eq!(
+            json_to_canonical_string(&value).unwrap(),
+            "[9007199254740992,-9007199254740992,9223372036854776000,\
+             -1.7014118346046923e+38,1.2345678901234568e+29]"
+        );
+    }
+
+    #[test]
+    fn large_whole_floats_print_every_digit_so_they_parse_back_the_same() {
+        for number in [2f64.powi(60), -2e30, 1e300, f64::MAX] {
+            let printed = json_to_string(&number.to_json(), 2).unwrap();
+
+            assert_eq!(parse(&printed), Ok(number.to_json()), "{printed}");
+        }
+        assert_eq!(
+            json_to_string(&2f64.powi(60).to_json(), 2).unwrap(),
+            "1152921504606846976"
         );
     }
 }
@@ -0,0 +1,340 @@
+//! Selecting values with a subset of JSONPath: `$` for the root, `.key` and
+//! `['key']` for properties, `[0]` for items, `[1:3]` for slices of items,
+//! `.*` and `[*]` for every child, and `..` for every descendant.
+
+use std::{error, fmt};
+
+use crate::ast::Json;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryError {
+    pub message: String,
+    /// The byte offset into the query where the problem was found.
+    pub position: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_fmt(format_args!(
+            "ERROR: Invalid JSON query - {} at position {}",
+            self.message, self.position
+        ))
+    }
+}
+
+impl error::Error for QueryError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Selector {
+    Key(String),
+    /// An index, counting back from the end if negative.
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Wildcard,
+}
+
+/// One step of a query, applied to the values found by the steps before it.
+#[derive(Clone, Debug, PartialEq)]
+struct Step {
+    /// Apply the selector to every descendant of each value, as well as the
+    /// value itself.
+    descendants: bool,
+    selector: Selector,
+}
+
+impl Json {
+    /// Finds the values a JSONPath query selects, in document order. Only
+    /// part of JSONPath is supported: `$` for the root, `.key` and `['key']`
+    /// for properties, `[0]` and `[-1]` for items, `[1:3]` for slices of
+    /// items, `.*` and `[*]` for every child, and `..` before any of these to
+    /// select from every descendant.
+    pub fn query(&self, query: &str) -> Result<Vec<&Json>, QueryError> {
+        let steps = parse_query(query)?;
+
+        let mut selected = vec![self];
+        for step in steps {
+            let mut next = Vec::new();
+            for value in selected {
+                if step.descendants {
+                    value.walk(|_, descendant| select(descendant, &step.selector, &mut next));
+                } else {
+                    select(value, &step.selector, &mut next);
+                }
+            }
+            selected = next;
+        }
+        Ok(selected)
+    }
+}
+
+fn select<'a>(value: &'a Json, selector: &Selector, selected: &mut Vec<&'a Json>) {
+    match (value, selector) {
+        (Json::Object(properties), Selector::Key(key)) => selected.extend(properties.get(key)),
+        (Json::Object(properties), Selector::Wildcard) => selected.extend(properties.values()),
+        (Json::Array(items), Selector::Wildcard) => selected.extend(items),
+        (Json::Array(items), Selector::Index(index)) => {
+            if let Some(index) = from_end(*index, items.len()) {
+                selected.extend(items.get(index));
+            }
+        }
+        (Json::Array(items), Selector::Slice(start, end)) => {
+            let bound = |bound: Option<i64>, default| {
+                bound.map_or(default, |bound| {
+                    from_end(bound, items.len()).unwrap_or(if bound < 0 { 0 } else { items.len() })
+                })
+            };
+            let (start, end) = (bound(*start, 0), bound(*end, items.len()));
+            if start < end {
+                selected.extend(&items[start..end]);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Converts an index which counts back from the end if negative into one
+/// counting from the start, if it is in range.
+fn from_end(index: i64, length: usize) -> Option<usize> {
+    let index = if index < 0 {
+        length as i64 + index
+    } else {
+        index
+    };
+    usize::try_from(index).ok().filter(|&index| index < length)
+}
+
+fn parse_query(query: &str) -> Result<Vec<Step>, QueryError> {
+    let error = |message: &str, position: usize| QueryError {
+        message: message.to_owned(),
+        position,
+    };
+
+    let Some(mut rest) = query.strip_prefix('$') else {
+        return Err(error("expected '$'", 0));
+    };
+    let mut steps = Vec::new();
+
+    while !rest.is_empty() {
+        let position = query.len() - rest.len();
+        let descendants = rest.starts_with("..");
+        let single_dot = !descendants && rest.starts_with('.');
+        if descendants {
+            rest = &rest[2..];
+        } else if single_dot {
+            rest = &rest[1..];
+        } else if !rest.starts_with('[') {
+            return Err(error("expected '.' or '['", position));
+        }
+
+        let selector_position = query.len() - rest.len();
+        let selector = if let Some(bracketed) = rest.strip_prefix('[').filter(|_| !single_dot) {
+            let (selector, after) = parse_bracket(bracketed)
+                .map_err(|(message, offset)| error(message, selector_position + 1 + offset))?;
+            rest = after;
+            selector
+        } else if let Some(after) = rest.strip_prefix('*') {
+            rest = after;
+            Selector::Wildcard
+        } else {
+            let length = rest.find(['.', '[']).unwrap_or(rest.len());
+            if length == 0 {
+                return Err(error("expected a key", selector_position));
+            }
+            let (key, after) = rest.split_at(length);
+            rest = after;
+            Selector::Key(key.to_owned())
+        };
+
+        steps.push(Step {
+            descendants,
+            selector,
+        });
+    }
+
+    Ok(steps)
+}
+
+/// Parses the contents of a `[...]` selector, returning the text after the
+/// closing bracket, or an error message with its offset into the contents.
+fn parse_bracket(contents: &str) -> Result<(Selector, &str), (&'static str, usize)> {
+    if let Some(after) = contents.strip_prefix("*]") {
+        return Ok((Selector::Wildcard, after));
+    }
+
+    if let Some(quote) = contents.chars().next().filter(|&c| c == '\'' || c == '"') {
+        let mut key = String::new();
+        let mut chars = contents.char_indices().skip(1);
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => key.push(escaped),
+                    None => break,
+                },
+                c if c == quote => {
+                    let after = &contents[offset + 1..];
+                    return match after.strip_prefix(']') {
+                        Some(after) => Ok((Selector::Key(key), after)),
+                        None => Err(("expected ']'", offset + 1)),
+                    };
+                }
+                c => key.push(c),
+            }
+        }
+        return Err(("unterminated key", 0));
+    }
+
+    let Some(length) = contents.find(']') else {
+        return Err(("expected ']'", contents.len()));
+    };
+    let integer = |text: &str, offset: usize| {
+        text.parse::<i64>()
+            .map_err(|_| ("expected an integer", offset))
+    };
+    let optional_integer = |text: &str, offset: usize| match text {
+        "" => Ok(None),
+        _ => integer(text, offset).map(Some),
+    };
+
+    let selector = match contents[..length].split_once(':') {
+        Some((start, end)) => Selector::Slice(
+            optional_integer(start, 0)?,
+            optional_integer(end, start.len() + 1)?,
+        ),
+        None => Selector::Index(integer(&contents[..length], 0)?),
+    };
+    Ok((selector, &contents[length + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Json, dsl::ToJson, parser::parse, query::QueryError};
+
+    fn bookstore() -> Json {
+        parse(
+            r#"{
+                "store": {
+                    "book": [
+                        {
+                            "category": "reference",
+                            "author": "Nigel Rees",
+                            "title": "Sayings of the Century",
+                            "price": 8.95
+                        },
+                        {
+                            "category": "fiction",
+                            "author": "Evelyn Waugh",
+                            "title": "Sword of Honour",
+                            "price": 12.99
+                        },
+                        {
+                            "category": "fiction",
+                            "author": "Herman Melville",
+                            "title": "Moby Dick",
+                            "isbn": "0-553-21311-3",
+                            "price": 8.99
+                        },
+                        {
+                            "category": "fiction",
+                            "author": "J. R. R. Tolkien",
+                            "title": "The Lord of the Rings",
+                            "isbn": "0-395-19395-8",
+                            "price": 22.99
+                        }
+                    ],
+                    "bicycle": {"color": "red", "price": 19.95},
+                    "odd key.[]": true
+                }
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn it_selects_values_from_the_bookstore_example() {
+        let store = bookstore();
+        let titles = |query: &str| -> Vec<Json> {
+            store
+                .query(query)
+                .unwrap()
+                .into_iter()
+                .map(|value| value.get("title").cloned().unwrap_or_else(|| value.clone()))
+                .collect()
+        };
+        let strings =
+            |strings: &[&str]| -> Vec<Json> { strings.iter().map(|s| s.to_json()).collect() };
+        let numbers =
+            |numbers: &[f64]| -> Vec<Json> { numbers.iter().map(|n| n.to_json()).collect() };
+        let authors = [
+            "Nigel Rees",
+            "Evelyn Waugh",
+            "Herman Melville",
+            "J. R. R. Tolkien",
+        ];
+        let books = [
+            "Sayings of the Century",
+            "Sword of Honour",
+            "Moby Dick",
+            "The Lord of the Rings",
+        ];
+
+        assert_eq!(titles("$.store.book[*].author"), strings(&authors));
+        assert_eq!(titles("$..author"), strings(&authors));
+        assert_eq!(
+            titles("$.store..price"),
+            numbers(&[8.95, 12.99, 8.99, 22.99, 19.95])
+        );
+        assert_eq!(titles("$..book[2]"), strings(&books[2..3]));
+        assert_eq!(titles("$..book[-1]"), strings(&books[3..]));
+        assert_eq!(titles("$..book[-1:]"), strings(&books[3..]));
+        assert_eq!(titles("$..book[0:2]"), strings(&books[..2]));
+        assert_eq!(titles("$..book[:2]"), strings(&books[..2]));
+        assert_eq!(titles("$..book[1:]"), strings(&books[1..]));
+        assert_eq!(titles("$..book[-3:-1]"), strings(&books[1..3]));
+        assert_eq!(titles("$..book[2:100]"), strings(&books[2..]));
+        assert_eq!(titles("$..book[3:1]"), strings(&[]));
+        assert_eq!(titles("$..book[4]"), strings(&[]));
+        assert_eq!(
+            titles("$..book[*].isbn"),
+            strings(&["0-553-21311-3", "0-395-19395-8"])
+        );
+        assert_eq!(titles("$['store']['bicycle']['color']"), strings(&["red"]));
+        assert_eq!(titles(r#"$.store.bicycle["color"]"#), strings(&["red"]));
+        assert_eq!(titles("$.store['odd key.[]']"), [true.to_json()]);
+        assert_eq!(titles("$.store.missing"), strings(&[]));
+        assert_eq!(titles("$.store.book.author"), strings(&[]));
+        assert_eq!(titles("$"), std::slice::from_ref(&store));
+        assert_eq!(
+            titles("$.store.*"),
+            [
+                store["store"]["book"].clone(),
+                store["store"]["bicycle"].clone(),
+                true.to_json()
+            ]
+        );
+        assert_eq!(store.query("$..*").unwrap().len(), store.node_count() - 1);
+        assert_eq!(store.query("$..[*]").unwrap().len(), store.node_count() - 1);
+    }
+
+    #[test]
+    fn it_reports_where_a_query_is_malformed() {
+        let error = |query: &str| bookstore().query(query).unwrap_err();
+        let expected = |message: &str, position| QueryError {
+            message: message.to_owned(),
+            position,
+        };
+
+        assert_eq!(error("store"), expected("expected '$'", 0));
+        assert_eq!(error("$store"), expected("expected '.' or '['", 1));
+        assert_eq!(error("$.store."), expected("expected a key", 8));
+        assert_eq!(error("$.store.[0]x"), expected("expected a key", 8));
+        assert_eq!(error("$.store[0"), expected("expected ']'", 9));
+        assert_eq!(error("$.store[a]"), expected("expected an integer", 8));
+        assert_eq!(error("$.store[1:b]"), expected("expected an integer", 10));
+        assert_eq!(error("$.store['book"), expected("unterminated key", 8));
+        assert_eq!(error("$.store['book'x"), expected("expected ']'", 14));
+        assert_eq!(
+            error("$.store[a]").to_string(),
+            "ERROR: Invalid JSON query - expected an integer at position 8"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Scrubbing secrets out of a document before it is printed somewhere public,
+//! such as a log.
+
+use crate::{
+    ast::Json,
+    dsl::ToJson,
+    walk::{PathSegment, Walk},
+};
+
+/// What `redact_default` and `redact_keys` replace values with.
+pub const REDACTED: &str = "***";
+
+impl Json {
+    /// Replaces the value at each JSON Pointer (RFC 6901) with the
+    /// placeholder, skipping pointers with no value. Gives back the pointers
+    /// which were skipped.
+    pub fn redact(&mut self, pointers: &[&str], placeholder: impl ToJson) -> Vec<String> {
+        let placeholder = placeholder.to_json();
+        let mut missing = Vec::new();
+        for pointer in pointers {
+            match self.pointer_mut(pointer) {
+                Some(value) => *value = placeholder.clone(),
+                None => missing.push((*pointer).to_owned()),
+            }
+        }
+        missing
+    }
+
+    /// Like `redact`, with `"***"` as the placeholder.
+    pub fn redact_default(&mut self, pointers: &[&str]) -> Vec<String> {
+        self.redact(pointers, REDACTED)
+    }
+
+    /// Replaces the value of every property anywhere in the document whose
+    /// key is exactly one of `keys` with `"***"`. Gives back how many values
+    /// were replaced.
+    pub fn redact_keys(&mut self, keys: &[&str]) -> usize {
+        let mut redacted = 0;
+        self.walk_mut(|path, value| match path.segments().last() {
+            Some(PathSegment::Key(key)) if keys.contains(&key.as_str()) => {
+                *value = REDACTED.to_json();
+                redacted += 1;
+                Walk::SkipChildren
+            }
+            _ => Walk::Continue,
+        });
+        redacted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Json, dsl::ToJson, parser::parse};
+
+    fn payload() -> Json {
+        parse(
+            r#"{
+                "user": "ada",
+                "password": "hunter2",
+                "credentials": [
+                    {"name": "db", "token": "abc", "meta": {"token": "nested"}},
+                    {"name": "api", "token": {"value": "def"}}
+                ],
+                "headers": {"Authorization": "Bearer x", "a/b": 1}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn redact_replaces_the_values_at_the_pointers() {
+        let mut payload = payload();
+
+        let missing = payload.redact(
+            &["/password", "/credentials/0/token", "/headers/a~1b"],
+            Json::Null,
+        );
+
+        assert!(missing.is_empty());
+        assert_eq!(payload["password"], Json::Null);
+        assert_eq!(payload["credentials"][0]["token"], Json::Null);
+        assert_eq!(payload["credentials"][1]["token"]["value"], "def".to_json());
+        assert_eq!(payload["headers"]["a/b"], Json::Null);
+    }
+
+    #[test]
+    fn redact_reports_the_pointers_it_skips() {
+        let mut payload = payload();
+
+        let missing = payload.redact_default(&["/secret", "/credentials/5/token", "user", "/user"]);
+
+        assert_eq!(missing, ["/secret", "/credentials/5/token", "user"]);
+        assert_eq!(payload["user"], "***".to_json());
+        assert_eq!(payload["password"], "hunter2".to_json());
+    }
+
+    #[test]
+    fn redact_keys_replaces_matching_keys_throughout_arrays_of_objects() {
+        let mut payload = payload();
+
+        let redacted = payload.redact_keys(&["token", "password", "authorization"]);
+
+        assert_eq!(redacted, 4);
+        assert_eq!(
+            payload,
+            parse(
+                r#"{
+                    "user": "ada",
+                    "password": "***",
+                    "credentials": [
+                        {"name": "db", "token": "***", "meta": {"token": "***"}},
+                        {"name": "api", "token": "***"}
+                    ],
+                    "headers": {"Authorization": "Bearer x", "a/b": 1}
+                }"#
+            )
+            .unwrap()
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! Renaming the keys of every object in a document, such as from the
+//! snake_case of a Rust service to the camelCase of a JavaScript client.
+
+use std::{collections::HashMap, error, fmt};
+
+use crate::{
+    ast::{push_pointer_token, Json},
+    map::Map,
+};
+
+/// A naming convention for `Json::rename_keys` to convert keys to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyStyle {
+    /// `user_id`, `address_line1`
+    SnakeCase,
+    /// `userId`, `addressLine1`
+    CamelCase,
+}
+
+impl KeyStyle {
+    /// Converts a key to the style. Words are split at `_`, `-` and spaces,
+    /// before an uppercase letter following a lowercase letter or digit, and
+    /// before the last letter of a run of uppercase letters followed by a
+    /// lowercase one, so `HTTPServer` is `HTTP` and `Server`. Digits belong
+    /// to the word before them. Leading underscores are kept.
+    ///
+    /// Converting a key which is already in the style gives it back, except
+    /// in camelCase for one-letter words next to each other: `a_b_c` becomes
+    /// `aBC`, which is read as `a` and `BC`, so becomes `aBc`.
+    pub fn convert(self, key: &str) -> String {
+        let body = key.trim_start_matches('_');
+        let mut converted = key[..key.len() - body.len()].to_owned();
+        let words = split_words(body);
+        match self {
+            KeyStyle::SnakeCase => {
+                let words: Vec<_> = words.iter().map(|word| word.to_lowercase()).collect();
+                converted.push_str(&words.join("_"));
+            }
+            KeyStyle::CamelCase => {
+                for (index, word) in words.iter().enumerate() {
+                    let mut chars = word.chars();
+                    if let (Some(first), false) = (chars.next(), index == 0) {
+                        converted.extend(first.to_uppercase());
+                        converted.push_str(&chars.as_str().to_lowercase());
+                    } else {
+                        converted.push_str(&word.to_lowercase());
+                    }
+                }
+            }
+        }
+        converted
+    }
+}
+
+fn split_words(key: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = key.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+    for (position, &(offset, c)) in chars.iter().enumerate() {
+        if c == '_' || c == '-' || c == ' ' {
+            if let Some(start) = start.take() {
+                words.push(&key[start..offset]);
+            }
+            continue;
+        }
+        let previous = position.checked_sub(1).map(|position| chars[position].1);
+        let next = chars.get(position + 1).map(|&(_, c)| c);
+        let is_boundary = c.is_uppercase()
+            && previous.is_some_and(|previous| {
+                previous.is_lowercase()
+                    || previous.is_ascii_digit()
+                    || (previous.is_uppercase() && next.is_some_and(char::is_lowercase))
+            });
+        match start {
+            Some(word_start) if is_boundary => {
+                words.push(&key[word_start..offset]);
+                start = Some(offset);
+            }
+            None => start = Some(offset),
+            _ => {}
+        }
+    }
+    if let Some(start) = start {
+        words.push(&key[start..]);
+    }
+    words
+}
+
+/// An error from renaming keys when two keys of the same object would have
+/// the same name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenameKeysError {
+    /// The JSON Pointer of the object.
+    pub pointer: String,
+    /// The two keys, in the object's order.
+    pub keys: (String, String),
+    /// The name both keys would have.
+    pub renamed: String,
+}
+
+impl fmt::Display for RenameKeysError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (first, second) = &self.keys;
+        formatter.write_fmt(format_args!(
+            "ERROR: Cannot rename JSON keys - {first:?} and {second:?} would both be {:?} in {:?}",
+            self.renamed, self.pointer
+        ))
+    }
+}
+
+impl error::Error for RenameKeysError {}
+
+impl Json {
+    /// Converts the key of every property of every object in the document to
+    /// the style, leaving the values alone. Fails, leaving the document
+    /// unchanged, if two keys of one object would have the same name.
+    pub fn rename_keys(&mut self, style: KeyStyle) -> Result<(), RenameKeysError> {
+        self.rename_keys_with(|key| style.convert(key))
+    }
+
+    /// Like `rename_keys`, but renames each key with the function.
+    pub fn rename_keys_with(
+        &mut self,
+        rename: impl Fn(&str) -> String,
+    ) -> Result<(), RenameKeysError> {
+        *self = renamed(self.clone(), &rename, &mut String::new())?;
+        Ok(())
+    }
+}
+
+fn renamed(
+    value: Json,
+    rename: &impl Fn(&str) -> String,
+    pointer: &mut String,
+) -> Result<Json, RenameKeysError> {
+    let mut rename_child = |segment: &str, child: Json| {
+        let length = push_pointer_token(pointer, segment);
+        let child = renamed(child, rename, pointer);
+        pointer.truncate(length);
+        child
+    };
+
+    match value {
+        Json::Array(items) => items
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| rename_child(&index.to_string(), item))
+            .collect::<Result<_, _>>()
+            .map(Json::Array),
+        Json::Object(properties) => {
+            let mut renamed_properties = Map::new();
+            let mut originals: HashMap<String, String> = HashMap::new();
+            for (key, property) in properties {
+                let new_key = rename(&key);
+                if let Some(first) = originals.get(&new_key) {
+                    return Err(RenameKeysError {
+                        pointer: pointer.clone(),
+                        keys: (first.clone(), key),
+                        renamed: new_key,
+                    });
+                }
+                let property = rename_child(&key, property)?;
+                originals.insert(new_key.clone(), key);
+                renamed_properties.insert(new_key, property);
+            }
+            Ok(Json::Object(renamed_properties))
+        }
+        leaf => Ok(leaf),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        parser::parse,
+        rename::{KeyStyle, RenameKeysError},
+    };
+
+    #[test]
+    fn it_converts_keys_between_styles() {
+        let cases = [
+            ("userID", "user_id", "userId"),
+            ("user_id", "user_id", "userId"),
+            ("userId", "user_id", "userId"),
+            ("addressLine1", "address_line1", "addressLine1"),
+            ("address_line_1", "address_line_1", "addressLine1"),
+            ("HTTPServer", "http_server", "httpServer"),
+            (
+                "getHTTPResponseCode",
+                "get_http_response_code",
+                "getHttpResponseCode",
+            ),
+            ("line2Id", "line2_id", "line2Id"),
+            ("UserName", "user_name", "userName"),
+            ("kebab-case key", "kebab_case_key", "kebabCaseKey"),
+            ("_private", "_private", "_private"),
+            ("__typeName", "__type_name", "__typeName"),
+            ("ID", "id", "id"),
+            ("a", "a", "a"),
+            ("", "", ""),
+            ("ÜberWert", "über_wert", "überWert"),
+            ("a_b_c", "a_b_c", "aBC"),
+            ("aBC", "a_bc", "aBc"),
+        ];
+
+        for (key, snake, camel) in cases {
+            assert_eq!(KeyStyle::SnakeCase.convert(key), snake, "{key}");
+            assert_eq!(KeyStyle::CamelCase.convert(key), camel, "{key}");
+        }
+    }
+
+    #[test]
+    fn converting_is_idempotent() {
+        for key in ["userID", "addressLine1", "HTTPServer", "a_b", "x1Y2"] {
+            for style in [KeyStyle::SnakeCase, KeyStyle::CamelCase] {
+                let once = style.convert(key);
+                assert_eq!(style.convert(&once), once, "{key}");
+            }
+        }
+    }
+
+    #[test]
+    fn it_renames_keys_through_nested_arrays_and_objects() {
+        let mut document = parse(
+            r#"{"user_id": 1, "home_address": {"address_line1": "x"}, "past_orders": [{"order_id": "order_id"}]}"#,
+        )
+        .unwrap();
+
+        document.rename_keys(KeyStyle::CamelCase).unwrap();
+
+        assert_eq!(
+            document,
+            parse(r#"{"userId": 1, "homeAddress": {"addressLine1": "x"}, "pastOrders": [{"orderId": "order_id"}]}"#)
+                .unwrap()
+        );
+        assert_eq!(
+            document.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["userId", "homeAddress", "pastOrders"]
+        );
+        document.rename_keys(KeyStyle::SnakeCase).unwrap();
+        assert_eq!(
+            document["past_orders"][0]["order_id"],
+            Json::String("order_id".to_owned())
+        );
+    }
+
+    #[test]
+    fn it_fails_on_keys_which_would_collide_without_changing_anything() {
+        let original =
+            parse(r#"{"ok": {"a": 1}, "list": [{}, {"userId": 1, "user_id": 2}]}"#).unwrap();
+        let mut document = original.clone();
+
+        let error = document.rename_keys(KeyStyle::SnakeCase).unwrap_err();
+
+        assert_eq!(
+            error,
+            RenameKeysError {
+                pointer: "/list/1".to_owned(),
+                keys: ("userId".to_owned(), "user_id".to_owned()),
+                renamed: "user_id".to_owned(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            r#"ERROR: Cannot rename JSON keys - "userId" and "user_id" would both be "user_id" in "/list/1""#
+        );
+        assert_eq!(document, original);
+    }
+
+    #[test]
+    fn rename_keys_with_applies_any_function() {
+        let mut document = parse(r#"{"a": {"b": [{"c": 1}]}}"#).unwrap();
+
+        document
+            .rename_keys_with(|key| format!("x_{}", key.to_uppercase()))
+            .unwrap();
+
+        assert_eq!(
+            document,
+            parse(r#"{"x_A": {"x_B": [{"x_C": 1}]}}"#).unwrap()
+        );
+        assert!(document.rename_keys_with(|_| "same".to_owned()).is_ok());
+        assert!(parse(r#"{"a": 1, "b": 2}"#)
+            .unwrap()
+            .rename_keys_with(|_| "same".to_owned())
+            .is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! Inferring a JSON Schema (draft-07) which describes example documents.
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    ast::{is_whole_number, Json},
+    map::Map,
+};
+
+/// The `$schema` of the schemas `infer_schema` gives.
+pub const DRAFT_07: &str = "http://json-schema.org/draft-07/schema#";
+
+/// Infers a schema which every sample is valid against, and which describes
+/// their types as narrowly as it can.
+///
+/// The schema gives the `type` of each value, as an array if the samples
+/// have more than one. Objects have the `properties` found in any sample,
+/// with those found in every sample `required`, and arrays have the schema
+/// of all their `items` merged, which is left out if every array is empty.
+/// Numbers are `integer` unless some sample has a fractional part, so `1.0`
+/// is an integer.
+pub fn infer_schema(samples: &[Json]) -> Json {
+    let mut shape = Shape::default();
+    for sample in samples {
+        shape.add(sample);
+    }
+    let mut schema = Map::new();
+    schema.insert("$schema".to_string(), Json::from(DRAFT_07));
+    shape.write(&mut schema);
+    Json::Object(schema)
+}
+
+/// What has been seen of the values at one place in the samples.
+#[derive(Default)]
+struct Shape {
+    null: bool,
+    boolean: bool,
+    integer: bool,
+    fraction: bool,
+    string: bool,
+    /// The merged shape of the items of every array seen, which is the
+    /// default if they were all empty.
+    array: Option<Box<Shape>>,
+    object: Option<ObjectShape>,
+}
+
+#[derive(Default)]
+struct ObjectShape {
+    /// The number of objects seen.
+    count: usize,
+    /// Each property seen, in the order first seen, with the number of
+    /// objects it was in.
+    properties: Vec<(String, usize, Shape)>,
+    /// The position of each property in `properties`.
+    index: BTreeMap<String, usize>,
+}
+
+impl Shape {
+    fn add(&mut self, value: &Json) {
+        match value {
+            Json::Null => self.null = true,
+            Json::Boolean(_) => self.boolean = true,
+            Json::Number(number) if is_whole_number(*number) => self.integer = true,
+            Json::Number(_) => self.fraction = true,
+            Json::BigInteger(_) => self.integer = true,
+            Json::String(_) => self.string = true,
+            Json::Array(items) => {
+                let shape = self.array.get_or_insert_with(Box::default);
+                for item in items {
+                    shape.add(item);
+                }
+            }
+            Json::Object(properties) => {
+                let object = self.object.get_or_insert_with(ObjectShape::default);
+                object.count += 1;
+                for (key, property) in properties {
+                    let position = *object.index.entry(key.clone()).or_insert_with(|| {
+                        object.properties.push((key.clone(), 0, Shape::default()));
+                        object.properties.len() - 1
+                    });
+                    let (_, count, shape) = &mut object.properties[position];
+                    *count += 1;
+                    shape.add(property);
+                }
+            }
+        }
+    }
+
+    /// Sets the keywords of the schema for the shape, which has no type if
+    /// nothing has been seen.
+    fn write(&self, schema: &mut Map) {
+        let number = if self.fraction {
+            Some("number")
+        } else if self.integer {
+            Some("integer")
+        } else {
+            None
+        };
+        let mut types: Vec<Json> = [
+            self.object.as_ref().map(|_| "object"),
+            self.array.as_ref().map(|_| "array"),
+            self.string.then_some("string"),
+            number,
+            self.boolean.then_some("boolean"),
+            self.null.then_some("null"),
+        ]
+        .into_iter()
+        .flatten()
+        .map(Json::from)
+        .collect();
+        match types.len() {
+            0 => return,
+            1 => schema.insert("type".to_string(), types.remove(0)),
+            _ => schema.insert("type".to_string(), Json::Array(types)),
+        };
+
+        if let Some(object) = &self.object {
+            let mut properties = Map::new();
+            let mut required = Vec::new();
+            for (key, count, shape) in &object.properties {
+                let mut property = Map::new();
+                shape.write(&mut property);
+                properties.insert(key.clone(), Json::Object(property));
+                if *count == object.count {
+                    required.push(Json::String(key.to_string()));
+                }
+            }
+            schema.insert("properties".to_string(), Json::Object(properties));
+            if !required.is_empty() {
+                schema.insert("required".to_string(), Json::Array(required));
+            }
+        }
+        if let Some(items) = &self.array {
+            let mut items_schema = Map::new();
+            items.write(&mut items_schema);
+            if !items_schema.is_empty() {
+                schema.insert("items".to_string(), Json::Object(items_schema));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Json, parser::parse, schema::infer_schema};
+
+    /// Infers the schema of the samples, without its `$schema`.
+    fn infer(samples: &[&str]) -> Json {
+        let samples: Vec<_> = samples.iter().map(|json| parse(json).unwrap()).collect();
+        let mut schema = infer_schema(&samples);
+        schema.remove("$schema");
+        schema
+    }
+
+    fn schema(json: &str) -> Json {
+        parse(json).unwrap()
+    }
+
+    #[test]
+    fn it_infers_the_type_of_scalars() {
+        assert_eq!(infer(&["\"a\""]), schema(r#"{"type":"string"}"#));
+        assert_eq!(infer(&["1", "2.0"]), schema(r#"{"type":"integer"}"#));
+        assert_eq!(infer(&["1", "2.5"]), schema(r#"{"type":"number"}"#));
+        assert_eq!(
+            infer(&["true", "null"]),
+            schema(r#"{"type":["boolean","null"]}"#)
+        );
+        assert_eq!(infer(&[]), schema("{}"));
+    }
+
+    #[test]
+    fn properties_in_every_sample_are_required() {
+        assert_eq!(
+            infer(&[
+                r#"{"id": 1, "name": "a", "email": null}"#,
+                r#"{"id": 2, "name": "b", "email": "b@example.com", "age": 3.5}"#,
+                r#"{"name": "c", "id": 3, "email": "c@example.com"}"#,
+            ]),
+            schema(
+                r#"{"type":"object","properties":{"id":{"type":"integer"},"name":{"type":"string"},"email":{"type":["string","null"]},"age":{"type":"number"}},"required":["id","name","email"]}"#
+            )
+        );
+        assert_eq!(
+            infer(&["{}", r#"{"a": 1}"#]),
+            schema(r#"{"type":"object","properties":{"a":{"type":"integer"}}}"#)
+        );
+    }
+
+    #[test]
+    fn it_merges_the_items_of_arrays() {
+        assert_eq!(
+            infer(&[r#"[{"a": 1, "b": true}, {"a": 2.5}]"#, r#"[[], "x"]"#, "[]"]),
+            schema(
+                r#"{"type":"array","items":{"type":["object","array","string"],"properties":{"a":{"type":"number"},"b":{"type":"boolean"}},"required":["a"]}}"#
+            )
+        );
+        assert_eq!(infer(&["[]", "[]"]), schema(r#"{"type":"array"}"#));
+        assert_eq!(
+            infer(&[r#"{"tags": []}"#, r#"{"tags": null}"#]),
+            schema(
+                r#"{"type":"object","properties":{"tags":{"type":["array","null"]}},"required":["tags"]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn it_gives_a_draft_07_schema() {
+        let schema = infer_schema(&[parse("1").unwrap()]);
+
+        assert_eq!(
+            format!("{schema:#}"),
+            r#"{"$schema":"http://json-schema.org/draft-07/schema#","type":"integer"}"#
+        );
+    }
+}
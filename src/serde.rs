@@ -0,0 +1,275 @@
+//! Converting between `Json` and serde's data model, behind the `serde`
+//! feature, and `serde_json::Value`, behind the `serde_json` feature.
+
+use std::fmt;
+
+use serde::{
+    de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor},
+    ser::{Serialize, Serializer},
+};
+
+use crate::{
+    ast::{integer_to_json, Json},
+    map::Map,
+};
+
+/// Serializes an object as a map in the map's order, and a number as an
+/// integer if it is a whole number which `as_i64` gives or a `BigInteger`.
+impl Serialize for Json {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Json::Null => serializer.serialize_unit(),
+            Json::Boolean(value) => serializer.serialize_bool(*value),
+            Json::String(text) => serializer.serialize_str(text),
+            Json::Number(number) => match self.as_i64() {
+                Some(integer) => serializer.serialize_i64(integer),
+                None => serializer.serialize_f64(*number),
+            },
+            Json::BigInteger(integer) => match (i64::try_from(*integer), u64::try_from(*integer)) {
+                (Ok(integer), _) => serializer.serialize_i64(integer),
+                (_, Ok(integer)) => serializer.serialize_u64(integer),
+                _ => serializer.serialize_i128(*integer),
+            },
+            Json::Array(items) => serializer.collect_seq(items),
+            Json::Object(properties) => serializer.collect_map(properties),
+        }
+    }
+}
+
+/// Deserializes any self-describing data. Integers beyond `MAX_SAFE_INTEGER`
+/// either way are kept exactly as a `Json::BigInteger`, and a repeated key of
+/// a map replaces the earlier value, as it does in `parse`.
+impl<'de> Deserialize<'de> for Json {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Json, D::Error> {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = Json;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Json, D::Error> {
+        Json::deserialize(deserializer)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Json, E> {
+        Ok(Json::Boolean(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Json, E> {
+        Ok(integer_to_json(value.into()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Json, E> {
+        Ok(integer_to_json(value.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Json, E> {
+        Ok(Json::Number(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Json, E> {
+        Ok(Json::String(value.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Json, E> {
+        Ok(Json::String(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Json, A::Error> {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Json::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Json, A::Error> {
+        let mut properties = Map::new();
+        while let Some((key, value)) = map.next_entry()? {
+            properties.insert(key, value);
+        }
+        Ok(Json::Object(properties))
+    }
+}
+
+/// Converts a `serde_json::Value`, keeping the order of its objects, which is
+/// sorted unless serde_json's `preserve_order` feature is enabled.
+#[cfg(feature = "serde_json")]
+impl From<serde_json::Value> for Json {
+    fn from(value: serde_json::Value) -> Json {
+        match value {
+            serde_json::Value::Null => Json::Null,
+            serde_json::Value::Bool(value) => Json::Boolean(value),
+            serde_json::Value::Number(number) => {
+                match number
+                    .as_i64()
+                    .map(i128::from)
+                    .or(number.as_u64().map(i128::from))
+                {
+                    Some(integer) => integer_to_json(integer),
+                    None => Json::Number(
+                        number
+                            .as_f64()
+                            .expect("serde_json converts every number to an f64"),
+                    ),
+                }
+            }
+            serde_json::Value::String(text) => Json::String(text),
+            serde_json::Value::Array(items) => {
+                Json::Array(items.into_iter().map(Json::from).collect())
+            }
+            serde_json::Value::Object(properties) => Json::Object(
+                properties
+                    .into_iter()
+                    .map(|(key, value)| (key, Json::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Converts to a `serde_json::Value`, which can't hold NaN or infinity, so
+/// those become `null`.
+#[cfg(feature = "serde_json")]
+impl From<Json> for serde_json::Value {
+    fn from(value: Json) -> serde_json::Value {
+        match value {
+            Json::Null => serde_json::Value::Null,
+            Json::Boolean(value) => serde_json::Value::Bool(value),
+            Json::Number(number) => match Json::Number(number).as_i64() {
+                Some(integer) => serde_json::Value::from(integer),
+                None => serde_json::Number::from_f64(number)
+                    .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            },
+            Json::BigInteger(integer) => match (i64::try_from(integer), u64::try_from(integer)) {
+                (Ok(integer), _) => serde_json::Value::from(integer),
+                (_, Ok(integer)) => serde_json::Value::from(integer),
+                _ => serde_json::Number::from_f64(integer as f64)
+                    .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            },
+            Json::String(text) => serde_json::Value::String(text),
+            Json::Array(items) => {
+                serde_json::Value::Array(items.into_iter().map(serde_json::Value::from).collect())
+            }
+            Json::Object(properties) => serde_json::Value::Object(
+                properties
+                    .into_iter()
+                    .map(|(key, value)| (key, serde_json::Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        ast::Json,
+        dsl::ToJson,
+        parser::parse,
+        printer::{json_to_string_with_options, PrintOptions, PrintStyle},
+    };
+
+    fn sample() -> &'static str {
+        r#"{
+            "text": "quote \" slash \\ tab \t newline \n control \u0001 emoji 😃  ",
+            "numbers": [0, -3, 1.5, 1e-7, 2405946039048539, 9007199254740993, 1e300, -2.5e-300],
+            "nested": {"z": [true, false, null], "a": {}, "": []}
+        }"#
+    }
+
+    fn compact(value: &Json) -> String {
+        json_to_string_with_options(value, &PrintOptions::new().style(PrintStyle::Compact)).unwrap()
+    }
+
+    #[test]
+    fn serde_json_reads_what_this_crate_prints() {
+        let value = parse(sample()).unwrap();
+
+        let read_back: Json = serde_json::from_str(&compact(&value)).unwrap();
+
+        assert_eq!(read_back, value);
+    }
+
+    #[test]
+    fn this_crate_reads_what_serde_json_prints() {
+        let value = parse(sample()).unwrap();
+
+        let text = serde_json::to_string(&value).unwrap();
+
+        assert_eq!(parse(&text).unwrap(), value);
+        assert!(text.starts_with(r#"{"text":"#));
+        assert!(text.contains(r#""numbers":[0,-3,1.5,"#));
+    }
+
+    #[test]
+    fn objects_keep_their_order_and_repeated_keys_replace_earlier_ones() {
+        let value: Json = serde_json::from_str(r#"{"b": 1, "a": 2, "b": 3}"#).unwrap();
+
+        assert_eq!(value.keys().collect::<Vec<_>>(), ["b", "a"]);
+        assert_eq!(value["b"], 3.to_json());
+        assert_eq!(
+            serde_json::to_string(&Json::object().set("b", 1).set("a", 2)).unwrap(),
+            r#"{"b":1,"a":2}"#
+        );
+    }
+
+    #[test]
+    fn non_finite_numbers_serialize_as_null() {
+        assert_eq!(serde_json::to_string(&f64::NAN.to_json()).unwrap(), "null");
+        assert_eq!(
+            serde_json::to_string(&vec![f64::INFINITY].to_json()).unwrap(),
+            "[null]"
+        );
+    }
+
+    #[test]
+    fn it_deserializes_from_other_serde_data() {
+        let typed = (-4, None::<&str>, BTreeMap::from([("tags", vec![1u8, 2])]));
+
+        let value: Json = serde_json::from_value(serde_json::to_value(typed).unwrap()).unwrap();
+
+        assert_eq!(
+            value,
+            vec![
+                (-4).to_json(),
+                Json::Null,
+                Json::object().set("tags", vec![1, 2]),
+            ]
+            .to_json()
+        );
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn it_converts_to_and_from_serde_json_values() {
+        let value = parse(sample()).unwrap();
+
+        let converted = serde_json::Value::from(value.clone());
+
+        assert_eq!(converted["numbers"][1], serde_json::json!(-3));
+        assert_eq!(converted["numbers"][2], serde_json::json!(1.5));
+        assert_eq!(Json::from(converted), value);
+        assert_eq!(
+            serde_json::Value::from(vec![1.0, f64::NAN].to_json()),
+            serde_json::json!([1, null])
+        );
+    }
+}
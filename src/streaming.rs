@@ -0,0 +1,390 @@
+use std::{
+    error,
+    fmt::{self, Write},
+};
+
+use crate::{
+    ast::{integer_to_json, Json},
+    printer::{
+        display_json_scalar, display_key, display_string_value, NonFinitePolicy, PrintOptions,
+    },
+};
+
+/// A step through a JSON document, as produced by a streaming parser.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event<'a> {
+    ObjectStart,
+    ObjectEnd,
+    ArrayStart,
+    ArrayEnd,
+    /// The key of the next object entry, which must be followed by its value.
+    Key(&'a str),
+    String(&'a str),
+    Number(f64),
+    /// An integer, which is printed exactly even beyond the 53 bits an `f64`
+    /// holds, as a parsed `Json::BigInteger` is.
+    Integer(i128),
+    Boolean(bool),
+    Null,
+}
+
+/// An error from `StreamingPrinter`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StreamError {
+    /// The event can't come next in a valid document, such as a `Key` outside
+    /// an object, an `ArrayEnd` which closes an object, or any event after the
+    /// top-level value is complete. Nothing is written for the event.
+    UnexpectedEvent,
+    /// The document was finished before the top-level value was complete.
+    Incomplete,
+    /// A number is NaN or an infinity and the policy is `NonFinitePolicy::Error`.
+    NonFiniteNumber(f64),
+    /// The writer failed.
+    Write,
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::UnexpectedEvent => {
+                formatter.write_str("ERROR: Invalid JSON events - unexpected event")
+            }
+            StreamError::Incomplete => {
+                formatter.write_str("ERROR: Invalid JSON events - the document is incomplete")
+            }
+            StreamError::NonFiniteNumber(number) => formatter.write_fmt(format_args!(
+                "ERROR: Unprintable JSON - {number} is not a valid JSON number"
+            )),
+            StreamError::Write => formatter.write_str("ERROR: Failed to write JSON"),
+        }
+    }
+}
+
+impl error::Error for StreamError {}
+
+impl From<fmt::Error> for StreamError {
+    fn from(_: fmt::Error) -> StreamError {
+        StreamError::Write
+    }
+}
+
+/// A pretty-printer which writes a document as it is given the events making
+/// it up, without building the value in memory.
+///
+/// Arrays and objects are always laid out in the `Pretty` style, with object
+/// entries in the order their keys are given. Of the other options, those for
+/// indentation, strings, numbers and keys apply.
+pub struct StreamingPrinter<W> {
+    output: W,
+    options: PrintOptions,
+    containers: Vec<Container>,
+    /// The indentation for the deepest level printed so far.
+    indentation: String,
+    complete: bool,
+}
+
+struct Container {
+    is_object: bool,
+    items: usize,
+    /// Whether an object has been given a key, but not its value.
+    awaiting_value: bool,
+}
+
+impl<W: Write> StreamingPrinter<W> {
+    pub fn new(output: W, options: PrintOptions) -> StreamingPrinter<W> {
+        StreamingPrinter {
+            output,
+            options,
+            containers: Vec::new(),
+            indentation: String::new(),
+            complete: false,
+        }
+    }
+
+    pub fn event(&mut self, event: Event) -> Result<(), StreamError> {
+        match event {
+            Event::Key(key) => self.key(key),
+            Event::ArrayEnd => self.close(false),
+            Event::ObjectEnd => self.close(true),
+            Event::ArrayStart | Event::ObjectStart => {
+                self.start_value()?;
+                self.output
+                    .write_char(if event == Event::ArrayStart { '[' } else { '{' })?;
+                self.containers.push(Container {
+                    is_object: event == Event::ObjectStart,
+                    items: 0,
+                    awaiting_value: false,
+                });
+                Ok(())
+            }
+            Event::String(string) => {
+                self.start_value()?;
+                display_string_value(string, &mut self.output, &self.options)?;
+                self.end_value();
+                Ok(())
+            }
+            Event::Number(number) => self.scalar(&Json::Number(number)),
+            Event::Integer(integer) => self.scalar(&integer_to_json(integer)),
+            Event::Boolean(boolean) => self.scalar(&Json::Boolean(boolean)),
+            Event::Null => self.scalar(&Json::Null),
+        }
+    }
+
+    /// Checks that the document is complete, returning the writer.
+    pub fn finish(self) -> Result<W, StreamError> {
+        if self.complete {
+            Ok(self.output)
+        } else {
+            Err(StreamError::Incomplete)
+        }
+    }
+
+    fn key(&mut self, key: &str) -> Result<(), StreamError> {
+        match self.containers.last() {
+            Some(container) if container.is_object && !container.awaiting_value => {}
+            _ => return Err(StreamError::UnexpectedEvent),
+        }
+
+        self.display_item_start()?;
+        display_key(key, &mut self.output, &self.options)?;
+        let separator = match (
+            self.options.space_before_colon,
+            self.options.space_after_colon.unwrap_or(true),
+        ) {
+            (false, false) => ":",
+            (false, true) => ": ",
+            (true, false) => " :",
+            (true, true) => " : ",
+        };
+        self.output.write_str(separator)?;
+
+        let container = self.containers.last_mut().expect("An object is open");
+        container.awaiting_value = true;
+        Ok(())
+    }
+
+    fn scalar(&mut self, value: &Json) -> Result<(), StreamError> {
+        if let Json::Number(number) = value {
+            if !number.is_finite() && self.options.non_finite == NonFinitePolicy::Error {
+                return Err(StreamError::NonFiniteNumber(*number));
+            }
+        }
+
+        self.start_value()?;
+        display_json_scalar(value, &mut self.output, &self.options)?;
+        self.end_value();
+        Ok(())
+    }
+
+    /// Checks that a value can come next, and writes what comes before it.
+    fn start_value(&mut self) -> Result<(), StreamError> {
+        match self.containers.last() {
+            None if self.complete => Err(StreamError::UnexpectedEvent),
+            None => Ok(()),
+            Some(container) if container.is_object && !container.awaiting_value => {
+                Err(StreamError::UnexpectedEvent)
+            }
+            Some(container) if container.is_object => Ok(()),
+            Some(_) => self.display_item_start(),
+        }
+    }
+
+    fn end_value(&mut self) {
+        match self.containers.last_mut() {
+            Some(container) => {
+                container.items += 1;
+                container.awaiting_value = false;
+            }
+            None => self.complete = true,
+        }
+    }
+
+    fn close(&mut self, is_object: bool) -> Result<(), StreamError> {
+        match self.containers.last() {
+            Some(container) if container.is_object == is_object && !container.awaiting_value => {}
+            _ => return Err(StreamError::UnexpectedEvent),
+        }
+
+        let container = self.containers.pop().expect("A container is open");
+        if container.items > 0 {
+            self.display_line_break()?;
+        }
+        self.output.write_char(if is_object { '}' } else { ']' })?;
+        self.end_value();
+        Ok(())
+    }
+
+    /// Writes the comma after the previous item, if there is one, and the line
+    /// break before the next.
+    fn display_item_start(&mut self) -> Result<(), StreamError> {
+        let container = self.containers.last_mut().expect("A container is open");
+        let first = container.items == 0;
+        if !first {
+            self.output.write_char(',')?;
+        }
+        self.display_line_break()
+    }
+
+    /// Starts a new line, indented for the innermost open container's items.
+    fn display_line_break(&mut self) -> Result<(), StreamError> {
+        let level = self.options.indent_levels(self.containers.len() as u64);
+        let width = self.options.indent.len() * level as usize;
+        while self.indentation.len() < width {
+            self.indentation.push_str(&self.options.indent);
+        }
+
+        self.output.write_char('\n')?;
+        self.output.write_str(&self.indentation[..width])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        dsl::ToJson,
+        parser::parse,
+        printer::{json_to_string, json_to_string_with_options, PrintOptions},
+        streaming::{Event, StreamError, StreamingPrinter},
+    };
+
+    fn events(value: &Json) -> Vec<Event<'_>> {
+        fn walk<'a>(value: &'a Json, events: &mut Vec<Event<'a>>) {
+            match value {
+                Json::Null => events.push(Event::Null),
+                Json::Boolean(boolean) => events.push(Event::Boolean(*boolean)),
+                Json::Number(number) => events.push(Event::Number(*number)),
+                Json::BigInteger(integer) => events.push(Event::Integer(*integer)),
+                Json::String(string) => events.push(Event::String(string)),
+                Json::Array(items) => {
+                    events.push(Event::ArrayStart);
+                    for item in items {
+                        walk(item, events);
+                    }
+                    events.push(Event::ArrayEnd);
+                }
+                Json::Object(object) => {
+                    events.push(Event::ObjectStart);
+                    for (key, value) in object.iter_sorted() {
+                        events.push(Event::Key(key));
+                        walk(value, events);
+                    }
+                    events.push(Event::ObjectEnd);
+                }
+            }
+        }
+
+        let mut events = Vec::new();
+        walk(value, &mut events);
+        events
+    }
+
+    fn print(events: &[Event], options: PrintOptions) -> Result<String, StreamError> {
+        let mut printer = StreamingPrinter::new(String::new(), options);
+        for event in events {
+            printer.event(*event)?;
+        }
+        printer.finish()
+    }
+
+    #[test]
+    fn it_prints_events_like_the_tree_printer() {
+        for json in [
+            "null",
+            "[]",
+            "{}",
+            r#""a \"string\"""#,
+            r#"[1, [], {}, [[2.5, true]], {"b": {"c": null}, "a": [false]}]"#,
+            r#"{"z": 1, "a": {"nested": [{"deeper": ["x", "y"]}]}, "m": {}}"#,
+        ] {
+            let value = parse(json).unwrap();
+            for indent in [0, 2, 4] {
+                assert_eq!(
+                    print(
+                        &events(&value),
+                        PrintOptions::new().indent(" ".repeat(indent))
+                    ),
+                    json_to_string(&value, indent as u64).map_err(|_| StreamError::Write),
+                    "{json} with indent {indent}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn it_uses_the_options_for_strings_and_numbers() {
+        let value = ["<a>".to_json(), 0.125.to_json()].to_json();
+        let options = PrintOptions::new().html_safe(true).indent("\t");
+
+        assert_eq!(
+            print(&events(&value), options.clone()),
+            Ok(json_to_string_with_options(&value, &options).unwrap())
+        );
+        assert!(matches!(
+            print(&[Event::Number(f64::NAN)], options),
+            Err(StreamError::NonFiniteNumber(_))
+        ));
+    }
+
+    #[test]
+    fn it_prints_integers_beyond_an_f64_exactly_like_the_tree_printer() {
+        let value =
+            parse("[9007199254740993, -170141183460469231731687303715884105728, 7]").unwrap();
+
+        for options in [
+            PrintOptions::new(),
+            PrintOptions::new().big_int_as_string(true),
+        ] {
+            assert_eq!(
+                print(&events(&value), options.clone()),
+                Ok(json_to_string_with_options(&value, &options).unwrap())
+            );
+        }
+        assert_eq!(
+            print(&[Event::Integer(9007199254740993)], PrintOptions::new()),
+            Ok("9007199254740993".to_owned())
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_event_sequences() {
+        use Event::{ArrayEnd, ArrayStart, Key, Null, ObjectEnd, ObjectStart};
+
+        for events in [
+            &[Key("a")][..],
+            &[ArrayStart, Key("a")],
+            &[ObjectStart, Null],
+            &[ObjectStart, Key("a"), Key("b")],
+            &[ObjectStart, Key("a"), ObjectEnd],
+            &[ArrayStart, ObjectEnd],
+            &[ObjectStart, ArrayEnd],
+            &[ArrayEnd],
+            &[Null, Null],
+            &[ArrayStart, ArrayEnd, ArrayStart],
+        ] {
+            let mut printer = StreamingPrinter::new(String::new(), PrintOptions::new());
+            let (last, rest) = events.split_last().unwrap();
+            for event in rest {
+                printer.event(*event).unwrap();
+            }
+            assert_eq!(
+                printer.event(*last),
+                Err(StreamError::UnexpectedEvent),
+                "{events:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn it_fails_to_finish_an_incomplete_document() {
+        use Event::{ArrayStart, Key, ObjectStart};
+
+        for events in [&[][..], &[ArrayStart], &[ObjectStart, Key("a")]] {
+            assert_eq!(
+                print(events, PrintOptions::new()),
+                Err(StreamError::Incomplete)
+            );
+        }
+    }
+}
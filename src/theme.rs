@@ -0,0 +1,127 @@
+//! Styles for colouring printed JSON with ANSI escape codes.
+
+use alloc::{borrow::ToOwned, format, string::ToString, vec::Vec};
+use core::fmt::{self, Write};
+
+/// A terminal colour.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    /// One of the 16 standard colours: 0 to 7 are black, red, green, yellow,
+    /// blue, magenta, cyan and white, and 8 to 15 are their bright versions.
+    Ansi(u8),
+    /// A colour from the 256-colour palette.
+    Ansi256(u8),
+    /// A 24-bit colour, as red, green and blue.
+    Rgb(u8, u8, u8),
+}
+
+/// How one kind of token is displayed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Style {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+}
+
+impl Style {
+    pub fn color(color: Color) -> Style {
+        Style {
+            color: Some(color),
+            ..Style::default()
+        }
+    }
+
+    pub fn bold(mut self) -> Style {
+        self.bold = true;
+        self
+    }
+
+    pub fn dim(mut self) -> Style {
+        self.dim = true;
+        self
+    }
+
+    /// Writes the escape code which starts this style.
+    pub(crate) fn display_start<W: Write>(&self, output: &mut W) -> Result<(), fmt::Error> {
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push("1".to_owned());
+        }
+        if self.dim {
+            codes.push("2".to_owned());
+        }
+        match self.color {
+            Some(Color::Ansi(color @ 0..=7)) => codes.push((30 + color).to_string()),
+            Some(Color::Ansi(color)) => codes.push((90 + (color & 7)).to_string()),
+            Some(Color::Ansi256(color)) => codes.push(format!("38;5;{color}")),
+            Some(Color::Rgb(red, green, blue)) => codes.push(format!("38;2;{red};{green};{blue}")),
+            None => {}
+        }
+        output.write_fmt(format_args!("\x1b[{}m", codes.join(";")))
+    }
+}
+
+/// The escape code which ends a style.
+pub(crate) const RESET: &str = "\x1b[0m";
+
+/// The style of each kind of token in the printed output. A kind of token
+/// with no style is written without any escape codes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub key: Option<Style>,
+    pub string: Option<Style>,
+    pub number: Option<Style>,
+    pub boolean: Option<Style>,
+    pub null: Option<Style>,
+    /// Brackets, commas and colons.
+    pub punctuation: Option<Style>,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            key: Some(Style::color(Color::Ansi(4)).bold()),
+            string: Some(Style::color(Color::Ansi(2))),
+            number: Some(Style::color(Color::Ansi(6))),
+            boolean: Some(Style::color(Color::Ansi(3))),
+            null: Some(Style::color(Color::Ansi(8))),
+            punctuation: None,
+        }
+    }
+}
+
+impl Theme {
+    /// A theme with no styles, giving the same output as printing without one.
+    pub fn plain() -> Theme {
+        Theme {
+            key: None,
+            string: None,
+            number: None,
+            boolean: None,
+            null: None,
+            punctuation: None,
+        }
+    }
+
+    pub(crate) fn style(&self, token: Token) -> Option<&Style> {
+        match token {
+            Token::Key => self.key.as_ref(),
+            Token::String => self.string.as_ref(),
+            Token::Number => self.number.as_ref(),
+            Token::Boolean => self.boolean.as_ref(),
+            Token::Null => self.null.as_ref(),
+            Token::Punctuation => self.punctuation.as_ref(),
+        }
+    }
+}
+
+/// The kinds of token which a theme styles.
+#[derive(Clone, Copy)]
+pub(crate) enum Token {
+    Key,
+    String,
+    Number,
+    Boolean,
+    Null,
+    Punctuation,
+}
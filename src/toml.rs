@@ -0,0 +1,416 @@
+//! Converting a document to TOML, for configuration read by tools which
+//! expect it.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Write};
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::{
+    ast::{is_whole_number, push_pointer_token, Json},
+    map::Map,
+};
+
+/// An error from converting a value which TOML can't represent.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TomlConvertError {
+    /// The document has this type, but must be an object to be a TOML table.
+    NotATable(&'static str),
+    /// There is a `null`, which TOML has no equivalent of, at this JSON
+    /// Pointer.
+    Null(String),
+    /// The array at this JSON Pointer has items of different types.
+    MixedArray(String),
+}
+
+impl fmt::Display for TomlConvertError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("ERROR: Cannot convert JSON to TOML - ")?;
+        match self {
+            TomlConvertError::NotATable(found) => {
+                formatter.write_fmt(format_args!("expected an object, found {found}"))
+            }
+            TomlConvertError::Null(pointer) => {
+                formatter.write_fmt(format_args!("null is not allowed at {pointer:?}"))
+            }
+            TomlConvertError::MixedArray(pointer) => {
+                formatter.write_fmt(format_args!("the array at {pointer:?} mixes types"))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for TomlConvertError {}
+
+/// Converts an object to a TOML document. Each object in it becomes a table
+/// under a `[header]`, and each non-empty array of objects an array of tables
+/// under `[[headers]]`, except inside other arrays, where they are inline.
+/// The values of a table come before the tables inside it, as TOML requires,
+/// but are otherwise in the map's order.
+///
+/// Whole numbers in the range of an `i64` are integers, and other numbers
+/// floats, including `inf` and `nan`. An array mixing the two is written as
+/// floats, since the items of a TOML array must have one type, which is also
+/// why arrays with items of different JSON types fail.
+pub fn json_to_toml(value: &Json) -> Result<String, TomlConvertError> {
+    let properties = value
+        .as_object()
+        .ok_or(TomlConvertError::NotATable(value.type_name()))?;
+    let mut output = String::new();
+    write_table(properties, &mut Vec::new(), &mut String::new(), &mut output)?;
+    Ok(output)
+}
+
+/// Writes the properties of a table which are values, then each table and
+/// array of tables inside it under its header.
+fn write_table(
+    properties: &Map,
+    path: &mut Vec<String>,
+    pointer: &mut String,
+    output: &mut String,
+) -> Result<(), TomlConvertError> {
+    for (key, value) in properties.iter().filter(|(_, value)| !needs_header(value)) {
+        let length = push_pointer_token(pointer, key);
+        output.push_str(&format_key(key));
+        output.push_str(" = ");
+        write_value(value, pointer, false, output)?;
+        output.push('\n');
+        pointer.truncate(length);
+    }
+
+    for (key, value) in properties.iter().filter(|(_, value)| needs_header(value)) {
+        let length = push_pointer_token(pointer, key);
+        path.push(format_key(key));
+        match value {
+            Json::Object(table) => {
+                // A table holding only other tables is defined by their
+                // headers, so needs none of its own.
+                if table.is_empty() || table.values().any(|value| !needs_header(value)) {
+                    write_header(path, "[", "]", output);
+                }
+                write_table(table, path, pointer, output)?;
+            }
+            items => {
+                for (index, item) in items.members().enumerate() {
+                    let length = push_pointer_token(pointer, &index.to_string());
+                    write_header(path, "[[", "]]", output);
+                    write_table(
+                        item.as_object().unwrap_or(&Map::new()),
+                        path,
+                        pointer,
+                        output,
+                    )?;
+                    pointer.truncate(length);
+                }
+            }
+        }
+        path.pop();
+        pointer.truncate(length);
+    }
+    Ok(())
+}
+
+/// Whether a property is written under a header: objects, and arrays of
+/// objects.
+fn needs_header(value: &Json) -> bool {
+    match value {
+        Json::Object(_) => true,
+        Json::Array(items) => !items.is_empty() && items.iter().all(Json::is_object),
+        _ => false,
+    }
+}
+
+fn write_header(path: &[String], open: &str, close: &str, output: &mut String) {
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output.push_str(open);
+    output.push_str(&path.join("."));
+    output.push_str(close);
+    output.push('\n');
+}
+
+/// Writes a value inline, with arrays on one line and objects as inline
+/// tables. Numbers are floats if `as_float` is set.
+fn write_value(
+    value: &Json,
+    pointer: &mut String,
+    as_float: bool,
+    output: &mut String,
+) -> Result<(), TomlConvertError> {
+    match value {
+        Json::Null => return Err(TomlConvertError::Null(pointer.clone())),
+        Json::Boolean(boolean) => output.push_str(if *boolean { "true" } else { "false" }),
+        Json::Number(number) => write_number(*number, as_float, output),
+        Json::BigInteger(integer) => match i64::try_from(*integer) {
+            Ok(integer) if !as_float => output.push_str(&integer.to_string()),
+            _ => write_number(*integer as f64, as_float, output),
+        },
+        Json::String(string) => write_string(string, output),
+        Json::Array(items) => {
+            if let Some(first) = items.first() {
+                if items
+                    .iter()
+                    .any(|item| item.type_name() != first.type_name())
+                {
+                    return Err(TomlConvertError::MixedArray(pointer.clone()));
+                }
+            }
+            let as_float = items.iter().any(|item| match item {
+                Json::BigInteger(integer) => i64::try_from(*integer).is_err(),
+                item => item
+                    .as_f64()
+                    .is_some_and(|number| integer_value(number).is_none()),
+            });
+            output.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(", ");
+                }
+                let length = push_pointer_token(pointer, &index.to_string());
+                write_value(item, pointer, as_float, output)?;
+                pointer.truncate(length);
+            }
+            output.push(']');
+        }
+        Json::Object(properties) if properties.is_empty() => output.push_str("{}"),
+        Json::Object(properties) => {
+            output.push_str("{ ");
+            for (index, (key, value)) in properties.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(", ");
+                }
+                let length = push_pointer_token(pointer, key);
+                output.push_str(&format_key(key));
+                output.push_str(" = ");
+                write_value(value, pointer, false, output)?;
+                pointer.truncate(length);
+            }
+            output.push_str(" }");
+        }
+    }
+    Ok(())
+}
+
+/// The number as a TOML integer, if it is a whole number in the range of an
+/// `i64`. Negative zero isn't, so that its sign is kept.
+fn integer_value(number: f64) -> Option<i64> {
+    let is_integer = is_whole_number(number)
+        && (-9223372036854775808.0..9223372036854775808.0).contains(&number)
+        && !(number == 0.0 && number.is_sign_negative());
+    is_integer.then_some(number as i64)
+}
+
+fn write_number(number: f64, as_float: bool, output: &mut String) {
+    match integer_value(number) {
+        Some(integer) if !as_float => output.push_str(&integer.to_string()),
+        _ if number.is_nan() => output.push_str("nan"),
+        // `Debug` always writes a decimal point or an exponent, which is what
+        // makes a TOML number a float, and writes infinities as `inf`.
+        _ => output.push_str(&format!("{number:?}")),
+    }
+}
+
+/// Writes a basic string, escaping quotes, backslashes and control
+/// characters.
+fn write_string(string: &str, output: &mut String) {
+    output.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\u{8}' => output.push_str("\\b"),
+            '\t' => output.push_str("\\t"),
+            '\n' => output.push_str("\\n"),
+            '\u{c}' => output.push_str("\\f"),
+            '\r' => output.push_str("\\r"),
+            c if c.is_control() && c <= '\u{7f}' => {
+                let _ = write!(output, "\\u{:04X}", u32::from(c));
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+/// A key as it is, if it is a bare key, or else quoted.
+fn format_key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if is_bare {
+        key.to_string()
+    } else {
+        let mut quoted = String::new();
+        write_string(key, &mut quoted);
+        quoted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        parser::parse,
+        toml::{json_to_toml, TomlConvertError},
+    };
+
+    fn toml(json: &str) -> String {
+        let text = json_to_toml(&parse(json).unwrap()).unwrap();
+        if let Err(error) = text.parse::<::toml::Table>() {
+            panic!("{text}\n{error}");
+        }
+        text
+    }
+
+    #[test]
+    fn it_writes_tables_and_arrays_of_tables_under_headers() {
+        let text = toml(
+            r#"{
+                "package": {"name": "demo", "version": "0.1.0", "authors": ["a", "b"]},
+                "edition": 2021,
+                "dependencies": {"serde": {"version": "1", "features": ["derive"]}},
+                "bin": [{"name": "one", "test": false}, {"name": "two", "doc": {"x": 1}}],
+                "profile": {"release": {"lto": true}, "dev": {"opt-level": 1.5}}
+            }"#,
+        );
+
+        assert_eq!(
+            text,
+            r#"edition = 2021
+
+[package]
+name = "demo"
+version = "0.1.0"
+authors = ["a", "b"]
+
+[dependencies.serde]
+version = "1"
+features = ["derive"]
+
+[[bin]]
+name = "one"
+test = false
+
+[[bin]]
+name = "two"
+
+[bin.doc]
+x = 1
+
+[profile.release]
+lto = true
+
+[profile.dev]
+opt-level = 1.5
+"#
+        );
+    }
+
+    #[test]
+    fn it_reads_back_as_the_same_structure() {
+        let text = toml(
+            r#"{
+                "a": {"b": {"c": [{"d": 1}, {"d": 2, "e": [{"f": true}]}]}},
+                "empty": {},
+                "nested": [[1, 2], ["x"], [{"inline": {"y": "z"}}]],
+                "none": []
+            }"#,
+        );
+        let table: ::toml::Table = text.parse().unwrap();
+
+        assert_eq!(table["a"]["b"]["c"][1]["d"].as_integer(), Some(2));
+        assert_eq!(table["a"]["b"]["c"][1]["e"][0]["f"].as_bool(), Some(true));
+        assert!(table["empty"].as_table().unwrap().is_empty());
+        assert_eq!(table["nested"][2][0]["inline"]["y"].as_str(), Some("z"));
+        assert!(table["none"].as_array().unwrap().is_empty());
+        assert!(text.contains(r#"nested = [[1, 2], ["x"], [{ inline = { y = "z" } }]]"#));
+    }
+
+    #[test]
+    fn it_escapes_strings_and_quotes_keys_which_need_it() {
+        let text = toml(
+            r#"{
+                "bare_key-1": "quote \" backslash \\ tab \t newline \n nul \u0000 del \u007f é",
+                "needs quoting": 1,
+                "dotted.key": 2,
+                "": 3,
+                "ключ": "x",
+                "table with space": {"\"": true}
+            }"#,
+        );
+
+        assert_eq!(
+            text,
+            r#"bare_key-1 = "quote \" backslash \\ tab \t newline \n nul \u0000 del \u007F é"
+"needs quoting" = 1
+"dotted.key" = 2
+"" = 3
+"ключ" = "x"
+
+["table with space"]
+"\"" = true
+"#
+        );
+        let table: ::toml::Table = text.parse().unwrap();
+        assert_eq!(
+            table["bare_key-1"].as_str(),
+            Some("quote \" backslash \\ tab \t newline \n nul \u{0} del \u{7f} é")
+        );
+        assert_eq!(table["dotted.key"].as_integer(), Some(2));
+    }
+
+    #[test]
+    fn numbers_are_integers_or_floats() {
+        let text = toml(
+            r#"{"int": -42, "float": 0.1, "big": 1e20, "small": 1e-7, "whole": 3.0,
+                "mixed": [1, 2.5], "ints": [1, 2], "zero": -0.0}"#,
+        );
+
+        assert_eq!(
+            text,
+            "int = -42\nfloat = 0.1\nbig = 1e20\nsmall = 1e-7\nwhole = 3\n\
+             mixed = [1.0, 2.5]\nints = [1, 2]\nzero = -0.0\n"
+        );
+        let special = Json::object()
+            .set("nan", f64::NAN)
+            .set("inf", [f64::INFINITY, f64::NEG_INFINITY].as_slice());
+        assert_eq!(
+            json_to_toml(&special).unwrap(),
+            "nan = nan\ninf = [inf, -inf]\n"
+        );
+    }
+
+    #[test]
+    fn it_fails_on_values_toml_cannot_hold() {
+        let error = |json: &str| json_to_toml(&parse(json).unwrap()).unwrap_err();
+
+        assert_eq!(error("[1]"), TomlConvertError::NotATable("array"));
+        assert_eq!(
+            error(r#"{"a": {"b": [[1], [null]]}}"#),
+            TomlConvertError::Null("/a/b/1/0".to_owned())
+        );
+        assert_eq!(
+            error(r#"{"list": [{"x": null}]}"#),
+            TomlConvertError::Null("/list/0/x".to_owned())
+        );
+        assert_eq!(
+            error(r#"{"a/b": [1, "1"]}"#),
+            TomlConvertError::MixedArray("/a~1b".to_owned())
+        );
+        assert_eq!(
+            error(r#"{"a": [{"b": 1}, 2]}"#),
+            TomlConvertError::MixedArray("/a".to_owned())
+        );
+        assert_eq!(
+            error(r#"{"a": [[1], [{"b": null}]]}"#).to_string(),
+            r#"ERROR: Cannot convert JSON to TOML - null is not allowed at "/a/1/0/b""#
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Drawing a value as a tree, with a line for each item and property joined
+//! to its parent's line by box-drawing connectors.
+
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+    ast::Json,
+    printer::{
+        escape_into, json_to_string_with_options, KeyOrder, PrintError, PrintOptions, PrintStyle,
+    },
+};
+
+/// How `json_to_tree_with_options` draws connectors and strings, and orders
+/// properties.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeOptions {
+    pub glyphs: TreeGlyphs,
+    /// The order of each object's properties, which like the printer's is
+    /// `KeyOrder::Lexicographic` by default.
+    pub key_order: KeyOrder,
+    /// Shortens string values with more than this many characters, as
+    /// `PrintOptions::max_string_length` does.
+    pub max_string_length: Option<usize>,
+}
+
+impl Default for TreeOptions {
+    fn default() -> TreeOptions {
+        TreeOptions {
+            glyphs: TreeGlyphs::default(),
+            key_order: KeyOrder::default(),
+            max_string_length: Some(40),
+        }
+    }
+}
+
+/// The characters connecting each line to its parent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TreeGlyphs {
+    /// `├──`, `└──` and `│`.
+    #[default]
+    Unicode,
+    /// `|--`, `` `-- `` and `|`, for terminals without box-drawing characters.
+    Ascii,
+}
+
+impl TreeGlyphs {
+    /// The connectors before an item which has a sibling after it, before
+    /// the last item, and below an item which has a sibling after it.
+    fn connectors(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            TreeGlyphs::Unicode => ("├── ", "└── ", "│   "),
+            TreeGlyphs::Ascii => ("|-- ", "`-- ", "|   "),
+        }
+    }
+}
+
+/// Draws a value as a tree with the default options, ending each line with
+/// `\n`.
+///
+/// The first line is `.` for the value itself, unless it is a scalar, which
+/// is its only line. Properties are drawn as `key: value` if their value is
+/// a scalar or an empty array or object, or else as `key` with their own
+/// items or properties below them, and items likewise with `[index]` for the
+/// key. Scalars are printed as JSON.
+pub fn json_to_tree(value: &Json) -> Result<String, PrintError> {
+    json_to_tree_with_options(value, &TreeOptions::default())
+}
+
+pub fn json_to_tree_with_options(
+    value: &Json,
+    options: &TreeOptions,
+) -> Result<String, PrintError> {
+    let mut print_options = PrintOptions::new().style(PrintStyle::Compact);
+    if let Some(max_string_length) = options.max_string_length {
+        print_options = print_options.max_string_length(max_string_length);
+    }
+    let mut drawer = TreeDrawer {
+        connectors: options.glyphs.connectors(),
+        key_order: &options.key_order,
+        print_options,
+        output: String::new(),
+        prefix: String::new(),
+    };
+    if has_children(value) {
+        drawer.output.push_str(".\n");
+        drawer.children(value)?;
+    } else {
+        drawer.scalar(value)?;
+    }
+    Ok(drawer.output)
+}
+
+struct TreeDrawer<'a> {
+    connectors: (&'static str, &'static str, &'static str),
+    key_order: &'a KeyOrder,
+    print_options: PrintOptions,
+    output: String,
+    /// The connectors drawn before each line at the current depth.
+    prefix: String,
+}
+
+impl TreeDrawer<'_> {
+    fn children(&mut self, value: &Json) -> Result<(), PrintError> {
+        match value {
+            Json::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    let label = format!("[{index}]");
+                    self.child(&label, item, index + 1 == items.len())?;
+                }
+            }
+            Json::Object(properties) => {
+                let mut entries: Vec<_> = properties.iter().collect();
+                entries.sort_by(|(a, _), (b, _)| self.key_order.compare(a, b));
+                for (index, (key, property)) in entries.into_iter().enumerate() {
+                    let mut label = String::new();
+                    escape_into(key, &mut label).map_err(|_| PrintError::Write)?;
+                    self.child(&label, property, index + 1 == properties.len())?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn child(&mut self, label: &str, value: &Json, is_last: bool) -> Result<(), PrintError> {
+        let (middle, last, below) = self.connectors;
+        self.output.push_str(&self.prefix);
+        self.output.push_str(if is_last { last } else { middle });
+        self.output.push_str(label);
+        if !has_children(value) {
+            self.output.push_str(": ");
+            return self.scalar(value);
+        }
+        self.output.push('\n');
+
+        let length = self.prefix.len();
+        self.prefix.push_str(if is_last { "    " } else { below });
+        self.children(value)?;
+        self.prefix.truncate(length);
+        Ok(())
+    }
+
+    /// Writes a scalar, or an empty array or object, and ends the line.
+    fn scalar(&mut self, value: &Json) -> Result<(), PrintError> {
+        self.output
+            .push_str(&json_to_string_with_options(value, &self.print_options)?);
+        self.output.push('\n');
+        Ok(())
+    }
+}
+
+fn has_children(value: &Json) -> bool {
+    match value {
+        Json::Array(items) => !items.is_empty(),
+        Json::Object(properties) => !properties.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        dsl::ToJson,
+        parser::parse,
+        printer::{KeyOrder, PrintError},
+        tree::{json_to_tree, json_to_tree_with_options, TreeGlyphs, TreeOptions},
+    };
+
+    const FIXTURE: &str = r#"{
+        "name": "api",
+        "servers": [
+            {"host": "a.example.com", "ports": [80, 443], "tags": {}},
+            "standby"
+        ],
+        "limits": {"rate": 1.5, "burst": null, "nested": {"deep": [[true]]}},
+        "line\nbreak": "a string which is much too long to show in full",
+        "empty": []
+    }"#;
+
+    #[test]
+    fn it_draws_a_nested_document_with_box_drawing_connectors() {
+        assert_eq!(
+            json_to_tree(&parse(FIXTURE).unwrap()).unwrap(),
+            r#".
+├── empty: []
+├── limits
+│   ├── burst: null
+│   ├── nested
+│   │   └── deep
+│   │       └── [0]
+│   │           └── [0]: true
+│   └── rate: 1.5
+├── line\nbreak: "a string which is much too long to show … (+7 chars)"
+├── name: "api"
+└── servers
+    ├── [0]
+    │   ├── host: "a.example.com"
+    │   ├── ports
+    │   │   ├── [0]: 80
+    │   │   └── [1]: 443
+    │   └── tags: {}
+    └── [1]: "standby"
+"#
+        );
+    }
+
+    #[test]
+    fn it_draws_ascii_connectors_full_strings_and_document_order_as_configured() {
+        let options = TreeOptions {
+            glyphs: TreeGlyphs::Ascii,
+            key_order: KeyOrder::Document,
+            max_string_length: None,
+        };
+
+        assert_eq!(
+            json_to_tree_with_options(&parse(FIXTURE).unwrap(), &options).unwrap(),
+            r#".
+|-- name: "api"
+|-- servers
+|   |-- [0]
+|   |   |-- host: "a.example.com"
+|   |   |-- ports
+|   |   |   |-- [0]: 80
+|   |   |   `-- [1]: 443
+|   |   `-- tags: {}
+|   `-- [1]: "standby"
+|-- limits
+|   |-- rate: 1.5
+|   |-- burst: null
+|   `-- nested
+|       `-- deep
+|           `-- [0]
+|               `-- [0]: true
+|-- line\nbreak: "a string which is much too long to show in full"
+`-- empty: []
+"#
+        );
+    }
+
+    #[test]
+    fn scalars_and_empty_values_are_a_single_line() {
+        assert_eq!(json_to_tree(&"x".to_json()).unwrap(), "\"x\"\n");
+        assert_eq!(json_to_tree(&Json::object()).unwrap(), "{}\n");
+        assert_eq!(
+            json_to_tree(&vec![vec![1]].to_json()).unwrap(),
+            ".\n└── [0]\n    └── [0]: 1\n"
+        );
+    }
+
+    #[test]
+    fn it_fails_on_non_finite_numbers() {
+        assert_eq!(
+            json_to_tree(&Json::object().set("n", f64::INFINITY)),
+            Err(PrintError::NonFiniteNumber(f64::INFINITY))
+        );
+    }
+}
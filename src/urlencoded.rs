@@ -0,0 +1,337 @@
+//! Encoding an object as an `application/x-www-form-urlencoded` query
+//! string, such as `name=ada&tag=a&tag=b`.
+
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+use core::fmt::{self, Write};
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::{
+    ast::{push_pointer_token, Json},
+    printer::json_to_compact_string,
+};
+
+/// How `json_to_query_string_with_options` writes spaces, arrays and nested
+/// objects.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QueryEncodeOptions {
+    /// Encode spaces as `%20` rather than `+`.
+    pub percent_encode_spaces: bool,
+    pub arrays: ArrayEncoding,
+    /// Write the properties of nested objects with their keys in brackets
+    /// after the object's key, as in `user[name]=ada`, rather than failing.
+    /// Arrays and objects inside arrays are written with their index in
+    /// brackets, as in `users[0][name]=ada`.
+    pub flatten_objects: bool,
+}
+
+/// How the items of an array property are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayEncoding {
+    /// `tag=a&tag=b`
+    #[default]
+    RepeatedKeys,
+    /// `tag[]=a&tag[]=b`
+    Brackets,
+}
+
+/// An error from encoding a value which doesn't fit in a query string.
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueryEncodeError {
+    /// The value has this type, but must be an object.
+    NotAnObject(&'static str),
+    /// There is an array or object at this JSON Pointer, nested where
+    /// objects aren't being flattened.
+    Nested(String),
+    /// The number at this JSON Pointer is NaN or an infinity, which JSON
+    /// can't represent.
+    NonFiniteNumber(String),
+}
+
+impl fmt::Display for QueryEncodeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("ERROR: Cannot encode query string - ")?;
+        match self {
+            QueryEncodeError::NotAnObject(found) => {
+                formatter.write_fmt(format_args!("expected an object, found {found}"))
+            }
+            QueryEncodeError::Nested(pointer) => formatter.write_fmt(format_args!(
+                "the value at {pointer:?} is nested too deeply"
+            )),
+            QueryEncodeError::NonFiniteNumber(pointer) => formatter.write_fmt(format_args!(
+                "the number at {pointer:?} is not a valid JSON number"
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for QueryEncodeError {}
+
+/// Encodes the properties of an object as a query string, with array
+/// properties as repeated keys and spaces as `+`, failing on nested objects.
+///
+/// Keys and values are percent-encoded as UTF-8, except for ASCII letters,
+/// digits and `*-._`. Strings are written as they are, other values as
+/// JSON, and `null` as an empty value, like `""`.
+pub fn json_to_query_string(value: &Json) -> Result<String, QueryEncodeError> {
+    json_to_query_string_with_options(value, &QueryEncodeOptions::default())
+}
+
+pub fn json_to_query_string_with_options(
+    value: &Json,
+    options: &QueryEncodeOptions,
+) -> Result<String, QueryEncodeError> {
+    let properties = value
+        .as_object()
+        .ok_or(QueryEncodeError::NotAnObject(value.type_name()))?;
+    let mut encoder = Encoder {
+        options,
+        output: String::new(),
+        pointer: String::new(),
+    };
+    for (key, value) in properties {
+        let mut name = String::new();
+        encoder.percent_encode(key, &mut name);
+        encoder.with_token(key, |encoder| encoder.pair(&name, value))?;
+    }
+    Ok(encoder.output)
+}
+
+struct Encoder<'a> {
+    options: &'a QueryEncodeOptions,
+    output: String,
+    /// The JSON Pointer of the value being encoded, for errors.
+    pointer: String,
+}
+
+impl Encoder<'_> {
+    /// Writes a value under its encoded name, or each of its items or
+    /// properties under names derived from it.
+    fn pair(&mut self, name: &str, value: &Json) -> Result<(), QueryEncodeError> {
+        match value {
+            Json::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.with_token(&index.to_string(), |encoder| match item {
+                        Json::Array(_) | Json::Object(_) if encoder.options.flatten_objects => {
+                            encoder.pair(&format!("{name}[{index}]"), item)
+                        }
+                        Json::Array(_) | Json::Object(_) => {
+                            Err(QueryEncodeError::Nested(encoder.pointer.clone()))
+                        }
+                        _ => match encoder.options.arrays {
+                            ArrayEncoding::RepeatedKeys => encoder.pair(name, item),
+                            ArrayEncoding::Brackets => encoder.pair(&format!("{name}[]"), item),
+                        },
+                    })?;
+                }
+            }
+            Json::Object(properties) if self.options.flatten_objects => {
+                for (key, property) in properties {
+                    let mut nested_name = format!("{name}[");
+                    self.percent_encode(key, &mut nested_name);
+                    nested_name.push(']');
+                    self.with_token(key, |encoder| encoder.pair(&nested_name, property))?;
+                }
+            }
+            Json::Object(_) => return Err(QueryEncodeError::Nested(self.pointer.clone())),
+            scalar => {
+                if !self.output.is_empty() {
+                    self.output.push('&');
+                }
+                self.output.push_str(name);
+                self.output.push('=');
+                let mut text = String::new();
+                match scalar {
+                    Json::String(string) => text.push_str(string),
+                    Json::Null => {}
+                    scalar => {
+                        text = json_to_compact_string(scalar)
+                            .map_err(|_| QueryEncodeError::NonFiniteNumber(self.pointer.clone()))?;
+                    }
+                }
+                let mut encoded = String::new();
+                self.percent_encode(&text, &mut encoded);
+                self.output.push_str(&encoded);
+            }
+        }
+        Ok(())
+    }
+
+    fn with_token(
+        &mut self,
+        token: &str,
+        encode: impl FnOnce(&mut Self) -> Result<(), QueryEncodeError>,
+    ) -> Result<(), QueryEncodeError> {
+        let length = push_pointer_token(&mut self.pointer, token);
+        encode(self)?;
+        self.pointer.truncate(length);
+        Ok(())
+    }
+
+    fn percent_encode(&self, text: &str, output: &mut String) {
+        for byte in text.bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                    output.push(char::from(byte))
+                }
+                b' ' if !self.options.percent_encode_spaces => output.push('+'),
+                _ => {
+                    let _ = write!(output, "%{byte:02X}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        dsl::ToJson,
+        parser::parse,
+        urlencoded::{
+            json_to_query_string, json_to_query_string_with_options, ArrayEncoding,
+            QueryEncodeError, QueryEncodeOptions,
+        },
+    };
+
+    fn encode(json: &str, options: &QueryEncodeOptions) -> Result<String, QueryEncodeError> {
+        json_to_query_string_with_options(&parse(json).unwrap(), options)
+    }
+
+    #[test]
+    fn it_encodes_scalars_in_order() {
+        assert_eq!(
+            encode(
+                r#"{"name": "ada", "age": 36, "ratio": 0.5, "admin": false, "big": 1e21}"#,
+                &QueryEncodeOptions::default()
+            ),
+            Ok("name=ada&age=36&ratio=0.5&admin=false&big=1000000000000000000000".to_owned())
+        );
+        assert_eq!(json_to_query_string(&Json::object()), Ok(String::new()));
+    }
+
+    #[test]
+    fn it_percent_encodes_reserved_characters_and_unicode() {
+        let document = Json::object()
+            .set("q", "a b&c=d/e?f#g+h%i")
+            .set("kept", "AZaz09*-._")
+            .set("other", "~!'()[],;:@$")
+            .set("unicode", "é 😀")
+            .set("key with spaces & =", "x");
+
+        assert_eq!(
+            json_to_query_string(&document).unwrap(),
+            "q=a+b%26c%3Dd%2Fe%3Ff%23g%2Bh%25i\
+             &kept=AZaz09*-._\
+             &other=%7E%21%27%28%29%5B%5D%2C%3B%3A%40%24\
+             &unicode=%C3%A9+%F0%9F%98%80\
+             &key+with+spaces+%26+%3D=x"
+        );
+        assert_eq!(
+            json_to_query_string_with_options(
+                &document,
+                &QueryEncodeOptions {
+                    percent_encode_spaces: true,
+                    ..QueryEncodeOptions::default()
+                }
+            )
+            .unwrap()
+            .split('&')
+            .nth(3),
+            Some("unicode=%C3%A9%20%F0%9F%98%80")
+        );
+    }
+
+    #[test]
+    fn empty_strings_and_nulls_are_empty_values() {
+        assert_eq!(
+            encode(
+                r#"{"empty": "", "none": null, "": "no key", "list": []}"#,
+                &QueryEncodeOptions::default()
+            ),
+            Ok("empty=&none=&=no+key".to_owned())
+        );
+    }
+
+    #[test]
+    fn arrays_repeat_their_key_or_add_brackets() {
+        let document = r#"{"tag": ["a", "b c"], "n": [1, null]}"#;
+        let brackets = QueryEncodeOptions {
+            arrays: ArrayEncoding::Brackets,
+            ..QueryEncodeOptions::default()
+        };
+
+        assert_eq!(
+            encode(document, &QueryEncodeOptions::default()),
+            Ok("tag=a&tag=b+c&n=1&n=".to_owned())
+        );
+        assert_eq!(
+            encode(document, &brackets),
+            Ok("tag[]=a&tag[]=b+c&n[]=1&n[]=".to_owned())
+        );
+    }
+
+    #[test]
+    fn nested_objects_are_flattened_with_brackets() {
+        let document = r#"{
+            "user": {"name": "ada", "address": {"city": "Zürich"}, "tags": ["x", "y"]},
+            "rows": [{"id": 1}, [2, 3]],
+            "a[b]": {"c d": 1}
+        }"#;
+        let flatten = |arrays| QueryEncodeOptions {
+            flatten_objects: true,
+            arrays,
+            ..QueryEncodeOptions::default()
+        };
+
+        assert_eq!(
+            encode(document, &flatten(ArrayEncoding::RepeatedKeys)),
+            Ok(
+                "user[name]=ada&user[address][city]=Z%C3%BCrich&user[tags]=x&user[tags]=y\
+                &rows[0][id]=1&rows[1]=2&rows[1]=3&a%5Bb%5D[c+d]=1"
+                    .to_owned()
+            )
+        );
+        assert_eq!(
+            encode(document, &flatten(ArrayEncoding::Brackets)),
+            Ok(
+                "user[name]=ada&user[address][city]=Z%C3%BCrich&user[tags][]=x&user[tags][]=y\
+                &rows[0][id]=1&rows[1][]=2&rows[1][]=3&a%5Bb%5D[c+d]=1"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn it_fails_on_values_a_query_string_cannot_hold() {
+        let options = QueryEncodeOptions::default();
+
+        assert_eq!(
+            encode("[1]", &options),
+            Err(QueryEncodeError::NotAnObject("array"))
+        );
+        assert_eq!(
+            encode(r#"{"ok": 1, "user": {"name": "ada"}}"#, &options),
+            Err(QueryEncodeError::Nested("/user".to_owned()))
+        );
+        assert_eq!(
+            encode(r#"{"a/b": [1, [2]]}"#, &options),
+            Err(QueryEncodeError::Nested("/a~1b/1".to_owned()))
+        );
+        assert_eq!(
+            json_to_query_string(&Json::object().set("n", vec![f64::INFINITY]))
+                .unwrap_err()
+                .to_string(),
+            r#"ERROR: Cannot encode query string - the number at "/n/0" is not a valid JSON number"#
+        );
+        assert_eq!(
+            json_to_query_string(&1.to_json()),
+            Err(QueryEncodeError::NotAnObject("number"))
+        );
+    }
+}
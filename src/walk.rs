@@ -0,0 +1,326 @@
+//! Visiting every value in a document along with its path.
+
+use std::fmt::{self, Write};
+
+use crate::ast::{escape_pointer_token, Json};
+
+/// One step from a value into a property of an object or an item of an array.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Where a value lives in a document, displayed as a JSON Pointer (RFC 6901).
+/// The path of the document itself is empty.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JsonPath(Vec<PathSegment>);
+
+impl JsonPath {
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.0 {
+            formatter.write_char('/')?;
+            match segment {
+                PathSegment::Key(key) => formatter.write_str(&escape_pointer_token(key))?,
+                PathSegment::Index(index) => formatter.write_fmt(format_args!("{index}"))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a walk should do after visiting a value. Callbacks which return `()`
+/// always continue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Walk {
+    /// Go on to visit the value's children.
+    Continue,
+    /// Skip the value's children, going on to its next sibling.
+    SkipChildren,
+}
+
+impl From<()> for Walk {
+    fn from(_: ()) -> Walk {
+        Walk::Continue
+    }
+}
+
+impl Json {
+    /// Calls `visit` on every value in the document with its path. Each value
+    /// is visited before its children, the entries of an object in the map's
+    /// order and the items of an array in index order.
+    pub fn walk<'a, R: Into<Walk>>(&'a self, mut visit: impl FnMut(&JsonPath, &'a Json) -> R) {
+        walk(self, &mut JsonPath::default(), &mut visit);
+    }
+
+    /// Like `walk`, but the callback can change the values it is given. The
+    /// children visited are those of the value the callback leaves in place.
+    pub fn walk_mut<R: Into<Walk>>(&mut self, mut visit: impl FnMut(&JsonPath, &mut Json) -> R) {
+        walk_mut(self, &mut JsonPath::default(), &mut visit);
+    }
+
+    /// Finds every value matching the predicate, with its JSON Pointer, in
+    /// the order `walk` visits them. A match inside another match is found
+    /// as well.
+    pub fn find_all(&self, predicate: impl Fn(&Json) -> bool) -> Vec<(String, &Json)> {
+        let mut found = Vec::new();
+        self.walk(|path, value| {
+            if predicate(value) {
+                found.push((path.to_string(), value));
+            }
+        });
+        found
+    }
+
+    /// Finds every object property whose key matches the predicate, giving
+    /// the JSON Pointer and value of each.
+    pub fn find_keys(&self, predicate: impl Fn(&str) -> bool) -> Vec<(String, &Json)> {
+        let mut found = Vec::new();
+        self.walk(|path, value| {
+            if let Some(PathSegment::Key(key)) = path.segments().last() {
+                if predicate(key) {
+                    found.push((path.to_string(), value));
+                }
+            }
+        });
+        found
+    }
+}
+
+fn walk<'a, R: Into<Walk>>(
+    value: &'a Json,
+    path: &mut JsonPath,
+    visit: &mut impl FnMut(&JsonPath, &'a Json) -> R,
+) {
+    if visit(path, value).into() == Walk::SkipChildren {
+        return;
+    }
+
+    match value {
+        Json::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                path.0.push(PathSegment::Index(index));
+                walk(item, path, visit);
+                path.0.pop();
+            }
+        }
+        Json::Object(properties) => {
+            for (key, property) in properties {
+                path.0.push(PathSegment::Key(key.clone()));
+                walk(property, path, visit);
+                path.0.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+fn walk_mut<R: Into<Walk>>(
+    value: &mut Json,
+    path: &mut JsonPath,
+    visit: &mut impl FnMut(&JsonPath, &mut Json) -> R,
+) {
+    if visit(path, value).into() == Walk::SkipChildren {
+        return;
+    }
+
+    match value {
+        Json::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                path.0.push(PathSegment::Index(index));
+                walk_mut(item, path, visit);
+                path.0.pop();
+            }
+        }
+        Json::Object(properties) => {
+            for (key, property) in properties {
+                path.0.push(PathSegment::Key(key.clone()));
+                walk_mut(property, path, visit);
+                path.0.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        dsl::ToJson,
+        parser::parse,
+        walk::{JsonPath, PathSegment, Walk},
+    };
+
+    fn fixture() -> Json {
+        parse(r#"{"b": [1, {"c/d": null}], "a": "x", "e~": {}, "f": [true]}"#).unwrap()
+    }
+
+    #[test]
+    fn it_visits_parents_before_children_in_document_order() {
+        let mut visited = Vec::new();
+
+        fixture().walk(|path, value| visited.push((path.to_string(), value.type_name())));
+
+        assert_eq!(
+            visited,
+            [
+                ("", "object"),
+                ("/b", "array"),
+                ("/b/0", "number"),
+                ("/b/1", "object"),
+                ("/b/1/c~1d", "null"),
+                ("/a", "string"),
+                ("/e~0", "object"),
+                ("/f", "array"),
+                ("/f/0", "boolean"),
+            ]
+            .map(|(pointer, type_name)| (pointer.to_owned(), type_name))
+        );
+    }
+
+    #[test]
+    fn its_paths_address_the_values_they_are_given() {
+        let document = fixture();
+
+        document.walk(|path, value| {
+            assert_eq!(document.pointer(&path.to_string()), Some(value));
+        });
+    }
+
+    #[test]
+    fn it_can_skip_the_children_of_a_value() {
+        let mut visited = Vec::new();
+
+        fixture().walk(|path, value| {
+            visited.push(path.to_string());
+            if value.is_array() {
+                Walk::SkipChildren
+            } else {
+                Walk::Continue
+            }
+        });
+
+        assert_eq!(visited, ["", "/b", "/a", "/e~0", "/f"]);
+    }
+
+    #[test]
+    fn it_gives_paths_as_segments() {
+        let mut paths = Vec::new();
+
+        parse(r#"{"a": [0]}"#)
+            .unwrap()
+            .walk(|path, _| paths.push(path.clone()));
+
+        assert_eq!(paths[0], JsonPath::default());
+        assert_eq!(
+            paths[2].segments(),
+            [PathSegment::Key("a".to_owned()), PathSegment::Index(0)]
+        );
+    }
+
+    #[test]
+    fn walk_mut_visits_the_children_of_the_value_the_callback_leaves() {
+        let mut document = fixture();
+        let mut visited = Vec::new();
+
+        document.walk_mut(|path, value| {
+            visited.push(path.to_string());
+            if path.to_string() == "/f" {
+                *value = 2.to_json();
+            } else if value == &"x".to_json() {
+                *value = vec!["y", "z"].to_json();
+            } else if let Json::String(string) = value {
+                *string = string.to_uppercase();
+            } else if let Json::Number(number) = value {
+                *number *= 10.0;
+            }
+        });
+
+        assert_eq!(
+            document,
+            parse(r#"{"b": [10, {"c/d": null}], "a": ["Y", "Z"], "e~": {}, "f": 2}"#).unwrap()
+        );
+        assert_eq!(
+            visited,
+            [
+                "",
+                "/b",
+                "/b/0",
+                "/b/1",
+                "/b/1/c~1d",
+                "/a",
+                "/a/0",
+                "/a/1",
+                "/e~0",
+                "/f"
+            ]
+        );
+    }
+
+    #[test]
+    fn walk_mut_can_skip_the_children_of_a_value() {
+        let mut document = fixture();
+
+        document.walk_mut(|_, value| match value {
+            Json::Object(_) => Walk::SkipChildren,
+            _ => {
+                *value = Json::Null;
+                Walk::Continue
+            }
+        });
+
+        assert_eq!(document, fixture());
+    }
+
+    #[test]
+    fn find_all_gives_every_match_with_its_pointer_in_document_order() {
+        let document = parse(
+            r#"{"a/b": {"password": "x", "list": ["password", {"~": "password"}]}, "c": "password"}"#,
+        )
+        .unwrap();
+
+        let found = document.find_all(|value| value == &"password".to_json() || value.is_object());
+
+        assert_eq!(
+            found
+                .iter()
+                .map(|(pointer, value)| (pointer.as_str(), value.type_name()))
+                .collect::<Vec<_>>(),
+            [
+                ("", "object"),
+                ("/a~1b", "object"),
+                ("/a~1b/list/0", "string"),
+                ("/a~1b/list/1", "object"),
+                ("/a~1b/list/1/~0", "string"),
+                ("/c", "string"),
+            ]
+        );
+        assert!(document.find_all(|value| value.is_number()).is_empty());
+    }
+
+    #[test]
+    fn find_keys_gives_the_properties_with_matching_keys() {
+        let document =
+            parse(r#"{"password": {"password": 1}, "list": [{"user/password": 2}], "other": 3}"#)
+                .unwrap();
+
+        assert_eq!(
+            document.find_keys(|key| key.contains("password")),
+            [
+                (
+                    "/password".to_owned(),
+                    &parse(r#"{"password": 1}"#).unwrap()
+                ),
+                ("/password/password".to_owned(), &1.to_json()),
+                ("/list/0/user~1password".to_owned(), &2.to_json()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Bindings for formatting JSON from JavaScript, in a browser or Node.js,
+//! behind the `wasm` feature. Build them for `wasm32-unknown-unknown` with
+//! wasm-bindgen or wasm-pack.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    parser::{self, parse, JsonParseError},
+    printer::{json_to_string, PrintError},
+};
+
+/// An error from `format` or `validate`, with the line and column of the
+/// problem in the input if it is known.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonError {
+    pub message: String,
+    /// The line number, starting from 1.
+    pub line: Option<u32>,
+    /// The column number in characters, starting from 1.
+    pub column: Option<u32>,
+}
+
+impl From<JsonParseError> for JsonError {
+    fn from(error: JsonParseError) -> JsonError {
+        let to_u32 = |number: usize| u32::try_from(number).unwrap_or(u32::MAX);
+        JsonError {
+            message: error.to_string(),
+            line: error.position.map(|position| to_u32(position.line)),
+            column: error.position.map(|position| to_u32(position.column)),
+        }
+    }
+}
+
+impl From<PrintError> for JsonError {
+    fn from(error: PrintError) -> JsonError {
+        JsonError {
+            message: error.to_string(),
+            line: None,
+            column: None,
+        }
+    }
+}
+
+/// Formats a JSON document, indenting by the number of spaces.
+#[wasm_bindgen]
+pub fn format(input: &str, indent: u32) -> Result<String, JsValue> {
+    format_document(input, indent).map_err(JsValue::from)
+}
+
+/// Checks that the input is a valid JSON document.
+#[wasm_bindgen]
+pub fn validate(input: &str) -> Result<(), JsValue> {
+    parser::validate(input)
+        .map_err(JsonError::from)
+        .map_err(JsValue::from)
+}
+
+fn format_document(input: &str, indent: u32) -> Result<String, JsonError> {
+    let json = parse(input)?;
+    Ok(json_to_string(&json, indent.into())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        parser,
+        printer::json_to_string,
+        wasm::{format_document, JsonError},
+    };
+
+    #[test]
+    fn it_formats_documents_with_the_indent() {
+        assert_eq!(
+            format_document(r#"{"a": [1]}"#, 4),
+            Ok("{\n    \"a\": [\n        1\n    ]\n}".to_owned())
+        );
+        assert_eq!(format_document(" true ", 0), Ok("true".to_owned()));
+    }
+
+    #[test]
+    fn parse_errors_carry_the_message_line_and_column() {
+        let error = format_document("{\n  \"a\": tru\n}", 2).unwrap_err();
+
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.column, Some(11));
+        assert!(error.message.starts_with("ERROR: Invalid JSON - "));
+        assert_eq!(
+            JsonError::from(parser::validate("[1,]").unwrap_err()),
+            format_document("[1,]", 2).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn numbers_too_large_for_an_f64_fail_to_format_and_validate() {
+        let error = JsonError {
+            message: "ERROR: Invalid JSON - Number too large for an f64 at line 1, column 2"
+                .to_owned(),
+            line: Some(1),
+            column: Some(2),
+        };
+
+        assert_eq!(format_document("[1e999]", 2), Err(error.clone()));
+        assert_eq!(
+            parser::validate("[1e999]").map_err(JsonError::from),
+            Err(error)
+        );
+    }
+
+    #[test]
+    fn print_errors_have_no_position() {
+        let error = JsonError::from(json_to_string(&Json::Number(f64::INFINITY), 2).unwrap_err());
+
+        assert_eq!(
+            error,
+            JsonError {
+                message: "ERROR: Unprintable JSON - inf is not a valid JSON number".to_owned(),
+                line: None,
+                column: None,
+            }
+        );
+    }
+}
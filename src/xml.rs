@@ -0,0 +1,518 @@
+//! Converting a document to XML, for systems which only read that.
+//!
+//! The document is written in a root element. The properties of an object
+//! are elements named by their keys, and an array property repeats its
+//! element once for each item, so `{"tag": ["a", "b"]}` is
+//! `<tag>a</tag><tag>b</tag>`. The items of any other array, such as the
+//! root or an array inside an array, are elements named by
+//! `XmlOptions::item`. Strings, numbers and booleans are the text of their
+//! element, and `null` is an empty element.
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::{
+    ast::{push_pointer_token, Json},
+    printer::json_to_compact_string,
+};
+
+/// How `json_to_xml` names and lays out elements.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XmlOptions {
+    /// The name of the element the document is written in, `root` by
+    /// default.
+    pub root: String,
+    /// The name of the elements for the items of arrays which aren't the
+    /// value of a property, `item` by default.
+    pub item: String,
+    /// Replace each character which can't be in an element or attribute
+    /// name with `_`, and put `_` before a name which can't start with its
+    /// first character, rather than failing.
+    pub sanitize_names: bool,
+    /// Write properties whose keys start with `@` as attributes of their
+    /// object's element, and a `#text` property as its text, as in
+    /// `{"@id": 1, "#text": "a"}` for `<item id="1">a</item>`. Properties
+    /// which are `null` are left out.
+    pub attributes: bool,
+    /// The number of spaces to indent each level of elements by, each on its
+    /// own line, or `None` to write everything on one line. Elements with
+    /// text and other elements inside them are always on one line, so that
+    /// no space is added to the text. 2 by default.
+    pub indent: Option<usize>,
+}
+
+impl Default for XmlOptions {
+    fn default() -> XmlOptions {
+        XmlOptions {
+            root: "root".to_owned(),
+            item: "item".to_owned(),
+            sanitize_names: false,
+            attributes: false,
+            indent: Some(2),
+        }
+    }
+}
+
+/// An error from converting a value which can't be written as XML. Each
+/// gives the JSON Pointer of the value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum XmlConvertError {
+    /// A key, or the root or item name of the options, isn't a valid name,
+    /// and names aren't being sanitized.
+    InvalidName { pointer: String, name: String },
+    /// A string contains a character which XML 1.0 doesn't allow, even
+    /// escaped, such as most control characters.
+    InvalidCharacter(String),
+    /// A number is NaN or an infinity, which JSON can't represent.
+    NonFiniteNumber(String),
+    /// An attribute or `#text` property is an array or object.
+    NotScalar(String),
+}
+
+impl fmt::Display for XmlConvertError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("ERROR: Cannot convert JSON to XML - ")?;
+        match self {
+            XmlConvertError::InvalidName { pointer, name } => formatter.write_fmt(format_args!(
+                "{name:?} is not a valid element name at {pointer:?}"
+            )),
+            XmlConvertError::InvalidCharacter(pointer) => formatter.write_fmt(format_args!(
+                "the string at {pointer:?} has a character XML doesn't allow"
+            )),
+            XmlConvertError::NonFiniteNumber(pointer) => formatter.write_fmt(format_args!(
+                "the number at {pointer:?} is not a valid JSON number"
+            )),
+            XmlConvertError::NotScalar(pointer) => formatter.write_fmt(format_args!(
+                "the attribute or text at {pointer:?} is not a string, number or boolean"
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for XmlConvertError {}
+
+/// Converts a value to an XML document, with an XML declaration, in the
+/// element named by `options.root`.
+pub fn json_to_xml(value: &Json, options: &XmlOptions) -> Result<String, XmlConvertError> {
+    let mut writer = XmlWriter {
+        options,
+        output: String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#),
+        pointer: String::new(),
+        item: String::new(),
+    };
+    writer.item = writer.name(&options.item)?;
+    let root = writer.name(&options.root)?;
+    writer.element(&root, value, 0, true)?;
+    writer.output.push('\n');
+    Ok(writer.output)
+}
+
+struct XmlWriter<'a> {
+    options: &'a XmlOptions,
+    output: String,
+    /// The JSON Pointer of the value being written, for errors.
+    pointer: String,
+    /// The checked or sanitized name for array items.
+    item: String,
+}
+
+impl XmlWriter<'_> {
+    /// Writes a value as an element, starting on a new line if `indented`.
+    fn element(
+        &mut self,
+        name: &str,
+        value: &Json,
+        depth: usize,
+        indented: bool,
+    ) -> Result<(), XmlConvertError> {
+        if indented {
+            self.line_break(depth);
+        }
+        self.output.push('<');
+        self.output.push_str(name);
+
+        // Each child has the key and index of its JSON Pointer below this
+        // element, its name and its value.
+        let mut text = None;
+        let mut children: Vec<(Option<&str>, Option<usize>, String, &Json)> = Vec::new();
+        match value {
+            Json::Null => {}
+            Json::Array(items) => children.extend(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| (None, Some(index), self.item.clone(), item)),
+            ),
+            Json::Object(properties) => {
+                for (key, property) in properties {
+                    let length = push_pointer_token(&mut self.pointer, key);
+                    match key.strip_prefix('@') {
+                        Some(attribute) if self.options.attributes => {
+                            self.attribute(attribute, property)?;
+                        }
+                        _ if self.options.attributes && key == "#text" => {
+                            text = Some(self.scalar(property)?).filter(|_| !property.is_null());
+                        }
+                        _ => {
+                            let name = self.name(key)?;
+                            match property {
+                                Json::Array(items) => children.extend(
+                                    items.iter().enumerate().map(|(index, item)| {
+                                        (Some(key.as_str()), Some(index), name.clone(), item)
+                                    }),
+                                ),
+                                property => children.push((Some(key), None, name, property)),
+                            }
+                        }
+                    }
+                    self.pointer.truncate(length);
+                }
+            }
+            scalar => text = Some(scalar),
+        }
+
+        if text.is_none() && children.is_empty() {
+            self.output.push_str("/>");
+            return Ok(());
+        }
+        self.output.push('>');
+        if let Some(text) = text {
+            self.text(text, false)?;
+        }
+        let indent_children = indented && text.is_none();
+        for (key, index, name, child) in children {
+            let length = self.pointer.len();
+            if let Some(key) = key {
+                push_pointer_token(&mut self.pointer, key);
+            }
+            if let Some(index) = index {
+                push_pointer_token(&mut self.pointer, &index.to_string());
+            }
+            self.element(&name, child, depth + 1, indent_children)?;
+            self.pointer.truncate(length);
+        }
+        if indent_children {
+            self.line_break(depth);
+        }
+        self.output.push_str("</");
+        self.output.push_str(name);
+        self.output.push('>');
+        Ok(())
+    }
+
+    fn attribute(&mut self, name: &str, value: &Json) -> Result<(), XmlConvertError> {
+        let value = self.scalar(value)?;
+        if value.is_null() {
+            return Ok(());
+        }
+        let name = self.name(name)?;
+        self.output.push(' ');
+        self.output.push_str(&name);
+        self.output.push_str("=\"");
+        self.text(value, true)?;
+        self.output.push('"');
+        Ok(())
+    }
+
+    /// Checks that the value of an attribute or `#text` property isn't an
+    /// array or object.
+    fn scalar<'a>(&self, value: &'a Json) -> Result<&'a Json, XmlConvertError> {
+        match value {
+            Json::Array(_) | Json::Object(_) => {
+                Err(XmlConvertError::NotScalar(self.pointer.clone()))
+            }
+            scalar => Ok(scalar),
+        }
+    }
+
+    /// Writes a string, number or boolean, escaping the characters which
+    /// would be read as markup, and those which an attribute's value would
+    /// have normalized to spaces.
+    fn text(&mut self, value: &Json, in_attribute: bool) -> Result<(), XmlConvertError> {
+        let text = match value {
+            Json::String(string) => string.as_str(),
+            Json::Boolean(true) => "true",
+            Json::Boolean(false) => "false",
+            number => {
+                let number = json_to_compact_string(number)
+                    .map_err(|_| XmlConvertError::NonFiniteNumber(self.pointer.clone()))?;
+                self.output.push_str(&number);
+                return Ok(());
+            }
+        };
+        for c in text.chars() {
+            match c {
+                '&' => self.output.push_str("&amp;"),
+                '<' => self.output.push_str("&lt;"),
+                '>' => self.output.push_str("&gt;"),
+                '"' => self.output.push_str("&quot;"),
+                '\'' => self.output.push_str("&apos;"),
+                '\r' => self.output.push_str("&#13;"),
+                '\n' if in_attribute => self.output.push_str("&#10;"),
+                '\t' if in_attribute => self.output.push_str("&#9;"),
+                '\t' | '\n' | ' '..='\u{d7ff}' | '\u{e000}'..='\u{fffd}' | '\u{10000}'.. => {
+                    self.output.push(c)
+                }
+                _ => return Err(XmlConvertError::InvalidCharacter(self.pointer.clone())),
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a key is a valid name, or sanitizes it if the options
+    /// say to. Colons are never allowed, since they mark namespaces.
+    fn name(&self, key: &str) -> Result<String, XmlConvertError> {
+        let is_name_start = |c: char| c == '_' || c.is_alphabetic();
+        let is_name = |c: char| is_name_start(c) || c.is_alphanumeric() || c == '-' || c == '.';
+        let is_valid = key.chars().next().is_some_and(is_name_start) && key.chars().all(is_name);
+        if is_valid {
+            Ok(key.to_owned())
+        } else if self.options.sanitize_names {
+            let sanitized = key.chars().map(|c| if is_name(c) { c } else { '_' });
+            match key.chars().next() {
+                Some(first) if is_name_start(first) => Ok(sanitized.collect()),
+                _ => Ok(core::iter::once('_').chain(sanitized).collect()),
+            }
+        } else {
+            Err(XmlConvertError::InvalidName {
+                pointer: self.pointer.clone(),
+                name: key.to_owned(),
+            })
+        }
+    }
+
+    fn line_break(&mut self, depth: usize) {
+        if let Some(indent) = self.options.indent {
+            self.output.push('\n');
+            self.output
+                .extend(core::iter::repeat_n(' ', indent * depth));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::Json,
+        dsl::ToJson,
+        parser::parse,
+        xml::{json_to_xml, XmlConvertError, XmlOptions},
+    };
+
+    fn xml(json: &str, options: &XmlOptions) -> String {
+        let text = json_to_xml(&parse(json).unwrap(), options).unwrap();
+        if let Err(error) = roxmltree::Document::parse(&text) {
+            panic!("{text}\n{error}");
+        }
+        text
+    }
+
+    fn with_attributes() -> XmlOptions {
+        XmlOptions {
+            attributes: true,
+            ..XmlOptions::default()
+        }
+    }
+
+    #[test]
+    fn it_writes_objects_and_arrays_as_nested_elements() {
+        let text = xml(
+            r#"{
+                "order": {
+                    "id": 17,
+                    "paid": true,
+                    "note": null,
+                    "line": [{"sku": "a-1", "qty": 2}, {"sku": "b-2", "qty": 1.5}],
+                    "tags": [],
+                    "matrix": [[1, 2], [], [null]]
+                }
+            }"#,
+            &XmlOptions::default(),
+        );
+
+        assert_eq!(
+            text,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<root>
+  <order>
+    <id>17</id>
+    <paid>true</paid>
+    <note/>
+    <line>
+      <sku>a-1</sku>
+      <qty>2</qty>
+    </line>
+    <line>
+      <sku>b-2</sku>
+      <qty>1.5</qty>
+    </line>
+    <matrix>
+      <item>1</item>
+      <item>2</item>
+    </matrix>
+    <matrix/>
+    <matrix>
+      <item/>
+    </matrix>
+  </order>
+</root>
+"#
+        );
+    }
+
+    #[test]
+    fn it_reads_back_as_the_same_structure() {
+        let text = xml(
+            r#"[{"a": [{"b": [1, 2]}, {"b": [[3]]}]}, "x", [], {}]"#,
+            &XmlOptions {
+                root: "list".to_owned(),
+                item: "entry".to_owned(),
+                indent: None,
+                ..XmlOptions::default()
+            },
+        );
+        let document = roxmltree::Document::parse(&text).unwrap();
+        let root = document.root_element();
+        let names = |node: roxmltree::Node| {
+            node.children()
+                .filter(roxmltree::Node::is_element)
+                .map(|child| child.tag_name().name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        let entries: Vec<_> = root.children().collect();
+
+        assert_eq!(root.tag_name().name(), "list");
+        assert_eq!(names(root), ["entry", "entry", "entry", "entry"]);
+        assert_eq!(names(entries[0]), ["a", "a"]);
+        assert_eq!(names(entries[0].first_child().unwrap()), ["b", "b"]);
+        assert_eq!(entries[1].text(), Some("x"));
+        assert_eq!(text.lines().count(), 1);
+        assert!(text.contains("<b><entry>3</entry></b>"));
+    }
+
+    #[test]
+    fn it_escapes_text_and_attributes() {
+        let text = xml(
+            r##"{"text": "a & b < c > d \"e\" 'f' \r\n\tg", "q": {"@v": "1\n2\t3 & \"x\"", "#text": "<"}}"##,
+            &with_attributes(),
+        );
+        let document = roxmltree::Document::parse(&text).unwrap();
+        let text_node = document
+            .descendants()
+            .find(|node| node.has_tag_name("text"));
+        let q = document
+            .descendants()
+            .find(|node| node.has_tag_name("q"))
+            .unwrap();
+
+        assert!(text.contains(
+            "<text>a &amp; b &lt; c &gt; d &quot;e&quot; &apos;f&apos; &#13;\n\tg</text>"
+        ));
+        assert!(text.contains(r#"<q v="1&#10;2&#9;3 &amp; &quot;x&quot;">&lt;</q>"#));
+        assert_eq!(
+            text_node.unwrap().text(),
+            Some("a & b < c > d \"e\" 'f' \r\n\tg")
+        );
+        assert_eq!(q.attribute("v"), Some("1\n2\t3 & \"x\""));
+        assert_eq!(q.text(), Some("<"));
+    }
+
+    #[test]
+    fn attributes_and_text_come_from_at_and_hash_keys() {
+        let document = r##"{
+            "@version": 2,
+            "price": {"@currency": "EUR", "@note": null, "#text": 9.5},
+            "item": [{"@id": "a", "#text": "first", "child": 1}, {"@id": "b"}]
+        }"##;
+
+        assert_eq!(
+            xml(document, &with_attributes()),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<root version="2">
+  <price currency="EUR">9.5</price>
+  <item id="a">first<child>1</child></item>
+  <item id="b"/>
+</root>
+"#
+        );
+        assert_eq!(
+            json_to_xml(&parse(document).unwrap(), &XmlOptions::default()),
+            Err(XmlConvertError::InvalidName {
+                pointer: "/@version".to_owned(),
+                name: "@version".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_names_fail_or_are_sanitized() {
+        let document = parse(r#"{"ok": {"2nd place": 1, "a:b": 2, "": 3, "été-1.x": 4}}"#).unwrap();
+
+        assert_eq!(
+            json_to_xml(&document, &XmlOptions::default()),
+            Err(XmlConvertError::InvalidName {
+                pointer: "/ok/2nd place".to_owned(),
+                name: "2nd place".to_owned(),
+            })
+        );
+        assert_eq!(
+            json_to_xml(
+                &Json::Null,
+                &XmlOptions {
+                    root: "1".to_owned(),
+                    ..XmlOptions::default()
+                }
+            )
+            .unwrap_err()
+            .to_string(),
+            r#"ERROR: Cannot convert JSON to XML - "1" is not a valid element name at """#
+        );
+
+        let sanitized = json_to_xml(
+            &document,
+            &XmlOptions {
+                sanitize_names: true,
+                indent: None,
+                ..XmlOptions::default()
+            },
+        )
+        .unwrap();
+        assert!(sanitized.contains(
+            "<ok><_2nd_place>1</_2nd_place><a_b>2</a_b><_>3</_><été-1.x>4</été-1.x></ok>"
+        ));
+        assert!(roxmltree::Document::parse(&sanitized).is_ok());
+    }
+
+    #[test]
+    fn it_fails_on_values_xml_cannot_hold() {
+        let convert = |value: Json| json_to_xml(&value, &with_attributes());
+
+        assert_eq!(
+            convert(Json::object().set("a", vec!["ok", "bell \u{7}"])),
+            Err(XmlConvertError::InvalidCharacter("/a/1".to_owned()))
+        );
+        assert_eq!(
+            convert(Json::object().set("a", "\u{fffe}")),
+            Err(XmlConvertError::InvalidCharacter("/a".to_owned()))
+        );
+        assert_eq!(
+            convert(vec![f64::NAN].to_json()),
+            Err(XmlConvertError::NonFiniteNumber("/0".to_owned()))
+        );
+        assert_eq!(
+            convert(Json::object().set("a", Json::object().set("@b", vec![1]))),
+            Err(XmlConvertError::NotScalar("/a/@b".to_owned()))
+        );
+        assert_eq!(
+            convert(Json::object().set("#text", Json::object())),
+            Err(XmlConvertError::NotScalar("/#text".to_owned()))
+        );
+    }
+}